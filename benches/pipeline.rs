@@ -0,0 +1,90 @@
+//! Each stage a scan goes through between being read and being drawn, on the
+//! sample scans: the 9k-triangle teapot and the 700k-triangle Eiffel tower
+//!
+//! Run with `cargo bench`, or `cargo bench -- teapot` for a subset. Criterion
+//! compares each run against the previous one and reports regressions
+
+use std::{fs, path::Path};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use scan_classification::{
+    render_software, strip_redundant_triangles, Mesh, MeshMetrics, RenderOptions, Shells,
+    WeldedMesh,
+};
+
+const SAMPLES: [(&str, &str); 2] = [
+    ("teapot", "Utah_teapot_(solid).stl"),
+    ("eiffel", "Eiffel_tower_sample.stl"),
+];
+
+fn read(file: &str) -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(file)).unwrap()
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for &(name, file) in &SAMPLES {
+        let bytes = read(file);
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| Mesh::from_bytes(&bytes, Some(file)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn analysis(c: &mut Criterion) {
+    let mut group = c.benchmark_group("analysis");
+    group.sample_size(20);
+
+    for &(name, file) in &SAMPLES {
+        let mesh = Mesh::from_bytes(&read(file), Some(file)).unwrap();
+        let welded = WeldedMesh::new(&mesh);
+
+        group.throughput(Throughput::Elements(mesh.triangle_count() as u64));
+
+        group.bench_function(format!("weld/{}", name), |b| {
+            b.iter(|| WeldedMesh::new(&mesh))
+        });
+        group.bench_function(format!("metrics/{}", name), |b| {
+            b.iter(|| MeshMetrics::new(&mesh))
+        });
+        group.bench_function(format!("strip-redundant/{}", name), |b| {
+            b.iter(|| strip_redundant_triangles(&mesh))
+        });
+        group.bench_function(format!("reorient/{}", name), |b| {
+            b.iter(|| welded.reoriented())
+        });
+        group.bench_function(format!("shells/{}", name), |b| {
+            b.iter(|| Shells::from_welded(&welded))
+        });
+        group.bench_function(format!("explode/{}", name), |b| {
+            let shells = Shells::from_welded(&welded);
+            b.iter(|| shells.explode(&mesh, 1.5))
+        });
+    }
+
+    group.finish();
+}
+
+/// Drawing on the CPU, which the GL upload cannot be measured against
+/// without a context, but which bounds the fallback renderer's frame time
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render-software");
+    group.sample_size(10);
+
+    for &(name, file) in &SAMPLES {
+        let mesh = Mesh::from_bytes(&read(file), Some(file)).unwrap();
+        let options = RenderOptions::default();
+
+        group.bench_function(name, |b| b.iter(|| render_software(&mesh, &options)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse, analysis, render);
+criterion_main!(benches);
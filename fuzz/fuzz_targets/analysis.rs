@@ -0,0 +1,20 @@
+//! Everything done to a scan once it is parsed, on whatever meshes the parser
+//! accepts: metrics, repairs, and shell detection
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scan_classification::{strip_redundant_triangles, Mesh, MeshMetrics, Shells, WeldedMesh};
+
+fuzz_target!(|data: &[u8]| {
+    let mesh = match Mesh::from_bytes(data, None) {
+        Ok(mesh) => mesh,
+        Err(_) => return,
+    };
+
+    let _ = MeshMetrics::new(&mesh);
+
+    let (stripped, _) = strip_redundant_triangles(&mesh);
+    let (reoriented, _) = WeldedMesh::new(&stripped).reoriented();
+
+    let _ = Shells::new(&reoriented);
+});
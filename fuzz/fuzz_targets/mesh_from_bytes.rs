@@ -0,0 +1,11 @@
+//! Parsing arbitrary bytes as a scan must return an error rather than panic,
+//! whatever format they are sniffed as
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scan_classification::Mesh;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Mesh::from_bytes(data, None);
+    let _ = Mesh::from_stl(data);
+});
@@ -0,0 +1,13 @@
+//! Format detection runs on every file in a scan directory, so it must
+//! handle any contents and name
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scan_classification::ScanFormat;
+
+fuzz_target!(|input: (&[u8], &str)| {
+    let (contents, name) = input;
+
+    let _ = ScanFormat::sniff(contents, Some(name));
+    let _ = ScanFormat::from_name(name);
+});
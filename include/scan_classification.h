@@ -0,0 +1,59 @@
+#ifndef SCAN_CLASSIFICATION_H
+#define SCAN_CLASSIFICATION_H
+
+/* Generated with cbindgen. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum ScanStatus {
+  ScanStatus_Ok = 0,
+  ScanStatus_NullPointer = 1,
+  ScanStatus_InvalidPath = 2,
+  ScanStatus_Io = 3,
+  ScanStatus_Parse = 4,
+  ScanStatus_Panic = 5,
+} ScanStatus;
+
+typedef struct ScanMetrics {
+  double volume;
+  double surface_area;
+  uint64_t hole_count;
+  uint64_t triangle_count;
+  uint64_t vertex_count;
+  float bbox_min[3];
+  float bbox_max[3];
+  uint64_t shell_count;
+  uint64_t inconsistent_triangle_count;
+  uint64_t degenerate_triangle_count;
+  uint64_t duplicate_triangle_count;
+  /**
+   * Surface roughness in degrees
+   */
+  double noise;
+} ScanMetrics;
+
+/**
+ * Analyze the STL file at the NUL-terminated, UTF-8 `path`
+ *
+ * # Safety
+ *
+ * `path` must be a valid NUL-terminated string and `out` must point to
+ * writable memory for a `ScanMetrics`. `out` is only written on success
+ */
+ScanStatus scan_analyze_file(const char *path, struct ScanMetrics *out);
+
+/**
+ * Analyze an in-memory STL file of `len` bytes
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` must point to
+ * writable memory for a `ScanMetrics`. `out` is only written on success
+ */
+ScanStatus scan_analyze_stl_buffer(const uint8_t *data, size_t len, struct ScanMetrics *out);
+
+#endif /* SCAN_CLASSIFICATION_H */
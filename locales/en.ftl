@@ -0,0 +1,104 @@
+scan-title = { $path } | { $triangles } triangles, volume { $volume }, area { $area }, { $holes } holes, { $shells } shells, noise { $noise }°
+stopped-at = Stopped at file #{ $index }
+config-error = { $error }, using defaults
+context-failure = Unable to create an OpenGL window. Tried:
+context-failure-attempt = - { $attempt }: { $reason }
+context-failure-hint = Updating your graphics driver usually fixes this. Remote desktop sessions and virtual machines often lack OpenGL 3.3 support entirely.
+gpu-fallback = Drawing through { $api } instead of OpenGL
+gpu-unavailable = No other GPU API is available either: { $error }
+software-fallback = Falling back to software rendering
+software-failure = The software renderer also failed: { $error }
+bug-report-saved = Saved bug report to { $path }
+bug-report-failed = Unable to save bug report: { $error }
+bounding-box-size = { $x } × { $y } × { $z }
+cleaned-scan-saved = Saved cleaned scan to { $path } ({ $removed } shells removed)
+cleaned-scan-failed = Unable to save cleaned scan: { $error }
+redundant-triangles = { $degenerate } degenerate and { $duplicate } duplicate triangles
+inconsistent-triangles = { $count } inconsistently wound triangles
+shared-context-unavailable = Scans will be uploaded on the main thread: { $error }
+empty-scan = { $path } | Empty scan, recorded as invalid. Press any label key to continue
+corrupt-scan = { $path } | Unable to load: { $error }. Press C to record it as invalid, or Space to skip it
+loading-scan = Loading { $path }…
+loading-timed-out = { $path } | Still loading after { $seconds } s. Press R to retry, or Space to skip it
+cleaned-scan-clipped = Unable to save a cleaned scan while its manifest entry clips it
+filter-skipped = Leaving { $path } out of the filtered queue: { $error }
+filter-summary = { $matched } of { $total } scans match the filter
+sample-saved = Saved { $count } scans to { $path }
+sample-failed = Unable to save sample: { $error }
+expedited-scan = URGENT
+urgent-waiting = { $count } urgent scans next
+label-pending = { $path } | Labeled { $category }. Press Esc to cancel
+label-confirm = { $path } | Label as { $category }? Press Y to confirm, or N to cancel
+category-drift = { $category } is { $share }% of labels, expected { $expected }%
+labeling-too-fast = SLOW DOWN: labeling faster than scans can be inspected
+render-stats = { $draws } draws, { $triangles } triangles, { $changes } state changes, { $uniforms } uniform uploads
+selection-area = Brush { $radius }, selected { $area } over { $triangles } triangles
+mask-saved = Saved region mask to { $path }
+mask-failed = Unable to save region mask: { $error }
+heatmap-legend = Distance from occlusal plane: RMS { $rms }, max { $max }
+fit-plane = Plane
+fit-sphere = Sphere
+fit-cylinder = Cylinder
+fit-summary = { $shape } fit to { $points } points: radius { $radius }, RMS { $rms }, max { $max }
+fit-unavailable = { $shape } fit: paint a region with the brush (M)
+histogram-edges = Edge lengths: median { $median }, { $peaks } peaks, { $tail }% over { $factor }× median
+histogram-areas = Triangle areas: median { $median }, { $peaks } peaks, { $tail }% over { $factor }× median
+section-caption = Cross section: { $contours } contours, { $length } long, grid { $grid }
+export-saved = Exported view to { $path }
+export-failed = Unable to export view: { $error }
+sheet-saved = Saved { $pages } contact sheet pages to { $path }
+sheet-failed = Unable to write contact sheets: { $error }
+sheet-empty = No labeled scans to put on contact sheets
+sheet-skipped = Left { $path } off the contact sheet: { $error }
+sheet-software = Rendering thumbnails on the CPU, which is slower: { $error }
+arch-upper = upper?
+arch-lower = lower?
+teeth-count = ~{ $count } teeth
+teeth-partial = ~{ $count } teeth, partial arch?
+bite-summary = Bite: closest { $closest }, { $penetrating } triangles through the antagonist
+bite-apart = Bite: antagonist farther than { $range }
+bite-unavailable = { $path } has no antagonist in the manifest
+bite-failed = Unable to read antagonist { $path }: { $error }
+undercut-summary = Undercut { $area } ({ $percent }% of surface) along the insertion axis
+source-report-saved = Saved labels by scanner and clinic to { $path }
+source-report-failed = Unable to save labels by scanner and clinic: { $error }
+link-unavailable = No case link for this scan; start with --link-template to open one
+link-failed = Unable to open { $url }: { $error }
+webhook-finished = Finished the queue: { $labeled } of { $scans } scans labeled
+webhook-error-spike = { $failed } of the last { $window } scans failed to load
+webhook-crashed = Crashed: { $error }
+webhook-restarted = Restarted after the previous session ended without shutting down
+webhook-failed = Unable to send notification: { $error }
+metrics-server-failed = Unable to serve metrics on { $address }: { $error }
+queue-unavailable = Unable to list scans in { $path }: { $error }
+metrics-cache-failed = Unable to save metrics cache: { $error }
+urgent-queue-failed = Unable to read urgent queue { $path }: { $error }
+labels-unwritable = Unable to write labels, stopping: { $error }
+kiosk-resumed = Resuming after { $recorded } scans already recorded, { $remaining } left
+tab-main = main
+tab-title = [{ $tab }] { $title }
+tab-report = Queue { $tab }:
+reference-unavailable = No reference template configured, set `path` under [reference]
+reference-failed = Unable to read reference template { $path }: { $error }
+label-undone = Took back the label of { $path }
+voxels-failed = Unable to save occupancy grid { $path }: { $error }
+progress-resumed = Resuming at file #{ $index }, { $recorded } scans recorded so far
+progress-stale = Saved progress is for a different queue, starting from the first file
+distance-caption = Signed distance across { $axis } = { $position } ({ $across } right, { $up } up), ±{ $range }; { $inside }% of the border reads inside
+near-duplicate = NEAR DUPLICATE of { $path }, labeled { $label }
+embed-saved = Saved the embeddings of { $count } scans to { $path }
+embed-failed = Unable to write embeddings: { $error }
+embed-skipped = Left { $path } out of the embeddings: { $error }
+cluster-suggestion = Cluster { $cluster }: { $exemplar } was labeled { $category }, Enter to agree
+context-created = Created { $attempt } context
+mesh-cache-store-failed = Unable to cache mesh at { $path }: { $error }
+mesh-cache-trim-failed = Unable to trim mesh cache at { $path }: { $error }
+metrics-cache-unreadable = Unable to read metrics cache at { $path }: { $error }
+categories-unreadable = Unable to read categories: { $error }
+categories-invalid = Invalid categories: { $error }
+manifest-unreadable = Unable to read manifest: { $error }
+manifest-invalid = Invalid manifest: { $error }
+table-unreadable = Unable to read table: { $error }
+table-missing-column = Table has no `{ $column }` column
+filter-invalid = Invalid filter at position { $position }: { $message }
+link-template-invalid = Invalid link template at position { $position }: { $message }
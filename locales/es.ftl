@@ -0,0 +1,104 @@
+scan-title = { $path } | { $triangles } triángulos, volumen { $volume }, área { $area }, { $holes } agujeros, { $shells } piezas, ruido { $noise }°
+stopped-at = Detenido en el archivo #{ $index }
+config-error = { $error }, usando valores predeterminados
+context-failure = No se pudo crear una ventana OpenGL. Se intentó:
+context-failure-attempt = - { $attempt }: { $reason }
+context-failure-hint = Actualizar el controlador gráfico suele solucionarlo. Las sesiones de escritorio remoto y las máquinas virtuales a menudo no admiten OpenGL 3.3.
+gpu-fallback = Dibujando con { $api } en lugar de OpenGL
+gpu-unavailable = Tampoco hay otra API de GPU disponible: { $error }
+software-fallback = Usando el renderizado por software
+software-failure = El renderizado por software también falló: { $error }
+bug-report-saved = Informe de errores guardado en { $path }
+bug-report-failed = No se pudo guardar el informe de errores: { $error }
+bounding-box-size = { $x } × { $y } × { $z }
+cleaned-scan-saved = Escaneo limpio guardado en { $path } ({ $removed } piezas eliminadas)
+cleaned-scan-failed = No se pudo guardar el escaneo limpio: { $error }
+redundant-triangles = { $degenerate } triángulos degenerados y { $duplicate } duplicados
+inconsistent-triangles = { $count } triángulos con orientación inconsistente
+shared-context-unavailable = Los escaneos se cargarán en el hilo principal: { $error }
+empty-scan = { $path } | Escaneo vacío, registrado como inválido. Pulsa cualquier tecla de etiqueta para continuar
+corrupt-scan = { $path } | No se pudo cargar: { $error }. Pulsa C para registrarlo como inválido o Espacio para omitirlo
+loading-scan = Cargando { $path }…
+loading-timed-out = { $path } | Sigue cargando tras { $seconds } s. Pulsa R para reintentar o Espacio para omitirlo
+cleaned-scan-clipped = No se puede guardar un escaneo limpio mientras su entrada del manifiesto lo recorta
+filter-skipped = Se excluye { $path } de la cola filtrada: { $error }
+filter-summary = { $matched } de { $total } escaneos coinciden con el filtro
+sample-saved = Se guardaron { $count } escaneos en { $path }
+sample-failed = No se pudo guardar la muestra: { $error }
+expedited-scan = URGENTE
+urgent-waiting = { $count } escaneos urgentes a continuación
+label-pending = { $path } | Etiquetado como { $category }. Pulsa Esc para cancelar
+label-confirm = { $path } | ¿Etiquetar como { $category }? Pulsa Y para confirmar o N para cancelar
+category-drift = { $category } es el { $share }% de las etiquetas, se esperaba { $expected }%
+labeling-too-fast = MÁS DESPACIO: etiquetando más rápido de lo que se pueden revisar los escaneos
+render-stats = { $draws } llamadas de dibujo, { $triangles } triángulos, { $changes } cambios de estado, { $uniforms } subidas de uniformes
+selection-area = Pincel { $radius }, seleccionado { $area } en { $triangles } triángulos
+mask-saved = Máscara de región guardada en { $path }
+mask-failed = No se pudo guardar la máscara de región: { $error }
+heatmap-legend = Distancia al plano oclusal: RMS { $rms }, máx. { $max }
+fit-plane = Plano
+fit-sphere = Esfera
+fit-cylinder = Cilindro
+fit-summary = { $shape } ajustado a { $points } puntos: radio { $radius }, RMS { $rms }, máx. { $max }
+fit-unavailable = Ajuste de { $shape }: pinte una región con el pincel (M)
+histogram-edges = Longitud de aristas: mediana { $median }, { $peaks } picos, { $tail } % por encima de { $factor }× la mediana
+histogram-areas = Área de triángulos: mediana { $median }, { $peaks } picos, { $tail } % por encima de { $factor }× la mediana
+section-caption = Sección transversal: { $contours } contornos, { $length } de longitud, cuadrícula { $grid }
+export-saved = Vista exportada a { $path }
+export-failed = No se pudo exportar la vista: { $error }
+sheet-saved = Se guardaron { $pages } páginas de hojas de contactos en { $path }
+sheet-failed = No se pudieron escribir las hojas de contactos: { $error }
+sheet-empty = No hay escaneos etiquetados para las hojas de contactos
+sheet-skipped = Se omitió { $path } de la hoja de contactos: { $error }
+sheet-software = Renderizando miniaturas en la CPU, lo cual es más lento: { $error }
+arch-upper = ¿superior?
+arch-lower = ¿inferior?
+teeth-count = ~{ $count } dientes
+teeth-partial = ~{ $count } dientes, ¿arcada parcial?
+bite-summary = Mordida: lo más cercano { $closest }, { $penetrating } triángulos atraviesan el antagonista
+bite-apart = Mordida: antagonista a más de { $range }
+bite-unavailable = { $path } no tiene antagonista en el manifiesto
+bite-failed = No se pudo leer el antagonista { $path }: { $error }
+undercut-summary = Socavado { $area } ({ $percent }% de la superficie) según el eje de inserción
+source-report-saved = Etiquetas por escáner y clínica guardadas en { $path }
+source-report-failed = No se pudieron guardar las etiquetas por escáner y clínica: { $error }
+link-unavailable = Este escaneo no tiene enlace al caso; inicie con --link-template para abrirlo
+link-failed = No se pudo abrir { $url }: { $error }
+webhook-finished = Cola terminada: { $labeled } de { $scans } escaneos etiquetados
+webhook-error-spike = { $failed } de los últimos { $window } escaneos no se pudieron cargar
+webhook-crashed = Se bloqueó: { $error }
+webhook-restarted = Reiniciado después de que la sesión anterior terminara sin cerrarse
+webhook-failed = No se pudo enviar la notificación: { $error }
+metrics-server-failed = No se pudieron servir las métricas en { $address }: { $error }
+queue-unavailable = No se pudieron listar los escaneos en { $path }: { $error }
+metrics-cache-failed = No se pudo guardar la caché de métricas: { $error }
+urgent-queue-failed = No se pudo leer la cola urgente { $path }: { $error }
+labels-unwritable = No se pudieron escribir las etiquetas, deteniendo: { $error }
+kiosk-resumed = Reanudando después de { $recorded } escaneos ya registrados, quedan { $remaining }
+tab-main = principal
+tab-title = [{ $tab }] { $title }
+tab-report = Cola { $tab }:
+reference-unavailable = No hay plantilla de referencia configurada, defina `path` en [reference]
+reference-failed = No se pudo leer la plantilla de referencia { $path }: { $error }
+label-undone = Se deshizo la etiqueta de { $path }
+voxels-failed = No se pudo guardar la rejilla de ocupación { $path }: { $error }
+progress-resumed = Reanudando en el archivo #{ $index }, { $recorded } escaneos registrados hasta ahora
+progress-stale = El progreso guardado es de otra cola, empezando por el primer archivo
+distance-caption = Distancia con signo en { $axis } = { $position } ({ $across } a la derecha, { $up } arriba), ±{ $range }; { $inside }% del borde queda dentro
+near-duplicate = CASI DUPLICADO de { $path }, etiquetado { $label }
+embed-saved = Se guardaron los embeddings de { $count } escaneos en { $path }
+embed-failed = No se pudieron escribir los embeddings: { $error }
+embed-skipped = Se omitió { $path } de los embeddings: { $error }
+cluster-suggestion = Grupo { $cluster }: { $exemplar } se etiquetó { $category }, Enter para aceptar
+context-created = Contexto { $attempt } creado
+mesh-cache-store-failed = No se pudo guardar la malla en la caché { $path }: { $error }
+mesh-cache-trim-failed = No se pudo recortar la caché de mallas { $path }: { $error }
+metrics-cache-unreadable = No se pudo leer la caché de métricas { $path }: { $error }
+categories-unreadable = No se pudieron leer las categorías: { $error }
+categories-invalid = Categorías inválidas: { $error }
+manifest-unreadable = No se pudo leer el manifiesto: { $error }
+manifest-invalid = Manifiesto inválido: { $error }
+table-unreadable = No se pudo leer la tabla: { $error }
+table-missing-column = La tabla no tiene la columna `{ $column }`
+filter-invalid = Filtro inválido en la posición { $position }: { $message }
+link-template-invalid = Plantilla de enlace inválida en la posición { $position }: { $message }
@@ -0,0 +1,104 @@
+scan-title = { $path } | { $triangles } triângulos, volume { $volume }, área { $area }, { $holes } furos, { $shells } peças, ruído { $noise }°
+stopped-at = Parado no arquivo #{ $index }
+config-error = { $error }, usando os valores padrão
+context-failure = Não foi possível criar uma janela OpenGL. Tentativas:
+context-failure-attempt = - { $attempt }: { $reason }
+context-failure-hint = Atualizar o driver de vídeo geralmente resolve isso. Sessões de área de trabalho remota e máquinas virtuais muitas vezes não suportam OpenGL 3.3.
+gpu-fallback = Desenhando com { $api } em vez de OpenGL
+gpu-unavailable = Nenhuma outra API de GPU está disponível: { $error }
+software-fallback = Usando renderização por software
+software-failure = A renderização por software também falhou: { $error }
+bug-report-saved = Relatório de erro salvo em { $path }
+bug-report-failed = Não foi possível salvar o relatório de erro: { $error }
+bounding-box-size = { $x } × { $y } × { $z }
+cleaned-scan-saved = Escaneamento limpo salvo em { $path } ({ $removed } peças removidas)
+cleaned-scan-failed = Não foi possível salvar o escaneamento limpo: { $error }
+redundant-triangles = { $degenerate } triângulos degenerados e { $duplicate } duplicados
+inconsistent-triangles = { $count } triângulos com orientação inconsistente
+shared-context-unavailable = Os escaneamentos serão enviados na thread principal: { $error }
+empty-scan = { $path } | Escaneamento vazio, registrado como inválido. Pressione qualquer tecla de rótulo para continuar
+corrupt-scan = { $path } | Não foi possível carregar: { $error }. Pressione C para registrá-lo como inválido ou Espaço para pulá-lo
+loading-scan = Carregando { $path }…
+loading-timed-out = { $path } | Ainda carregando após { $seconds } s. Pressione R para tentar de novo ou Espaço para pulá-lo
+cleaned-scan-clipped = Não é possível salvar um escaneamento limpo enquanto sua entrada do manifesto o recorta
+filter-skipped = Deixando { $path } fora da fila filtrada: { $error }
+filter-summary = { $matched } de { $total } escaneamentos correspondem ao filtro
+sample-saved = { $count } escaneamentos salvos em { $path }
+sample-failed = Não foi possível salvar a amostra: { $error }
+expedited-scan = URGENTE
+urgent-waiting = { $count } escaneamentos urgentes a seguir
+label-pending = { $path } | Rotulado como { $category }. Pressione Esc para cancelar
+label-confirm = { $path } | Rotular como { $category }? Pressione Y para confirmar ou N para cancelar
+category-drift = { $category } é { $share }% dos rótulos, esperado { $expected }%
+labeling-too-fast = DEVAGAR: rotulando mais rápido do que os escaneamentos podem ser inspecionados
+render-stats = { $draws } chamadas de desenho, { $triangles } triângulos, { $changes } mudanças de estado, { $uniforms } envios de uniformes
+selection-area = Pincel { $radius }, selecionado { $area } em { $triangles } triângulos
+mask-saved = Máscara de região salva em { $path }
+mask-failed = Não foi possível salvar a máscara de região: { $error }
+heatmap-legend = Distância do plano oclusal: RMS { $rms }, máx. { $max }
+fit-plane = Plano
+fit-sphere = Esfera
+fit-cylinder = Cilindro
+fit-summary = { $shape } ajustado a { $points } pontos: raio { $radius }, RMS { $rms }, máx. { $max }
+fit-unavailable = Ajuste de { $shape }: pinte uma região com o pincel (M)
+histogram-edges = Comprimento das arestas: mediana { $median }, { $peaks } picos, { $tail }% acima de { $factor }× a mediana
+histogram-areas = Área dos triângulos: mediana { $median }, { $peaks } picos, { $tail }% acima de { $factor }× a mediana
+section-caption = Seção transversal: { $contours } contornos, { $length } de comprimento, grade { $grid }
+export-saved = Visualização exportada para { $path }
+export-failed = Não foi possível exportar a visualização: { $error }
+sheet-saved = { $pages } páginas de folhas de contato salvas em { $path }
+sheet-failed = Não foi possível escrever as folhas de contato: { $error }
+sheet-empty = Nenhum escaneamento rotulado para as folhas de contato
+sheet-skipped = { $path } foi omitido da folha de contato: { $error }
+sheet-software = Renderizando miniaturas na CPU, o que é mais lento: { $error }
+arch-upper = superior?
+arch-lower = inferior?
+teeth-count = ~{ $count } dentes
+teeth-partial = ~{ $count } dentes, arcada parcial?
+bite-summary = Mordida: mais próximo { $closest }, { $penetrating } triângulos atravessam o antagonista
+bite-apart = Mordida: antagonista a mais de { $range }
+bite-unavailable = { $path } não tem antagonista no manifesto
+bite-failed = Não foi possível ler o antagonista { $path }: { $error }
+undercut-summary = Retenção { $area } ({ $percent }% da superfície) ao longo do eixo de inserção
+source-report-saved = Rótulos por scanner e clínica salvos em { $path }
+source-report-failed = Não foi possível salvar os rótulos por scanner e clínica: { $error }
+link-unavailable = Este escaneamento não tem link para o caso; inicie com --link-template para abri-lo
+link-failed = Não foi possível abrir { $url }: { $error }
+webhook-finished = Fila concluída: { $labeled } de { $scans } escaneamentos rotulados
+webhook-error-spike = { $failed } dos últimos { $window } escaneamentos não puderam ser carregados
+webhook-crashed = Travou: { $error }
+webhook-restarted = Reiniciado após a sessão anterior terminar sem ser encerrada
+webhook-failed = Não foi possível enviar a notificação: { $error }
+metrics-server-failed = Não foi possível servir as métricas em { $address }: { $error }
+queue-unavailable = Não foi possível listar os escaneamentos em { $path }: { $error }
+metrics-cache-failed = Não foi possível salvar o cache de métricas: { $error }
+urgent-queue-failed = Não foi possível ler a fila urgente { $path }: { $error }
+labels-unwritable = Não foi possível gravar os rótulos, parando: { $error }
+kiosk-resumed = Retomando após { $recorded } escaneamentos já registrados, restam { $remaining }
+tab-main = principal
+tab-title = [{ $tab }] { $title }
+tab-report = Fila { $tab }:
+reference-unavailable = Nenhum modelo de referência configurado, defina `path` em [reference]
+reference-failed = Não foi possível ler o modelo de referência { $path }: { $error }
+label-undone = O rótulo de { $path } foi desfeito
+voxels-failed = Não foi possível salvar a grade de ocupação { $path }: { $error }
+progress-resumed = Retomando no arquivo #{ $index }, { $recorded } escaneamentos registrados até agora
+progress-stale = O progresso salvo é de outra fila, começando pelo primeiro arquivo
+distance-caption = Distância com sinal em { $axis } = { $position } ({ $across } à direita, { $up } para cima), ±{ $range }; { $inside }% da borda fica dentro
+near-duplicate = QUASE DUPLICADO de { $path }, rotulado { $label }
+embed-saved = Embeddings de { $count } escaneamentos salvos em { $path }
+embed-failed = Não foi possível gravar os embeddings: { $error }
+embed-skipped = { $path } ficou fora dos embeddings: { $error }
+cluster-suggestion = Grupo { $cluster }: { $exemplar } foi rotulado { $category }, Enter para concordar
+context-created = Contexto { $attempt } criado
+mesh-cache-store-failed = Não foi possível salvar a malha no cache { $path }: { $error }
+mesh-cache-trim-failed = Não foi possível reduzir o cache de malhas { $path }: { $error }
+metrics-cache-unreadable = Não foi possível ler o cache de métricas { $path }: { $error }
+categories-unreadable = Não foi possível ler as categorias: { $error }
+categories-invalid = Categorias inválidas: { $error }
+manifest-unreadable = Não foi possível ler o manifesto: { $error }
+manifest-invalid = Manifesto inválido: { $error }
+table-unreadable = Não foi possível ler a tabela: { $error }
+table-missing-column = A tabela não tem a coluna `{ $column }`
+filter-invalid = Filtro inválido na posição { $position }: { $message }
+link-template-invalid = Modelo de link inválido na posição { $position }: { $message }
@@ -0,0 +1,657 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mesh::{BoundingBox, Mesh, VERTEX_STRIDE},
+    scratch::ScanScratch,
+};
+
+/// Summary geometry of a scan, shared by the interactive tool and the
+/// bindings so both report identical numbers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MeshMetrics {
+    /// Enclosed volume, only meaningful for closed meshes
+    pub volume: f64,
+    pub surface_area: f64,
+    /// Number of closed loops of boundary edges
+    pub hole_count: usize,
+    /// Number of disconnected pieces, see [`Shells`]
+    pub shell_count: usize,
+    /// Triangles with zero area, see [`WeldedMesh::redundant_triangles`]
+    pub degenerate_triangle_count: usize,
+    /// Triangles using the same three vertices as an earlier triangle
+    pub duplicate_triangle_count: usize,
+    /// Triangles wound against their neighbors, which
+    /// [`WeldedMesh::reoriented`] would flip
+    pub inconsistent_triangle_count: usize,
+    pub triangle_count: usize,
+    pub vertex_count: usize,
+    pub bounding_box: BoundingBox,
+    /// Roughness of the surface in degrees, see [`WeldedMesh::noise`]
+    pub noise: f64,
+}
+
+impl MeshMetrics {
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::from_welded(mesh, &WeldedMesh::new(mesh))
+    }
+
+    /// Like [`MeshMetrics::new`], for a mesh already welded
+    pub fn from_welded(mesh: &Mesh, welded: &WeldedMesh) -> Self {
+        let mut volume = 0.0_f64;
+        let mut surface_area = 0.0_f64;
+
+        for tri in &welded.triangles {
+            let [a, b, c] = welded.corners(tri);
+            let (a, b, c) = (widen(a), widen(b), widen(c));
+
+            volume += a.dot(&b.cross(&c)) / 6.0;
+            surface_area += (b - a).cross(&(c - a)).norm() / 2.0;
+        }
+
+        let (_, redundant) = welded.redundant_triangles();
+
+        Self {
+            volume: volume.abs(),
+            surface_area,
+            hole_count: welded.hole_count(),
+            shell_count: Shells::from_welded(welded).count(),
+            degenerate_triangle_count: redundant.degenerate,
+            duplicate_triangle_count: redundant.duplicate,
+            inconsistent_triangle_count: welded
+                .consistent_orientation()
+                .into_iter()
+                .filter(|&flip| flip)
+                .count(),
+            triangle_count: welded.triangles.len(),
+            vertex_count: welded.positions.len(),
+            bounding_box: mesh.bounding_box(),
+            noise: welded.noise(),
+        }
+    }
+}
+
+/// Times vertex normals are averaged with their neighbours' for
+/// [`WeldedMesh::noise`], which smooths over features a few edges across
+const NOISE_SMOOTHING_PASSES: usize = 3;
+
+/// Accumulating in `f64` keeps volumes of multi-million triangle scans stable
+fn widen(v: Vector3<f32>) -> Vector3<f64> {
+    Vector3::new(v.x as f64, v.y as f64, v.z as f64)
+}
+
+/// A mesh whose vertices have been merged by position alone
+///
+/// The render mesh duplicates vertices that share a position but not a
+/// normal, which hides the connectivity needed for topological analysis
+#[derive(Debug, Clone)]
+pub struct WeldedMesh {
+    pub positions: Vec<Vector3<f32>>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl WeldedMesh {
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::new_in(mesh, &mut ScanScratch::new())
+    }
+
+    /// Like [`WeldedMesh::new`], reusing the lookup table and remapping in
+    /// `scratch` rather than allocating them for every mesh
+    pub fn new_in(mesh: &Mesh, scratch: &mut ScanScratch) -> Self {
+        let lookup = &mut scratch.weld_lookup;
+        let remap = &mut scratch.weld_remap;
+        let mut positions = Vec::new();
+
+        lookup.clear();
+        remap.clear();
+        remap.reserve(mesh.vertex_count());
+
+        for idx in 0..mesh.vertex_count() as u32 {
+            let pos = mesh.position(idx);
+            // adding zero folds `-0.0` into `0.0` so they hash identically
+            let key = [
+                (pos.x + 0.0).to_bits(),
+                (pos.y + 0.0).to_bits(),
+                (pos.z + 0.0).to_bits(),
+            ];
+
+            let welded = *lookup.entry(key).or_insert_with(|| {
+                positions.push(pos);
+                positions.len() as u32 - 1
+            });
+
+            remap.push(welded);
+        }
+
+        let triangles = mesh
+            .triangles()
+            .map(|[a, b, c]| [remap[a as usize], remap[b as usize], remap[c as usize]])
+            .collect();
+
+        Self {
+            positions,
+            triangles,
+        }
+    }
+
+    pub fn corners(&self, tri: &[u32; 3]) -> [Vector3<f32>; 3] {
+        [
+            self.positions[tri[0] as usize],
+            self.positions[tri[1] as usize],
+            self.positions[tri[2] as usize],
+        ]
+    }
+
+    /// The normal at each vertex, averaged over the triangles using it and
+    /// weighted by their area. Zero for unused vertices
+    pub fn vertex_normals(&self) -> Vec<Vector3<f32>> {
+        let mut normals = vec![Vector3::zeros(); self.positions.len()];
+
+        for tri in &self.triangles {
+            let [a, b, c] = self.corners(tri);
+            let normal = (b - a).cross(&(c - a));
+
+            for &vertex in tri {
+                normals[vertex as usize] += normal;
+            }
+        }
+
+        for normal in &mut normals {
+            *normal = normal
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::zeros);
+        }
+
+        normals
+    }
+
+    /// How rough the surface is: the RMS angle, in degrees and weighted by
+    /// area, between each triangle's normal and the surface's normal there
+    /// once smoothed over its neighbours. Clean scans of smooth anatomy stay
+    /// within a few degrees, while scanner noise and stitching artifacts
+    /// tilt triangles far from their surroundings. Independent of the
+    /// scan's units and, mostly, of its resolution
+    pub fn noise(&self) -> f64 {
+        let edges: Vec<(u32, u32)> = self.edge_counts().into_iter().map(|(e, _)| e).collect();
+        let mut smoothed = self.vertex_normals();
+
+        for _ in 0..NOISE_SMOOTHING_PASSES {
+            let mut next = smoothed.clone();
+
+            for &(a, b) in &edges {
+                next[a as usize] += smoothed[b as usize];
+                next[b as usize] += smoothed[a as usize];
+            }
+
+            smoothed = next
+                .into_iter()
+                .map(|normal| {
+                    normal
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(Vector3::zeros)
+                })
+                .collect();
+        }
+
+        let (mut sum, mut area) = (0.0_f64, 0.0_f64);
+
+        for tri in &self.triangles {
+            let [a, b, c] = self.corners(tri);
+            let cross = (b - a).cross(&(c - a));
+            let twice_area = cross.norm();
+
+            let reference = tri
+                .iter()
+                .map(|&vertex| smoothed[vertex as usize])
+                .sum::<Vector3<f32>>()
+                .try_normalize(f32::EPSILON);
+
+            let reference = match reference {
+                Some(reference) if twice_area > f32::EPSILON => reference,
+                _ => continue,
+            };
+
+            // Unsigned, so a triangle wound the wrong way is not counted as
+            // flipped over
+            let cosine = (cross / twice_area).dot(&reference).abs().min(1.0);
+            let angle = cosine.acos() as f64;
+
+            sum += angle * angle * twice_area as f64;
+            area += twice_area as f64;
+        }
+
+        if area > 0.0 {
+            (sum / area).sqrt().to_degrees()
+        } else {
+            0.0
+        }
+    }
+
+    /// Number of triangles using each undirected edge, keyed by
+    /// `(min, max)` vertex index
+    pub fn edge_counts(&self) -> HashMap<(u32, u32), u32> {
+        let mut edges = HashMap::with_capacity(self.triangles.len() * 3 / 2);
+
+        for &[a, b, c] in &self.triangles {
+            for &(start, end) in &[(a, b), (b, c), (c, a)] {
+                *edges.entry((start.min(end), start.max(end))).or_insert(0) += 1;
+            }
+        }
+
+        edges
+    }
+
+    /// Edges used by exactly one triangle
+    pub fn boundary_edges(&self) -> Vec<(u32, u32)> {
+        self.edge_counts()
+            .into_iter()
+            .filter(|&(_, count)| count == 1)
+            .map(|(edge, _)| edge)
+            .collect()
+    }
+
+    /// Which triangles contribute nothing to the surface: those with zero
+    /// area, and those using the same three vertices as an earlier triangle,
+    /// in either winding
+    pub fn redundant_triangles(&self) -> (Vec<bool>, RedundantTriangles) {
+        let mut seen = HashSet::with_capacity(self.triangles.len());
+        let mut counts = RedundantTriangles::default();
+
+        let flags = self
+            .triangles
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = self.corners(tri);
+
+                if (b - a).cross(&(c - a)).norm_squared() == 0.0 {
+                    counts.degenerate += 1;
+                    return true;
+                }
+
+                let mut key = *tri;
+                key.sort_unstable();
+
+                if !seen.insert(key) {
+                    counts.duplicate += 1;
+                    return true;
+                }
+
+                false
+            })
+            .collect();
+
+        (flags, counts)
+    }
+
+    /// Which triangles must be flipped for every pair of neighbors to wind
+    /// their shared edge in opposite directions
+    ///
+    /// Orientation is propagated across each shell along a spanning tree of
+    /// manifold edges. Each shell then keeps whichever orientation flips the
+    /// fewest triangles, unless it is closed, in which case it is oriented
+    /// so its normals point outward
+    pub fn consistent_orientation(&self) -> Vec<bool> {
+        // Triangles using each undirected edge, and whether each runs from
+        // the lower to the higher vertex index
+        let mut edges: HashMap<(u32, u32), Vec<(usize, bool)>> = HashMap::new();
+
+        for (idx, &[a, b, c]) in self.triangles.iter().enumerate() {
+            for &(start, end) in &[(a, b), (b, c), (c, a)] {
+                edges
+                    .entry((start.min(end), start.max(end)))
+                    .or_default()
+                    .push((idx, start < end));
+            }
+        }
+
+        let mut flips = vec![false; self.triangles.len()];
+        let mut visited = vec![false; self.triangles.len()];
+        let mut queue = VecDeque::new();
+
+        for root in 0..self.triangles.len() {
+            if visited[root] {
+                continue;
+            }
+
+            visited[root] = true;
+            queue.push_back(root);
+
+            let mut shell = vec![root];
+            let mut is_closed = true;
+
+            while let Some(idx) = queue.pop_front() {
+                let [a, b, c] = self.triangles[idx];
+
+                for &(start, end) in &[(a, b), (b, c), (c, a)] {
+                    let users = &edges[&(start.min(end), start.max(end))];
+
+                    if users.len() == 1 {
+                        is_closed = false;
+                    }
+
+                    // Orientation is ambiguous across non-manifold edges
+                    if users.len() != 2 {
+                        continue;
+                    }
+
+                    let forward = (start < end) != flips[idx];
+
+                    for &(neighbor, neighbor_forward) in users {
+                        if visited[neighbor] {
+                            continue;
+                        }
+
+                        visited[neighbor] = true;
+                        flips[neighbor] = neighbor_forward == forward;
+
+                        shell.push(neighbor);
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            let invert = if is_closed {
+                self.signed_volume(shell.iter().map(|&idx| (idx, flips[idx]))) < 0.0
+            } else {
+                shell.iter().filter(|&&idx| flips[idx]).count() * 2 > shell.len()
+            };
+
+            if invert {
+                for idx in shell {
+                    flips[idx] = !flips[idx];
+                }
+            }
+        }
+
+        flips
+    }
+
+    /// Signed volume of the given triangles, each flipped if its flag is set
+    fn signed_volume(&self, triangles: impl Iterator<Item = (usize, bool)>) -> f64 {
+        triangles
+            .map(|(idx, flip)| {
+                let [a, b, c] = self.corners(&self.triangles[idx]);
+                let (a, b, c) = (widen(a), widen(b), widen(c));
+
+                let volume = a.dot(&b.cross(&c)) / 6.0;
+
+                if flip {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .sum()
+    }
+
+    /// A consistently oriented, flat-shaded copy of the welded mesh, with
+    /// normals recomputed from the corrected winding, and the number of
+    /// triangles that were flipped
+    pub fn reoriented(&self) -> (Mesh, usize) {
+        let flips = self.consistent_orientation();
+
+        // Vertices are shared only by triangles with identical normals
+        let mut lookup: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::with_capacity(self.triangles.len() * 3);
+
+        for (tri, &flip) in self.triangles.iter().zip(&flips) {
+            let tri = if flip { [tri[0], tri[2], tri[1]] } else { *tri };
+
+            let [a, b, c] = self.corners(&tri);
+            let normal = (b - a)
+                .cross(&(c - a))
+                .try_normalize(0.0)
+                .unwrap_or_else(Vector3::zeros);
+            let key = [
+                (normal.x + 0.0).to_bits(),
+                (normal.y + 0.0).to_bits(),
+                (normal.z + 0.0).to_bits(),
+            ];
+
+            for &position in &tri {
+                let idx = *lookup.entry((position, key)).or_insert_with(|| {
+                    vertices.extend_from_slice(self.positions[position as usize].as_slice());
+                    vertices.extend_from_slice(normal.as_slice());
+
+                    (vertices.len() / VERTEX_STRIDE) as u32 - 1
+                });
+
+                indices.push(idx);
+            }
+        }
+
+        let flipped = flips.into_iter().filter(|&flip| flip).count();
+
+        (Mesh::new(vertices, indices), flipped)
+    }
+
+    pub fn hole_count(&self) -> usize {
+        let boundary = self.boundary_edges();
+
+        let mut sets = DisjointSet::new(self.positions.len());
+
+        for &(a, b) in &boundary {
+            sets.union(a, b);
+        }
+
+        let mut roots: Vec<u32> = boundary.iter().map(|&(a, _)| sets.find(a)).collect();
+        roots.sort_unstable();
+        roots.dedup();
+
+        roots.len()
+    }
+}
+
+/// Counts of triangles found by [`WeldedMesh::redundant_triangles`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RedundantTriangles {
+    pub degenerate: usize,
+    pub duplicate: usize,
+}
+
+/// `mesh` without its zero-area and duplicated triangles
+pub fn strip_redundant_triangles(mesh: &Mesh) -> (Mesh, RedundantTriangles) {
+    strip_redundant_triangles_in(mesh, &WeldedMesh::new(mesh), &mut ScanScratch::new())
+}
+
+/// Like [`strip_redundant_triangles`], for a mesh already welded, building
+/// the result in buffers taken from `scratch`
+pub fn strip_redundant_triangles_in(
+    mesh: &Mesh,
+    welded: &WeldedMesh,
+    scratch: &mut ScanScratch,
+) -> (Mesh, RedundantTriangles) {
+    let (redundant, counts) = welded.redundant_triangles();
+
+    let keep: Vec<bool> = redundant
+        .into_iter()
+        .map(|is_redundant| !is_redundant)
+        .collect();
+
+    (mesh.retain_triangles_in(&keep, scratch), counts)
+}
+
+/// The connected pieces of a scan. A clean scan has a single shell; extra
+/// shells are usually floating debris
+#[derive(Debug, Clone)]
+pub struct Shells {
+    /// Shell of each triangle, in `0..count`
+    pub triangle_shells: Vec<u32>,
+    /// Area-weighted centroid of each shell
+    pub centroids: Vec<Vector3<f32>>,
+}
+
+impl Shells {
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::from_welded(&WeldedMesh::new(mesh))
+    }
+
+    /// Triangles of `welded` are in the same order as the mesh it was welded
+    /// from, so the result applies to both
+    pub fn from_welded(welded: &WeldedMesh) -> Self {
+        let mut sets = DisjointSet::new(welded.positions.len());
+
+        for &[a, b, c] in &welded.triangles {
+            sets.union(a, b);
+            sets.union(b, c);
+        }
+
+        let mut shell_of_root = HashMap::new();
+        let mut weighted_sums: Vec<(Vector3<f64>, f64)> = Vec::new();
+
+        let triangle_shells = welded
+            .triangles
+            .iter()
+            .map(|tri| {
+                let root = sets.find(tri[0]);
+                let shell = *shell_of_root.entry(root).or_insert_with(|| {
+                    weighted_sums.push((Vector3::zeros(), 0.0));
+                    weighted_sums.len() as u32 - 1
+                });
+
+                let [a, b, c] = welded.corners(tri);
+                let (a, b, c) = (widen(a), widen(b), widen(c));
+                // Degenerate triangles still count a little, so shells made
+                // only of them have a centroid
+                let area = (b - a).cross(&(c - a)).norm() / 2.0 + f64::EPSILON;
+
+                let (sum, total) = &mut weighted_sums[shell as usize];
+                *sum += (a + b + c) / 3.0 * area;
+                *total += area;
+
+                shell
+            })
+            .collect();
+
+        let centroids = weighted_sums
+            .into_iter()
+            .map(|(sum, total)| {
+                let centroid = sum / total;
+
+                Vector3::new(centroid.x as f32, centroid.y as f32, centroid.z as f32)
+            })
+            .collect();
+
+        Self {
+            triangle_shells,
+            centroids,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.centroids.len()
+    }
+
+    /// Number of triangles in each shell
+    pub fn triangle_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.count()];
+
+        for &shell in &self.triangle_shells {
+            counts[shell as usize] += 1;
+        }
+
+        counts
+    }
+
+    /// Whether each shell has fewer than `fraction` of the scan's triangles
+    pub fn small_shells(&self, fraction: f32) -> Vec<bool> {
+        let threshold = self.triangle_shells.len() as f32 * fraction;
+
+        self.triangle_counts()
+            .into_iter()
+            .map(|count| (count as f32) < threshold)
+            .collect()
+    }
+
+    /// The triangles of `mesh` belonging to shells for which `selected` is
+    /// true, sharing the full vertex buffer
+    ///
+    /// `mesh` must be the mesh these shells were computed from
+    pub fn extract(&self, mesh: &Mesh, selected: &[bool]) -> Mesh {
+        let keep: Vec<bool> = self
+            .triangle_shells
+            .iter()
+            .map(|&shell| selected[shell as usize])
+            .collect();
+
+        mesh.retain_triangles(&keep)
+    }
+
+    /// A copy of `mesh` with each shell pushed away from the center of the
+    /// scan by `factor` times its centroid's distance from it
+    ///
+    /// `mesh` must be the mesh these shells were computed from
+    pub fn explode(&self, mesh: &Mesh, factor: f32) -> Mesh {
+        let center = mesh.bounding_box().center();
+
+        let mut vertices = mesh.vertices().to_vec();
+        let mut moved = vec![false; mesh.vertex_count()];
+
+        for (tri, &shell) in mesh.triangles().zip(&self.triangle_shells) {
+            let offset = (self.centroids[shell as usize] - center) * factor;
+
+            // A render vertex is only shared by triangles of the same shell,
+            // since shells share no positions
+            for &idx in &tri {
+                if std::mem::replace(&mut moved[idx as usize], true) {
+                    continue;
+                }
+
+                let start = idx as usize * VERTEX_STRIDE;
+
+                for axis in 0..3 {
+                    vertices[start + axis] += offset[axis];
+                }
+            }
+        }
+
+        Mesh::new(vertices, mesh.indices().to_vec())
+    }
+}
+
+/// Union-find over vertex indices
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parents: Vec<u32>,
+    ranks: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len as u32).collect(),
+            ranks: vec![0; len],
+        }
+    }
+
+    pub fn find(&mut self, mut idx: u32) -> u32 {
+        while self.parents[idx as usize] != idx {
+            let parent = self.parents[idx as usize];
+            self.parents[idx as usize] = self.parents[parent as usize];
+            idx = parent;
+        }
+
+        idx
+    }
+
+    pub fn union(&mut self, a: u32, b: u32) {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return;
+        }
+
+        match self.ranks[a as usize].cmp(&self.ranks[b as usize]) {
+            std::cmp::Ordering::Less => self.parents[a as usize] = b,
+            std::cmp::Ordering::Greater => self.parents[b as usize] = a,
+            std::cmp::Ordering::Equal => {
+                self.parents[b as usize] = a;
+                self.ranks[a as usize] += 1;
+            }
+        }
+    }
+}
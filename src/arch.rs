@@ -0,0 +1,257 @@
+//! Guessing whether a scan is of the upper or lower arch
+//!
+//! Telling the arches apart is the most common decision made while
+//! labeling, and usually obvious at a glance, so even a hint that is often
+//! right saves a moment on most scans. Two cues vote:
+//!
+//! - Which way the teeth face. Intraoral scanners export both arches of a
+//!   case in one frame, with +z pointing from the lower arch toward the
+//!   upper, so the biting surfaces of an upper arch face -z. The biting side
+//!   is told from the base by its curvature, since cusps are far bumpier
+//!   than the gums or the flat cut of a model base
+//! - Whether the inside of the arch is filled. Upper scans usually take in
+//!   the palate, while the floor of the mouth under the tongue is left out
+//!   of lower scans, which leaves them U-shaped
+
+use std::collections::HashSet;
+
+use nalgebra::{Matrix3, Vector2, Vector3};
+
+use crate::analysis::WeldedMesh;
+
+/// Scans whose thinnest extent has more than this fraction of the variance
+/// of the next are too bulky to be an arch, and get no guess
+const FLATNESS: f32 = 0.35;
+
+/// Cells across the longer side of the grid the filled fraction of an arch
+/// is measured on
+const GRID_CELLS: usize = 48;
+
+/// Fraction of an arch's outline that is filled, above which it reads as an
+/// upper arch with its palate, and how quickly the vote saturates either
+/// side of it
+const PALATE_COVERAGE: f32 = 0.75;
+const PALATE_SPREAD: f32 = 0.15;
+
+/// Guesses with a weaker combined vote are not shown
+pub const MIN_SCORE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchEstimate {
+    pub arch: Arch,
+    /// Combined vote in `[0, 1]`, 0 a coin flip and 1 both cues agreeing
+    /// fully
+    pub score: f32,
+    /// Mean of the scan's vertices
+    pub center: Vector3<f32>,
+    /// Unit direction the teeth face
+    pub occlusal: Vector3<f32>,
+    /// Fraction of the arch's outline, seen along `occlusal`, that the
+    /// scan covers
+    pub coverage: f32,
+}
+
+impl ArchEstimate {
+    /// `None` if `welded` is not shaped like an arch
+    pub fn new(welded: &WeldedMesh) -> Option<Self> {
+        let positions = &welded.positions;
+
+        if positions.len() < 3 || welded.triangles.is_empty() {
+            return None;
+        }
+
+        let centroid = positions.iter().sum::<Vector3<f32>>() / positions.len() as f32;
+        let covariance = positions.iter().fold(Matrix3::zeros(), |sum, &position| {
+            let offset = position - centroid;
+            sum + offset * offset.transpose()
+        }) / positions.len() as f32;
+
+        let eigen = covariance.symmetric_eigen();
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| {
+            eigen.eigenvalues[b]
+                .partial_cmp(&eigen.eigenvalues[a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let [widest, middle, thinnest] = order;
+
+        let is_flat = eigen.eigenvalues[thinnest] < FLATNESS * eigen.eigenvalues[middle];
+
+        if !is_flat {
+            return None;
+        }
+
+        let axis = eigen.eigenvectors.column(thinnest).into_owned();
+        let plane = [
+            eigen.eigenvectors.column(widest).into_owned(),
+            eigen.eigenvectors.column(middle).into_owned(),
+        ];
+
+        let occlusal = axis * biting_side(welded, centroid, axis);
+        let coverage = coverage(welded, plane);
+
+        let facing = -occlusal.z;
+        let palate = ((coverage - PALATE_COVERAGE) / PALATE_SPREAD).clamp(-1.0, 1.0);
+        let vote = (facing + palate) / 2.0;
+
+        Some(Self {
+            arch: if vote > 0.0 { Arch::Upper } else { Arch::Lower },
+            score: vote.abs(),
+            center: centroid,
+            occlusal,
+            coverage,
+        })
+    }
+
+    /// Whether the guess is strong enough to show
+    pub fn is_confident(&self) -> bool {
+        self.score >= MIN_SCORE
+    }
+}
+
+/// 1 if the teeth are on the side of `axis` it points to, -1 otherwise,
+/// from where the most curved vertices lie along it
+fn biting_side(welded: &WeldedMesh, centroid: Vector3<f32>, axis: Vector3<f32>) -> f32 {
+    let normals = welded.vertex_normals();
+
+    // How quickly the normal turns along each edge, summed at its ends
+    let mut curvature = vec![0.0; welded.positions.len()];
+
+    for &(a, b) in welded.edge_counts().keys() {
+        let (a, b) = (a as usize, b as usize);
+        let length = (welded.positions[a] - welded.positions[b]).norm();
+
+        if length > f32::EPSILON {
+            let turn = (normals[a] - normals[b]).norm() / length;
+            curvature[a] += turn;
+            curvature[b] += turn;
+        }
+    }
+
+    let (mut height, mut weighted, mut weight) = (0.0, 0.0, 0.0);
+
+    for (position, &curvature) in welded.positions.iter().zip(&curvature) {
+        let along = (position - centroid).dot(&axis);
+        height += along;
+        weighted += along * curvature;
+        weight += curvature;
+    }
+
+    let mean = height / welded.positions.len() as f32;
+
+    if weight > 0.0 && weighted / weight < mean {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Fraction of the cells inside the convex outline of the scan, projected
+/// onto `plane`, that the scan covers
+fn coverage(welded: &WeldedMesh, plane: [Vector3<f32>; 2]) -> f32 {
+    let project = |point: Vector3<f32>| Vector2::new(point.dot(&plane[0]), point.dot(&plane[1]));
+
+    let projected: Vec<Vector2<f32>> = welded.positions.iter().map(|&p| project(p)).collect();
+    let (min, max) = projected.iter().fold(
+        (Vector2::repeat(f32::MAX), Vector2::repeat(f32::MIN)),
+        |(min, max), point| (min.inf(point), max.sup(point)),
+    );
+
+    let cell = (max - min).max() / GRID_CELLS as f32;
+
+    if !cell.is_finite() || cell <= 0.0 {
+        return 0.0;
+    }
+
+    let to_cell = |point: Vector2<f32>| {
+        let offset = (point - min) / cell;
+        (
+            (offset.x as i64).min(GRID_CELLS as i64 - 1),
+            (offset.y as i64).min(GRID_CELLS as i64 - 1),
+        )
+    };
+
+    // Triangle centers as well, so large triangles between sparse vertices
+    // still cover their cells
+    let mut covered: HashSet<(i64, i64)> = projected.iter().map(|&p| to_cell(p)).collect();
+    covered.extend(welded.triangles.iter().map(|&[a, b, c]| {
+        to_cell((projected[a as usize] + projected[b as usize] + projected[c as usize]) / 3.0)
+    }));
+
+    let mut cells: Vec<(i64, i64)> = covered.iter().copied().collect();
+    cells.sort_unstable();
+    let hull = convex_hull(&cells);
+
+    if hull.len() < 3 {
+        return 1.0;
+    }
+
+    let (min_x, max_x) = (cells[0].0, cells[cells.len() - 1].0);
+    let (min_y, max_y) = cells
+        .iter()
+        .fold((i64::MAX, i64::MIN), |(lo, hi), &(_, y)| {
+            (lo.min(y), hi.max(y))
+        });
+
+    let mut inside = 0;
+    let mut filled = 0;
+
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            if contains(&hull, (x, y)) {
+                inside += 1;
+                filled += covered.contains(&(x, y)) as usize;
+            }
+        }
+    }
+
+    filled as f32 / inside.max(1) as f32
+}
+
+/// Counterclockwise hull of `points`, which must be sorted, by Andrew's
+/// monotone chain
+fn convex_hull(points: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut hull: Vec<(i64, i64)> = Vec::with_capacity(points.len() + 1);
+
+    for pass in 0..2 {
+        let start = hull.len();
+
+        let mut push = |point: (i64, i64)| {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        };
+
+        if pass == 0 {
+            points.iter().copied().for_each(&mut push);
+        } else {
+            points.iter().rev().copied().for_each(&mut push);
+        }
+
+        // The last point of each chain starts the other
+        hull.pop();
+    }
+
+    hull
+}
+
+/// Whether `point` is inside or on the counterclockwise `hull`
+fn contains(hull: &[(i64, i64)], point: (i64, i64)) -> bool {
+    (0..hull.len()).all(|idx| cross(hull[idx], hull[(idx + 1) % hull.len()], point) >= 0)
+}
+
+/// Z of the cross product of `b - a` and `c - a`, positive if `a`, `b`, `c`
+/// turn counterclockwise
+fn cross(a: (i64, i64), b: (i64, i64), c: (i64, i64)) -> i64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
@@ -0,0 +1,97 @@
+//! Reading ASCII STL scans
+//!
+//! Each `facet` gives its normal and the corners of one polygon, nearly
+//! always a triangle; larger ones are split into fans. Corners with the same
+//! position and normal share a vertex, as they do in binary STL meshes, and
+//! facets with a zero normal, which some exporters write, get the normal of
+//! their winding
+
+use nalgebra::Vector3;
+
+use crate::{
+    loader::FacetBuilder,
+    mesh::{Mesh, MeshError},
+    scratch::ScanScratch,
+};
+
+pub fn parse(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+    let text = String::from_utf8_lossy(buffer);
+
+    let mut mesh = FacetBuilder::new(scratch);
+
+    // The facet being read, if inside one
+    let mut facet: Option<(Vector3<f32>, Vec<Vector3<f32>>)> = None;
+    let mut solids = 0;
+
+    for (idx, line) in text.lines().enumerate() {
+        let number = idx + 1;
+        let mut fields = line.split_whitespace();
+
+        match fields.next() {
+            Some("solid") => solids += 1,
+            Some("facet") => {
+                if facet.is_some() {
+                    return Err(error(number, "facet inside another facet"));
+                }
+
+                if fields.next() != Some("normal") {
+                    return Err(error(number, "expected `facet normal`"));
+                }
+
+                facet = Some((vector(fields, number)?, Vec::with_capacity(3)));
+            }
+            Some("vertex") => match &mut facet {
+                Some((_, corners)) => corners.push(vector(fields, number)?),
+                None => return Err(error(number, "vertex outside a facet")),
+            },
+            Some("endfacet") => {
+                let (normal, corners) = facet
+                    .take()
+                    .ok_or_else(|| error(number, "endfacet without a facet"))?;
+
+                if corners.len() < 3 {
+                    return Err(error(number, "facet with fewer than 3 vertices"));
+                }
+
+                for corner in 1..corners.len() - 1 {
+                    mesh.push([corners[0], corners[corner], corners[corner + 1]], normal);
+                }
+            }
+            // `outer loop`, `endloop`, and `endsolid` carry nothing needed
+            _ => {}
+        }
+    }
+
+    if solids == 0 {
+        return Err(MeshError::Parse(String::from("ASCII STL: no `solid`")));
+    }
+
+    if facet.is_some() {
+        return Err(MeshError::Parse(String::from(
+            "ASCII STL: file ends inside a facet",
+        )));
+    }
+
+    Ok(mesh.finish())
+}
+
+/// The three coordinates after a `facet normal` or `vertex`
+fn vector<'a>(
+    mut fields: impl Iterator<Item = &'a str>,
+    number: usize,
+) -> Result<Vector3<f32>, MeshError> {
+    let mut vector = Vector3::zeros();
+
+    for component in vector.iter_mut() {
+        *component = fields
+            .next()
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(|| error(number, "expected three coordinates"))?;
+    }
+
+    Ok(vector)
+}
+
+fn error(number: usize, message: &str) -> MeshError {
+    MeshError::Parse(format!("ASCII STL line {}: {}", number, message))
+}
@@ -0,0 +1,299 @@
+use nalgebra::Matrix4;
+
+use crate::{
+    camera::StationaryCamera,
+    color::Color,
+    manifest::ShadingMode,
+    mesh::Mesh,
+    renderer::{
+        BufferContext, DrawCommand, DrawList, Light, RenderStats, Renderer, ScanUniforms,
+        UploadedMesh,
+    },
+    shader::{Shader, Uniform},
+    vertex_format::VertexFormat,
+};
+
+/// Everything a backend needs to draw the current scan for one frame
+pub struct ScanFrame<'a> {
+    pub stationary: &'a StationaryCamera,
+    /// World transform of the scan
+    pub model: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    pub background: Color,
+}
+
+/// A graphics API capable of displaying scans
+///
+/// The interactive tool and the offscreen renderer only talk to the scan
+/// pass through this trait, so APIs other than OpenGL can be slotted in on
+/// machines where GL is unavailable or broken
+pub trait RenderBackend {
+    /// Short name used in diagnostics, e.g. `"opengl"`
+    fn name(&self) -> &'static str;
+
+    /// Replace the currently displayed scan
+    fn load_scan(&mut self, mesh: &Mesh);
+
+    fn resize(&mut self, width: u32, height: u32);
+
+    fn set_object_color(&mut self, color: Color);
+
+    /// Clear the target and draw the current scan, if any
+    fn draw_scan(&mut self, frame: &ScanFrame);
+
+    /// The work done drawing since the last call, which resets the counters
+    fn take_stats(&mut self) -> RenderStats;
+}
+
+/// A backend that draws into memory rather than a window, for showing in a
+/// plain framebuffer window when no GL context could be created
+pub trait PixelBackend: RenderBackend {
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+
+    /// `0x00RRGGBB`, top row first
+    fn pixels(&self) -> &[u32];
+}
+
+pub struct GlBackend {
+    renderer: Renderer,
+    shader: Shader,
+    buffer_context: Option<BufferContext>,
+    object_color: Color,
+    /// Parts of the current scan drawn again on top in other colors
+    highlights: Vec<(BufferContext, Color)>,
+    /// A second, aligned scan drawn translucently over the first
+    comparison: Option<(BufferContext, f32)>,
+    /// A coarse copy of the current scan, drawn instead of it while
+    /// `interacting`
+    proxy: Option<BufferContext>,
+    interacting: bool,
+    shading: ShadingMode,
+    vertex_format: VertexFormat,
+}
+
+impl GlBackend {
+    /// Requires a current GL context with loaded function pointers
+    pub fn new(light: &Light, object_color: Color) -> Self {
+        let renderer = Renderer::new();
+
+        let shader = Renderer::scan_shader(
+            &StationaryCamera::default_model(),
+            &Matrix4::identity(),
+            light,
+            object_color,
+        );
+
+        Self {
+            renderer,
+            shader,
+            buffer_context: None,
+            object_color,
+            highlights: Vec::new(),
+            comparison: None,
+            proxy: None,
+            interacting: false,
+            shading: ShadingMode::default(),
+            vertex_format: VertexFormat::default(),
+        }
+    }
+
+    /// How scans loaded from now on are stored on the GPU
+    pub fn set_vertex_format(&mut self, format: VertexFormat) {
+        self.vertex_format = format;
+    }
+
+    pub fn vertex_format(&self) -> VertexFormat {
+        self.vertex_format
+    }
+
+    /// Show a scan whose buffers were uploaded ahead of time, rather than
+    /// uploading it as [`RenderBackend::load_scan`] does
+    pub fn load_uploaded(&mut self, uploaded: UploadedMesh) {
+        self.buffer_context = Some(BufferContext::from_uploaded(uploaded));
+        self.proxy = None;
+    }
+
+    pub fn buffer_context(&self) -> Option<&BufferContext> {
+        self.buffer_context.as_ref()
+    }
+
+    /// Redraw each mesh, a subset of the current scan's triangles, in its
+    /// color. Later highlights are drawn over earlier ones
+    pub fn set_highlights(&mut self, highlights: &[(Mesh, Color)]) {
+        self.highlights = highlights
+            .iter()
+            .filter(|(mesh, _)| mesh.triangle_count() > 0)
+            .map(|(mesh, color)| (BufferContext::with_format(mesh, self.vertex_format), *color))
+            .collect();
+    }
+
+    /// Draw `mesh`, a decimated copy of the current scan, in its place while
+    /// the view is moving. Cleared whenever a scan is loaded
+    pub fn set_proxy(&mut self, mesh: Option<&Mesh>) {
+        self.proxy = mesh.map(|mesh| {
+            let mut proxy = BufferContext::with_format(mesh, self.vertex_format);
+
+            // Framed like the scan, so swapping them does not shift the view
+            if let Some(buffer_context) = &self.buffer_context {
+                proxy.bbox = buffer_context.bbox;
+            }
+
+            proxy
+        });
+    }
+
+    pub fn has_proxy(&self) -> bool {
+        self.proxy.is_some()
+    }
+
+    /// Whether the view is moving, so the proxy is drawn if there is one.
+    /// Highlights are left out meanwhile, since they match the scan's
+    /// triangles rather than the proxy's
+    pub fn set_interacting(&mut self, interacting: bool) {
+        self.interacting = interacting;
+    }
+
+    /// How the scan and its highlights are drawn. The comparison scan is
+    /// always shaded
+    pub fn set_shading(&mut self, shading: ShadingMode) {
+        self.shading = shading;
+    }
+
+    /// Draw `mesh` at `alpha` over the current scan, in the same space, or
+    /// stop drawing a comparison scan if `None`
+    pub fn set_comparison(&mut self, mesh: Option<&Mesh>, alpha: f32) {
+        self.comparison =
+            mesh.map(|mesh| (BufferContext::with_format(mesh, self.vertex_format), alpha));
+    }
+}
+
+impl RenderBackend for GlBackend {
+    fn name(&self) -> &'static str {
+        "opengl"
+    }
+
+    fn load_scan(&mut self, mesh: &Mesh) {
+        self.buffer_context = Some(BufferContext::with_format(mesh, self.vertex_format));
+        self.proxy = None;
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        };
+
+        self.renderer.clear();
+    }
+
+    fn set_object_color(&mut self, color: Color) {
+        self.object_color = color;
+
+        self.shader.bind();
+        set_color(&mut self.shader, color);
+    }
+
+    fn draw_scan(&mut self, frame: &ScanFrame) {
+        self.renderer.clear_color(frame.background);
+
+        // Overlays drawn since the last frame bind their own programs
+        self.renderer.invalidate_bindings();
+
+        if self.shading == ShadingMode::Wireframe {
+            unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
+        }
+
+        let proxy = self.proxy.as_ref().filter(|_| self.interacting);
+
+        // The scan, then each highlight over it in order
+        let draws: Vec<(&BufferContext, Color)> = match proxy {
+            Some(proxy) => vec![(proxy, self.object_color)],
+            None => self
+                .buffer_context
+                .iter()
+                .map(|context| (context, self.object_color))
+                .chain(
+                    self.highlights
+                        .iter()
+                        .map(|(context, color)| (context, *color)),
+                )
+                .collect(),
+        };
+
+        let uniforms: Vec<ScanUniforms> = draws
+            .iter()
+            .map(|(context, _)| {
+                ScanUniforms::new(context, frame.stationary, &frame.model, &frame.projection)
+            })
+            .collect();
+        let uniforms: Vec<_> = uniforms.iter().map(ScanUniforms::uniforms).collect();
+        let colors: Vec<[Uniform; 1]> = draws
+            .iter()
+            .map(|(_, color)| [color_uniform(*color)])
+            .collect();
+
+        let mut list = DrawList::new();
+
+        for (idx, (context, _)) in draws.iter().enumerate() {
+            list.push(DrawCommand {
+                layer: idx.min(1) as u32,
+                shader: 0,
+                material: idx as u32,
+                material_uniforms: &colors[idx],
+                uniforms: &uniforms[idx],
+                va: &context.va,
+                ib: &context.ib,
+            });
+        }
+
+        // Highlights share the scan's vertices, so equal depths must pass
+        unsafe { gl::DepthFunc(gl::LEQUAL) };
+
+        self.renderer.draw_list(list, &mut [&mut self.shader]);
+
+        unsafe { gl::DepthFunc(gl::LESS) };
+
+        if draws.len() > 1 {
+            set_color(&mut self.shader, self.object_color);
+        }
+
+        if self.shading == ShadingMode::Wireframe {
+            unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
+        }
+
+        if let Some((comparison, alpha)) = &mut self.comparison {
+            // Frame the comparison with the current scan's camera so the two
+            // stay aligned
+            if let Some(buffer_context) = &self.buffer_context {
+                comparison.bbox = buffer_context.bbox;
+            }
+
+            self.renderer.draw_scan_transparent(
+                &mut self.shader,
+                comparison,
+                frame.stationary,
+                &frame.model,
+                &frame.projection,
+                *alpha,
+            );
+        }
+    }
+
+    fn take_stats(&mut self) -> RenderStats {
+        self.renderer.take_stats()
+    }
+}
+
+fn set_color(shader: &mut Shader, color: Color) {
+    shader.set_uniform(&color_uniform(color));
+}
+
+fn color_uniform(color: Color) -> Uniform<'static> {
+    Uniform::ThreeFloat {
+        name: "object_color",
+        v0: color.red,
+        v1: color.green,
+        v2: color.blue,
+    }
+}
@@ -0,0 +1,165 @@
+//! Distances between a scan and the opposing arch it bites against
+//!
+//! Occlusion errors, where a bite registration leaves the arches apart or
+//! pushed through each other, only show with both arches in place. Each
+//! triangle's distance to the opposing arch is measured along that arch's
+//! surface normal, so triangles inside it come out negative
+
+use nalgebra::Vector3;
+
+use crate::{analysis::WeldedMesh, mesh::Mesh, picking::TriangleBvh};
+
+/// Signed distance from each triangle of a scan to its antagonist, the
+/// opposing arch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiteDistances {
+    /// Per triangle of the scan, `None` if the antagonist is farther than
+    /// `range`. Negative inside the antagonist
+    pub distances: Vec<Option<f32>>,
+    pub range: f32,
+}
+
+impl BiteDistances {
+    /// Measure from the center of each triangle of `welded` to
+    /// `antagonist`, already moved into the scan's coordinates
+    pub fn new(welded: &WeldedMesh, antagonist: &Mesh, range: f32) -> Self {
+        let bvh = TriangleBvh::new(antagonist);
+
+        // Triangles are found by their centers, which are no farther than
+        // this from any of their points
+        let longest_edge = antagonist
+            .triangles()
+            .map(|[a, b, c]| {
+                let (a, b, c) = (
+                    antagonist.position(a),
+                    antagonist.position(b),
+                    antagonist.position(c),
+                );
+                (a - b).norm().max((b - c).norm()).max((c - a).norm())
+            })
+            .fold(0.0, f32::max);
+
+        let distances = welded
+            .triangles
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = welded.corners(tri);
+                let center = (a + b + c) / 3.0;
+                let mut nearest: Option<f32> = None;
+
+                bvh.triangles_near(center, range + longest_edge, |_, corners| {
+                    let closest = closest_point(center, corners);
+                    let distance = (center - closest).norm();
+
+                    if distance > range || nearest.map_or(false, |d| d.abs() <= distance) {
+                        return;
+                    }
+
+                    let [a, b, c] = corners;
+                    let outward = (b - a).cross(&(c - a));
+                    let sign = if (center - closest).dot(&outward) < 0.0 {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+
+                    nearest = Some(sign * distance);
+                });
+
+                nearest
+            })
+            .collect();
+
+        Self { distances, range }
+    }
+
+    /// Which of `bands` equal steps of distance from contact out to `range`
+    /// each triangle falls in, the first nearest. Triangles inside the
+    /// antagonist are in band `bands`, past the last
+    pub fn bands(&self, bands: usize) -> Vec<Option<usize>> {
+        self.distances
+            .iter()
+            .map(|distance| {
+                distance.map(|distance| {
+                    if distance < 0.0 {
+                        bands
+                    } else {
+                        ((distance / self.range * bands as f32) as usize).min(bands - 1)
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// The signed distance of the triangle nearest or deepest into the
+    /// antagonist
+    pub fn closest(&self) -> Option<f32> {
+        self.distances
+            .iter()
+            .flatten()
+            .copied()
+            .fold(None, |closest: Option<f32>, distance| {
+                Some(closest.map_or(distance, |closest| closest.min(distance)))
+            })
+    }
+
+    /// Number of triangles inside the antagonist
+    pub fn penetrating(&self) -> usize {
+        self.distances
+            .iter()
+            .flatten()
+            .filter(|&&distance| distance < 0.0)
+            .count()
+    }
+}
+
+/// The point of the triangle `[a, b, c]` closest to `point`, after
+/// Ericson, "Real-Time Collision Detection", 5.1.5
+pub(crate) fn closest_point(point: Vector3<f32>, [a, b, c]: [Vector3<f32>; 3]) -> Vector3<f32> {
+    let (ab, ac, ap) = (b - a, c - a, point - a);
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = point - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = va + vb + vc;
+
+    if denominator.abs() <= f32::EPSILON {
+        return a;
+    }
+
+    a + ab * (vb / denominator) + ac * (vc / denominator)
+}
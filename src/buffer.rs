@@ -1,28 +1,60 @@
 use std::mem;
 
+use bytemuck::Pod;
+
 use crate::check;
 
+/// Upload `data` to the buffer bound to `target`. Empty slices are allowed
+fn buffer_data<T: Pod>(target: u32, data: &[T], usage: u32) {
+    let bytes: &[u8] = bytemuck::cast_slice(data);
+
+    check!(unsafe {
+        gl::BufferData(
+            target,
+            bytes.len() as isize,
+            bytes.as_ptr() as *const _,
+            usage,
+        )
+    });
+}
+
+#[derive(Debug)]
 pub struct VertexBuffer {
     id: u32,
 }
 
 impl VertexBuffer {
-    pub fn new(positions: &[f32]) -> Self {
+    pub fn new<T: Pod>(data: &[T]) -> Self {
         let mut id = 0;
         unsafe {
             gl::GenBuffers(1, &mut id);
             gl::BindBuffer(gl::ARRAY_BUFFER, id);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (mem::size_of::<f32>() * positions.len()) as isize,
-                mem::transmute(&positions[0]),
-                gl::STATIC_DRAW,
-            );
         }
 
+        buffer_data(gl::ARRAY_BUFFER, data, gl::STATIC_DRAW);
+
         VertexBuffer { id }
     }
 
+    /// A buffer whose contents are expected to change often, such as
+    /// per-instance attributes
+    pub fn dynamic(data: &[f32]) -> Self {
+        let mut id = 0;
+        check!(unsafe { gl::GenBuffers(1, &mut id) });
+
+        let vb = VertexBuffer { id };
+        vb.update(data);
+
+        vb
+    }
+
+    /// Replace the contents of the buffer, which is left bound
+    pub fn update(&self, data: &[f32]) {
+        self.bind();
+
+        buffer_data(gl::ARRAY_BUFFER, data, gl::DYNAMIC_DRAW);
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindBuffer(gl::ARRAY_BUFFER, self.id);
@@ -56,14 +88,10 @@ impl IndexBuffer {
         unsafe {
             check!(gl::GenBuffers(1, &mut id));
             check!(gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, id));
-            check!(gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (mem::size_of::<u32>() * indices.len()) as isize,
-                mem::transmute(&indices[0]),
-                gl::STATIC_DRAW,
-            ));
         }
 
+        buffer_data(gl::ELEMENT_ARRAY_BUFFER, indices, gl::STATIC_DRAW);
+
         IndexBuffer {
             id,
             count: indices.len() as u32,
@@ -95,14 +123,29 @@ impl Drop for IndexBuffer {
 #[derive(Debug, Clone, Copy)]
 pub enum BufferElementType {
     Float = gl::FLOAT,
+    /// 16-bit IEEE floats
+    HalfFloat = gl::HALF_FLOAT,
     UnsignedInt = gl::UNSIGNED_INT,
+    /// Four signed components of 10, 10, 10, and 2 bits packed into one
+    /// 32-bit word, `x` in the lowest bits. Must be pushed with a count of 4
+    Int2101010Rev = gl::INT_2_10_10_10_REV,
 }
 
 impl BufferElementType {
+    /// Bytes per component. Packed types report the size of the whole word
     pub fn size_of(&self) -> usize {
         match self {
             Self::Float => mem::size_of::<f32>(),
-            Self::UnsignedInt => mem::size_of::<u32>(),
+            Self::HalfFloat => mem::size_of::<u16>(),
+            Self::UnsignedInt | Self::Int2101010Rev => mem::size_of::<u32>(),
+        }
+    }
+
+    /// Bytes taken by an attribute of `count` components of this type
+    pub fn attribute_size(&self, count: u32) -> u32 {
+        match self {
+            Self::Int2101010Rev => self.size_of() as u32,
+            _ => self.size_of() as u32 * count,
         }
     }
 }
@@ -127,7 +170,7 @@ impl VertexBufferLayout {
     }
 
     pub fn push(&mut self, ty: BufferElementType, count: u32, normalized: bool) {
-        self.stride += ty.size_of() as u32 * count;
+        self.stride += ty.attribute_size(count);
         self.elements.push(VertexBufferElement {
             count,
             ty,
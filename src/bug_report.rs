@@ -0,0 +1,87 @@
+//! Everything needed to reproduce a problem, collected into one zip archive
+//! that can be attached to an issue
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{capabilities::GlCapabilities, image::RgbaImage};
+
+#[derive(Debug, Default)]
+pub struct BugReport {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl BugReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A file name that will not collide with earlier reports, e.g.
+    /// `bug-report-1614556800.zip`
+    pub fn default_file_name() -> String {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        format!("bug-report-{}.zip", seconds)
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, contents: impl Into<Vec<u8>>) {
+        self.entries.push((name.into(), contents.into()));
+    }
+
+    /// Add the file at `path` if it can be read, or a note explaining why
+    /// it could not
+    pub fn add_file(&mut self, name: impl Into<String>, path: impl AsRef<Path>) {
+        let contents = std::fs::read(&path).unwrap_or_else(|e| {
+            format!("unable to read {}: {}", path.as_ref().display(), e).into_bytes()
+        });
+
+        self.add(name, contents);
+    }
+
+    pub fn add_image(&mut self, name: impl Into<String>, image: &RgbaImage) -> io::Result<()> {
+        let mut png = Vec::new();
+        image
+            .write_png(&mut png)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        self.add(name, png);
+
+        Ok(())
+    }
+
+    /// Add the capabilities probed from a GL context, including every extension
+    /// it reports
+    pub fn add_gl_info(&mut self, name: impl Into<String>, capabilities: &GlCapabilities) {
+        let mut info = capabilities.to_string();
+
+        for extension in &capabilities.extensions {
+            info.push_str("\n  ");
+            info.push_str(extension);
+        }
+
+        self.add(name, info);
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        for (name, contents) in &self.entries {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(contents)?;
+        }
+
+        zip.finish()?;
+
+        Ok(())
+    }
+}
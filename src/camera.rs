@@ -1,9 +1,36 @@
 use std::time::Instant;
 
+#[cfg(not(target_arch = "wasm32"))]
 use glutin::event::MouseScrollDelta;
-use nalgebra::{Matrix4, Vector3};
+use nalgebra::{Matrix4, Vector2, Vector3};
 use nalgebra_glm::vec3;
 
+use crate::{
+    mesh::BoundingBox,
+    scene::{NodeId, Scene},
+};
+
+/// Field of view, in degrees, before zooming
+const DEFAULT_FOV: f32 = 45.0;
+
+/// Multiplier for rotation and zoom steps while Shift is held
+pub const COARSE_STEP_SCALE: f32 = 10.0;
+
+/// Multiplier for rotation and zoom steps while Alt is held
+pub const FINE_STEP_SCALE: f32 = 0.1;
+
+/// How much to scale a rotation or zoom step by, given which modifiers are
+/// held. Coarse wins if both are
+pub fn step_scale(coarse: bool, fine: bool) -> f32 {
+    if coarse {
+        COARSE_STEP_SCALE
+    } else if fine {
+        FINE_STEP_SCALE
+    } else {
+        1.0
+    }
+}
+
 pub struct FlightCamera {
     pos: Vector3<f32>,
     front: Vector3<f32>,
@@ -40,7 +67,7 @@ impl FlightCamera {
             start: Instant::now(),
             yaw: -95.0,
             pitch: 4.0,
-            fov: 45.0,
+            fov: DEFAULT_FOV,
             first_mouse_movement: true,
         }
     }
@@ -75,13 +102,32 @@ impl FlightCamera {
         self.front = nalgebra_glm::normalize(&direction);
     }
 
-    pub fn scroll(&mut self, delta: MouseScrollDelta) {
-        self.fov -= match delta {
-            MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
-            MouseScrollDelta::LineDelta(_, y) => y,
-        };
+    /// Zoom by a wheel movement, scaled by `scale`, see [`step_scale`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn scroll(&mut self, delta: MouseScrollDelta, scale: f32) {
+        self.zoom(
+            scale
+                * match delta {
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                },
+        );
+    }
 
-        self.fov = self.fov.clamp(1.0, 45.0);
+    /// Set the field of view to `fov` radians, within the usual zoom limits
+    pub fn zoom_to(&mut self, fov: f32) {
+        self.fov = fov.to_degrees().clamp(1.0, DEFAULT_FOV);
+    }
+
+    /// Narrow the field of view by `delta` degrees
+    pub fn zoom(&mut self, delta: f32) {
+        self.fov -= delta;
+
+        self.fov = self.fov.clamp(1.0, DEFAULT_FOV);
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.fov = DEFAULT_FOV;
     }
 
     pub fn next_frame(&mut self) {
@@ -142,3 +188,109 @@ impl FlightCamera {
         &self.pos.data
     }
 }
+
+/// Orbits a scene node in place in front of a camera fixed relative to the
+/// scan's bounding box
+pub struct StationaryCamera {
+    speed: f32,
+    /// How far the view is shifted, in view space, as a fraction of the
+    /// distance from the camera to the scan
+    pan: Vector2<f32>,
+}
+
+impl StationaryCamera {
+    pub fn new() -> Self {
+        Self {
+            speed: 1.0_f32.to_radians(),
+            pan: Vector2::zeros(),
+        }
+    }
+
+    /// Slide the scan across the screen by `dx` and `dy`, as fractions of
+    /// the distance from the camera to the scan. Positive values move it
+    /// right and up
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.pan += Vector2::new(dx, dy);
+    }
+
+    /// Like [`StationaryCamera::pan`], but by `offset` in view-space units
+    /// for a scan bounded by `bbox`
+    pub fn pan_view_space(&mut self, bbox: BoundingBox, offset: Vector2<f32>) {
+        let distance = Self::distance(bbox);
+
+        if distance > f32::EPSILON {
+            self.pan += offset / distance;
+        }
+    }
+
+    /// Center the scan again after panning
+    pub fn reset_pan(&mut self) {
+        self.pan = Vector2::zeros();
+    }
+
+    /// The orientation a freshly loaded scan is shown in
+    pub fn default_model() -> Matrix4<f32> {
+        nalgebra_glm::rotate(
+            &nalgebra_glm::one(),
+            -55.0_f32.to_radians(),
+            &Vector3::x_axis(),
+        )
+    }
+
+    /// Rotate `node` one step, 1° times `scale`, to the left
+    pub fn left(&self, scene: &mut Scene, node: NodeId, scale: f32) {
+        scene.rotate(node, -self.speed * scale, &Vector3::y_axis());
+    }
+
+    pub fn right(&self, scene: &mut Scene, node: NodeId, scale: f32) {
+        scene.rotate(node, self.speed * scale, &Vector3::y_axis());
+    }
+
+    pub fn up(&self, scene: &mut Scene, node: NodeId, scale: f32) {
+        scene.rotate(node, -self.speed * scale, &Vector3::x_axis());
+    }
+
+    pub fn down(&self, scene: &mut Scene, node: NodeId, scale: f32) {
+        scene.rotate(node, self.speed * scale, &Vector3::x_axis());
+    }
+
+    pub fn move_mouse(&self, scene: &mut Scene, node: NodeId, x_offset: f32, y_offset: f32) {
+        scene.rotate(node, x_offset.to_radians() / 2.0, &Vector3::y_axis());
+        scene.rotate(node, -y_offset.to_radians() / 2.0, &Vector3::x_axis());
+    }
+
+    pub fn pos(&self, bbox: BoundingBox) -> [f32; 3] {
+        let dimensions = bbox.delta();
+
+        [dimensions.x * 2.0, dimensions.y * 2.0, dimensions.z * 2.0]
+    }
+
+    pub fn view(&self, bbox: BoundingBox) -> Matrix4<f32> {
+        let center = bbox.center();
+        let dimensions = bbox.delta();
+        let eye = vec3(dimensions.x, dimensions.y, dimensions.z) * 2.0;
+
+        let look_at = nalgebra_glm::look_at(
+            &eye,
+            &vec3(center.x, center.y, center.z),
+            &Vector3::new(0.0, 1.0, 0.0),
+        );
+
+        let pan = self.pan * Self::distance(bbox);
+
+        Matrix4::new_translation(&Vector3::new(pan.x, pan.y, 0.0)) * look_at
+    }
+
+    /// From the camera to the center of the scan
+    fn distance(bbox: BoundingBox) -> f32 {
+        let dimensions = bbox.delta();
+
+        (dimensions * 2.0 - bbox.center()).norm()
+    }
+}
+
+impl Default for StationaryCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
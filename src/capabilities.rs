@@ -0,0 +1,133 @@
+use std::{collections::BTreeSet, ffi::CStr, fmt};
+
+use crate::context::gl_string;
+
+/// What the current context's driver reports supporting
+///
+/// Optional rendering features should be enabled based on these probes
+/// rather than on the requested context version, since drivers are free to
+/// hand back a different version than the one asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlCapabilities {
+    pub vendor: String,
+    pub renderer: String,
+    pub version: String,
+    pub shading_language_version: String,
+    /// `(major, minor)` of the context
+    pub version_number: (i32, i32),
+    pub max_texture_size: i32,
+    /// Largest sample count usable with multisampled render targets
+    pub max_samples: i32,
+    pub extensions: BTreeSet<String>,
+}
+
+impl GlCapabilities {
+    /// Query the current context, which must have GL function pointers loaded
+    pub fn probe() -> Self {
+        let major = get_integer(gl::MAJOR_VERSION);
+        let minor = get_integer(gl::MINOR_VERSION);
+
+        let extensions = (0..get_integer(gl::NUM_EXTENSIONS).max(0) as u32)
+            .filter_map(|idx| {
+                let ptr = unsafe { gl::GetStringi(gl::EXTENSIONS, idx) };
+
+                if ptr.is_null() {
+                    return None;
+                }
+
+                Some(
+                    unsafe { CStr::from_ptr(ptr as *const _) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect();
+
+        Self {
+            vendor: gl_string(gl::VENDOR),
+            renderer: gl_string(gl::RENDERER),
+            version: gl_string(gl::VERSION),
+            shading_language_version: gl_string(gl::SHADING_LANGUAGE_VERSION),
+            version_number: (major, minor),
+            max_texture_size: get_integer(gl::MAX_TEXTURE_SIZE),
+            max_samples: get_integer(gl::MAX_SAMPLES),
+            extensions,
+        }
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    fn is_at_least(&self, major: i32, minor: i32) -> bool {
+        self.version_number >= (major, minor)
+    }
+
+    pub fn supports_uniform_buffers(&self) -> bool {
+        self.is_at_least(3, 1) || self.has_extension("GL_ARB_uniform_buffer_object")
+    }
+
+    /// `glDebugMessageCallback`, core in 4.3
+    pub fn supports_debug_output(&self) -> bool {
+        self.is_at_least(4, 3) || self.has_extension("GL_KHR_debug")
+    }
+
+    /// SSAO renders depth and normals to floating point targets and samples
+    /// a noise texture per fragment, which is too slow on most software
+    /// implementations to be worthwhile
+    pub fn supports_ssao(&self) -> bool {
+        let is_software = ["llvmpipe", "softpipe", "swrast", "GDI Generic"]
+            .iter()
+            .any(|name| self.renderer.contains(name));
+
+        !is_software && (self.is_at_least(3, 3) || self.has_extension("GL_ARB_texture_float"))
+    }
+
+    /// Fences, core in 3.2, which reading pixels back without stalling
+    /// relies on, see [`crate::PendingReadback`]
+    pub fn supports_async_readback(&self) -> bool {
+        self.is_at_least(3, 2) || self.has_extension("GL_ARB_sync")
+    }
+
+    pub fn supports_multisampling(&self, samples: i32) -> bool {
+        self.max_samples >= samples
+    }
+}
+
+impl fmt::Display for GlCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+
+        writeln!(f, "vendor: {}", self.vendor)?;
+        writeln!(f, "renderer: {}", self.renderer)?;
+        writeln!(f, "version: {}", self.version)?;
+        writeln!(f, "shading language: {}", self.shading_language_version)?;
+        writeln!(f, "max texture size: {}", self.max_texture_size)?;
+        writeln!(f, "max samples: {}", self.max_samples)?;
+        writeln!(
+            f,
+            "uniform buffers: {}",
+            yes_no(self.supports_uniform_buffers())
+        )?;
+        writeln!(f, "debug output: {}", yes_no(self.supports_debug_output()))?;
+        writeln!(f, "ssao: {}", yes_no(self.supports_ssao()))?;
+        writeln!(
+            f,
+            "async readback: {}",
+            yes_no(self.supports_async_readback())
+        )?;
+        write!(f, "extensions: {}", self.extensions.len())
+    }
+}
+
+fn get_integer(name: u32) -> i32 {
+    let mut value = 0;
+
+    unsafe { gl::GetIntegerv(name, &mut value) };
+
+    // Clear GL_INVALID_ENUM from queries an older driver does not recognize,
+    // so it is not attributed to the next checked call
+    crate::clear_error();
+
+    value
+}
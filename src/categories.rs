@@ -0,0 +1,230 @@
+//! The categories scans are labeled with, read from a TOML file such as
+//!
+//! ```toml
+//! [[category]]
+//! name = "crown-prep"
+//! key = "1"
+//! file = "labels/crown-prep"
+//! ```
+//!
+//! Without the file, scans are labeled `w`, `a`, `s`, or `d` with the keys of
+//! the same names, into files of the same names
+
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    ops::Index,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// Default location of the categories file, relative to the working
+/// directory
+pub const CATEGORIES_PATH: &str = "labels.toml";
+
+/// Keys bound to something other than labeling, which categories cannot use
+pub const RESERVED_KEYS: &str = "BCEFGHIJKLMNOPQRTUVXYZ";
+
+/// A label scans can be given
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Category {
+    /// Recorded as the label, and used for the category in reports and
+    /// under `[labeling]`, e.g. in `confirm` and `priors`
+    pub name: String,
+    /// The letter or digit that labels a scan with the category
+    pub key: char,
+    /// Where the paths of scans labeled with the category are appended.
+    /// Defaults to a file named after the category in the working directory
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+}
+
+impl Category {
+    fn new(name: &str, key: char) -> Self {
+        Self {
+            name: name.to_owned(),
+            key,
+            file: None,
+        }
+    }
+
+    pub fn file(&self) -> PathBuf {
+        self.file
+            .clone()
+            .unwrap_or_else(|| Path::new(".").join(&self.name))
+    }
+
+    /// How the category is shown while labeling: its key, followed by its
+    /// name unless that is the key anyway
+    pub fn caption(&self) -> String {
+        let key = self.key.to_ascii_uppercase();
+
+        if self.name.eq_ignore_ascii_case(&key.to_string()) {
+            key.to_string()
+        } else {
+            format!("{} {}", key, self.name)
+        }
+    }
+}
+
+/// Every category, in the order their colors are taken from the theme
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Categories {
+    #[serde(rename = "category")]
+    categories: Vec<Category>,
+}
+
+impl Default for Categories {
+    fn default() -> Self {
+        Self {
+            categories: vec![
+                Category::new("w", 'w'),
+                Category::new("a", 'a'),
+                Category::new("s", 's'),
+                Category::new("d", 'd'),
+            ],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CategoriesError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// The file was read, but its categories cannot be used as they are
+    Invalid(String),
+}
+
+impl fmt::Display for CategoriesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read categories: {}", e),
+            Self::Parse(e) => write!(f, "invalid categories: {}", e),
+            Self::Invalid(reason) => write!(f, "invalid categories: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CategoriesError {}
+
+impl Categories {
+    /// Read the categories at `path`, falling back to the defaults if the
+    /// file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CategoriesError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(CategoriesError::Io(e)),
+        };
+
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, CategoriesError> {
+        let categories: Self = toml::from_str(contents).map_err(CategoriesError::Parse)?;
+        categories.validate()?;
+
+        Ok(categories)
+    }
+
+    /// Every category needs a name and a key of its own, and a key not used
+    /// for anything else
+    fn validate(&self) -> Result<(), CategoriesError> {
+        let invalid = |reason: String| Err(CategoriesError::Invalid(reason));
+
+        if self.categories.is_empty() {
+            return invalid(String::from("no categories"));
+        }
+
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        let mut files = HashSet::new();
+
+        for category in &self.categories {
+            let key = category.key.to_ascii_uppercase();
+
+            if category.name.trim().is_empty() {
+                return invalid(String::from("a category has no name"));
+            }
+
+            if !key.is_ascii_alphanumeric() || RESERVED_KEYS.contains(key) {
+                return invalid(format!(
+                    "{} cannot be labeled with {:?}, use a digit or W, A, S, or D",
+                    category.name, category.key
+                ));
+            }
+
+            if !names.insert(category.name.as_str()) {
+                return invalid(format!("{} is listed twice", category.name));
+            }
+
+            if !keys.insert(key) {
+                return invalid(format!("{:?} labels more than one category", category.key));
+            }
+
+            if !files.insert(category.file()) {
+                return invalid(format!(
+                    "{} shares its file with another category",
+                    category.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Category> {
+        self.categories.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Category> {
+        self.categories.iter()
+    }
+
+    /// Index of the category labeled with `key`, ignoring case
+    pub fn by_key(&self, key: char) -> Option<usize> {
+        self.categories
+            .iter()
+            .position(|category| category.key.eq_ignore_ascii_case(&key))
+    }
+
+    /// The file of every category, in order
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.categories.iter().map(Category::file).collect()
+    }
+
+    /// Index of the category whose file is at `path`, with `./labels/x`
+    /// and `labels/x` the same file
+    pub fn by_file(&self, path: &Path) -> Option<usize> {
+        let path = without_current_dir(path);
+
+        self.categories
+            .iter()
+            .position(|category| without_current_dir(&category.file()) == path)
+    }
+}
+
+fn without_current_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+impl Index<usize> for Categories {
+    type Output = Category;
+
+    fn index(&self, index: usize) -> &Category {
+        &self.categories[index]
+    }
+}
@@ -0,0 +1,388 @@
+//! Command-line arguments of the labeling tool
+
+use crate::sheet::{Paper, SheetLayout};
+
+pub const USAGE: &str = "\
+usage: scan-classification [OPTIONS] [SCANS]
+       scan-classification sample [OPTIONS] TABLE
+       scan-classification sheet [OPTIONS] [LABEL FILES]
+       scan-classification embed [OPTIONS] SCANS
+
+SCANS is a directory searched recursively for scans, or a single scan
+
+options:
+    --config <PATH>    read settings from PATH instead of scan-classification.toml
+    --labels <PATH>    read the categories scans are labeled with, their keys,
+                       and their files from PATH instead of labels.toml
+    --manifest <PATH>  label the scans listed in the JSON manifest at PATH, with
+                       their render settings, instead of SCANS
+    --filter <EXPR>    only label scans whose metrics match EXPR, e.g.
+                       'triangles > 100000 && bbox.z < 40'
+    --clusters <PATH>  label a cluster at a time, from a CSV table at PATH with
+                       `path` and `cluster` columns: each cluster's first scan
+                       is labeled as usual, and Enter gives the rest its label
+    --urgent <PATH>    watch PATH for scans, one per line, to label before the
+                       rest of the queue
+    --lang <LANG>      language of the interface, e.g. `es` or `pt`
+    --link-template <URL>
+                       record a link to each labeled scan's case, e.g.
+                       'https://cases.example.com/{scan_id}', and open it
+                       with Ctrl+O
+    --csv <PATH>       append each label to the CSV file at PATH instead of the
+                       category files, one row of path, label, timestamp,
+                       annotator, and duration_ms per scan
+    --jsonl <PATH>     append every decision, labeled, invalid, or skipped, to
+                       the JSON Lines file at PATH instead of the category
+                       files, with the time, session, and scan's metrics.
+                       Cannot be used with --csv
+    --kiosk            run unattended: fullscreen, resuming after scans already
+                       labeled, retrying unreachable scans, and quitting only
+                       with Ctrl+Alt+Shift+Q
+    --tab <NAME>=<SCANS>
+                       also open the scans in SCANS as a separate queue,
+                       labeled into the directory NAME and switched to with
+                       Ctrl+Tab. May be given more than once
+    -h, --help         print this message
+
+Run `scan-classification sample --help` for how to build balanced batches,
+`scan-classification sheet --help` for how to print labeled scans, and
+`scan-classification embed --help` for how to export features to cluster";
+
+pub const SAMPLE_USAGE: &str = "\
+usage: scan-classification sample [OPTIONS] TABLE
+
+Pick up to N scans from each group of a CSV table, e.g. predictions with a
+`path` column and `predicted`, `clinic`, or `scanner` columns, and write
+them to a queue manifest for `--manifest`
+
+options:
+    --by <COLUMN>      column to group scans by (required)
+    -n, --per-group <N>
+                       scans to pick from each group (required)
+    --seed <SEED>      pick a different, but still reproducible, sample
+    -o, --output <PATH>
+                       where to write the manifest, default `queue.json`
+    -h, --help         print this message";
+
+pub const SHEET_USAGE: &str = "\
+usage: scan-classification sheet [OPTIONS] [LABEL FILES]
+
+Render a thumbnail of every scan in the label files, by default `w`, `a`,
+`s`, and `d`, and tile them onto printable pages, each label starting a new
+page
+
+options:
+    --paper <SIZE>     `letter` or `a4`, default `letter`
+    --columns <N>      thumbnails across each page, default 4
+    --dpi <DPI>        resolution pages are rendered at, default 150
+    --png              write each page to a PNG instead of one PDF
+    -o, --output <PATH>
+                       where to write the PDF, default `contact-sheet.pdf`, or
+                       the directory for PNGs, default `contact-sheets`
+    -h, --help         print this message";
+
+pub const EMBED_USAGE: &str = "\
+usage: scan-classification embed [OPTIONS] SCANS
+
+Measure every scan in SCANS as it is measured while labeling, and write its
+metrics and shape fingerprint as one row of a CSV file, for clustering the
+scans or building batches with `sample`
+
+options:
+    -o, --output <PATH>
+                       where to write the CSV, default `embeddings.csv`
+    -h, --help         print this message";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    pub queue_root: Option<String>,
+    pub config: Option<String>,
+    pub labels: Option<String>,
+    pub manifest: Option<String>,
+    pub filter: Option<String>,
+    pub clusters: Option<String>,
+    pub urgent: Option<String>,
+    pub lang: Option<String>,
+    pub link_template: Option<String>,
+    /// Labels are written to this CSV file, with their metadata, instead of
+    /// the category files
+    pub label_csv: Option<String>,
+    /// Every decision is written to this JSON Lines file, and labels are
+    /// not written to the category files
+    pub jsonl: Option<String>,
+    pub kiosk: bool,
+    /// Names and scan directories of the queues opened alongside SCANS
+    pub tabs: Vec<(String, String)>,
+    pub help: bool,
+}
+
+/// Arguments of the `sample` subcommand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleArgs {
+    pub table: Option<String>,
+    pub by: Option<String>,
+    pub per_group: Option<usize>,
+    pub seed: u64,
+    pub output: String,
+    pub help: bool,
+}
+
+impl Default for SampleArgs {
+    fn default() -> Self {
+        Self {
+            table: None,
+            by: None,
+            per_group: None,
+            seed: 0,
+            output: String::from("queue.json"),
+            help: false,
+        }
+    }
+}
+
+/// Arguments of the `sheet` subcommand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetArgs {
+    /// Empty for the label files of the working directory
+    pub label_files: Vec<String>,
+    pub layout: SheetLayout,
+    pub png: bool,
+    pub output: Option<String>,
+    pub help: bool,
+}
+
+impl Default for SheetArgs {
+    fn default() -> Self {
+        Self {
+            label_files: Vec::new(),
+            layout: SheetLayout::default(),
+            png: false,
+            output: None,
+            help: false,
+        }
+    }
+}
+
+/// Arguments of the `embed` subcommand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedArgs {
+    pub queue_root: Option<String>,
+    pub output: String,
+    pub help: bool,
+}
+
+impl Default for EmbedArgs {
+    fn default() -> Self {
+        Self {
+            queue_root: None,
+            output: String::from("embeddings.csv"),
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    /// Parse arguments, not including the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = split_flag(&arg);
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--config" => parsed.config = Some(value()?),
+                "--labels" => parsed.labels = Some(value()?),
+                "--manifest" => parsed.manifest = Some(value()?),
+                "--filter" => parsed.filter = Some(value()?),
+                "--clusters" => parsed.clusters = Some(value()?),
+                "--urgent" => parsed.urgent = Some(value()?),
+                "--lang" => parsed.lang = Some(value()?),
+                "--link-template" => parsed.link_template = Some(value()?),
+                "--csv" => parsed.label_csv = Some(value()?),
+                "--jsonl" => parsed.jsonl = Some(value()?),
+                "--kiosk" => parsed.kiosk = true,
+                "--tab" => {
+                    let tab = value()?;
+                    let (name, root) = match tab.find('=') {
+                        Some(idx) if idx > 0 => (&tab[..idx], &tab[idx + 1..]),
+                        _ => return Err(format!("expected `--tab NAME=SCANS`, got `{}`", tab)),
+                    };
+
+                    parsed.tabs.push((name.to_owned(), root.to_owned()));
+                }
+                _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+                _ if parsed.queue_root.is_none() => parsed.queue_root = Some(arg),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+
+        if parsed.label_csv.is_some() && parsed.jsonl.is_some() {
+            return Err(String::from(
+                "`--csv` and `--jsonl` cannot be used together",
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl SampleArgs {
+    /// Parse arguments following `sample`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = split_flag(&arg);
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--by" => parsed.by = Some(value()?),
+                "-n" | "--per-group" => {
+                    let n = value()?;
+                    parsed.per_group = Some(
+                        n.parse()
+                            .map_err(|_| format!("invalid number of scans `{}`", n))?,
+                    );
+                }
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = seed
+                        .parse()
+                        .map_err(|_| format!("invalid seed `{}`", seed))?;
+                }
+                "-o" | "--output" => parsed.output = value()?,
+                _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+                _ if parsed.table.is_none() => parsed.table = Some(arg),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+
+        if !parsed.help {
+            if parsed.table.is_none() {
+                return Err(String::from("missing TABLE"));
+            }
+
+            if parsed.by.is_none() {
+                return Err(String::from("missing `--by`"));
+            }
+
+            if parsed.per_group.is_none() {
+                return Err(String::from("missing `--per-group`"));
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl SheetArgs {
+    /// Parse arguments following `sheet`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = split_flag(&arg);
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--paper" => {
+                    let paper = value()?;
+                    parsed.layout.paper = Paper::parse(&paper)
+                        .ok_or_else(|| format!("unknown paper size `{}`", paper))?;
+                }
+                "--columns" => {
+                    let columns = value()?;
+                    parsed.layout.columns = columns
+                        .parse()
+                        .ok()
+                        .filter(|&columns| columns > 0)
+                        .ok_or_else(|| format!("invalid number of columns `{}`", columns))?;
+                }
+                "--dpi" => {
+                    let dpi = value()?;
+                    parsed.layout.dpi = dpi
+                        .parse()
+                        .ok()
+                        .filter(|dpi| (36..=600).contains(dpi))
+                        .ok_or_else(|| {
+                            format!("invalid resolution `{}`, expected 36 to 600", dpi)
+                        })?;
+                }
+                "--png" => parsed.png = true,
+                "-o" | "--output" => parsed.output = Some(value()?),
+                _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+                _ => parsed.label_files.push(arg),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl EmbedArgs {
+    /// Parse arguments following `embed`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = split_flag(&arg);
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "-o" | "--output" => parsed.output = value()?,
+                _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+                _ if parsed.queue_root.is_none() => parsed.queue_root = Some(arg),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+
+        if !parsed.help && parsed.queue_root.is_none() {
+            return Err(String::from("missing SCANS"));
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Split `--flag=value` into its flag and value
+fn split_flag(arg: &str) -> (String, Option<String>) {
+    match arg.find('=') {
+        Some(idx) if arg.starts_with("--") => {
+            (arg[..idx].to_owned(), Some(arg[idx + 1..].to_owned()))
+        }
+        _ => (arg.to_owned(), None),
+    }
+}
@@ -0,0 +1,74 @@
+//! Clusters of similar scans, e.g. from clustering the output of `embed`,
+//! for labeling a cluster by example
+//!
+//! The first scan of each cluster is its exemplar and is labeled as usual.
+//! The rest follow right after it, each suggesting the exemplar's label so
+//! homogeneous clusters take a keypress a scan to confirm
+
+use std::{collections::HashMap, path::Path};
+
+use crate::sample::{SampleError, Table};
+
+/// Which cluster each scan is in, read from a CSV table with `path` and
+/// `cluster` columns
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterAssignment {
+    /// Each scan's cluster and row in the table
+    by_path: HashMap<String, (String, usize)>,
+    /// Each cluster's position among the clusters, by its first row
+    rank: HashMap<String, usize>,
+}
+
+/// The label a scan's cluster suggests for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub cluster: String,
+    /// The scan of the cluster labeled first
+    pub exemplar: String,
+    pub label: String,
+}
+
+impl ClusterAssignment {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SampleError> {
+        Self::from_table(&Table::read(path)?)
+    }
+
+    pub fn from_table(table: &Table) -> Result<Self, SampleError> {
+        let path_column = table.column("path")?;
+        let cluster_column = table.column("cluster")?;
+        let mut assignment = Self::default();
+
+        for (row, fields) in table.rows.iter().enumerate() {
+            let (path, cluster) = match (fields.get(path_column), fields.get(cluster_column)) {
+                (Some(path), Some(cluster)) => (path, cluster),
+                _ => continue,
+            };
+
+            let clusters = assignment.rank.len();
+            assignment.rank.entry(cluster.clone()).or_insert(clusters);
+            assignment
+                .by_path
+                .entry(path.clone())
+                .or_insert_with(|| (cluster.clone(), row));
+        }
+
+        Ok(assignment)
+    }
+
+    pub fn cluster(&self, path: &str) -> Option<&str> {
+        self.by_path.get(path).map(|(cluster, _)| cluster.as_str())
+    }
+
+    /// `queue` with the scans of each cluster together, clusters in the
+    /// order they are first listed and their scans in the order listed, so
+    /// the first listed is the exemplar. Scans in no cluster follow in the
+    /// order of `queue`
+    pub fn order(&self, mut queue: Vec<String>) -> Vec<String> {
+        queue.sort_by_key(|path| match self.by_path.get(path) {
+            Some((cluster, row)) => (self.rank[cluster], *row),
+            None => (usize::MAX, 0),
+        });
+
+        queue
+    }
+}
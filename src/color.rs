@@ -0,0 +1,83 @@
+/// A color with components in `[0, 1]`
+///
+/// Colors are treated as sRGB-encoded unless noted otherwise, since that is
+/// what the default framebuffer displays. Convert to linear with
+/// [`Color::to_linear`] before blending or interpolating
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0);
+
+    pub const fn new(red: f32, green: f32, blue: f32) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Parse a `0xRRGGBB` sRGB color
+    pub fn from_hex(hex: u32) -> Self {
+        Self {
+            red: ((hex >> 16) & 0xff) as f32 / 255.0,
+            green: ((hex >> 8) & 0xff) as f32 / 255.0,
+            blue: (hex & 0xff) as f32 / 255.0,
+        }
+    }
+
+    pub fn to_linear(self) -> Self {
+        Self {
+            red: srgb_to_linear(self.red),
+            green: srgb_to_linear(self.green),
+            blue: srgb_to_linear(self.blue),
+        }
+    }
+
+    pub fn to_srgb(self) -> Self {
+        Self {
+            red: linear_to_srgb(self.red),
+            green: linear_to_srgb(self.green),
+            blue: linear_to_srgb(self.blue),
+        }
+    }
+
+    /// Interpolate between two sRGB colors in linear space, `t` in `[0, 1]`
+    pub fn mix(self, other: Self, t: f32) -> Self {
+        let a = self.to_linear();
+        let b = other.to_linear();
+
+        Self {
+            red: a.red + (b.red - a.red) * t,
+            green: a.green + (b.green - a.green) * t,
+            blue: a.blue + (b.blue - a.blue) * t,
+        }
+        .to_srgb()
+    }
+
+    pub fn to_rgba8(self) -> [u8; 4] {
+        [
+            (self.red.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (self.green.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (self.blue.clamp(0.0, 1.0) * 255.0).round() as u8,
+            255,
+        ]
+    }
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
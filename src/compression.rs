@@ -0,0 +1,66 @@
+//! Reading scans stored compressed, e.g. `scan.stl.gz` or `scan.stl.zst`
+//!
+//! Compression is told from the magic bytes at the start of the stream, not
+//! the extension, and the scan is decompressed as it is read, so archives do
+//! not have to be unpacked before labeling. Anything else is read as is
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use flate2::read::MultiGzDecoder;
+use ruzstd::StreamingDecoder;
+
+/// Extensions compressed scans carry after their format's, ignored when
+/// guessing the format from a file name
+pub const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "zst"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression `header`, the start of a file, was written with, if
+    /// any
+    pub fn sniff(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1F, 0x8B]) {
+            Some(Self::Gzip)
+        } else if header.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// `name` without a trailing compressed extension, so `scan.stl.gz` is
+/// `scan.stl`
+pub fn strip_compressed_extension(name: &str) -> &str {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|extension| {
+            let stem = name.len().checked_sub(extension.len() + 1)?;
+            let suffix = name.get(stem..)?;
+
+            if suffix.starts_with('.') && suffix[1..].eq_ignore_ascii_case(extension) {
+                Some(&name[..stem])
+            } else {
+                None
+            }
+        })
+        .unwrap_or(name)
+}
+
+/// Read all of `reader` onto the end of `contents`, decompressing it first
+/// if it is compressed. Returns the number of bytes added
+pub fn read_decompressed(reader: impl Read, contents: &mut Vec<u8>) -> io::Result<usize> {
+    let mut reader = BufReader::new(reader);
+
+    match Compression::sniff(reader.fill_buf()?) {
+        Some(Compression::Gzip) => MultiGzDecoder::new(reader).read_to_end(contents),
+        Some(Compression::Zstd) => StreamingDecoder::new(&mut reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .read_to_end(contents),
+        None => reader.read_to_end(contents),
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    selection::{MaskFormat, MaskIndexing},
+    theme::Theme,
+    vertex_format::{NormalFormat, PositionFormat, VertexFormat},
+};
+
+/// Default location of the config file, relative to the working directory
+pub const CONFIG_PATH: &str = "scan-classification.toml";
+
+/// User settings, read from a TOML file. Every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub theme: Theme,
+    pub analysis: AnalysisConfig,
+    pub display: DisplayConfig,
+    pub cache: CacheConfig,
+    pub loading: LoadingConfig,
+    pub labeling: LabelingConfig,
+    pub masks: MaskConfig,
+    pub export: ExportConfig,
+    pub notifications: NotificationConfig,
+    pub monitoring: MonitoringConfig,
+    pub reference: ReferenceConfig,
+    pub voxels: VoxelConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    /// Shells with fewer than this fraction of a scan's triangles are
+    /// highlighted as debris
+    pub small_shell_fraction: f32,
+    /// Remove zero-area and duplicated triangles when a scan is loaded
+    pub strip_redundant_triangles: bool,
+    /// Flip triangles wound against their neighbors when a scan is loaded,
+    /// so lighting reflects the actual surface
+    pub repair_normals: bool,
+    /// Distance in scan units, usually millimeters, out to which the gap to
+    /// a scan's antagonist is colored in the bite view
+    pub bite_range: f32,
+    /// Cells along the longest side of a scan for the signed distance field
+    /// sliced with Z
+    pub distance_resolution: usize,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            small_shell_fraction: 0.01,
+            strip_redundant_triangles: true,
+            repair_normals: false,
+            bite_range: 1.0,
+            distance_resolution: 48,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Synchronize buffer swaps with the monitor's refresh rate. Some virtual
+    /// machines block for far too long on each swap with this on
+    pub vsync: bool,
+    /// Upper bound on frames drawn per second, unlimited if unset
+    pub max_fps: Option<u32>,
+    /// Only redraw in response to input or while an animation is running,
+    /// rather than continuously
+    pub power_saving: bool,
+    /// How scan positions are stored on the GPU. `"half"` saves memory on
+    /// very large scans
+    pub position_format: PositionFormat,
+    /// How scan normals are stored on the GPU
+    pub normal_format: NormalFormat,
+    /// Shade the patch of the scan under the cursor and draw a crosshair
+    /// over it
+    pub hover_highlight: bool,
+    /// Scans with more than twice this many triangles are drawn as a
+    /// decimated proxy of about this many while the view moves. 0 to always
+    /// draw the full scan
+    pub lod_triangles: usize,
+    /// How long the view must hold still before the full scan is drawn again
+    pub lod_settle_ms: u64,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            max_fps: None,
+            power_saving: false,
+            position_format: PositionFormat::default(),
+            normal_format: NormalFormat::default(),
+            hover_highlight: true,
+            lod_triangles: 250_000,
+            lod_settle_ms: 200,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Minimum time between frames, if `max_fps` is set
+    pub fn frame_interval(&self) -> Option<Duration> {
+        self.max_fps
+            .filter(|&fps| fps > 0)
+            .map(|fps| Duration::from_secs(1) / fps)
+    }
+
+    /// Size of the proxy drawn while the view moves, if enabled
+    pub fn lod_target(&self) -> Option<usize> {
+        Some(self.lod_triangles).filter(|&triangles| triangles > 0)
+    }
+
+    pub fn lod_settle(&self) -> Duration {
+        Duration::from_millis(self.lod_settle_ms)
+    }
+
+    pub fn vertex_format(&self) -> VertexFormat {
+        VertexFormat {
+            positions: self.position_format,
+            normals: self.normal_format,
+        }
+    }
+}
+
+/// Where regions painted with the brush are saved when a scan is labeled
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaskConfig {
+    /// Created if missing. Each scan's mask is named after its path
+    pub directory: PathBuf,
+    pub indexing: MaskIndexing,
+    pub format: MaskFormat,
+}
+
+impl Default for MaskConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("masks"),
+            indexing: MaskIndexing::default(),
+            format: MaskFormat::default(),
+        }
+    }
+}
+
+impl MaskConfig {
+    /// Where the mask of the scan at `scan` is saved
+    pub fn path(&self, scan: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}.{}", file_name(scan), self.format.extension()))
+    }
+}
+
+/// Where images of the view exported with Ctrl+E are saved
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    /// Created if missing
+    pub directory: PathBuf,
+    /// Draw the scan, label, annotator, and time into a band along the
+    /// bottom of each image. They are stored as PNG text chunks either way
+    pub stamp: bool,
+    /// Recorded in exported images. Defaults to the `USER` or `USERNAME`
+    /// environment variable
+    pub annotator: Option<String>,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("exports"),
+            stamp: true,
+            annotator: None,
+        }
+    }
+}
+
+impl ExportConfig {
+    /// Where an image of the scan at `scan` exported at `unix_seconds` is
+    /// saved
+    pub fn path(&self, scan: &str, unix_seconds: u64) -> PathBuf {
+        self.directory
+            .join(format!("{}-{}.png", file_name(scan), unix_seconds))
+    }
+
+    pub fn annotator(&self) -> Option<String> {
+        self.annotator
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .filter(|annotator| !annotator.is_empty())
+    }
+}
+
+/// Where and when leads are notified about a session
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    /// Notifications are posted here as JSON, e.g. to a Slack incoming
+    /// webhook. Disabled if unset
+    pub webhook_url: Option<String>,
+    /// Body of each post, with `{event}`, `{message}`, and `{annotator}`
+    /// replaced. Values are escaped for use inside JSON strings
+    pub payload_template: String,
+    /// Notify when at least this share of the last `error_window` scans
+    /// opened failed to load
+    pub error_rate: f64,
+    pub error_window: usize,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            payload_template: String::from(r#"{"text": "{message}"}"#),
+            error_rate: 0.25,
+            error_window: 20,
+        }
+    }
+}
+
+/// Metrics for monitoring stations that label all day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitoringConfig {
+    /// Serve Prometheus metrics from `/metrics` at this address, e.g.
+    /// `"127.0.0.1:9464"`. Disabled if unset
+    pub metrics_address: Option<String>,
+}
+
+/// A template, such as an ideal arch, drawn over scans to compare them with
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReferenceConfig {
+    /// The template scan, in any format scans are read in. V does nothing if
+    /// unset
+    pub path: Option<String>,
+    /// Opacity the template is drawn at
+    pub alpha: f32,
+}
+
+impl Default for ReferenceConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            alpha: 0.35,
+        }
+    }
+}
+
+/// Showing scans as blocks with J, and saving their occupancy grids
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VoxelConfig {
+    /// Voxels along the longest side of each scan's bounding box
+    pub resolution: usize,
+    /// Save the occupancy grid of every scan labeled as a NumPy array
+    pub export: bool,
+    /// Where grids are saved. Created if missing
+    pub directory: PathBuf,
+}
+
+impl Default for VoxelConfig {
+    fn default() -> Self {
+        Self {
+            resolution: 64,
+            export: false,
+            directory: PathBuf::from("voxels"),
+        }
+    }
+}
+
+impl VoxelConfig {
+    /// Where the occupancy grid of the scan at `scan` is saved
+    pub fn path(&self, scan: &str) -> PathBuf {
+        self.directory.join(format!("{}.npy", file_name(scan)))
+    }
+}
+
+/// A file name derived from a scan's path. Separators are replaced so scans
+/// with the same file name in different directories do not collide
+fn file_name(scan: &str) -> String {
+    scan.chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '!' => '_',
+            ch => ch,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Where parsed meshes are stored, so scans opened before are not parsed
+    /// again. Disabled if unset
+    pub directory: Option<PathBuf>,
+    /// Oldest entries are deleted once the cache grows past this many
+    /// megabytes. Unlimited if unset
+    pub max_size_mb: Option<u64>,
+}
+
+impl CacheConfig {
+    pub fn max_bytes(&self) -> Option<u64> {
+        self.max_size_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadingConfig {
+    /// Seconds to wait for a scan to load before offering to retry or skip
+    /// it, e.g. on a slow network mount
+    pub timeout_secs: f32,
+    /// How many scans after the current one are loaded in the background.
+    /// With 0, each scan is only loaded once the previous one is labeled
+    pub prefetch_ahead: usize,
+    /// Upper bound on how fast scans are read, so prefetching does not
+    /// saturate a slow link. Unlimited if unset
+    pub max_kb_per_sec: Option<u64>,
+}
+
+impl Default for LoadingConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10.0,
+            prefetch_ahead: 1,
+            max_kb_per_sec: None,
+        }
+    }
+}
+
+impl LoadingConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs_f32(self.timeout_secs.max(0.0))
+    }
+
+    pub fn max_bytes_per_sec(&self) -> Option<u64> {
+        self.max_kb_per_sec.map(|kb| kb.saturating_mul(1024))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LabelingConfig {
+    /// Seconds a labeled scan stays on screen, showing its category, before
+    /// the label is written and the next scan shown. During the delay Esc
+    /// cancels the label. Labels are committed immediately if 0
+    pub commit_delay_secs: f32,
+    /// Categories, by the name of their label file, e.g. `"d"`, that must be
+    /// confirmed with Y before a scan is labeled with them
+    pub confirm: Vec<String>,
+    /// Expected share of scans in each category, by the name of its label
+    /// file, in `[0, 1]`. Categories left out are not checked
+    pub priors: BTreeMap<String, f64>,
+    /// How far a category's share of this session's labels may drift from
+    /// its prior before the title bar warns about it
+    pub prior_tolerance: f64,
+    /// Scans labeled this session before shares are compared to priors
+    pub prior_min_labels: usize,
+    /// Warn when the last `pace_window` labels took less than this many
+    /// seconds each on average, too fast to have looked at the scans.
+    /// Disabled if 0
+    pub min_secs_per_label: f32,
+    pub pace_window: usize,
+    /// Ask for confirmation, as for the categories in `confirm`, while
+    /// labeling too fast
+    pub confirm_when_fast: bool,
+    /// Show a guess of whether the scan is of the upper or lower arch at
+    /// the top of the window
+    pub arch_hint: bool,
+    /// Show an estimate of how many teeth the scan shows below the arch
+    /// hint, in the warning color if fewer than `partial_arch_teeth`
+    pub tooth_count: bool,
+    pub partial_arch_teeth: usize,
+    /// Record scans passed over with Space in `./skipped`, to be looked at
+    /// again later
+    pub record_skipped: bool,
+    /// Warn in the title bar when a scan's shape is within this distance of
+    /// a scan labeled before, see [`ShapeFingerprint::distance`]. Disabled
+    /// if 0
+    ///
+    /// [`ShapeFingerprint::distance`]: crate::ShapeFingerprint::distance
+    pub near_duplicate_distance: f32,
+}
+
+impl Default for LabelingConfig {
+    fn default() -> Self {
+        Self {
+            commit_delay_secs: 0.0,
+            confirm: Vec::new(),
+            priors: BTreeMap::new(),
+            prior_tolerance: 0.15,
+            prior_min_labels: 20,
+            min_secs_per_label: 0.0,
+            pace_window: 5,
+            confirm_when_fast: false,
+            arch_hint: true,
+            tooth_count: true,
+            partial_arch_teeth: 10,
+            record_skipped: true,
+            near_duplicate_distance: 0.03,
+        }
+    }
+}
+
+impl LabelingConfig {
+    pub fn commit_delay(&self) -> Duration {
+        Duration::from_secs_f32(self.commit_delay_secs.max(0.0))
+    }
+
+    pub fn min_time_per_label(&self) -> Duration {
+        Duration::from_secs_f32(self.min_secs_per_label.max(0.0))
+    }
+
+    pub fn requires_confirmation(&self, category: &str) -> bool {
+        self.confirm
+            .iter()
+            .any(|confirm| confirm.eq_ignore_ascii_case(category))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read config: {}", e),
+            Self::Parse(e) => write!(f, "invalid config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read the config at `path`, falling back to defaults if the file does
+    /// not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Io(e)),
+        };
+
+        toml::from_str(&contents).map_err(ConfigError::Parse)
+    }
+}
@@ -0,0 +1,212 @@
+use std::{ffi::CStr, fmt, os::raw::c_void};
+
+use glutin::{
+    dpi::PhysicalSize,
+    event_loop::EventLoop,
+    window::{Window, WindowBuilder},
+    Context, ContextBuilder, ContextWrapper, CreationError, GlProfile, GlRequest, NotCurrent,
+    PossiblyCurrent,
+};
+
+/// A set of context parameters to try when creating the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextAttempt {
+    pub version: (u8, u8),
+    pub multisampling: u16,
+    pub vsync: bool,
+}
+
+impl fmt::Display for ContextAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OpenGL {}.{} core", self.version.0, self.version.1)?;
+
+        if self.multisampling > 0 {
+            write!(f, ", {}x MSAA", self.multisampling)?;
+        } else {
+            write!(f, ", no MSAA")?;
+        }
+
+        if self.vsync {
+            write!(f, ", vsync")
+        } else {
+            write!(f, ", no vsync")
+        }
+    }
+}
+
+/// Attempts are made in order, from most to least capable. The shaders only
+/// require OpenGL 3.3
+pub const CONTEXT_LADDER: &[ContextAttempt] = &[
+    ContextAttempt {
+        version: (4, 5),
+        multisampling: 4,
+        vsync: true,
+    },
+    ContextAttempt {
+        version: (3, 3),
+        multisampling: 4,
+        vsync: true,
+    },
+    ContextAttempt {
+        version: (3, 3),
+        multisampling: 0,
+        vsync: true,
+    },
+    ContextAttempt {
+        version: (3, 3),
+        multisampling: 0,
+        vsync: false,
+    },
+];
+
+/// Every attempt failed; holds the reason each one was rejected
+#[derive(Debug)]
+pub struct ContextFailure {
+    pub attempts: Vec<(ContextAttempt, String)>,
+}
+
+impl fmt::Display for ContextFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Unable to create an OpenGL window. Tried:")?;
+
+        for (attempt, reason) in &self.attempts {
+            writeln!(f, "  - {}: {}", attempt, reason)?;
+        }
+
+        write!(
+            f,
+            "Updating your graphics driver usually fixes this. Remote desktop sessions and \
+             virtual machines often lack OpenGL 3.3 support entirely."
+        )
+    }
+}
+
+impl std::error::Error for ContextFailure {}
+
+/// Create a window with the most capable context from [`CONTEXT_LADDER`] the
+/// driver supports, make it current, and load GL function pointers
+///
+/// If `vsync` is false, every attempt is made without vsync
+pub fn create_windowed_context(
+    window: impl Fn() -> WindowBuilder,
+    event_loop: &EventLoop<()>,
+    vsync: bool,
+) -> Result<(ContextWrapper<PossiblyCurrent, Window>, ContextAttempt), ContextFailure> {
+    let mut failure = ContextFailure {
+        attempts: Vec::new(),
+    };
+
+    for attempt in CONTEXT_LADDER {
+        let attempt = ContextAttempt {
+            vsync: attempt.vsync && vsync,
+            ..*attempt
+        };
+
+        if failure.attempts.iter().any(|(tried, _)| *tried == attempt) {
+            continue;
+        }
+        let gl_window = ContextBuilder::new()
+            .with_gl(GlRequest::Specific(glutin::Api::OpenGl, attempt.version))
+            .with_gl_profile(GlProfile::Core)
+            .with_vsync(attempt.vsync)
+            .with_multisampling(attempt.multisampling)
+            .build_windowed(window(), event_loop);
+
+        let gl_window = match gl_window {
+            Ok(gl_window) => gl_window,
+            Err(e) => {
+                failure.attempts.push((attempt, e.to_string()));
+                continue;
+            }
+        };
+
+        let gl_window = match unsafe { gl_window.make_current() } {
+            Ok(gl_window) => gl_window,
+            Err((_, e)) => {
+                failure.attempts.push((attempt, e.to_string()));
+                continue;
+            }
+        };
+
+        gl::load_with(|symbol| gl_window.get_proc_address(symbol));
+
+        return Ok((gl_window, attempt));
+    }
+
+    Err(failure)
+}
+
+/// Create a headless context sharing buffers with `window`'s, so scans can be
+/// uploaded on another thread while the window keeps drawing
+///
+/// GL function pointers are loaded once, from the window's context, and are
+/// assumed to be valid on this one too, as they are for contexts created by
+/// the same driver. Some platforms cannot share objects between windowed and
+/// headless contexts, in which case uploads must stay on the window's thread
+pub fn create_shared_context(
+    window: &ContextWrapper<PossiblyCurrent, Window>,
+    event_loop: &EventLoop<()>,
+    attempt: ContextAttempt,
+) -> Result<Context<NotCurrent>, CreationError> {
+    ContextBuilder::new()
+        .with_gl(GlRequest::Specific(glutin::Api::OpenGl, attempt.version))
+        .with_gl_profile(GlProfile::Core)
+        .with_shared_lists(window.context())
+        .build_headless(event_loop, PhysicalSize::new(1, 1))
+}
+
+/// Query a string such as `GL_VENDOR` from the current context
+pub fn gl_string(name: u32) -> String {
+    let ptr = unsafe { gl::GetString(name) };
+
+    if ptr.is_null() {
+        return String::from("unknown");
+    }
+
+    unsafe { CStr::from_ptr(ptr as *const _) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Print driver messages about errors and undefined behavior as they happen,
+/// rather than relying on `glGetError` after each call
+///
+/// Only call this if [`GlCapabilities::supports_debug_output`] is true
+///
+/// [`GlCapabilities::supports_debug_output`]: crate::GlCapabilities::supports_debug_output
+pub fn enable_debug_output() {
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(Some(debug_callback), std::ptr::null());
+        gl::DebugMessageControl(
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            gl::DEBUG_SEVERITY_NOTIFICATION,
+            0,
+            std::ptr::null(),
+            gl::FALSE,
+        );
+    }
+}
+
+extern "system" fn debug_callback(
+    _source: u32,
+    _kind: u32,
+    _id: u32,
+    severity: u32,
+    _length: i32,
+    message: *const gl::types::GLchar,
+    _user_param: *mut c_void,
+) {
+    let severity = match severity {
+        gl::DEBUG_SEVERITY_HIGH => "high",
+        gl::DEBUG_SEVERITY_MEDIUM => "medium",
+        gl::DEBUG_SEVERITY_LOW => "low",
+        _ => "info",
+    };
+
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+
+    eprintln!("OpenGL ({}): {}", severity, message);
+}
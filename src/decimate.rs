@@ -0,0 +1,107 @@
+//! Coarse stand-ins for very large scans
+//!
+//! A scan of several million triangles rotates sluggishly on modest GPUs.
+//! While the view is moving, detail is lost to motion anyway, so a proxy of
+//! a few hundred thousand triangles is drawn instead and the full scan is
+//! swapped back in once the view settles
+
+use std::collections::{HashMap, HashSet};
+
+use nalgebra::Vector3;
+
+use crate::mesh::Mesh;
+
+/// A copy of `mesh` with about `target_triangles` triangles, made by merging
+/// the vertices in each cell of a uniform grid. Fast and robust to the
+/// defects scans have, at the cost of rounding off small features. Meshes
+/// already small enough are copied unchanged
+pub fn decimate(mesh: &Mesh, target_triangles: usize) -> Mesh {
+    if mesh.triangle_count() <= target_triangles || target_triangles == 0 {
+        return mesh.clone();
+    }
+
+    let area: f32 = mesh
+        .triangles()
+        .map(|[a, b, c]| {
+            let (a, b, c) = (mesh.position(a), mesh.position(b), mesh.position(c));
+            (b - a).cross(&(c - a)).norm() / 2.0
+        })
+        .sum();
+
+    // A surface tiled by square cells has about two triangles per cell
+    let cell = (2.0 * area / target_triangles as f32).sqrt();
+
+    if !cell.is_finite() || cell <= 0.0 {
+        return mesh.clone();
+    }
+
+    let origin = mesh.bounding_box().min;
+    let mut cells: HashMap<[i32; 3], u32> = HashMap::new();
+    let mut sums: Vec<(Vector3<f32>, u32)> = Vec::new();
+
+    let remap: Vec<u32> = (0..mesh.vertex_count() as u32)
+        .map(|idx| {
+            let position = mesh.position(idx);
+            let key = ((position - origin) / cell).map(|c| c.floor() as i32);
+
+            let cluster = *cells.entry([key.x, key.y, key.z]).or_insert_with(|| {
+                sums.push((Vector3::zeros(), 0));
+                sums.len() as u32 - 1
+            });
+
+            let sum = &mut sums[cluster as usize];
+            sum.0 += position;
+            sum.1 += 1;
+
+            cluster
+        })
+        .collect();
+
+    let positions: Vec<Vector3<f32>> = sums
+        .into_iter()
+        .map(|(sum, count)| sum / count as f32)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for [a, b, c] in mesh.triangles() {
+        let tri = [remap[a as usize], remap[b as usize], remap[c as usize]];
+
+        if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+            continue;
+        }
+
+        // Both windings of a collapsed sliver land in the same cells
+        let mut key = tri;
+        key.sort_unstable();
+
+        if !seen.insert(key) {
+            continue;
+        }
+
+        let corners = [
+            positions[tri[0] as usize],
+            positions[tri[1] as usize],
+            positions[tri[2] as usize],
+        ];
+
+        let normal = match (corners[1] - corners[0])
+            .cross(&(corners[2] - corners[0]))
+            .try_normalize(f32::EPSILON)
+        {
+            Some(normal) => normal,
+            None => continue,
+        };
+
+        // Flat shaded, like the STL scans it stands in for
+        for corner in &corners {
+            indices.push(indices.len() as u32);
+            vertices.extend_from_slice(corner.as_slice());
+            vertices.extend_from_slice(normal.as_slice());
+        }
+    }
+
+    Mesh::new(vertices, indices)
+}
@@ -0,0 +1,100 @@
+//! Decisions about scans as JSON Lines, one object per line, for ingesting
+//! labels into a data pipeline without parsing the label files
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    analysis::MeshMetrics,
+    labels::{fnv1a, LabelRecord},
+    session::Record,
+    stamp::format_utc,
+};
+
+/// What was decided about one scan
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decision<'a> {
+    pub path: &'a str,
+    /// `labeled`, `invalid`, or `skipped`
+    pub decision: &'static str,
+    /// The category's name, for scans labeled
+    pub label: Option<&'a str>,
+    /// When the decision was made, as an ISO 8601 UTC time
+    pub timestamp: String,
+    /// The same for every decision of one run of the tool
+    pub session_id: &'a str,
+    /// Of the scan as loaded, if it could be read
+    pub metrics: Option<&'a MeshMetrics>,
+}
+
+impl<'a> Decision<'a> {
+    pub fn new(
+        record: &'a Record,
+        session_id: &'a str,
+        unix_seconds: u64,
+        metrics: Option<&'a MeshMetrics>,
+    ) -> Self {
+        let (decision, label) = match record {
+            Record::Labeled { label, .. } => ("labeled", Some(label.as_str())),
+            Record::Invalid { .. } => ("invalid", None),
+            Record::Skipped { .. } => ("skipped", None),
+        };
+
+        Self {
+            path: record.path(),
+            decision,
+            label,
+            timestamp: format_utc(unix_seconds),
+            session_id,
+            metrics,
+        }
+    }
+
+    /// Write the decision as one line of JSON
+    pub fn write_line(&self, mut writer: impl Write) -> io::Result<()> {
+        serde_json::to_writer(&mut writer, self)?;
+        writer.write_all(b"\n")
+    }
+}
+
+/// The scans labeled in the JSON Lines file at `path`, leaving out those
+/// recorded as invalid or skipped and lines that cannot be read
+pub fn parse_decisions(path: impl AsRef<Path>) -> io::Result<Vec<LabelRecord>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let decision: Value = serde_json::from_str(line).ok()?;
+
+            if decision["decision"] != "labeled" {
+                return None;
+            }
+
+            Some(LabelRecord {
+                path: decision["path"].as_str()?.to_owned(),
+                label: decision["label"].as_str()?.to_owned(),
+            })
+        })
+        .collect())
+}
+
+/// An identifier for this run of the tool, from the time it started and
+/// its process ID, so decisions from one session can be grouped
+pub fn new_session_id() -> String {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+
+    format!(
+        "{:016x}",
+        fnv1a(format!("{}-{}", started, process::id()).as_bytes())
+    )
+}
@@ -0,0 +1,94 @@
+//! How far each vertex of a scan lies from its occlusal plane
+//!
+//! A complete, level arch stays close to the plane fitted through it. Large
+//! deviations on one side point to a tilted capture or a partially scanned
+//! arch, which is one of the categories scans are sorted into
+
+use nalgebra::Vector3;
+
+use crate::{
+    analysis::WeldedMesh,
+    fitting::{Plane, Residuals},
+};
+
+/// Fraction of vertices whose distance fits within [`PlaneDeviation::range`],
+/// so a few stray points do not wash out the heatmap
+const RANGE_PERCENTILE: f32 = 0.95;
+
+/// Signed distances from the plane fitted to a scan's vertices
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaneDeviation {
+    /// Its normal points toward the occlusal surface, for open scans
+    pub plane: Plane,
+    /// Indexed like [`WeldedMesh::positions`]
+    pub distances: Vec<f32>,
+    pub residuals: Residuals,
+    /// Distance on either side mapped to the ends of the heatmap
+    pub range: f32,
+}
+
+impl PlaneDeviation {
+    /// Fit the occlusal plane of `welded`. `None` if it has too few vertices
+    pub fn occlusal(welded: &WeldedMesh) -> Option<Self> {
+        let mut plane = Plane::fit(&welded.positions)?;
+
+        // Intraoral scans are open toward the gums, so their surface faces
+        // the occlusal side on the whole
+        let facing = welded
+            .triangles
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = welded.corners(triangle);
+                (b - a).cross(&(c - a))
+            })
+            .sum::<Vector3<f32>>()
+            .dot(&plane.normal);
+
+        if facing < 0.0 {
+            plane = plane.flipped();
+        }
+
+        let distances: Vec<f32> = welded
+            .positions
+            .iter()
+            .map(|&position| plane.signed_distance(position))
+            .collect();
+
+        let mut magnitudes: Vec<f32> = distances.iter().map(|distance| distance.abs()).collect();
+        magnitudes.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let percentile = ((magnitudes.len() - 1) as f32 * RANGE_PERCENTILE).round() as usize;
+        let range = magnitudes[percentile].max(f32::EPSILON);
+
+        Some(Self {
+            plane,
+            residuals: Residuals::new(distances.iter().copied()),
+            distances,
+            range,
+        })
+    }
+
+    /// Where `distance` falls on the heatmap, from 0 at `-range` through 0.5
+    /// on the plane to 1 at `range`
+    pub fn heat(&self, distance: f32) -> f32 {
+        (0.5 + distance / (2.0 * self.range)).clamp(0.0, 1.0)
+    }
+
+    /// The heatmap band of each triangle of `welded`, from 0 to `bands - 1`,
+    /// by the mean distance of its vertices
+    pub fn face_bands(&self, welded: &WeldedMesh, bands: usize) -> Vec<usize> {
+        welded
+            .triangles
+            .iter()
+            .map(|triangle| {
+                let mean = triangle
+                    .iter()
+                    .map(|&vertex| self.distances[vertex as usize])
+                    .sum::<f32>()
+                    / 3.0;
+
+                ((self.heat(mean) * bands as f32) as usize).min(bands - 1)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+//! C bindings for mesh analysis. The header in `include/` is generated from
+//! this module with `cbindgen --config cbindgen.toml --output include/scan_classification.h`
+
+use std::{ffi::CStr, os::raw::c_char, panic, slice};
+
+use crate::{
+    analysis::MeshMetrics,
+    mesh::{Mesh, MeshError},
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidPath = 2,
+    Io = 3,
+    Parse = 4,
+    Panic = 5,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanMetrics {
+    pub volume: f64,
+    pub surface_area: f64,
+    pub hole_count: u64,
+    pub triangle_count: u64,
+    pub vertex_count: u64,
+    pub bbox_min: [f32; 3],
+    pub bbox_max: [f32; 3],
+    pub shell_count: u64,
+    pub inconsistent_triangle_count: u64,
+    pub degenerate_triangle_count: u64,
+    pub duplicate_triangle_count: u64,
+    /// Surface roughness in degrees
+    pub noise: f64,
+}
+
+impl From<MeshMetrics> for ScanMetrics {
+    fn from(metrics: MeshMetrics) -> Self {
+        let bbox = metrics.bounding_box;
+
+        Self {
+            volume: metrics.volume,
+            surface_area: metrics.surface_area,
+            hole_count: metrics.hole_count as u64,
+            triangle_count: metrics.triangle_count as u64,
+            vertex_count: metrics.vertex_count as u64,
+            bbox_min: [bbox.min.x, bbox.min.y, bbox.min.z],
+            bbox_max: [bbox.max.x, bbox.max.y, bbox.max.z],
+            shell_count: metrics.shell_count as u64,
+            inconsistent_triangle_count: metrics.inconsistent_triangle_count as u64,
+            degenerate_triangle_count: metrics.degenerate_triangle_count as u64,
+            duplicate_triangle_count: metrics.duplicate_triangle_count as u64,
+            noise: metrics.noise,
+        }
+    }
+}
+
+fn write_metrics(
+    out: *mut ScanMetrics,
+    load: impl FnOnce() -> Result<Mesh, MeshError> + panic::UnwindSafe,
+) -> ScanStatus {
+    let result = panic::catch_unwind(|| load().map(|mesh| MeshMetrics::new(&mesh)));
+
+    match result {
+        Ok(Ok(metrics)) => {
+            unsafe { *out = metrics.into() };
+            ScanStatus::Ok
+        }
+        Ok(Err(MeshError::Io(..))) => ScanStatus::Io,
+        Ok(Err(MeshError::Parse(..))) => ScanStatus::Parse,
+        Ok(Err(MeshError::UnsupportedFormat(..))) => ScanStatus::Parse,
+        Ok(Err(MeshError::UnknownFormat)) => ScanStatus::Parse,
+        Err(..) => ScanStatus::Panic,
+    }
+}
+
+/// Analyze the STL file at the NUL-terminated, UTF-8 `path`
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string and `out` must point to
+/// writable memory for a `ScanMetrics`. `out` is only written on success
+#[no_mangle]
+pub unsafe extern "C" fn scan_analyze_file(
+    path: *const c_char,
+    out: *mut ScanMetrics,
+) -> ScanStatus {
+    if path.is_null() || out.is_null() {
+        return ScanStatus::NullPointer;
+    }
+
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path.to_owned(),
+        Err(..) => return ScanStatus::InvalidPath,
+    };
+
+    write_metrics(out, move || Mesh::open(path))
+}
+
+/// Analyze an in-memory STL file of `len` bytes
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to
+/// writable memory for a `ScanMetrics`. `out` is only written on success
+#[no_mangle]
+pub unsafe extern "C" fn scan_analyze_stl_buffer(
+    data: *const u8,
+    len: usize,
+    out: *mut ScanMetrics,
+) -> ScanStatus {
+    if data.is_null() || out.is_null() {
+        return ScanStatus::NullPointer;
+    }
+
+    let bytes = slice::from_raw_parts(data, len);
+
+    write_metrics(out, move || Mesh::from_stl(bytes))
+}
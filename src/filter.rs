@@ -0,0 +1,504 @@
+//! Predicates over [`MeshMetrics`] for building focused queues, e.g.
+//! `triangles > 100000 && bbox.z < 40`
+//!
+//! Expressions support numbers, the variables listed in [`VARIABLES`],
+//! arithmetic (`+ - * /`), comparisons (`< <= > >= == !=`), and boolean
+//! logic (`&& || !`), with parentheses for grouping. The whole expression
+//! must be boolean
+
+use std::fmt;
+
+use crate::analysis::MeshMetrics;
+
+/// Every variable a filter can refer to, and what it measures
+pub const VARIABLES: &[(&str, &str)] = &[
+    ("triangles", "triangle count"),
+    (
+        "vertices",
+        "vertex count, with vertices at the same position merged",
+    ),
+    ("volume", "enclosed volume"),
+    ("area", "surface area"),
+    ("holes", "number of holes"),
+    ("shells", "number of disconnected pieces"),
+    ("degenerate", "zero-area triangles"),
+    ("duplicate", "duplicated triangles"),
+    ("inconsistent", "inconsistently wound triangles"),
+    ("noise", "surface roughness in degrees"),
+    ("bbox.x", "bounding box width"),
+    ("bbox.y", "bounding box depth"),
+    ("bbox.z", "bounding box height"),
+];
+
+/// A parsed, type-checked filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    /// Byte offset into the expression where the error was found
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid filter at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(source)?;
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            next: 0,
+            end: source.len(),
+        };
+
+        let expr = parser.or()?;
+
+        if let Some((position, token)) = parser.peek() {
+            return Err(FilterError {
+                position,
+                message: format!("unexpected `{}`", token),
+            });
+        }
+
+        if expr.ty() != Type::Bool {
+            return Err(FilterError {
+                position: 0,
+                message: String::from("expression is a number, not a condition"),
+            });
+        }
+
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, metrics: &MeshMetrics) -> bool {
+        self.expr.eval_bool(metrics)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Number,
+    Bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// The type of both operands and of the result
+    fn types(self) -> (Type, Type) {
+        match self {
+            Self::Add | Self::Sub | Self::Mul | Self::Div => (Type::Number, Type::Number),
+            Self::Lt | Self::Le | Self::Gt | Self::Ge | Self::Eq | Self::Ne => {
+                (Type::Number, Type::Bool)
+            }
+            Self::And | Self::Or => (Type::Bool, Type::Bool),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(usize),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn ty(&self) -> Type {
+        match self {
+            Self::Number(..) | Self::Variable(..) | Self::Neg(..) => Type::Number,
+            Self::Not(..) => Type::Bool,
+            Self::Binary(op, ..) => op.types().1,
+        }
+    }
+
+    fn eval_number(&self, metrics: &MeshMetrics) -> f64 {
+        match self {
+            Self::Number(n) => *n,
+            Self::Variable(idx) => variable(*idx, metrics),
+            Self::Neg(expr) => -expr.eval_number(metrics),
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval_number(metrics), rhs.eval_number(metrics));
+
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    _ => unreachable!("type checked when parsed"),
+                }
+            }
+            Self::Not(..) => unreachable!("type checked when parsed"),
+        }
+    }
+
+    fn eval_bool(&self, metrics: &MeshMetrics) -> bool {
+        match self {
+            Self::Not(expr) => !expr.eval_bool(metrics),
+            Self::Binary(BinaryOp::And, lhs, rhs) => {
+                lhs.eval_bool(metrics) && rhs.eval_bool(metrics)
+            }
+            Self::Binary(BinaryOp::Or, lhs, rhs) => {
+                lhs.eval_bool(metrics) || rhs.eval_bool(metrics)
+            }
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval_number(metrics), rhs.eval_number(metrics));
+
+                match op {
+                    BinaryOp::Lt => lhs < rhs,
+                    BinaryOp::Le => lhs <= rhs,
+                    BinaryOp::Gt => lhs > rhs,
+                    BinaryOp::Ge => lhs >= rhs,
+                    BinaryOp::Eq => lhs == rhs,
+                    BinaryOp::Ne => lhs != rhs,
+                    _ => unreachable!("type checked when parsed"),
+                }
+            }
+            _ => unreachable!("type checked when parsed"),
+        }
+    }
+}
+
+/// The value of `VARIABLES[idx]`
+pub(crate) fn variable(idx: usize, metrics: &MeshMetrics) -> f64 {
+    let size = metrics.bounding_box.delta();
+
+    match VARIABLES[idx].0 {
+        "triangles" => metrics.triangle_count as f64,
+        "vertices" => metrics.vertex_count as f64,
+        "volume" => metrics.volume,
+        "area" => metrics.surface_area,
+        "holes" => metrics.hole_count as f64,
+        "shells" => metrics.shell_count as f64,
+        "degenerate" => metrics.degenerate_triangle_count as f64,
+        "duplicate" => metrics.duplicate_triangle_count as f64,
+        "inconsistent" => metrics.inconsistent_triangle_count as f64,
+        "noise" => metrics.noise,
+        "bbox.x" => size.x as f64,
+        "bbox.y" => size.y as f64,
+        "bbox.z" => size.z as f64,
+        name => unreachable!("no value for variable {}", name),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Ident(name) => f.write_str(name),
+            Self::Op(op) => f.write_str(op),
+            Self::Open => f.write_str("("),
+            Self::Close => f.write_str(")"),
+        }
+    }
+}
+
+/// Longest operators first, so `<=` is not read as `<` followed by `=`
+const OPERATORS: &[&str] = &[
+    "&&", "||", "<=", ">=", "==", "!=", "<", ">", "!", "+", "-", "*", "/",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push((start, if c == '(' { Token::Open } else { Token::Close }));
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+
+            while let Some(&(idx, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.' || c == '_') {
+                    break;
+                }
+
+                end = idx + c.len_utf8();
+                chars.next();
+            }
+
+            let text = source[start..end].replace('_', "");
+            let number = text.parse().map_err(|_| FilterError {
+                position: start,
+                message: format!("invalid number `{}`", &source[start..end]),
+            })?;
+
+            tokens.push((start, Token::Number(number)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = start;
+
+            while let Some(&(idx, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    break;
+                }
+
+                end = idx + c.len_utf8();
+                chars.next();
+            }
+
+            tokens.push((start, Token::Ident(source[start..end].to_owned())));
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| source[start..].starts_with(*op))
+                .ok_or_else(|| FilterError {
+                    position: start,
+                    message: format!("unexpected `{}`", c),
+                })?;
+
+            for _ in 0..op.len() {
+                chars.next();
+            }
+
+            tokens.push((start, Token::Op(*op)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent, from lowest to highest precedence: `||`, `&&`, `!`,
+/// comparisons, `+ -`, `* /`, unary `-`
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    next: usize,
+    /// Reported as the position of errors at the end of the input
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<(usize, &'a Token)> {
+        self.tokens
+            .get(self.next)
+            .map(|(position, token)| (*position, token))
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map_or(self.end, |(position, _)| position)
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<(usize, &'static str)> {
+        match self.peek() {
+            Some((position, Token::Op(op))) if ops.contains(op) => {
+                self.next += 1;
+                Some((position, op))
+            }
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.and()?;
+
+        while let Some((position, _)) = self.eat_op(&["||"]) {
+            lhs = binary(BinaryOp::Or, lhs, self.and()?, position)?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.not()?;
+
+        while let Some((position, _)) = self.eat_op(&["&&"]) {
+            lhs = binary(BinaryOp::And, lhs, self.not()?, position)?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, FilterError> {
+        match self.eat_op(&["!"]) {
+            Some((position, _)) => {
+                let expr = self.not()?;
+                expect(&expr, Type::Bool, position)?;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            None => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FilterError> {
+        let lhs = self.sum()?;
+
+        let op = match self.eat_op(&["<", "<=", ">", ">=", "==", "!="]) {
+            Some((position, op)) => (position, op),
+            None => return Ok(lhs),
+        };
+
+        let rhs = self.sum()?;
+
+        let kind = match op.1 {
+            "<" => BinaryOp::Lt,
+            "<=" => BinaryOp::Le,
+            ">" => BinaryOp::Gt,
+            ">=" => BinaryOp::Ge,
+            "==" => BinaryOp::Eq,
+            _ => BinaryOp::Ne,
+        };
+
+        binary(kind, lhs, rhs, op.0)
+    }
+
+    fn sum(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.product()?;
+
+        while let Some((position, op)) = self.eat_op(&["+", "-"]) {
+            let kind = if op == "+" {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+
+            lhs = binary(kind, lhs, self.product()?, position)?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.unary()?;
+
+        while let Some((position, op)) = self.eat_op(&["*", "/"]) {
+            let kind = if op == "*" {
+                BinaryOp::Mul
+            } else {
+                BinaryOp::Div
+            };
+
+            lhs = binary(kind, lhs, self.unary()?, position)?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        match self.eat_op(&["-"]) {
+            Some((position, _)) => {
+                let expr = self.unary()?;
+                expect(&expr, Type::Number, position)?;
+                Ok(Expr::Neg(Box::new(expr)))
+            }
+            None => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, FilterError> {
+        let position = self.position();
+
+        let token = match self.peek() {
+            Some((_, token)) => token,
+            None => {
+                return Err(FilterError {
+                    position,
+                    message: String::from("unexpected end of filter"),
+                })
+            }
+        };
+
+        self.next += 1;
+
+        match token {
+            Token::Number(n) => Ok(Expr::Number(*n)),
+            Token::Ident(name) => VARIABLES
+                .iter()
+                .position(|(known, _)| known == name)
+                .map(Expr::Variable)
+                .ok_or_else(|| FilterError {
+                    position,
+                    message: format!(
+                        "unknown variable `{}`, expected one of {}",
+                        name,
+                        VARIABLES
+                            .iter()
+                            .map(|(name, _)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }),
+            Token::Open => {
+                let expr = self.or()?;
+
+                match self.peek() {
+                    Some((_, Token::Close)) => {
+                        self.next += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(FilterError {
+                        position: self.position(),
+                        message: String::from("expected `)`"),
+                    }),
+                }
+            }
+            token => Err(FilterError {
+                position,
+                message: format!("unexpected `{}`", token),
+            }),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, lhs: Expr, rhs: Expr, position: usize) -> Result<Expr, FilterError> {
+    let (operand, _) = op.types();
+
+    expect(&lhs, operand, position)?;
+    expect(&rhs, operand, position)?;
+
+    Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+}
+
+fn expect(expr: &Expr, ty: Type, position: usize) -> Result<(), FilterError> {
+    if expr.ty() == ty {
+        return Ok(());
+    }
+
+    Err(FilterError {
+        position,
+        message: match ty {
+            Type::Number => String::from("expected a number, found a condition"),
+            Type::Bool => String::from("expected a condition, found a number"),
+        },
+    })
+}
@@ -0,0 +1,332 @@
+//! Geometric fingerprints of scans, for noticing one that was labeled before
+//!
+//! The fingerprint is the scan's D2 shape distribution: a histogram of the
+//! distances between random pairs of points on its surface. It does not
+//! change when the scan is moved, turned, or remeshed, so the same case
+//! exported twice, or exported again after a small edit, has nearly the same
+//! fingerprint even though its files differ byte for byte
+
+use std::{
+    cmp::Ordering,
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use nalgebra::Vector3;
+
+use crate::{
+    analysis::{MeshMetrics, WeldedMesh},
+    filter::{variable, VARIABLES},
+    labels::csv_escape,
+    sample::Table,
+};
+
+/// Buckets of the histogram
+pub const FINGERPRINT_BINS: usize = 32;
+
+/// Pairs of points measured per scan
+const PAIRS: usize = 8192;
+
+/// Distances are bucketed up to this multiple of their mean, and farther
+/// ones counted in the last bucket
+const RANGE: f32 = 3.0;
+
+/// Points are sampled the same way for every scan, so a scan's fingerprint
+/// is the same every time it is taken
+const SEED: u64 = 0x5eed_d2d2_5eed_d2d2;
+
+/// The distribution of distances across a scan's surface
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeFingerprint {
+    /// The mean distance between points, in the scan's units
+    pub scale: f32,
+    /// Share of pairs in each bucket, relative to `scale`, summing to 1
+    pub bins: Vec<f32>,
+}
+
+impl ShapeFingerprint {
+    pub fn new(welded: &WeldedMesh) -> Self {
+        let mut cumulative = Vec::with_capacity(welded.triangles.len());
+        let mut total = 0.0;
+
+        for tri in &welded.triangles {
+            let [a, b, c] = welded.corners(tri);
+            total += (b - a).cross(&(c - a)).norm() / 2.0;
+            cumulative.push(total);
+        }
+
+        if total <= 0.0 {
+            return Self {
+                scale: 0.0,
+                bins: vec![0.0; FINGERPRINT_BINS],
+            };
+        }
+
+        let mut rng = SplitMix64(SEED);
+        let mut sample = || {
+            let at = rng.next_f32() * total;
+            let idx = cumulative
+                .binary_search_by(|probe: &f32| probe.partial_cmp(&at).unwrap_or(Ordering::Equal))
+                .unwrap_or_else(|idx| idx)
+                .min(cumulative.len() - 1);
+            let [a, b, c] = welded.corners(&welded.triangles[idx]);
+
+            // Folding the unit square onto the triangle keeps points uniform
+            let (mut u, mut v) = (rng.next_f32(), rng.next_f32());
+            if u + v > 1.0 {
+                u = 1.0 - u;
+                v = 1.0 - v;
+            }
+
+            a + (b - a) * u + (c - a) * v
+        };
+
+        let distances: Vec<f32> = (0..PAIRS)
+            .map(|_| {
+                let from: Vector3<f32> = sample();
+                (sample() - from).norm()
+            })
+            .collect();
+
+        let scale = distances.iter().sum::<f32>() / PAIRS as f32;
+        let mut bins = vec![0.0; FINGERPRINT_BINS];
+
+        if scale > 0.0 {
+            for distance in &distances {
+                let bin = (distance / scale / RANGE * FINGERPRINT_BINS as f32) as usize;
+                bins[bin.min(FINGERPRINT_BINS - 1)] += 1.0 / PAIRS as f32;
+            }
+        }
+
+        Self { scale, bins }
+    }
+
+    /// Between 0 for scans of the same shape and size and 1 for scans with
+    /// nothing in common: the larger of how much of one histogram would
+    /// have to move to match the other, and how much the scales differ,
+    /// relative to the larger
+    pub fn distance(&self, other: &Self) -> f32 {
+        let shape = self
+            .bins
+            .iter()
+            .zip(&other.bins)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / 2.0;
+        let larger = self.scale.max(other.scale);
+        let size = if larger > 0.0 {
+            (self.scale - other.scale).abs() / larger
+        } else {
+            0.0
+        };
+
+        shape.max(size)
+    }
+
+    /// Read back the fields written by the fingerprint's [`Display`]
+    /// implementation
+    ///
+    /// [`Display`]: fmt::Display
+    pub fn parse(fields: &[String]) -> Option<Self> {
+        if fields.len() != 1 + FINGERPRINT_BINS {
+            return None;
+        }
+
+        let mut values = fields.iter().map(|field| field.trim().parse::<f32>().ok());
+        let scale = values.next()??;
+        let bins = values.collect::<Option<Vec<f32>>>()?;
+
+        Some(Self { scale, bins })
+    }
+}
+
+/// The scale, then each bucket, separated by commas
+impl fmt::Display for ShapeFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.scale)?;
+
+        for bin in &self.bins {
+            write!(f, ",{}", bin)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A labeled scan's fingerprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledFingerprint {
+    pub path: String,
+    pub label: String,
+    pub fingerprint: ShapeFingerprint,
+}
+
+/// Fingerprints of the scans labeled so far, read from and appended to a
+/// CSV file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FingerprintIndex {
+    entries: Vec<LabeledFingerprint>,
+}
+
+impl FingerprintIndex {
+    /// Read the fingerprints at `path`, with none if the file does not exist
+    /// yet. Rows that cannot be read are left out
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let entries = Table::parse(&contents)
+            .rows
+            .iter()
+            .filter(|row| row.len() > 2)
+            .filter_map(|row| {
+                Some(LabeledFingerprint {
+                    path: row[0].clone(),
+                    label: row[1].clone(),
+                    fingerprint: ShapeFingerprint::parse(&row[2..])?,
+                })
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    /// The header of the CSV file
+    pub fn header() -> String {
+        format!("path,label,{}", fingerprint_columns())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add `entry` to the index and write it to `writer` as a row of the
+    /// CSV file
+    pub fn push(&mut self, entry: LabeledFingerprint, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{},{},{}",
+            csv_escape(&entry.path),
+            csv_escape(&entry.label),
+            entry.fingerprint
+        )?;
+
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Forget the last fingerprint added if it is of the scan at `path`,
+    /// after its label is taken back
+    pub fn pop(&mut self, path: &str) {
+        if self
+            .entries
+            .last()
+            .map_or(false, |entry| entry.path == path)
+        {
+            self.entries.pop();
+        }
+    }
+
+    /// The scan most like `fingerprint` of those within `max_distance` of
+    /// it, other than the scan at `path` itself, and how far it is
+    pub fn nearest(
+        &self,
+        fingerprint: &ShapeFingerprint,
+        path: &str,
+        max_distance: f32,
+    ) -> Option<(&LabeledFingerprint, f32)> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.path != path)
+            .map(|entry| (entry, entry.fingerprint.distance(fingerprint)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+    }
+}
+
+/// A scan as a row of numbers for clustering: every metric a filter can
+/// refer to, then its fingerprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanEmbedding {
+    /// Named as in filters, in the order of [`ScanEmbedding::header`]
+    pub metrics: Vec<f64>,
+    pub fingerprint: ShapeFingerprint,
+}
+
+impl ScanEmbedding {
+    /// `metrics` and `fingerprint` should be of the scan as loaded, as
+    /// [`MeshMetrics::from_welded`] and [`ShapeFingerprint::new`] measure it
+    /// while labeling
+    pub fn new(metrics: &MeshMetrics, fingerprint: ShapeFingerprint) -> Self {
+        Self {
+            metrics: (0..VARIABLES.len())
+                .map(|idx| variable(idx, metrics))
+                .collect(),
+            fingerprint,
+        }
+    }
+
+    /// The header of a CSV file of embeddings, each row starting with the
+    /// scan's path
+    pub fn header() -> String {
+        let mut header = String::from("path");
+
+        for (name, _) in VARIABLES {
+            header.push(',');
+            header.push_str(name);
+        }
+
+        format!("{},{}", header, fingerprint_columns())
+    }
+
+    /// Write the embedding of the scan at `path` as a row of the CSV file
+    pub fn write_row(&self, mut writer: impl Write, path: &str) -> io::Result<()> {
+        write!(writer, "{}", csv_escape(path))?;
+
+        for value in &self.metrics {
+            write!(writer, ",{}", value)?;
+        }
+
+        writeln!(writer, ",{}", self.fingerprint)
+    }
+}
+
+/// Names of the fields of [`ShapeFingerprint`]'s [`Display`](fmt::Display)
+/// implementation
+fn fingerprint_columns() -> String {
+    let mut columns = String::from("scale");
+
+    for bin in 0..FINGERPRINT_BINS {
+        columns.push_str(&format!(",d2_{}", bin));
+    }
+
+    columns
+}
+
+/// A small, fast generator, plenty for scattering points
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,326 @@
+//! Least-squares fits of simple shapes to points on a scan
+//!
+//! Fitting a plane, sphere, or cylinder to part of a scan and looking at the
+//! residuals shows how flat a surface that should be flat really is, or how
+//! round a preparation is, without exporting the scan to CAD software
+
+use nalgebra::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+
+/// An infinite plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub point: Vector3<f32>,
+    /// Unit length
+    pub normal: Vector3<f32>,
+}
+
+impl Plane {
+    /// The plane minimizing the sum of squared distances to `points`, with
+    /// its normal in either direction. `None` for fewer than three points
+    pub fn fit(points: &[Vector3<f32>]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        // In f64, since scans have millions of points
+        let centroid = centroid(points);
+        let mut covariance = Matrix3::zeros();
+
+        for point in points {
+            let offset = point.map(f64::from) - centroid;
+            covariance += offset * offset.transpose();
+        }
+
+        // The direction the points vary least along
+        let eigen = covariance.symmetric_eigen();
+        let normal = eigen
+            .eigenvectors
+            .column(eigen.eigenvalues.imin())
+            .into_owned();
+
+        if !normal.iter().all(|component| component.is_finite()) {
+            return None;
+        }
+
+        Some(Self {
+            point: centroid.map(|component| component as f32),
+            normal: normal.normalize().map(|component| component as f32),
+        })
+    }
+
+    /// Positive on the side the normal points to
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        (point - self.point).dot(&self.normal)
+    }
+
+    /// The same plane with its normal reversed
+    pub fn flipped(self) -> Self {
+        Self {
+            point: self.point,
+            normal: -self.normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// The sphere best fitting `points`, by the algebraic distance
+    /// `|p - c|² - r²`. `None` for fewer than four points or coplanar ones
+    pub fn fit(points: &[Vector3<f32>]) -> Option<Self> {
+        if points.len() < 4 {
+            return None;
+        }
+
+        // Centered, so squared coordinates stay small
+        let origin = centroid(points);
+        let mut normal_matrix = Matrix4::zeros();
+        let mut rhs = Vector4::zeros();
+
+        for point in points {
+            let p = point.map(f64::from) - origin;
+            let row = Vector4::new(2.0 * p.x, 2.0 * p.y, 2.0 * p.z, 1.0);
+
+            normal_matrix += row * row.transpose();
+            rhs += row * p.norm_squared();
+        }
+
+        let solution = normal_matrix.lu().solve(&rhs)?;
+        let center = solution.xyz();
+        let radius_squared = solution.w + center.norm_squared();
+
+        if !radius_squared.is_finite() || radius_squared <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            center: (center + origin).map(|component| component as f32),
+            radius: radius_squared.sqrt() as f32,
+        })
+    }
+
+    /// Positive outside the sphere
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        (point - self.center).norm() - self.radius
+    }
+}
+
+/// An infinite cylinder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cylinder {
+    /// A point on the axis
+    pub point: Vector3<f32>,
+    /// Unit length
+    pub axis: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Cylinder {
+    /// The cylinder best fitting `points`, whose surface normals are
+    /// `normals`. The axis is the direction most perpendicular to the
+    /// normals, and the cross-section a circle fit to the points projected
+    /// along it. `None` for fewer than three points or collinear ones
+    pub fn fit(points: &[Vector3<f32>], normals: &[Vector3<f32>]) -> Option<Self> {
+        if points.len() < 3 || normals.len() != points.len() {
+            return None;
+        }
+
+        let mut scatter = Matrix3::zeros();
+
+        for normal in normals {
+            let normal = normal.map(f64::from);
+            scatter += normal * normal.transpose();
+        }
+
+        let eigen = scatter.symmetric_eigen();
+        let axis = eigen
+            .eigenvectors
+            .column(eigen.eigenvalues.imin())
+            .into_owned()
+            .try_normalize(f64::EPSILON)?;
+
+        // Any two directions perpendicular to the axis and each other
+        let helper = if axis.x.abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        let u = axis.cross(&helper).normalize();
+        let v = axis.cross(&u);
+
+        let origin = centroid(points);
+        let mut normal_matrix = Matrix3::zeros();
+        let mut rhs = Vector3::zeros();
+
+        for point in points {
+            let p = point.map(f64::from) - origin;
+            let projected = Vector2::new(p.dot(&u), p.dot(&v));
+            let row = Vector3::new(2.0 * projected.x, 2.0 * projected.y, 1.0);
+
+            normal_matrix += row * row.transpose();
+            rhs += row * projected.norm_squared();
+        }
+
+        let solution = normal_matrix.lu().solve(&rhs)?;
+        let radius_squared = solution.z + solution.x * solution.x + solution.y * solution.y;
+
+        if !radius_squared.is_finite() || radius_squared <= 0.0 {
+            return None;
+        }
+
+        let point = origin + u * solution.x + v * solution.y;
+
+        Some(Self {
+            point: point.map(|component| component as f32),
+            axis: axis.map(|component| component as f32),
+            radius: radius_squared.sqrt() as f32,
+        })
+    }
+
+    /// Positive outside the cylinder
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        let offset = point - self.point;
+        let along = offset.dot(&self.axis);
+
+        (offset - self.axis * along).norm() - self.radius
+    }
+}
+
+/// The shapes that can be fit to a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveKind {
+    Plane,
+    Sphere,
+    Cylinder,
+}
+
+impl PrimitiveKind {
+    /// The next kind to try when cycling through them, or `None` after the
+    /// last
+    pub fn next(kind: Option<Self>) -> Option<Self> {
+        match kind {
+            None => Some(Self::Plane),
+            Some(Self::Plane) => Some(Self::Sphere),
+            Some(Self::Sphere) => Some(Self::Cylinder),
+            Some(Self::Cylinder) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Plane(Plane),
+    Sphere(Sphere),
+    Cylinder(Cylinder),
+}
+
+impl Primitive {
+    /// Fit a shape of `kind` to `points`. `normals`, one per point, are only
+    /// used by cylinders
+    pub fn fit(
+        kind: PrimitiveKind,
+        points: &[Vector3<f32>],
+        normals: &[Vector3<f32>],
+    ) -> Option<Self> {
+        match kind {
+            PrimitiveKind::Plane => Plane::fit(points).map(Self::Plane),
+            PrimitiveKind::Sphere => Sphere::fit(points).map(Self::Sphere),
+            PrimitiveKind::Cylinder => Cylinder::fit(points, normals).map(Self::Cylinder),
+        }
+    }
+
+    pub fn kind(&self) -> PrimitiveKind {
+        match self {
+            Self::Plane(_) => PrimitiveKind::Plane,
+            Self::Sphere(_) => PrimitiveKind::Sphere,
+            Self::Cylinder(_) => PrimitiveKind::Cylinder,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        match self {
+            Self::Plane(plane) => plane.signed_distance(point),
+            Self::Sphere(sphere) => sphere.signed_distance(point),
+            Self::Cylinder(cylinder) => cylinder.signed_distance(point),
+        }
+    }
+
+    /// The radius of spheres and cylinders
+    pub fn radius(&self) -> Option<f32> {
+        match self {
+            Self::Plane(_) => None,
+            Self::Sphere(sphere) => Some(sphere.radius),
+            Self::Cylinder(cylinder) => Some(cylinder.radius),
+        }
+    }
+}
+
+/// A shape fitted to a region of a scan, and how well it fits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    pub primitive: Primitive,
+    pub residuals: Residuals,
+    pub point_count: usize,
+}
+
+impl Fit {
+    /// See [`Primitive::fit`]
+    pub fn new(
+        kind: PrimitiveKind,
+        points: &[Vector3<f32>],
+        normals: &[Vector3<f32>],
+    ) -> Option<Self> {
+        let primitive = Primitive::fit(kind, points, normals)?;
+
+        Some(Self {
+            primitive,
+            residuals: Residuals::new(points.iter().map(|&point| primitive.signed_distance(point))),
+            point_count: points.len(),
+        })
+    }
+}
+
+/// The mean of `points`, in f64
+fn centroid(points: &[Vector3<f32>]) -> Vector3<f64> {
+    points
+        .iter()
+        .fold(Vector3::zeros(), |sum: Vector3<f64>, point| {
+            sum + point.map(f64::from)
+        })
+        / points.len() as f64
+}
+
+/// How far points stray from a fitted shape
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Residuals {
+    /// Root mean square distance
+    pub rms: f32,
+    /// Largest distance, whichever side it is on
+    pub max: f32,
+}
+
+impl Residuals {
+    /// Of signed or unsigned distances from a shape
+    pub fn new(distances: impl IntoIterator<Item = f32>) -> Self {
+        let (mut count, mut sum_squared, mut max) = (0, 0.0_f64, 0.0_f32);
+
+        for distance in distances {
+            count += 1;
+            sum_squared += f64::from(distance) * f64::from(distance);
+            max = max.max(distance.abs());
+        }
+
+        if count == 0 {
+            return Self::default();
+        }
+
+        Self {
+            rms: (sum_squared / count as f64).sqrt() as f32,
+            max,
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! Signed distance field font atlases and text layout
+//!
+//! Each glyph is rasterized once at [`BASE_SIZE`] and stored as its distance
+//! to the outline rather than its coverage, so the same atlas draws crisp
+//! text at any size. Drawing is left to the renderer, see
+//! [`crate::TextRenderer`]
+
+use std::{collections::HashMap, fmt};
+
+use fontdue::{Font, FontSettings};
+use nalgebra::Vector2;
+
+/// DejaVu Sans, see `assets/fonts/LICENSE`
+const DEFAULT_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+/// Size in pixels glyphs are rasterized at
+pub const BASE_SIZE: f32 = 48.0;
+/// Distance in pixels at [`BASE_SIZE`] covered by the field on either side
+/// of an outline, which bounds how wide outlines and shadows can be
+pub const SPREAD: f32 = 8.0;
+
+const ATLAS_WIDTH: usize = 1024;
+/// Between glyphs in the atlas, so linear filtering does not bleed
+const PADDING: usize = 1;
+
+/// Drawn for characters missing from the atlas
+const FALLBACK: char = '?';
+
+#[derive(Debug)]
+pub enum FontError {
+    Parse(&'static str),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "unable to parse font: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// Where a glyph is in the atlas and how it sits on the baseline, in pixels
+/// at [`BASE_SIZE`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    /// Top left of the glyph's field in the atlas
+    pub atlas_position: Vector2<f32>,
+    /// Including [`SPREAD`] on every side
+    pub size: Vector2<f32>,
+    /// From the pen position on the baseline to the top left of the field,
+    /// y down
+    pub offset: Vector2<f32>,
+    pub advance: f32,
+}
+
+/// One glyph of laid out text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+    /// Corners on screen, in pixels from the top left of the text
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+    /// Corners in the atlas, from 0 to 1
+    pub uv_min: Vector2<f32>,
+    pub uv_max: Vector2<f32>,
+}
+
+/// Laid out text, see [`FontAtlas::layout`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    pub quads: Vec<GlyphQuad>,
+    /// Of the widest line
+    pub width: f32,
+    /// From the top of the first line to the bottom of the last
+    pub height: f32,
+}
+
+/// Distance fields of a font's glyphs packed into one single-channel image
+#[derive(Debug, Clone)]
+pub struct FontAtlas {
+    width: usize,
+    height: usize,
+    /// Row-major, top row first. 128 is the outline, higher values inside
+    pixels: Vec<u8>,
+    glyphs: HashMap<char, Glyph>,
+    ascent: f32,
+    line_height: f32,
+}
+
+impl FontAtlas {
+    /// The bundled font, covering ASCII and Latin-1
+    pub fn default_font() -> Self {
+        Self::from_bytes(DEFAULT_FONT).expect("bundled font is valid")
+    }
+
+    /// Bake the ASCII and Latin-1 glyphs of a TrueType or OpenType font
+    pub fn from_bytes(font: &[u8]) -> Result<Self, FontError> {
+        let font = Font::from_bytes(font, FontSettings::default()).map_err(FontError::Parse)?;
+
+        let chars = (' '..='~')
+            .chain('\u{a0}'..='\u{ff}')
+            .chain(['…', '—', '–', '•'].iter().copied());
+
+        let mut fields = Vec::new();
+
+        for ch in chars {
+            if ch != FALLBACK && font.lookup_glyph_index(ch) == 0 {
+                continue;
+            }
+
+            let (metrics, coverage) = font.rasterize(ch, BASE_SIZE);
+            let (field, width, height) = distance_field(&coverage, metrics.width, metrics.height);
+
+            let offset = Vector2::new(
+                metrics.xmin as f32 - SPREAD,
+                -(metrics.ymin as f32 + metrics.height as f32) - SPREAD,
+            );
+
+            fields.push((ch, field, width, height, offset, metrics.advance_width));
+        }
+
+        // Tallest first, then shelves of glyphs left to right
+        fields.sort_by(|a, b| b.3.cmp(&a.3));
+
+        let mut placements = Vec::with_capacity(fields.len());
+        let (mut x, mut y, mut shelf) = (PADDING, PADDING, 0);
+
+        for (_, _, width, height, _, _) in &fields {
+            if x + width + PADDING > ATLAS_WIDTH {
+                x = PADDING;
+                y += shelf + PADDING;
+                shelf = 0;
+            }
+
+            placements.push((x, y));
+            x += width + PADDING;
+            shelf = shelf.max(*height);
+        }
+
+        let height = (y + shelf + PADDING).next_power_of_two();
+        let mut pixels = vec![0; ATLAS_WIDTH * height];
+        let mut glyphs = HashMap::with_capacity(fields.len());
+
+        for ((ch, field, width, rows, offset, advance), (x, y)) in
+            fields.into_iter().zip(placements)
+        {
+            for row in 0..rows {
+                let start = (y + row) * ATLAS_WIDTH + x;
+                pixels[start..start + width]
+                    .copy_from_slice(&field[row * width..(row + 1) * width]);
+            }
+
+            glyphs.insert(
+                ch,
+                Glyph {
+                    atlas_position: Vector2::new(x as f32, y as f32),
+                    size: Vector2::new(width as f32, rows as f32),
+                    offset,
+                    advance,
+                },
+            );
+        }
+
+        let (ascent, line_height) = match font.horizontal_line_metrics(BASE_SIZE) {
+            Some(line) => (line.ascent, line.new_line_size),
+            None => (BASE_SIZE * 0.8, BASE_SIZE * 1.2),
+        };
+
+        Ok(Self {
+            width: ATLAS_WIDTH,
+            height,
+            pixels,
+            glyphs,
+            ascent,
+            line_height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.get(&ch).or_else(|| self.glyphs.get(&FALLBACK))
+    }
+
+    /// Height of a line of text `size` pixels tall, including the gap before
+    /// the next
+    pub fn line_height(&self, size: f32) -> f32 {
+        self.line_height * size / BASE_SIZE
+    }
+
+    /// Place each glyph of `text`, `size` pixels tall, with the top left of
+    /// the text at the origin. Lines are broken at `\n`
+    pub fn layout(&self, text: &str, size: f32) -> TextLayout {
+        let scale = size / BASE_SIZE;
+        let atlas_size = Vector2::new(self.width as f32, self.height as f32);
+
+        let mut layout = TextLayout::default();
+        let mut pen = Vector2::new(0.0, self.ascent);
+
+        for line in text.split('\n') {
+            pen.x = 0.0;
+
+            for ch in line.chars() {
+                let glyph = match self.glyph(ch) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+
+                if !ch.is_whitespace() {
+                    let min = pen + glyph.offset;
+
+                    layout.quads.push(GlyphQuad {
+                        min: min * scale,
+                        max: (min + glyph.size) * scale,
+                        uv_min: glyph.atlas_position.component_div(&atlas_size),
+                        uv_max: (glyph.atlas_position + glyph.size).component_div(&atlas_size),
+                    });
+                }
+
+                pen.x += glyph.advance;
+            }
+
+            layout.width = layout.width.max(pen.x * scale);
+            pen.y += self.line_height;
+        }
+
+        layout.height = (pen.y - self.ascent) * scale;
+        layout
+    }
+}
+
+/// The signed distance field of a coverage bitmap, padded by [`SPREAD`] on
+/// every side, with its width and height
+fn distance_field(coverage: &[u8], width: usize, height: usize) -> (Vec<u8>, usize, usize) {
+    let pad = SPREAD.ceil() as usize;
+    let (field_width, field_height) = (width + 2 * pad, height + 2 * pad);
+
+    let inside = |x: usize, y: usize| {
+        x >= pad
+            && y >= pad
+            && x < width + pad
+            && y < height + pad
+            && coverage[(y - pad) * width + x - pad] >= 128
+    };
+
+    // Squared distances to the nearest pixel outside and inside the glyph
+    let mut to_outside = vec![0.0; field_width * field_height];
+    let mut to_inside = vec![0.0; field_width * field_height];
+
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let idx = y * field_width + x;
+
+            if inside(x, y) {
+                to_outside[idx] = f32::INFINITY;
+            } else {
+                to_inside[idx] = f32::INFINITY;
+            }
+        }
+    }
+
+    distance_transform(&mut to_outside, field_width, field_height);
+    distance_transform(&mut to_inside, field_width, field_height);
+
+    let field = to_outside
+        .iter()
+        .zip(&to_inside)
+        .map(|(&outside, &inside)| {
+            // Half a pixel puts the outline between pixels rather than on them
+            let distance = if outside > 0.0 {
+                outside.sqrt() - 0.5
+            } else {
+                0.5 - inside.sqrt()
+            };
+
+            ((0.5 + distance / (2.0 * SPREAD)).max(0.0).min(1.0) * 255.0).round() as u8
+        })
+        .collect();
+
+    (field, field_width, field_height)
+}
+
+/// Replace each value of a grid, 0 at feature pixels and infinite elsewhere,
+/// by its squared distance to the nearest feature pixel, see Felzenszwalb
+/// and Huttenlocher, "Distance Transforms of Sampled Functions"
+fn distance_transform(grid: &mut [f32], width: usize, height: usize) {
+    let len = width.max(height);
+    let mut column = vec![0.0; len];
+    let mut output = vec![0.0; len];
+    let mut parabolas = vec![0; len];
+    let mut bounds = vec![0.0; len + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            column[y] = grid[y * width + x];
+        }
+
+        transform_1d(&column[..height], &mut output, &mut parabolas, &mut bounds);
+
+        for y in 0..height {
+            grid[y * width + x] = output[y];
+        }
+    }
+
+    for y in 0..height {
+        let row = &mut grid[y * width..(y + 1) * width];
+        column[..width].copy_from_slice(row);
+
+        transform_1d(&column[..width], &mut output, &mut parabolas, &mut bounds);
+
+        row.copy_from_slice(&output[..width]);
+    }
+}
+
+/// The lower envelope of parabolas rooted at each sample of `f`
+fn transform_1d(f: &[f32], output: &mut [f32], parabolas: &mut [usize], bounds: &mut [f32]) {
+    let n = f.len();
+
+    if n == 0 {
+        return;
+    }
+
+    // Infinite samples contribute no parabola
+    let mut k = 0;
+    let mut found = false;
+
+    for q in 0..n {
+        if !f[q].is_finite() {
+            continue;
+        }
+
+        if !found {
+            parabolas[0] = q;
+            bounds[0] = f32::NEG_INFINITY;
+            bounds[1] = f32::INFINITY;
+            found = true;
+            continue;
+        }
+
+        // `bounds[0]` is negative infinity, so this stops by the first
+        // parabola
+        let s = loop {
+            let v = parabolas[k];
+            let s = ((f[q] + (q * q) as f32) - (f[v] + (v * v) as f32)) / (2.0 * (q - v) as f32);
+
+            if s > bounds[k] {
+                break s;
+            }
+
+            k -= 1;
+        };
+
+        k += 1;
+        parabolas[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = f32::INFINITY;
+    }
+
+    if !found {
+        output[..n]
+            .iter_mut()
+            .for_each(|value| *value = f32::INFINITY);
+        return;
+    }
+
+    let mut k = 0;
+
+    for q in 0..n {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+
+        let v = parabolas[k];
+        let distance = q as f32 - v as f32;
+
+        output[q] = distance * distance + f[v];
+    }
+}
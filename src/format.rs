@@ -0,0 +1,185 @@
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::compression::strip_compressed_extension;
+
+/// Bytes needed to tell every known format apart: a binary STL's header and
+/// triangle count
+pub const SNIFF_LEN: usize = 84;
+
+/// File formats scans are recognized as, from their contents rather than
+/// their extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanFormat {
+    BinaryStl,
+    AsciiStl,
+    Ply,
+    /// Binary glTF
+    Glb,
+    /// Wavefront OBJ
+    Obj,
+    /// 3D Manufacturing Format, itself a zip archive
+    ThreeMf,
+    Zip,
+}
+
+impl ScanFormat {
+    /// Identify `contents` by its magic bytes, falling back to the extension
+    /// of `name` if no magic matches
+    pub fn sniff(contents: &[u8], name: Option<&str>) -> Option<Self> {
+        match Self::sniff_header(contents, contents.len() as u64) {
+            // Not every 3MF writer puts the model first, so the extension
+            // settles archives the first entry does not
+            Some(Self::Zip) if name.and_then(Self::from_name) == Some(Self::ThreeMf) => {
+                Some(Self::ThreeMf)
+            }
+            Some(format) => Some(format),
+            None => name.and_then(Self::from_name),
+        }
+    }
+
+    /// Identify a file from its first [`SNIFF_LEN`] bytes (or fewer, for
+    /// short files) and its total length, ignoring its name
+    pub fn sniff_header(header: &[u8], len: u64) -> Option<Self> {
+        if header.starts_with(b"PK\x03\x04") {
+            return Some(if is_3mf(header) {
+                Self::ThreeMf
+            } else {
+                Self::Zip
+            });
+        }
+
+        if header.starts_with(b"glTF") {
+            return Some(Self::Glb);
+        }
+
+        if header.starts_with(b"ply\n") || header.starts_with(b"ply\r\n") {
+            return Some(Self::Ply);
+        }
+
+        // Checked before `solid`, since many exporters write that into the
+        // header of binary files too
+        if header.len() >= SNIFF_LEN {
+            let mut count = [0; 4];
+            count.copy_from_slice(&header[80..84]);
+            let count = u64::from(u32::from_le_bytes(count));
+
+            if len == 84 + 50 * count {
+                return Some(Self::BinaryStl);
+            }
+        }
+
+        let text = header
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map_or(&[][..], |start| &header[start..]);
+
+        if text.starts_with(b"solid") {
+            return Some(Self::AsciiStl);
+        }
+
+        if is_obj(text) {
+            return Some(Self::Obj);
+        }
+
+        None
+    }
+
+    /// Sniff the file at `path`, reading only its first few bytes
+    pub fn sniff_file(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut header = Vec::with_capacity(SNIFF_LEN);
+        file.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+
+        Ok(Self::sniff_header(&header, len))
+    }
+
+    /// The format a file name's extension suggests, if any, looking past a
+    /// compressed extension such as `.gz`. STL files are assumed to be
+    /// binary, since ASCII ones are recognized by their contents
+    pub fn from_name(name: &str) -> Option<Self> {
+        let extension = Path::new(strip_compressed_extension(name))
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "stl" => Some(Self::BinaryStl),
+            "ply" => Some(Self::Ply),
+            "glb" => Some(Self::Glb),
+            "obj" => Some(Self::Obj),
+            "3mf" => Some(Self::ThreeMf),
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+
+    /// Whether scans in this format can be parsed into a
+    /// [`Mesh`](crate::Mesh)
+    pub fn is_supported(self) -> bool {
+        self.loader().is_some()
+    }
+}
+
+impl fmt::Display for ScanFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::BinaryStl => "binary STL",
+            Self::AsciiStl => "ASCII STL",
+            Self::Ply => "PLY",
+            Self::Glb => "glTF binary",
+            Self::Obj => "Wavefront OBJ",
+            Self::ThreeMf => "3MF",
+            Self::Zip => "zip archive",
+        })
+    }
+}
+
+/// Whether a zip archive's first entry, named in its local file header, is
+/// a 3MF model or the relationships pointing at one
+fn is_3mf(header: &[u8]) -> bool {
+    if header.len() < 30 {
+        return false;
+    }
+
+    let name_len = usize::from(u16::from_le_bytes([header[26], header[27]]));
+    let name = &header[30..header.len().min(30 + name_len)];
+
+    name.starts_with(b"3D/") || name == b"_rels/.rels"
+}
+
+/// Whether `text` opens like an OBJ file: its first line that is not a
+/// comment or blank starts with one of the statements OBJ exporters write
+/// first. A comment running past the end of `text` is inconclusive
+fn is_obj(text: &[u8]) -> bool {
+    const STATEMENTS: &[&[u8]] = &[
+        b"v", b"vn", b"vt", b"f", b"o", b"g", b"s", b"mtllib", b"usemtl",
+    ];
+
+    let mut lines = text.split(|&b| b == b'\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let is_last = lines.peek().is_none();
+
+        if line.starts_with(b"#") || line.iter().all(u8::is_ascii_whitespace) {
+            if is_last {
+                return false;
+            }
+            continue;
+        }
+
+        let keyword = line
+            .split(|b| b.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default();
+
+        return line.len() > keyword.len() && STATEMENTS.contains(&keyword);
+    }
+
+    false
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use crate::{check, image::RgbaImage};
+
+/// How long [`PendingReadback::finish`] waits on the GPU at a time before
+/// checking again
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An offscreen render target with an RGBA8 color attachment and a 24-bit
+/// depth attachment
+#[derive(Debug)]
+pub struct Framebuffer {
+    id: u32,
+    color: u32,
+    depth: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Framebuffer {
+    /// Returns the framebuffer status if the attachments could not be
+    /// combined into a complete framebuffer
+    pub fn new(width: u32, height: u32) -> Result<Self, u32> {
+        let mut id = 0;
+        let mut color = 0;
+        let mut depth = 0;
+
+        unsafe {
+            check!(gl::GenFramebuffers(1, &mut id));
+            check!(gl::BindFramebuffer(gl::FRAMEBUFFER, id));
+
+            check!(gl::GenRenderbuffers(1, &mut color));
+            check!(gl::BindRenderbuffer(gl::RENDERBUFFER, color));
+            check!(gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::RGBA8,
+                width as i32,
+                height as i32
+            ));
+            check!(gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                color
+            ));
+
+            check!(gl::GenRenderbuffers(1, &mut depth));
+            check!(gl::BindRenderbuffer(gl::RENDERBUFFER, depth));
+            check!(gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                width as i32,
+                height as i32
+            ));
+            check!(gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth
+            ));
+
+            check!(gl::BindRenderbuffer(gl::RENDERBUFFER, 0));
+        }
+
+        let framebuffer = Self {
+            id,
+            color,
+            depth,
+            width,
+            height,
+        };
+
+        let status = check!(unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) });
+
+        framebuffer.unbind();
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(status);
+        }
+
+        Ok(framebuffer)
+    }
+
+    pub fn bind(&self) {
+        check!(unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.id) });
+        check!(unsafe { gl::Viewport(0, 0, self.width as i32, self.height as i32) });
+    }
+
+    pub fn unbind(&self) {
+        check!(unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) });
+    }
+
+    /// Copy the color attachment into client memory. Rows are returned in
+    /// OpenGL order, bottom row first
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let mut pixels = vec![0_u8; self.width as usize * self.height as usize * 4];
+
+        self.bind();
+
+        unsafe {
+            check!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+            check!(gl::ReadPixels(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            ));
+        }
+
+        self.unbind();
+
+        pixels
+    }
+
+    /// Start copying the color attachment without waiting for it, see
+    /// [`PendingReadback`]
+    pub fn read_pixels_async(&self) -> PendingReadback {
+        self.bind();
+        let readback = PendingReadback::start(self.width, self.height);
+        self.unbind();
+
+        readback
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            check!(gl::DeleteRenderbuffers(1, &self.color));
+            check!(gl::DeleteRenderbuffers(1, &self.depth));
+            check!(gl::DeleteFramebuffers(1, &self.id));
+        }
+    }
+}
+
+/// Copy the color buffer of the window's default framebuffer, e.g. for a
+/// screenshot
+pub fn read_default_framebuffer(width: u32, height: u32) -> RgbaImage {
+    let mut pixels = vec![0_u8; width as usize * height as usize * 4];
+
+    unsafe {
+        check!(gl::BindFramebuffer(gl::FRAMEBUFFER, 0));
+        check!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+        check!(gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut _,
+        ));
+    }
+
+    let mut image = RgbaImage::from_raw(width, height, pixels);
+    image.flip_vertical();
+
+    image
+}
+
+/// Like [`read_default_framebuffer`], but returning as soon as the copy is
+/// queued, see [`PendingReadback`]
+pub fn read_default_framebuffer_async(width: u32, height: u32) -> PendingReadback {
+    check!(unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) });
+
+    PendingReadback::start(width, height)
+}
+
+/// Pixels being copied from a framebuffer into a pixel buffer object
+///
+/// `glReadPixels` into client memory waits for every queued draw to finish,
+/// stalling the frame it is called in. Copying into a buffer object instead
+/// returns immediately, and a fence tells when the copy has landed, so the
+/// pixels can be collected a frame or two later without a hitch. Requires
+/// sync objects, see [`crate::GlCapabilities::supports_async_readback`]
+#[derive(Debug)]
+pub struct PendingReadback {
+    pbo: u32,
+    fence: gl::types::GLsync,
+    width: u32,
+    height: u32,
+}
+
+impl PendingReadback {
+    /// Queue a copy of the bound read framebuffer's color buffer
+    fn start(width: u32, height: u32) -> Self {
+        let size = width as usize * height as usize * 4;
+        let mut pbo = 0;
+
+        let fence = unsafe {
+            check!(gl::GenBuffers(1, &mut pbo));
+            check!(gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo));
+            check!(gl::BufferData(
+                gl::PIXEL_PACK_BUFFER,
+                size as isize,
+                std::ptr::null(),
+                gl::STREAM_READ
+            ));
+
+            check!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+            // With a pack buffer bound, the pointer is an offset into it
+            check!(gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null_mut(),
+            ));
+
+            check!(gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0));
+
+            let fence = check!(gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+            // So the fence is submitted even if nothing else is drawn
+            check!(gl::Flush());
+
+            fence
+        };
+
+        Self {
+            pbo,
+            fence,
+            width,
+            height,
+        }
+    }
+
+    /// Whether the copy has finished, so [`PendingReadback::finish`] will not
+    /// block
+    pub fn is_ready(&self) -> bool {
+        self.wait(Duration::from_secs(0))
+    }
+
+    /// The pixels, top row first, waiting for the copy if it is still
+    /// running. The context it was started in must be current
+    pub fn finish(self) -> RgbaImage {
+        while !self.wait(WAIT_INTERVAL) {}
+
+        let size = self.width as usize * self.height as usize * 4;
+        let mut pixels = vec![0_u8; size];
+
+        unsafe {
+            check!(gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.pbo));
+
+            let mapped = check!(gl::MapBufferRange(
+                gl::PIXEL_PACK_BUFFER,
+                0,
+                size as isize,
+                gl::MAP_READ_BIT
+            ));
+
+            if !mapped.is_null() {
+                std::ptr::copy_nonoverlapping(mapped as *const u8, pixels.as_mut_ptr(), size);
+                check!(gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER));
+            }
+
+            check!(gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0));
+        }
+
+        let mut image = RgbaImage::from_raw(self.width, self.height, pixels);
+        image.flip_vertical();
+
+        image
+    }
+
+    /// Whether the fence was signaled within `timeout`. A lost context
+    /// counts as signaled, so nothing waits on it forever
+    fn wait(&self, timeout: Duration) -> bool {
+        let status =
+            check!(unsafe { gl::ClientWaitSync(self.fence, 0, timeout.as_nanos() as u64) });
+
+        status != gl::TIMEOUT_EXPIRED
+    }
+}
+
+impl Drop for PendingReadback {
+    fn drop(&mut self) {
+        unsafe {
+            check!(gl::DeleteSync(self.fence));
+            check!(gl::DeleteBuffers(1, &self.pbo));
+        }
+    }
+}
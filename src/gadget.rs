@@ -0,0 +1,125 @@
+//! The axis triad drawn in a corner of the window, showing which way the
+//! scan faces
+
+use nalgebra::{Matrix3, Matrix4, Vector2, Vector3, U1, U3};
+
+/// Base sizes in logical pixels, before [`crate::Theme::hud_scale`]
+const RADIUS: f32 = 36.0;
+const MARGIN: f32 = 16.0;
+/// How close a click must be to the end of an axis to snap to it
+const HIT_RADIUS: f32 = 10.0;
+
+/// One of the six directions along the scan's own axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisEnd {
+    /// 0, 1, or 2 for x, y, or z
+    pub axis: usize,
+    pub positive: bool,
+}
+
+impl AxisEnd {
+    pub const ALL: [AxisEnd; 6] = [
+        AxisEnd::new(0, true),
+        AxisEnd::new(1, true),
+        AxisEnd::new(2, true),
+        AxisEnd::new(0, false),
+        AxisEnd::new(1, false),
+        AxisEnd::new(2, false),
+    ];
+
+    pub const fn new(axis: usize, positive: bool) -> Self {
+        Self { axis, positive }
+    }
+
+    /// The unit vector along this end of the axis, in model space
+    pub fn direction(self) -> Vector3<f32> {
+        let mut direction = Vector3::zeros();
+        direction[self.axis] = if self.positive { 1.0 } else { -1.0 };
+        direction
+    }
+
+    /// The model orientation that turns this end of the axis toward a camera
+    /// with `view`, keeping y up, or z when looking along y
+    pub fn facing(self, view: &Matrix4<f32>) -> Matrix4<f32> {
+        let toward = self.direction();
+        let up = match (self.axis, self.positive) {
+            (1, true) => -Vector3::z(),
+            (1, false) => Vector3::z(),
+            _ => Vector3::y(),
+        };
+
+        // Columns are the model-space directions that should end up along
+        // the view's x, y, and z
+        let basis = Matrix3::from_columns(&[up.cross(&toward), up, toward]);
+
+        (basis * rotation(view)).transpose().to_homogeneous()
+    }
+}
+
+/// Where the axis triad sits on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisGadget {
+    /// In pixels from the top left of the viewport
+    pub center: Vector2<f32>,
+    /// Length of each axis in pixels
+    pub radius: f32,
+}
+
+impl AxisGadget {
+    /// In the bottom left corner of a viewport `height` pixels tall
+    pub fn new(height: u32, hud_scale: f32) -> Self {
+        let radius = RADIUS * hud_scale;
+        let inset = radius + MARGIN * hud_scale;
+
+        Self {
+            center: Vector2::new(inset, height as f32 - inset),
+            radius,
+        }
+    }
+
+    /// Each end of the axes, where it appears in pixels, and how far it
+    /// points toward the camera in `[-1, 1]`, for a scan transformed to view
+    /// space by `model_view`
+    pub fn project(&self, model_view: &Matrix4<f32>) -> Vec<(AxisEnd, Vector2<f32>, f32)> {
+        let rotation = rotation(model_view);
+
+        AxisEnd::ALL
+            .iter()
+            .map(|&end| {
+                let direction = rotation * end.direction();
+                let position = self.center + Vector2::new(direction.x, -direction.y) * self.radius;
+
+                (end, position, direction.z)
+            })
+            .collect()
+    }
+
+    /// The end of an axis under `cursor`, preferring those nearer the
+    /// camera when they overlap
+    pub fn hit(&self, model_view: &Matrix4<f32>, cursor: (f32, f32)) -> Option<AxisEnd> {
+        let cursor = Vector2::new(cursor.0, cursor.1);
+        let reach = self.radius * HIT_RADIUS / RADIUS;
+
+        self.project(model_view)
+            .into_iter()
+            .filter(|(_, position, _)| (position - cursor).norm() <= reach)
+            .max_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(end, _, _)| end)
+    }
+}
+
+/// The rotation part of `transform`, with any scaling removed
+fn rotation(transform: &Matrix4<f32>) -> Matrix3<f32> {
+    let column = |idx: usize| {
+        let column: Vector3<f32> = transform.fixed_slice::<U3, U1>(0, idx).into_owned();
+        let norm = column.norm();
+
+        if norm > f32::EPSILON {
+            column / norm
+        } else {
+            column
+        }
+    };
+
+    Matrix3::from_columns(&[column(0), column(1), column(2)])
+}
@@ -0,0 +1,301 @@
+//! Reading binary glTF 2.0 (GLB) scans
+//!
+//! Scans from the mesh-processing pipeline are a single mesh with a single
+//! triangle primitive, so only the first primitive of the first mesh is read:
+//! its positions, normals if present, and indices. Node transforms, extras,
+//! and materials are ignored, as they are for every other format
+
+use nalgebra::Vector3;
+use serde::Deserialize;
+
+use crate::{
+    loader::smooth_normals,
+    mesh::{Mesh, MeshError, VERTEX_STRIDE},
+    scratch::ScanScratch,
+};
+
+const MAGIC: &[u8] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const MODE_TRIANGLES: u32 = 4;
+
+const COMPONENT_U8: u32 = 5121;
+const COMPONENT_U16: u32 = 5123;
+const COMPONENT_U32: u32 = 5125;
+const COMPONENT_F32: u32 = 5126;
+
+/// The parts of the JSON chunk needed to find the first primitive's data
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    #[serde(default)]
+    meshes: Vec<MeshDef>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeshDef {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Primitive {
+    attributes: Attributes,
+    indices: Option<usize>,
+    #[serde(default = "default_mode")]
+    mode: u32,
+}
+
+fn default_mode() -> u32 {
+    MODE_TRIANGLES
+}
+
+#[derive(Debug, Deserialize)]
+struct Attributes {
+    #[serde(rename = "POSITION")]
+    position: usize,
+    #[serde(rename = "NORMAL")]
+    normal: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    /// Only the GLB's own binary chunk, buffer 0, is supported
+    #[serde(default)]
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+pub fn parse(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+    let (json, bin) = chunks(buffer)?;
+    let document: Document =
+        serde_json::from_slice(json).map_err(|e| error(&format!("invalid JSON: {}", e)))?;
+
+    let primitive = document
+        .meshes
+        .first()
+        .and_then(|mesh| mesh.primitives.first())
+        .ok_or_else(|| error("no mesh primitive"))?;
+
+    if primitive.mode != MODE_TRIANGLES {
+        return Err(error("only triangle primitives are supported"));
+    }
+
+    let positions = read_vectors(&document, bin, primitive.attributes.position)?;
+
+    let indices = match primitive.indices {
+        Some(accessor) => read_indices(&document, bin, accessor, scratch)?,
+        None => {
+            let mut indices = scratch.indices.take(positions.len());
+            indices.extend(0..positions.len() as u32);
+            indices
+        }
+    };
+
+    if indices.len() % 3 != 0 {
+        return Err(error("index count is not a multiple of 3"));
+    }
+
+    if indices
+        .iter()
+        .any(|&index| index as usize >= positions.len())
+    {
+        return Err(error("index refers to a missing vertex"));
+    }
+
+    let normals = match primitive.attributes.normal {
+        Some(accessor) => {
+            let normals = read_vectors(&document, bin, accessor)?;
+
+            if normals.len() != positions.len() {
+                return Err(error("NORMAL and POSITION counts differ"));
+            }
+
+            normals
+                .iter()
+                .map(|normal| {
+                    normal
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(Vector3::zeros)
+                })
+                .collect()
+        }
+        None => smooth_normals(&positions, &indices),
+    };
+
+    let mut vertices = scratch.vertices.take(positions.len() * VERTEX_STRIDE);
+
+    for (position, normal) in positions.iter().zip(&normals) {
+        vertices.extend(position.iter().chain(normal.iter()));
+    }
+
+    Ok(Mesh::new(vertices, indices))
+}
+
+/// The JSON chunk and the binary chunk, which is empty if missing
+fn chunks(buffer: &[u8]) -> Result<(&[u8], &[u8]), MeshError> {
+    if buffer.len() < 12 || !buffer.starts_with(MAGIC) {
+        return Err(error("missing glTF magic"));
+    }
+
+    if read_u32(buffer, 4) != 2 {
+        return Err(error("only glTF 2.0 is supported"));
+    }
+
+    let length = (read_u32(buffer, 8) as usize).min(buffer.len());
+    let mut offset = 12;
+    let (mut json, mut bin) = (None, None);
+
+    while offset + 8 <= length {
+        let chunk_length = read_u32(buffer, offset) as usize;
+        let chunk_type = read_u32(buffer, offset + 4);
+        let start = offset + 8;
+        let end = start
+            .checked_add(chunk_length)
+            .filter(|&end| end <= length)
+            .ok_or_else(|| error("chunk runs past the end of the file"))?;
+
+        match chunk_type {
+            CHUNK_JSON if json.is_none() => json = Some(&buffer[start..end]),
+            CHUNK_BIN if bin.is_none() => bin = Some(&buffer[start..end]),
+            _ => {}
+        }
+
+        offset = end;
+    }
+
+    let json = json.ok_or_else(|| error("no JSON chunk"))?;
+
+    Ok((json, bin.unwrap_or_default()))
+}
+
+/// The bytes of each element of `accessor`, which must be `size` bytes
+/// long, in order
+fn elements<'a>(
+    document: &Document,
+    bin: &'a [u8],
+    accessor: &Accessor,
+    size: usize,
+) -> Result<impl Iterator<Item = &'a [u8]>, MeshError> {
+    let view = accessor
+        .buffer_view
+        .and_then(|view| document.buffer_views.get(view))
+        .ok_or_else(|| error("accessor without a buffer view"))?;
+
+    if view.buffer != 0 {
+        return Err(error("external buffers are not supported"));
+    }
+
+    let stride = view.byte_stride.unwrap_or(size);
+    let overflow = || error("accessor offsets overflow");
+    let start = view
+        .byte_offset
+        .checked_add(accessor.byte_offset)
+        .ok_or_else(overflow)?;
+    let view_end = view
+        .byte_offset
+        .checked_add(view.byte_length)
+        .ok_or_else(overflow)?;
+
+    let end = match accessor.count {
+        0 => Some(start),
+        count => stride
+            .checked_mul(count - 1)
+            .and_then(|last| last.checked_add(start.checked_add(size)?)),
+    };
+    let fits = stride >= size && view_end <= bin.len() && end.map_or(false, |end| end <= view_end);
+
+    if !fits {
+        return Err(error("accessor runs past its buffer view"));
+    }
+
+    Ok((0..accessor.count).map(move |idx| &bin[start + idx * stride..][..size]))
+}
+
+fn read_vectors(
+    document: &Document,
+    bin: &[u8],
+    accessor: usize,
+) -> Result<Vec<Vector3<f32>>, MeshError> {
+    let accessor = document
+        .accessors
+        .get(accessor)
+        .ok_or_else(|| error("missing accessor"))?;
+
+    if accessor.kind != "VEC3" || accessor.component_type != COMPONENT_F32 {
+        return Err(error("positions and normals must be float VEC3"));
+    }
+
+    Ok(elements(document, bin, accessor, 12)?
+        .map(|bytes| Vector3::new(read_f32(bytes, 0), read_f32(bytes, 4), read_f32(bytes, 8)))
+        .collect())
+}
+
+fn read_indices(
+    document: &Document,
+    bin: &[u8],
+    accessor: usize,
+    scratch: &mut ScanScratch,
+) -> Result<Vec<u32>, MeshError> {
+    let accessor = document
+        .accessors
+        .get(accessor)
+        .ok_or_else(|| error("missing accessor"))?;
+
+    if accessor.kind != "SCALAR" {
+        return Err(error("indices must be SCALAR"));
+    }
+
+    let size = match accessor.component_type {
+        COMPONENT_U8 => 1,
+        COMPONENT_U16 => 2,
+        COMPONENT_U32 => 4,
+        _ => return Err(error("indices must be unsigned integers")),
+    };
+
+    // Checked against the buffer view before anything is allocated for it
+    let elements = elements(document, bin, accessor, size)?;
+    let mut indices = scratch.indices.take(accessor.count);
+
+    indices.extend(elements.map(|bytes| match size {
+        1 => u32::from(bytes[0]),
+        2 => u32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+        _ => read_u32(bytes, 0),
+    }));
+
+    Ok(indices)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut raw = [0; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(raw)
+}
+
+fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(read_u32(bytes, offset))
+}
+
+fn error(message: &str) -> MeshError {
+    MeshError::Parse(format!("glTF: {}", message))
+}
@@ -0,0 +1,136 @@
+//! Distributions of a scan's edge lengths and triangle areas
+//!
+//! A scanner meshes a capture at a fairly even resolution, so its edges
+//! cluster around one length. A second peak, or a long tail of much longer
+//! edges, usually means regions were patched or merged from another capture,
+//! which is worth labeling on its own
+
+use crate::analysis::WeldedMesh;
+
+/// Fraction of values the bins cover. Longer values are counted in
+/// [`Histogram::overflow`] so one huge patch does not squash the rest
+const RANGE_PERCENTILE: f32 = 0.99;
+
+/// Values more than this many times the median count toward
+/// [`Histogram::tail`]
+pub const TAIL_FACTOR: f32 = 3.0;
+
+/// A bin counts as a peak when it rises above its neighbours and holds at
+/// least this fraction of the tallest bin
+const PEAK_FRACTION: f32 = 0.25;
+
+/// Counts of values in equal bins from zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Upper end of the last bin
+    pub max: f32,
+    pub counts: Vec<usize>,
+    /// Values beyond `max`
+    pub overflow: usize,
+    pub median: f32,
+    /// Fraction of values more than [`TAIL_FACTOR`] times the median
+    pub tail: f32,
+}
+
+impl Histogram {
+    /// Sort `values` into `bins` bins. `None` if there are no values
+    pub fn new(mut values: Vec<f32>, bins: usize) -> Option<Self> {
+        if values.is_empty() || bins == 0 {
+            return None;
+        }
+
+        values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let percentile = |fraction: f32| values[((values.len() - 1) as f32 * fraction) as usize];
+        let median = percentile(0.5);
+        let max = percentile(RANGE_PERCENTILE).max(f32::EPSILON);
+
+        let mut counts = vec![0; bins];
+        let mut overflow = 0;
+
+        for &value in &values {
+            if value > max {
+                overflow += 1;
+            } else {
+                counts[((value / max * bins as f32) as usize).min(bins - 1)] += 1;
+            }
+        }
+
+        let long = values
+            .iter()
+            .filter(|&&value| value > median * TAIL_FACTOR)
+            .count();
+
+        Some(Self {
+            max,
+            counts,
+            overflow,
+            median,
+            tail: long as f32 / values.len() as f32,
+        })
+    }
+
+    pub fn bin_width(&self) -> f32 {
+        self.max / self.counts.len() as f32
+    }
+
+    /// Count of the fullest bin, including the overflow
+    pub fn tallest(&self) -> usize {
+        self.counts
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.overflow))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of distinct peaks. More than one suggests the scan mixes
+    /// regions meshed at different resolutions
+    pub fn peaks(&self) -> usize {
+        let threshold = (self.tallest() as f32 * PEAK_FRACTION).max(1.0);
+        let counts = &self.counts;
+
+        (0..counts.len())
+            .filter(|&idx| {
+                let count = counts[idx];
+                let left = if idx > 0 { counts[idx - 1] } else { 0 };
+                let right = counts.get(idx + 1).copied().unwrap_or(0);
+
+                count as f32 >= threshold && count > left && count >= right
+            })
+            .count()
+    }
+}
+
+/// The histograms shown for a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshHistograms {
+    /// Length of each edge, counted once however many triangles share it
+    pub edge_lengths: Histogram,
+    pub triangle_areas: Histogram,
+}
+
+impl MeshHistograms {
+    /// `None` for a scan without triangles
+    pub fn new(welded: &WeldedMesh, bins: usize) -> Option<Self> {
+        let lengths = welded
+            .edge_counts()
+            .keys()
+            .map(|&(a, b)| (welded.positions[a as usize] - welded.positions[b as usize]).norm())
+            .collect();
+
+        let areas = welded
+            .triangles
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = welded.corners(tri);
+                (b - a).cross(&(c - a)).norm() / 2.0
+            })
+            .collect();
+
+        Some(Self {
+            edge_lengths: Histogram::new(lengths, bins)?,
+            triangle_areas: Histogram::new(areas, bins)?,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! Translations of user-facing strings
+//!
+//! Catalogs live in `locales/<lang>.ftl` and use the subset of Fluent syntax
+//! made up of single-line `key = value` messages with `{ $name }`
+//! placeables. Keys missing from a catalog fall back to English
+
+use std::{collections::HashMap, fmt::Display};
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+
+/// Languages with a bundled catalog, as `(code, catalog)`
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", ENGLISH),
+    ("es", include_str!("../locales/es.ftl")),
+    ("pt", include_str!("../locales/pt.ftl")),
+];
+
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    lang: String,
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// The catalog for `lang`, e.g. `"es"` or `"pt_BR.UTF-8"`. Unknown
+    /// languages use English
+    pub fn new(lang: &str) -> Self {
+        let code = normalize(lang);
+
+        let (code, source) = CATALOGS
+            .iter()
+            .find(|(known, _)| *known == code)
+            .copied()
+            .unwrap_or(("en", ENGLISH));
+
+        Self {
+            lang: code.to_owned(),
+            messages: parse(source),
+            fallback: parse(ENGLISH),
+        }
+    }
+
+    /// The catalog for the language in the environment's locale variables
+    pub fn from_env() -> Self {
+        let lang = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        Self::new(&lang)
+    }
+
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let pattern = match self.messages.get(key).or_else(|| self.fallback.get(key)) {
+            Some(pattern) => pattern,
+            None => return key.to_owned(),
+        };
+
+        let mut out = String::with_capacity(pattern.len());
+        let mut rest = pattern.as_str();
+
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            };
+
+            let name = rest[start + 1..end].trim().trim_start_matches('$');
+
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(&rest[start..=end]),
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        out.push_str(rest);
+
+        out
+    }
+}
+
+/// `"pt_BR.UTF-8"` -> `"pt"`
+fn normalize(lang: &str) -> String {
+    lang.split(|c| c == '_' || c == '-' || c == '.')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn parse(source: &str) -> HashMap<String, String> {
+    source
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_at(line.find('=')?);
+
+            Some((
+                key.trim().to_owned(),
+                value[1..].trim_start_matches(' ').to_owned(),
+            ))
+        })
+        .collect()
+}
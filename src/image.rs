@@ -0,0 +1,221 @@
+use std::io::{Read, Write};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+
+/// PNG chunks marking pixels as sRGB, for viewers and report tools that
+/// color-manage images. `sRGB` holds the rendering intent, perceptual; `gAMA`
+/// and `cHRM` give the equivalent gamma and primaries, in units of 1/100000,
+/// for decoders that do not understand `sRGB`
+const SRGB_CHUNKS: [([u8; 4], &[u8]); 3] = [
+    (*b"sRGB", &[0]),
+    (*b"gAMA", &[0, 0, 0xb1, 0x8f]), // 45455
+    (
+        *b"cHRM",
+        &[
+            0, 0, 0x7a, 0x26, // white x 31270
+            0, 0, 0x80, 0x84, // white y 32900
+            0, 0, 0xfa, 0x00, // red x 64000
+            0, 0, 0x80, 0xe8, // red y 33000
+            0, 0, 0x75, 0x30, // green x 30000
+            0, 0, 0xea, 0x60, // green y 60000
+            0, 0, 0x3a, 0x98, // blue x 15000
+            0, 0, 0x17, 0x70, // blue y 6000
+        ],
+    ),
+];
+
+/// An 8-bit RGBA image stored top row first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Panics if `pixels` is not exactly `width * height * 4` bytes long
+    pub fn from_raw(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = self.offset(x, y);
+
+        [
+            self.pixels[start],
+            self.pixels[start + 1],
+            self.pixels[start + 2],
+            self.pixels[start + 3],
+        ]
+    }
+
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        let start = self.offset(x, y);
+
+        self.pixels[start..start + 4].copy_from_slice(&pixel);
+    }
+
+    /// Encode as an 8-bit RGBA PNG tagged as sRGB, which every image
+    /// rendered here is
+    pub fn write_png(&self, writer: impl Write) -> Result<(), png::EncodingError> {
+        self.write_png_with_text(writer, &[])
+    }
+
+    /// Like [`RgbaImage::write_png`], storing each `(keyword, text)` pair in
+    /// a text chunk: `tEXt` for ASCII text and `iTXt`, which holds UTF-8,
+    /// for the rest. Keywords must be 1 to 79 Latin-1 characters
+    pub fn write_png_with_text(
+        &self,
+        writer: impl Write,
+        text: &[(&str, &str)],
+    ) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+
+        for (name, data) in &SRGB_CHUNKS {
+            writer.write_chunk(*name, data)?;
+        }
+
+        for (keyword, value) in text {
+            let mut data = keyword.as_bytes().to_vec();
+            data.push(0);
+
+            if value.is_ascii() {
+                data.extend_from_slice(value.as_bytes());
+                writer.write_chunk(*b"tEXt", &data)?;
+            } else {
+                // Uncompressed, with empty language and translated keyword
+                data.extend_from_slice(&[0, 0, 0, 0]);
+                data.extend_from_slice(value.as_bytes());
+                writer.write_chunk(*b"iTXt", &data)?;
+            }
+        }
+
+        writer.write_image_data(&self.pixels)
+    }
+
+    /// Shrink by `factor` along each side, averaging each block of pixels in
+    /// linear light so edges are antialiased without darkening. Pixels past
+    /// the last whole block are dropped
+    pub fn downsample(&self, factor: u32) -> Self {
+        if factor <= 1 {
+            return self.clone();
+        }
+
+        let to_linear: Vec<f32> = (0..=255)
+            .map(|value| srgb_to_linear(value as f32 / 255.0))
+            .collect();
+
+        let (width, height) = (self.width / factor, self.height / factor);
+        let samples = (factor * factor) as f32;
+        let mut image = Self::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0_f32; 4];
+
+                for sy in 0..factor {
+                    for sx in 0..factor {
+                        let pixel = self.get_pixel(x * factor + sx, y * factor + sy);
+
+                        for (total, &value) in sum.iter_mut().zip(&pixel[..3]) {
+                            *total += to_linear[value as usize];
+                        }
+                        sum[3] += pixel[3] as f32 / 255.0;
+                    }
+                }
+
+                let encode = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+                image.put_pixel(
+                    x,
+                    y,
+                    [
+                        encode(linear_to_srgb(sum[0] / samples)),
+                        encode(linear_to_srgb(sum[1] / samples)),
+                        encode(linear_to_srgb(sum[2] / samples)),
+                        encode(sum[3] / samples),
+                    ],
+                );
+            }
+        }
+
+        image
+    }
+
+    /// Decode an 8-bit RGB or RGBA PNG, such as one written by
+    /// [`RgbaImage::write_png`]
+    pub fn read_png(reader: impl Read) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let (info, mut reader) = decoder.read_info()?;
+
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data)?;
+
+        let pixels = match (info.color_type, info.bit_depth) {
+            (png::ColorType::RGBA, png::BitDepth::Eight) => data,
+            (png::ColorType::RGB, png::BitDepth::Eight) => data
+                .chunks_exact(3)
+                .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            (color_type, bit_depth) => {
+                return Err(png::DecodingError::Other(
+                    format!("unsupported {:?} {:?} PNG", bit_depth, color_type).into(),
+                ))
+            }
+        };
+
+        Ok(Self::from_raw(info.width, info.height, pixels))
+    }
+
+    /// Reverse the order of the rows, converting between OpenGL's bottom-up
+    /// convention and the top-down one used by image formats
+    pub fn flip_vertical(&mut self) {
+        let row_len = self.width as usize * 4;
+        let height = self.height as usize;
+
+        for row in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - row - 1) * row_len);
+            top[row * row_len..(row + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+        }
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
@@ -0,0 +1,115 @@
+//! Running unattended under a process supervisor such as systemd
+//!
+//! In kiosk mode the tool is restarted rather than relaunched by hand, so it
+//! exits with codes a supervisor can act on, and tells systemd it is alive
+//! through the `sd_notify` protocol when started with `Type=notify` and
+//! `WatchdogSec=`. A frozen event loop stops the pings and gets the process
+//! restarted
+
+use std::time::{Duration, Instant};
+
+/// The queue was finished, or the station was shut down on purpose
+pub const EXIT_OK: i32 = 0;
+/// The queue could not be listed, e.g. a network mount is down. Worth
+/// restarting after a delay (`EX_TEMPFAIL`)
+pub const EXIT_QUEUE_UNAVAILABLE: i32 = 75;
+/// Labels could not be written, e.g. the disk is full (`EX_IOERR`)
+pub const EXIT_LABELS_UNWRITABLE: i32 = 74;
+
+/// How long to wait before reading a scan again after its source could not
+/// be reached
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Pings systemd's watchdog, if the service has one
+#[derive(Debug)]
+pub struct Watchdog {
+    #[cfg(unix)]
+    socket: Option<std::os::unix::net::UnixDatagram>,
+    #[cfg(unix)]
+    path: String,
+    /// Half the watchdog timeout, as systemd recommends
+    interval: Option<Duration>,
+    last_ping: Option<Instant>,
+}
+
+impl Watchdog {
+    /// Read `NOTIFY_SOCKET` and `WATCHDOG_USEC`. Without them every method
+    /// does nothing
+    pub fn from_env() -> Self {
+        let interval = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|&usec| usec > 0)
+            .map(|usec| Duration::from_micros(usec) / 2);
+
+        #[cfg(unix)]
+        {
+            // Abstract sockets, named with a leading `@`, cannot be reached
+            // through the standard library
+            let path = std::env::var("NOTIFY_SOCKET")
+                .ok()
+                .filter(|path| !path.is_empty() && !path.starts_with('@'));
+            let socket = path
+                .as_ref()
+                .and_then(|_| std::os::unix::net::UnixDatagram::unbound().ok());
+
+            Self {
+                socket,
+                path: path.unwrap_or_default(),
+                interval,
+                last_ping: None,
+            }
+        }
+
+        #[cfg(not(unix))]
+        Self {
+            interval,
+            last_ping: None,
+        }
+    }
+
+    /// Tell systemd startup is done
+    pub fn ready(&mut self) {
+        self.notify("READY=1");
+        self.last_ping = Some(Instant::now());
+    }
+
+    /// Ping the watchdog if the interval is up. Call from the event loop,
+    /// so pings stop if it hangs
+    pub fn poll(&mut self) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        if self
+            .last_ping
+            .map_or(true, |last_ping| last_ping.elapsed() >= interval)
+        {
+            self.notify("WATCHDOG=1");
+            self.last_ping = Some(Instant::now());
+        }
+    }
+
+    /// When the event loop must wake up to ping in time, if there is a
+    /// watchdog
+    pub fn next_ping(&self) -> Option<Instant> {
+        Some(self.last_ping? + self.interval?)
+    }
+
+    /// Tell systemd the station is shutting down on purpose
+    pub fn stopping(&mut self) {
+        self.notify("STOPPING=1");
+    }
+
+    #[cfg(unix)]
+    fn notify(&self, state: &str) {
+        if let Some(socket) = &self.socket {
+            // Nothing to be done if systemd is not listening
+            let _ = socket.send_to(state.as_bytes(), &self.path);
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn notify(&self, _state: &str) {}
+}
@@ -0,0 +1,377 @@
+//! Reading the label files written during a session
+//!
+//! Each label file holds one scan path per line, and the label assigned to
+//! those scans is the name of the category whose file it is
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+use crate::{categories::Categories, sample::Table};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LabelRecord {
+    pub path: String,
+    pub label: String,
+}
+
+/// The scans in the label file at `path`, labeled with the name of the
+/// category in `categories` whose file it is, or the file's name if it is
+/// none of theirs
+pub fn parse_label_file(
+    path: impl AsRef<Path>,
+    categories: &Categories,
+) -> io::Result<Vec<LabelRecord>> {
+    let path = path.as_ref();
+
+    let label = match categories.by_file(path) {
+        Some(index) => categories[index].name.clone(),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+
+    read_label_file(path, &label)
+}
+
+/// The scans in the label file at `path`, all labeled `label`
+pub fn read_label_file(path: impl AsRef<Path>, label: &str) -> io::Result<Vec<LabelRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut records = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        records.push(LabelRecord {
+            path: line.to_owned(),
+            label: label.to_owned(),
+        });
+    }
+
+    Ok(records)
+}
+
+/// The scans in a CSV file of labels with `path` and `label` columns, as
+/// written with `--csv`
+pub fn parse_label_csv(path: impl AsRef<Path>) -> io::Result<Vec<LabelRecord>> {
+    let table = Table::read(path)?;
+    let (path_column, label_column) = match (table.column("path"), table.column("label")) {
+        (Ok(path_column), Ok(label_column)) => (path_column, label_column),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "label CSV needs `path` and `label` columns",
+            ))
+        }
+    };
+
+    Ok(table
+        .rows
+        .iter()
+        .filter_map(|row| {
+            Some(LabelRecord {
+                path: row.get(path_column)?.clone(),
+                label: row.get(label_column)?.clone(),
+            })
+        })
+        .collect())
+}
+
+pub fn parse_label_files<P: AsRef<Path>>(
+    paths: &[P],
+    categories: &Categories,
+) -> io::Result<Vec<LabelRecord>> {
+    let mut records = Vec::new();
+
+    for path in paths {
+        records.extend(parse_label_file(path, categories)?);
+    }
+
+    Ok(records)
+}
+
+/// Number of scans assigned to each label
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionReport {
+    pub counts: BTreeMap<String, usize>,
+    pub total: usize,
+}
+
+impl SessionReport {
+    pub fn new(records: &[LabelRecord]) -> Self {
+        let mut report = Self::default();
+
+        for record in records {
+            report.push(&record.label);
+        }
+
+        report
+    }
+
+    pub fn push(&mut self, label: &str) {
+        *self.counts.entry(label.to_owned()).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Take back one scan pushed with `label`, if there is one
+    pub fn remove(&mut self, label: &str) {
+        if let Some(count) = self.counts.get_mut(label) {
+            *count -= 1;
+            self.total -= 1;
+
+            if *count == 0 {
+                self.counts.remove(label);
+            }
+        }
+    }
+
+    /// Share of all labeled scans with `label`, in `[0, 1]`
+    pub fn fraction(&self, label: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.counts.get(label).copied().unwrap_or(0) as f64 / self.total as f64
+    }
+}
+
+impl SessionReport {
+    /// Labels whose share of this report is more than `tolerance` away from
+    /// their expected share in `priors`, once at least `min_total` scans are
+    /// labeled. Labels not in `priors` are never reported
+    pub fn drift(
+        &self,
+        priors: &BTreeMap<String, f64>,
+        tolerance: f64,
+        min_total: usize,
+    ) -> Vec<PriorDrift> {
+        if self.total == 0 || self.total < min_total {
+            return Vec::new();
+        }
+
+        priors
+            .iter()
+            .map(|(label, &expected)| PriorDrift {
+                label: label.clone(),
+                fraction: self.fraction(label),
+                expected,
+            })
+            .filter(|drift| (drift.fraction - drift.expected).abs() > tolerance)
+            .collect()
+    }
+}
+
+/// A label assigned far more or less often than expected
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorDrift {
+    pub label: String,
+    /// Share of labeled scans, in `[0, 1]`
+    pub fraction: f64,
+    pub expected: f64,
+}
+
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} scans labeled", self.total)?;
+
+        for (label, count) in &self.counts {
+            writeln!(
+                f,
+                "  {:<12} {:>6} ({:.1}%)",
+                label,
+                count,
+                self.fraction(label) * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Labels given to the scans from each source, such as each scanner model or
+/// clinic, so one producing far more defects than the rest stands out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBreakdown {
+    /// By the kind of source, e.g. `scanner`, then the source
+    pub sources: BTreeMap<String, BTreeMap<String, SessionReport>>,
+}
+
+impl SourceBreakdown {
+    /// Count a scan from `source`, a `kind` of source, labeled `label`
+    pub fn push(&mut self, kind: &str, source: &str, label: &str) {
+        self.sources
+            .entry(kind.to_owned())
+            .or_default()
+            .entry(source.to_owned())
+            .or_default()
+            .push(label);
+    }
+
+    /// Take back a scan counted with [`SourceBreakdown::push`]
+    pub fn remove(&mut self, kind: &str, source: &str, label: &str) {
+        let sources = match self.sources.get_mut(kind) {
+            Some(sources) => sources,
+            None => return,
+        };
+
+        if let Some(report) = sources.get_mut(source) {
+            report.remove(label);
+
+            if report.total == 0 {
+                sources.remove(source);
+            }
+        }
+
+        if sources.is_empty() {
+            self.sources.remove(kind);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Write a row for each label given to each source, with its share of
+    /// the source's scans and, for comparison, its share of all scans in
+    /// `overall`
+    pub fn write_csv(&self, overall: &SessionReport, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "kind,source,label,count,share,overall_share")?;
+
+        for (kind, sources) in &self.sources {
+            for (source, report) in sources {
+                for (label, count) in &report.counts {
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{:.4},{:.4}",
+                        csv_escape(kind),
+                        csv_escape(source),
+                        csv_escape(label),
+                        count,
+                        report.fraction(label),
+                        overall.fraction(label)
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SourceBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (kind, sources) in &self.sources {
+            writeln!(f, "By {}:", kind)?;
+
+            for (source, report) in sources {
+                let shares: Vec<String> = report
+                    .counts
+                    .keys()
+                    .map(|label| format!("{} {:.1}%", label, report.fraction(label) * 100.0))
+                    .collect();
+
+                writeln!(
+                    f,
+                    "  {:<20} {:>6}  {}",
+                    source,
+                    report.total,
+                    shares.join(", ")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetSplit {
+    pub train: Vec<LabelRecord>,
+    pub validation: Vec<LabelRecord>,
+    pub test: Vec<LabelRecord>,
+}
+
+impl DatasetSplit {
+    /// Assign each record to a split by hashing its path, so a scan stays in
+    /// the same split as more labels are added
+    ///
+    /// `validation` and `test` are the fractions of records, in `[0, 1]`,
+    /// assigned to those splits. Everything else is used for training
+    pub fn new(records: &[LabelRecord], validation: f64, test: f64) -> Self {
+        let mut split = Self::default();
+
+        for record in records {
+            let bucket = (fnv1a(record.path.as_bytes()) % 10_000) as f64 / 10_000.0;
+
+            if bucket < test {
+                split.test.push(record.clone());
+            } else if bucket < test + validation {
+                split.validation.push(record.clone());
+            } else {
+                split.train.push(record.clone());
+            }
+        }
+
+        split
+    }
+
+    /// Write `train.csv`, `validation.csv`, and `test.csv` to `dir`
+    pub fn export(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+
+        fs::create_dir_all(dir)?;
+
+        for (name, records) in &[
+            ("train.csv", &self.train),
+            ("validation.csv", &self.validation),
+            ("test.csv", &self.test),
+        ] {
+            let mut file = File::create(dir.join(name))?;
+
+            writeln!(file, "path,label")?;
+
+            for record in records.iter() {
+                writeln!(
+                    file,
+                    "{},{}",
+                    csv_escape(&record.path),
+                    csv_escape(&record.label)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or line break
+pub fn csv_escape(field: &str) -> Cow<str> {
+    if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
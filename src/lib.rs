@@ -0,0 +1,242 @@
+pub use analysis::{
+    strip_redundant_triangles, strip_redundant_triangles_in, DisjointSet, MeshMetrics,
+    RedundantTriangles, Shells, WeldedMesh,
+};
+pub use arch::{Arch, ArchEstimate};
+pub use bite::BiteDistances;
+pub use camera::{step_scale, FlightCamera, StationaryCamera, COARSE_STEP_SCALE, FINE_STEP_SCALE};
+pub use categories::{Categories, CategoriesError, Category, CATEGORIES_PATH, RESERVED_KEYS};
+pub use clusters::{ClusterAssignment, Suggestion};
+pub use color::Color;
+pub use compression::{
+    read_decompressed, strip_compressed_extension, Compression, COMPRESSED_EXTENSIONS,
+};
+pub use config::{
+    AnalysisConfig, CacheConfig, Config, ConfigError, DisplayConfig, ExportConfig, LabelingConfig,
+    LoadingConfig, MaskConfig, MonitoringConfig, NotificationConfig, ReferenceConfig, VoxelConfig,
+    CONFIG_PATH,
+};
+pub use decimate::decimate;
+pub use decision::{new_session_id, parse_decisions, Decision};
+pub use deviation::PlaneDeviation;
+pub use filter::{Filter, FilterError};
+pub use fingerprint::{
+    FingerprintIndex, LabeledFingerprint, ScanEmbedding, ShapeFingerprint, FINGERPRINT_BINS,
+};
+pub use fitting::{Cylinder, Fit, Plane, Primitive, PrimitiveKind, Residuals, Sphere};
+pub use font::{FontAtlas, FontError};
+pub use format::ScanFormat;
+pub use gadget::{AxisEnd, AxisGadget};
+pub use histogram::{Histogram, MeshHistograms, TAIL_FACTOR};
+pub use i18n::Catalog;
+pub use image::RgbaImage;
+pub use labels::{
+    csv_escape, parse_label_csv, parse_label_file, parse_label_files, read_label_file,
+    DatasetSplit, LabelRecord, PriorDrift, SessionReport, SourceBreakdown,
+};
+pub use link::{LinkError, LinkTemplate};
+pub use loader::{
+    AsciiStlLoader, GlbLoader, MeshLoader, ObjLoader, PlyLoader, StlLoader, ThreeMfLoader,
+};
+pub use manifest::{Antagonist, ClipPreset, Manifest, ManifestEntry, ManifestError, ShadingMode};
+pub use mesh::{BoundingBox, Mesh, MeshError};
+pub use picking::{pick_point, pick_triangle, Hit, Ray, TriangleBvh};
+pub use queue::{build_queue, UrgentQueue};
+pub use reference::PrincipalFrame;
+pub use sample::{SampleError, StratifiedSample, Table};
+pub use scene::{Node, NodeId, Scene};
+pub use scratch::{BufferPool, ScanScratch};
+pub use sdf::{DistanceField, FieldSlice};
+pub use selection::{MaskFormat, MaskIndexing, SelectionMask};
+pub use session::{Checkpoint, Progress, Record, Session};
+pub use sheet::{ContactSheet, Paper, PdfWriter, SheetLayout};
+pub use slice::Section;
+pub use stamp::{format_utc, ImageStamp};
+pub use teeth::ToothCount;
+pub use theme::{Palette, Theme};
+pub use undercut::Undercuts;
+pub use vertex_format::{EncodedVertices, NormalFormat, PositionFormat, VertexFormat};
+pub use voxel::VoxelGrid;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+
+mod analysis;
+mod arch;
+mod ascii_stl;
+mod bite;
+mod camera;
+mod categories;
+pub mod cli;
+mod clusters;
+mod color;
+mod compression;
+mod config;
+mod decimate;
+mod decision;
+mod deviation;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod fingerprint;
+mod fitting;
+pub mod font;
+mod format;
+mod gadget;
+mod glb;
+mod histogram;
+mod i18n;
+mod image;
+mod labels;
+mod link;
+mod loader;
+mod manifest;
+mod mesh;
+mod obj;
+mod picking;
+mod ply;
+pub mod primitives;
+#[cfg(feature = "python")]
+mod python;
+mod queue;
+#[cfg(feature = "reconstruction")]
+pub mod reconstruct;
+mod reference;
+mod sample;
+mod scene;
+mod scratch;
+mod sdf;
+mod selection;
+mod session;
+mod sheet;
+mod slice;
+mod stamp;
+mod teeth;
+mod theme;
+mod three_mf;
+mod undercut;
+mod vertex_format;
+mod voxel;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+
+/// Desktop OpenGL rendering and windowing, unavailable when targeting the
+/// browser
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    pub use crate::backend::{GlBackend, PixelBackend, RenderBackend, ScanFrame};
+    pub use crate::buffer::{BufferElementType, IndexBuffer, VertexBuffer, VertexBufferLayout};
+    pub use crate::bug_report::BugReport;
+    pub use crate::capabilities::GlCapabilities;
+    pub use crate::context::{
+        create_shared_context, create_windowed_context, enable_debug_output, gl_string,
+        ContextAttempt, ContextFailure, CONTEXT_LADDER,
+    };
+    pub use crate::framebuffer::{
+        read_default_framebuffer, read_default_framebuffer_async, Framebuffer, PendingReadback,
+    };
+    pub use crate::kiosk::{
+        Watchdog, EXIT_LABELS_UNWRITABLE, EXIT_OK, EXIT_QUEUE_UNAVAILABLE, RECONNECT_DELAY,
+    };
+    pub use crate::lines::{LineBatch, LineRenderer, LineStyle};
+    pub use crate::mesh_cache::MeshCache;
+    pub use crate::metrics_cache::MetricsCache;
+    pub use crate::monitoring::{MetricsServer, StationMetrics};
+    pub use crate::offscreen::{
+        render_scan_to_image, render_software, OffscreenRenderer, RenderError, RenderOptions,
+        MAX_SUPERSAMPLING,
+    };
+    pub use crate::overlay::{OverlayBatch, OverlayPass};
+    pub use crate::prefetch::Prefetcher;
+    pub use crate::renderer::{
+        BufferContext, DrawCommand, DrawList, Instance, InstancedMesh, Light, RenderStats,
+        Renderer, ScanUniforms, UploadedMesh,
+    };
+    pub use crate::shader::{Material, Shader, Uniform};
+    pub use crate::software::SoftwareBackend;
+    pub use crate::source::{FileSource, ScanMetadata, ScanSource, SourceRegistry, ZipSource};
+    pub use crate::text::{TextBatch, TextRenderer, TextStyle};
+    pub use crate::texture::Texture;
+    pub use crate::throttle::ThrottledReader;
+    pub use crate::vertex_array::VertexArray;
+    pub use crate::webhook::{Webhook, WebhookError, WebhookEvent, RUNNING_MARKER_PATH};
+    pub use crate::wgpu_backend::{WgpuBackend, WgpuError};
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend;
+#[cfg(not(target_arch = "wasm32"))]
+mod buffer;
+#[cfg(not(target_arch = "wasm32"))]
+mod bug_report;
+#[cfg(not(target_arch = "wasm32"))]
+mod capabilities;
+#[cfg(not(target_arch = "wasm32"))]
+mod context;
+#[cfg(not(target_arch = "wasm32"))]
+mod framebuffer;
+#[cfg(not(target_arch = "wasm32"))]
+mod kiosk;
+#[cfg(not(target_arch = "wasm32"))]
+mod lines;
+#[cfg(not(target_arch = "wasm32"))]
+mod mesh_cache;
+#[cfg(not(target_arch = "wasm32"))]
+mod metrics_cache;
+#[cfg(not(target_arch = "wasm32"))]
+mod monitoring;
+#[cfg(not(target_arch = "wasm32"))]
+mod offscreen;
+#[cfg(not(target_arch = "wasm32"))]
+mod overlay;
+#[cfg(not(target_arch = "wasm32"))]
+mod prefetch;
+#[cfg(not(target_arch = "wasm32"))]
+mod renderer;
+#[cfg(not(target_arch = "wasm32"))]
+mod shader;
+#[cfg(not(target_arch = "wasm32"))]
+mod software;
+#[cfg(not(target_arch = "wasm32"))]
+mod source;
+#[cfg(not(target_arch = "wasm32"))]
+mod text;
+#[cfg(not(target_arch = "wasm32"))]
+mod texture;
+#[cfg(not(target_arch = "wasm32"))]
+mod throttle;
+#[cfg(not(target_arch = "wasm32"))]
+mod vertex_array;
+#[cfg(not(target_arch = "wasm32"))]
+mod webhook;
+#[cfg(not(target_arch = "wasm32"))]
+mod wgpu_backend;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn clear_error() {
+    while unsafe { gl::GetError() } != gl::NO_ERROR {}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[track_caller]
+pub fn check_error() {
+    assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR)
+}
+
+#[macro_export]
+macro_rules! check {
+    ($call:expr) => {{
+        #[cfg(debug_assertions)]
+        {
+            $crate::clear_error();
+            let x = $call;
+            $crate::check_error();
+            x
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            $call
+        }
+    }};
+}
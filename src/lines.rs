@@ -0,0 +1,193 @@
+use nalgebra::{Matrix4, Vector3};
+
+use crate::{
+    buffer::{BufferElementType, VertexBuffer, VertexBufferLayout},
+    check,
+    color::Color,
+    mesh::BoundingBox,
+    shader::{Shader, Uniform},
+    vertex_array::VertexArray,
+};
+
+const OVERLAY_VERTEX_SHADER: &str = include_str!("shaders/overlay-vs.shader");
+const OVERLAY_FRAGMENT_SHADER: &str = include_str!("shaders/overlay-fs.shader");
+const LINES_GEOMETRY_SHADER: &str = include_str!("shaders/lines-gs.shader");
+const POINTS_GEOMETRY_SHADER: &str = include_str!("shaders/points-gs.shader");
+
+/// Number of `f32`s per vertex: a position followed by a color
+const STRIDE: usize = 6;
+
+/// Line segments and points to draw over a scan, in model space
+#[derive(Debug, Clone, Default)]
+pub struct LineBatch {
+    lines: Vec<f32>,
+    points: Vec<f32>,
+}
+
+impl LineBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.points.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.points.is_empty()
+    }
+
+    pub fn line(&mut self, start: Vector3<f32>, end: Vector3<f32>, color: Color) {
+        push_vertex(&mut self.lines, start, color);
+        push_vertex(&mut self.lines, end, color);
+    }
+
+    pub fn point(&mut self, position: Vector3<f32>, color: Color) {
+        push_vertex(&mut self.points, position, color);
+    }
+
+    /// The twelve edges of `bbox`
+    pub fn bounding_box(&mut self, bbox: &BoundingBox, color: Color) {
+        let corner = |x: bool, y: bool, z: bool| {
+            Vector3::new(
+                if x { bbox.max.x } else { bbox.min.x },
+                if y { bbox.max.y } else { bbox.min.y },
+                if z { bbox.max.z } else { bbox.min.z },
+            )
+        };
+
+        for &a in &[false, true] {
+            for &b in &[false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+}
+
+fn push_vertex(data: &mut Vec<f32>, position: Vector3<f32>, color: Color) {
+    data.extend_from_slice(position.as_slice());
+    data.extend_from_slice(&[color.red, color.green, color.blue]);
+}
+
+/// Sizes of lines and points, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub line_width: f32,
+    pub point_size: f32,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            line_width: 1.5,
+            point_size: 6.0,
+        }
+    }
+}
+
+/// Draws a [`LineBatch`] with a width that does not depend on driver support
+/// for wide lines, by expanding each line and point into a quad in a
+/// geometry shader
+pub struct LineRenderer {
+    line_shader: Shader,
+    point_shader: Shader,
+    va: VertexArray,
+    vb: VertexBuffer,
+}
+
+impl LineRenderer {
+    /// Requires a current GL context with loaded function pointers
+    pub fn new() -> Self {
+        let mut va = VertexArray::new();
+        let vb = VertexBuffer::dynamic(&[]);
+        let mut layout = VertexBufferLayout::new();
+
+        layout.push(BufferElementType::Float, 3, false);
+        layout.push(BufferElementType::Float, 3, false);
+        va.add_buffer(&vb, &layout);
+
+        va.unbind();
+        vb.unbind();
+
+        Self {
+            line_shader: Shader::with_geometry(
+                OVERLAY_VERTEX_SHADER,
+                LINES_GEOMETRY_SHADER,
+                OVERLAY_FRAGMENT_SHADER,
+            ),
+            point_shader: Shader::with_geometry(
+                OVERLAY_VERTEX_SHADER,
+                POINTS_GEOMETRY_SHADER,
+                OVERLAY_FRAGMENT_SHADER,
+            ),
+            va,
+            vb,
+        }
+    }
+
+    /// `transform` maps the batch's positions to clip space, i.e.
+    /// `projection * view * model`
+    pub fn draw(
+        &mut self,
+        batch: &LineBatch,
+        transform: &Matrix4<f32>,
+        viewport_size: (u32, u32),
+        style: &LineStyle,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let mut data = Vec::with_capacity(batch.lines.len() + batch.points.len());
+        data.extend_from_slice(&batch.lines);
+        data.extend_from_slice(&batch.points);
+
+        self.vb.update(&data);
+        self.va.bind();
+
+        let line_vertices = (batch.lines.len() / STRIDE) as i32;
+        let point_vertices = (batch.points.len() / STRIDE) as i32;
+
+        let viewport = Uniform::TwoFloat {
+            name: "viewport_size",
+            v0: viewport_size.0 as f32,
+            v1: viewport_size.1 as f32,
+        };
+
+        if line_vertices > 0 {
+            self.line_shader.bind();
+            self.line_shader.set_uniform(&Uniform::MatrixFourFv {
+                name: "transform",
+                matrix: transform,
+            });
+            self.line_shader.set_uniform(&viewport);
+            self.line_shader.set_uniform(&Uniform::OneFloat {
+                name: "line_width",
+                v0: style.line_width,
+            });
+
+            check!(unsafe { gl::DrawArrays(gl::LINES, 0, line_vertices) });
+        }
+
+        if point_vertices > 0 {
+            self.point_shader.bind();
+            self.point_shader.set_uniform(&Uniform::MatrixFourFv {
+                name: "transform",
+                matrix: transform,
+            });
+            self.point_shader.set_uniform(&viewport);
+            self.point_shader.set_uniform(&Uniform::OneFloat {
+                name: "point_size",
+                v0: style.point_size,
+            });
+
+            check!(unsafe { gl::DrawArrays(gl::POINTS, line_vertices, point_vertices) });
+        }
+
+        self.va.unbind();
+        self.vb.unbind();
+    }
+}
@@ -0,0 +1,97 @@
+//! Links from a scan to its case in another system, built from a URL
+//! template such as `https://cases.example.com/{scan_id}`
+//!
+//! `{scan_id}` is the scan's file name without its extension, and `{path}`
+//! its path in the queue. Both are percent-encoded, so paths with spaces or
+//! archive separators still make working links
+
+use std::{fmt, path::Path};
+
+const PLACEHOLDERS: &[&str] = &["scan_id", "path"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTemplate {
+    template: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkError {
+    /// Byte offset into the template where the error was found
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid link template at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl LinkTemplate {
+    /// Check that every `{...}` in `template` is a known placeholder
+    pub fn parse(template: &str) -> Result<Self, LinkError> {
+        let mut rest = template;
+        let mut offset = 0;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| LinkError {
+                position: offset + start,
+                message: "unclosed `{`".to_owned(),
+            })?;
+            let name = &rest[start + 1..start + end];
+
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(LinkError {
+                    position: offset + start,
+                    message: format!(
+                        "unknown placeholder `{{{}}}`, expected `{{scan_id}}` or `{{path}}`",
+                        name
+                    ),
+                });
+            }
+
+            offset += start + end + 1;
+            rest = &rest[start + end + 1..];
+        }
+
+        Ok(Self {
+            template: template.to_owned(),
+        })
+    }
+
+    /// The link for the scan at `path`
+    pub fn url(&self, path: &str) -> String {
+        // Entries inside archives are named after the `!`
+        let entry = path.rsplit('!').next().unwrap_or(path);
+        let scan_id = Path::new(entry)
+            .file_stem()
+            .map_or_else(|| entry.into(), |stem| stem.to_string_lossy());
+
+        self.template
+            .replace("{scan_id}", &percent_encode(&scan_id))
+            .replace("{path}", &percent_encode(path))
+    }
+}
+
+/// Escape everything but the characters RFC 3986 leaves unreserved, and
+/// `/`, so paths stay readable
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for &byte in text.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
@@ -0,0 +1,201 @@
+//! Parsers for each scan format, behind one interface
+//!
+//! [`Mesh::from_bytes`] looks up the loader for the format a scan is sniffed
+//! as, so adding a format means implementing [`MeshLoader`] and returning it
+//! from [`ScanFormat::loader`]. Every loader produces the same indexed,
+//! interleaved position and normal buffers, whatever the file stores
+
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use crate::{
+    ascii_stl,
+    format::ScanFormat,
+    glb,
+    mesh::{Mesh, MeshError},
+    obj, ply,
+    scratch::ScanScratch,
+};
+
+pub trait MeshLoader: Sync {
+    /// Parse `buffer`, building the mesh in buffers taken from `scratch`
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError>;
+}
+
+/// Binary STL
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StlLoader;
+
+impl MeshLoader for StlLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        Mesh::from_stl_in(buffer, scratch)
+    }
+}
+
+/// ASCII STL. Files sniffed as ASCII only by their `solid` header may be
+/// binary files with trailing bytes, so those that do not parse as ASCII are
+/// read as binary instead
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiStlLoader;
+
+impl MeshLoader for AsciiStlLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        // A binary header read as text rarely fails outright, but has no
+        // facets
+        match ascii_stl::parse(buffer, scratch) {
+            Ok(mesh) if !mesh.is_empty() => Ok(mesh),
+            _ if is_binary_stl_size(buffer) => Mesh::from_stl_in(buffer, scratch),
+            result => result,
+        }
+    }
+}
+
+/// Wavefront OBJ
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjLoader;
+
+impl MeshLoader for ObjLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        obj::parse(buffer, scratch)
+    }
+}
+
+/// Stanford PLY, ASCII or binary of either byte order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlyLoader;
+
+impl MeshLoader for PlyLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        ply::parse(buffer, scratch)
+    }
+}
+
+/// Binary glTF 2.0, the first primitive of its first mesh
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlbLoader;
+
+impl MeshLoader for GlbLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        glb::parse(buffer, scratch)
+    }
+}
+
+/// 3MF, every object's mesh
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeMfLoader;
+
+impl MeshLoader for ThreeMfLoader {
+    fn load(&self, buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+        crate::three_mf::parse(buffer, scratch)
+    }
+}
+
+impl ScanFormat {
+    /// The parser for scans in this format, `None` if they cannot be parsed
+    /// yet
+    pub fn loader(self) -> Option<&'static dyn MeshLoader> {
+        match self {
+            Self::BinaryStl => Some(&StlLoader),
+            Self::AsciiStl => Some(&AsciiStlLoader),
+            Self::Obj => Some(&ObjLoader),
+            Self::Ply => Some(&PlyLoader),
+            Self::Glb => Some(&GlbLoader),
+            Self::ThreeMf => Some(&ThreeMfLoader),
+            Self::Zip => None,
+        }
+    }
+}
+
+/// Builds a mesh from triangles with flat normals, as STL stores them.
+/// Corners with the same position and normal share a vertex
+pub(crate) struct FacetBuilder {
+    remap: HashMap<[u32; 6], u32>,
+    vertices: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl FacetBuilder {
+    pub fn new(scratch: &mut ScanScratch) -> Self {
+        Self {
+            remap: HashMap::new(),
+            vertices: scratch.vertices.take(0),
+            indices: scratch.indices.take(0),
+        }
+    }
+
+    /// Add the triangle `corners` with `normal`, or the normal of its
+    /// winding if `normal` is zero, as some exporters write
+    pub fn push(&mut self, corners: [Vector3<f32>; 3], normal: Vector3<f32>) {
+        let [a, b, c] = corners;
+        let normal = normal
+            .try_normalize(f32::EPSILON)
+            .or_else(|| (b - a).cross(&(c - a)).try_normalize(f32::EPSILON))
+            .unwrap_or_else(Vector3::zeros);
+
+        for position in &corners {
+            let key = [
+                position.x.to_bits(),
+                position.y.to_bits(),
+                position.z.to_bits(),
+                normal.x.to_bits(),
+                normal.y.to_bits(),
+                normal.z.to_bits(),
+            ];
+            let next = self.remap.len() as u32;
+            let vertices = &mut self.vertices;
+
+            let vertex = *self.remap.entry(key).or_insert_with(|| {
+                vertices.extend(position.iter().chain(normal.iter()));
+                next
+            });
+
+            self.indices.push(vertex);
+        }
+    }
+
+    pub fn finish(self) -> Mesh {
+        Mesh::new(self.vertices, self.indices)
+    }
+}
+
+/// Whether `buffer` holds at least as many triangles as its binary STL
+/// header claims
+fn is_binary_stl_size(buffer: &[u8]) -> bool {
+    if buffer.len() < 84 {
+        return false;
+    }
+
+    let mut count = [0; 4];
+    count.copy_from_slice(&buffer[80..84]);
+
+    buffer.len() as u64 >= 84 + 50 * u64::from(u32::from_le_bytes(count))
+}
+
+/// The normal at each of `positions`, averaged over the triangles in
+/// `indices` using it and weighted by their area, for formats that may not
+/// store normals
+pub(crate) fn smooth_normals(positions: &[Vector3<f32>], indices: &[u32]) -> Vec<Vector3<f32>> {
+    let mut normals = vec![Vector3::zeros(); positions.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [
+            positions[triangle[0] as usize],
+            positions[triangle[1] as usize],
+            positions[triangle[2] as usize],
+        ];
+        let normal = (b - a).cross(&(c - a));
+
+        for &vertex in triangle {
+            normals[vertex as usize] += normal;
+        }
+    }
+
+    for normal in &mut normals {
+        *normal = normal
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros);
+    }
+
+    normals
+}
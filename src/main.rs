@@ -1,588 +1,5906 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::{self, Read, Write},
-    path::Path,
+    collections::{HashSet, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use glutin::{
     dpi::PhysicalSize,
-    event::{DeviceEvent, ElementState, Event, ModifiersState, VirtualKeyCode, WindowEvent},
+    event::{
+        DeviceEvent, ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
-    window::{CursorIcon, Window},
-    ContextWrapper, PossiblyCurrent,
+    window::{CursorIcon, Fullscreen, Window},
+    Context, ContextWrapper, NotCurrent, PossiblyCurrent,
 };
-use nalgebra::Vector3;
+use minifb::{Key, KeyRepeat, WindowOptions};
+use nalgebra::{Matrix3, Matrix4, Point3, Rotation3, Unit, Vector2, Vector3, U3};
 
-pub use buffer::{BufferElementType, IndexBuffer, VertexBuffer, VertexBufferLayout};
-pub use camera::FlightCamera;
-use nalgebra_glm::vec3;
-pub use renderer::Renderer;
-pub use shader::{Material, Shader, Uniform};
-use stl::StlFile;
-pub use texture::Texture;
-pub use vertex_array::VertexArray;
+use scan_classification::{
+    cli::{Args, EmbedArgs, SampleArgs, SheetArgs, EMBED_USAGE, SAMPLE_USAGE, SHEET_USAGE, USAGE},
+    create_shared_context, create_windowed_context, csv_escape, decimate, enable_debug_output,
+    format_utc, new_session_id, parse_decisions, parse_label_csv, parse_label_file, pick_point,
+    pick_triangle, read_decompressed, read_default_framebuffer, read_default_framebuffer_async,
+    read_label_file, render_software, step_scale, strip_redundant_triangles_in, AnalysisConfig,
+    Arch, ArchEstimate, AxisGadget, BiteDistances, BoundingBox, BugReport, CacheConfig, Catalog,
+    Categories, CategoriesError, Category, ClipPreset, ClusterAssignment, Color, Config,
+    ContactSheet, ContextAttempt, ContextFailure, Decision, DisplayConfig, DistanceField, Filter,
+    FingerprintIndex, Fit, FlightCamera, FontAtlas, GlBackend, GlCapabilities, Histogram,
+    ImageStamp, LabelRecord, LabeledFingerprint, Light, LineBatch, LineRenderer, LineStyle,
+    LinkTemplate, Manifest, ManifestEntry, ManifestError, Mesh, MeshCache, MeshError,
+    MeshHistograms, MeshMetrics, MetricsCache, MetricsServer, NodeId, OffscreenRenderer,
+    OverlayBatch, OverlayPass, PdfWriter, PendingReadback, PixelBackend, Plane, PlaneDeviation,
+    Prefetcher, PrimitiveKind, PrincipalFrame, Progress, Ray, Record, RenderBackend, RenderError,
+    RenderOptions, RenderStats, RgbaImage, SampleError, ScanEmbedding, ScanFrame, ScanScratch,
+    Scene, Section, SelectionMask, Session, ShadingMode, ShapeFingerprint, Shells, SoftwareBackend,
+    SourceBreakdown, SourceRegistry, StationaryCamera, StratifiedSample, Suggestion, Table,
+    TextStyle, Theme, ThrottledReader, ToothCount, TriangleBvh, Undercuts, UploadedMesh,
+    UrgentQueue, VoxelGrid, Watchdog, Webhook, WeldedMesh, WgpuBackend, CATEGORIES_PATH,
+    CONFIG_PATH, EXIT_LABELS_UNWRITABLE, EXIT_QUEUE_UNAVAILABLE, RECONNECT_DELAY, TAIL_FACTOR,
+};
+
+/// Scans that could not be shown are recorded here automatically. Created if
+/// missing, unlike the category files
+const INVALID_LABEL_PATH: &str = "./invalid";
+
+/// With `record_skipped` under `[labeling]`, scans passed over with Space
+/// are recorded here to be looked at again later. Created if missing
+const SKIPPED_LABEL_PATH: &str = "./skipped";
+/// How far through the queue the session got, rewritten after every scan
+/// committed and read back on restart to resume from
+const PROGRESS_PATH: &str = "./progress";
+
+/// Urgent scans are recorded here as well as in their category's file once
+/// labeled. Created if missing
+const EXPEDITED_LABEL_PATH: &str = "./expedited";
+/// Insertion axes set on scans while checking undercuts, appended as CSV
+/// rows of the scan's path and the axis in its coordinates
+const INSERTION_AXIS_PATH: &str = "./insertion-axes.csv";
+/// Fingerprints of the scans labeled, with `near_duplicate_distance` set
+/// under `[labeling]`, read back to notice scans labeled before
+const FINGERPRINTS_PATH: &str = "./fingerprints.csv";
+/// Labels given this session to each scanner and clinic named in the
+/// manifest, rewritten when the session ends
+const SOURCE_REPORT_PATH: &str = "./source-report.csv";
+/// With `--link-template`, each labeled scan's path, label, and case link,
+/// appended as CSV rows
+const LINKS_PATH: &str = "./links.csv";
+
+/// How often the urgent queue file is checked for new scans
+const URGENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far shells move apart in the exploded view, relative to their
+/// distance from the center of the scan
+const EXPLODE_FACTOR: f32 = 1.5;
+
+/// Half the height of the view after focusing with F, relative to the
+/// diagonal of the scan's bounding box
+const FOCUS_RADIUS: f32 = 0.1;
+
+/// How long the background flashes the category color after labeling
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+
+/// Inset of HUD text from the edges of the window, before the HUD scale
+const HUD_MARGIN: f32 = 12.0;
+/// Size of the category shown while a label is pending, relative to HUD text
+const CATEGORY_TEXT_SCALE: f32 = 8.0;
+
+/// Radius of the patch highlighted under the cursor, relative to the
+/// diagonal of the scan's bounding box
+const HOVER_RADIUS: f32 = 0.01;
+/// Opacity of the hover highlight at the point under the cursor, fading to
+/// nothing at its edge
+const HOVER_ALPHA: f32 = 0.6;
+/// Length of each arm of the crosshair and the gap at its center, in pixels
+/// before the HUD scale
+const CROSSHAIR_ARM: f32 = 8.0;
+const CROSSHAIR_GAP: f32 = 4.0;
+
+/// Initial radius of the selection brush, and the range `[` and `]` resize
+/// it within, relative to the diagonal of the scan's bounding box
+const BRUSH_RADIUS: f32 = 0.03;
+const BRUSH_RADIUS_RANGE: (f32, f32) = (0.005, 0.25);
+/// How much each press of `[` or `]` shrinks or grows the brush
+const BRUSH_RADIUS_STEP: f32 = 1.25;
+
+/// Flat colors the distance heatmap is drawn in
+const HEATMAP_BANDS: usize = 12;
+/// Opacity the antagonist is drawn at in the bite view, so contacts behind
+/// it stay visible
+const ANTAGONIST_ALPHA: f32 = 0.5;
+/// Size of the heatmap legend's color bar, in pixels before the HUD scale
+const LEGEND_SIZE: (f32, f32) = (16.0, 240.0);
+
+/// Bins in each histogram plot, the last followed by an overflow bar
+const HISTOGRAM_BINS: usize = 32;
+/// Size of each histogram plot, in pixels before the HUD scale
+const HISTOGRAM_SIZE: (f32, f32) = (192.0, 56.0);
+
+/// Half the size of the markers on counted teeth, in pixels before the HUD
+/// scale
+const TOOTH_MARKER_RADIUS: f32 = 9.0;
+
+/// Turn of the insertion axis per arrow key press, in degrees, before the
+/// step scale
+const AXIS_TILT_STEP: f32 = 2.0;
+/// Turn of the insertion axis per pixel dragged, in degrees
+const AXIS_DRAG_RATE: f32 = 0.25;
+/// While dragging the insertion axis, undercuts are found again at most this
+/// often, so large scans keep up with the drag
+const UNDERCUT_REFRESH: Duration = Duration::from_millis(100);
+/// Length of the insertion axis arrow, as a fraction of the diagonal of the
+/// scan's bounding box
+const AXIS_ARROW_LENGTH: f32 = 0.6;
+/// Width of the insertion axis arrow, in pixels before the HUD scale
+const AXIS_ARROW_WIDTH: f32 = 3.0;
+
+/// Size of the signed distance inset, in pixels before the HUD scale
+const DISTANCE_SIZE: (f32, f32) = (200.0, 200.0);
+/// How the signed distance caption names each axis
+const AXIS_NAMES: [&str; 3] = ["x", "y", "z"];
+
+/// Size of the cross section inset, in pixels before the HUD scale
+const SECTION_SIZE: (f32, f32) = (280.0, 200.0);
+/// Gridlines in the cross section are spaced so about this many span its
+/// longer side
+const SECTION_GRID_LINES: f32 = 8.0;
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("sample") => return sample(std::env::args().skip(2)),
+        Some("sheet") => return sheet(std::env::args().skip(2)),
+        Some("embed") => return embed(std::env::args().skip(2)),
+        _ => {}
+    }
+
+    let args = Args::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let catalog = match &args.lang {
+        Some(lang) => Catalog::new(lang),
+        None => Catalog::from_env(),
+    };
+
+    let event_loop = EventLoop::new();
+
+    let config_path = args.config.unwrap_or_else(|| CONFIG_PATH.to_owned());
+
+    let config = Config::load(&config_path).unwrap_or_else(|e| {
+        eprintln!("{}", catalog.format("config-error", &[("error", &e)]));
+        Config::default()
+    });
+
+    let categories = Categories::load(args.labels.as_deref().unwrap_or(CATEGORIES_PATH))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", describe_categories_error(&catalog, &e));
+            std::process::exit(2);
+        });
+
+    let sources = SourceRegistry::default();
+
+    let manifest = match &args.manifest {
+        Some(path) => Manifest::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", describe_manifest_error(&catalog, &e));
+            std::process::exit(2);
+        }),
+        None => Manifest::default(),
+    };
+
+    let path_queue = match &args.queue_root {
+        Some(root) => list_queue(&sources, root, &catalog),
+        None if args.manifest.is_some() => manifest.queue(),
+        None => vec![
+            "Eiffel_tower_sample.stl".to_owned(),
+            "Utah_teapot_(solid).stl".to_owned(),
+        ],
+    };
+
+    let filter = args.filter.as_ref().map(|expr| {
+        Filter::parse(expr).unwrap_or_else(|e| {
+            eprintln!(
+                "{}",
+                catalog.format(
+                    "filter-invalid",
+                    &[("position", &e.position), ("message", &e.message)],
+                )
+            );
+            std::process::exit(2);
+        })
+    });
+
+    let clusters = args.clusters.as_ref().map(|path| {
+        ClusterAssignment::read(path).unwrap_or_else(|e| {
+            eprintln!("{}", describe_table_error(&catalog, &e));
+            std::process::exit(2);
+        })
+    });
+
+    // Decisions in every tab share a session
+    let output = LabelOutput::new(&args, new_session_id());
+
+    // In the order clusters are labeled, so progress is saved over it, and
+    // with the labels of exemplars labeled before a restart
+    let new_session = |path_queue: Vec<String>, tab: Option<&str>| match &clusters {
+        Some(clusters) => Session::new(path_queue)
+            .with_clusters(clusters.clone())
+            .with_recorded(output.recorded(&categories, tab)),
+        None => Session::new(path_queue),
+    };
+
+    let kiosk = args.kiosk;
+    let prepare_queue = |path_queue: Vec<String>, tab: Option<&str>| {
+        let path_queue = match &filter {
+            Some(filter) => filter_queue(path_queue, filter, &sources, &config, &catalog),
+            None => path_queue,
+        };
+
+        // A restarted station picks up where it left off
+        if kiosk {
+            resume_queue(path_queue, &output, &categories, tab, &catalog)
+        } else {
+            path_queue
+        }
+    };
+
+    let path_queue = prepare_queue(path_queue, None);
+
+    let tabs: Vec<QueueTab> = args
+        .tabs
+        .iter()
+        .map(|(name, root)| {
+            let path_queue =
+                prepare_queue(list_queue(&sources, root, &catalog), Some(name.as_str()));
+
+            let mut path_loader =
+                PathLoader::for_tab(name, &output, &categories, config.export.annotator())
+                    .unwrap_or_else(|e| labels_unwritable(&catalog, &e));
+
+            if config.labeling.record_skipped {
+                path_loader = path_loader.with_skipped(tab_path(Some(name), SKIPPED_LABEL_PATH));
+            }
+
+            if config.labeling.near_duplicate_distance > 0.0 {
+                path_loader =
+                    path_loader.with_fingerprints(tab_path(Some(name), FINGERPRINTS_PATH));
+            }
+
+            let mut session = new_session(path_queue, Some(name.as_str()));
+
+            if !kiosk {
+                resume_progress(&path_loader, &mut session, Some(name.as_str()), &catalog);
+            }
+
+            QueueTab {
+                name: Some(name.clone()),
+                path_loader,
+                session,
+                breakdown: SourceBreakdown::default(),
+            }
+        })
+        .collect();
+
+    let manifest = Arc::new(manifest);
+
+    let mut session = new_session(path_queue, None);
+    let mut path_loader = PathLoader::new(
+        output.open(&categories, config.export.annotator(), None),
+        INVALID_LABEL_PATH,
+        INSERTION_AXIS_PATH,
+        PROGRESS_PATH,
+    );
+
+    // Kiosks already leave out scans that were recorded
+    if !kiosk {
+        resume_progress(&path_loader, &mut session, None, &catalog);
+    }
+
+    if let Some(urgent) = &args.urgent {
+        path_loader = path_loader.with_urgent(UrgentQueue::new(urgent), EXPEDITED_LABEL_PATH);
+    }
+
+    if let Some(template) = &args.link_template {
+        let template = LinkTemplate::parse(template).unwrap_or_else(|e| {
+            eprintln!(
+                "{}",
+                catalog.format(
+                    "link-template-invalid",
+                    &[("position", &e.position), ("message", &e.message)],
+                )
+            );
+            std::process::exit(2);
+        });
+
+        path_loader = path_loader.with_links(template, LINKS_PATH);
+    }
+
+    if config.labeling.record_skipped {
+        path_loader = path_loader.with_skipped(SKIPPED_LABEL_PATH);
+    }
+
+    if config.labeling.near_duplicate_distance > 0.0 {
+        path_loader = path_loader.with_fingerprints(FINGERPRINTS_PATH);
+    }
+
+    if let Some(webhook) = Webhook::new(
+        &config.notifications,
+        config.export.annotator(),
+        catalog.clone(),
+    ) {
+        webhook.install_panic_hook();
+        path_loader = path_loader.with_webhook(webhook);
+    }
+
+    let window = move || {
+        let window = glutin::window::WindowBuilder::new().with_title("");
+
+        if kiosk {
+            window.with_fullscreen(Some(Fullscreen::Borderless(None)))
+        } else {
+            window
+        }
+    };
+
+    let theme = config.theme;
+    let display = config.display;
+    let cache = mesh_cache(&config.cache, &catalog);
+
+    let failure = match create_windowed_context(window, &event_loop, display.vsync) {
+        Ok((window, attempt)) => {
+            println!(
+                "{}",
+                catalog.format("context-created", &[("attempt", &attempt)])
+            );
+
+            let upload_context = match create_shared_context(&window, &event_loop, attempt) {
+                Ok(context) => Some(context),
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        catalog.format("shared-context-unavailable", &[("error", &e)])
+                    );
+                    None
+                }
+            };
+
+            Program::init(
+                window,
+                attempt,
+                upload_context,
+                path_loader,
+                session,
+                categories.clone(),
+                sources,
+                manifest.clone(),
+                config,
+                config_path,
+                catalog,
+                kiosk,
+                tabs,
+            )
+            .run(event_loop)
+        }
+        Err(failure) => describe_context_failure(&catalog, &failure),
+    };
+
+    eprintln!("{}", failure);
+
+    let message = match FallbackViewer::new(
+        fallback_backend(&catalog, &theme),
+        path_loader,
+        session,
+        categories,
+        sources,
+        manifest,
+        theme,
+        display,
+        cache,
+        catalog.clone(),
+        kiosk,
+        tabs,
+    ) {
+        Ok(viewer) => return viewer.run(),
+        Err(e) => format!(
+            "{}\n\n{}",
+            failure,
+            catalog.format("software-failure", &[("error", &e)])
+        ),
+    };
+
+    eprintln!("{}", message);
+    tinyfiledialogs::message_box_ok(
+        "Scan classification",
+        &message,
+        tinyfiledialogs::MessageBoxIcon::Error,
+    );
+
+    std::process::exit(1);
+}
+
+/// `scan-classification sample`: write a queue balanced across groups of a
+/// table
+fn sample(args: impl Iterator<Item = String>) {
+    let args = SampleArgs::parse(args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, SAMPLE_USAGE);
+        std::process::exit(2);
+    });
+
+    if args.help {
+        println!("{}", SAMPLE_USAGE);
+        return;
+    }
+
+    let table = args.table.unwrap();
+    let by = args.by.unwrap();
+    let catalog = Catalog::from_env();
+
+    let sample = Table::read(&table)
+        .map_err(SampleError::from)
+        .and_then(|table| StratifiedSample::new(&table, &by, args.per_group.unwrap(), args.seed))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", describe_table_error(&catalog, &e));
+            std::process::exit(1);
+        });
+
+    for (group, paths) in &sample.groups {
+        println!("  {:<12} {:>6}", group, paths.len());
+    }
+
+    let queue = sample.queue();
+    let count = queue.len();
+
+    match Manifest::from_queue(queue).save(&args.output) {
+        Ok(()) => println!(
+            "{}",
+            catalog.format("sample-saved", &[("count", &count), ("path", &args.output)])
+        ),
+        Err(e) => {
+            eprintln!("{}", catalog.format("sample-failed", &[("error", &e)]));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `scan-classification sheet`: tile thumbnails of labeled scans onto
+/// printable pages
+fn sheet(args: impl Iterator<Item = String>) {
+    let args = SheetArgs::parse(args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, SHEET_USAGE);
+        std::process::exit(2);
+    });
+
+    if args.help {
+        println!("{}", SHEET_USAGE);
+        return;
+    }
+
+    let catalog = Catalog::from_env();
+    let categories = Categories::load(CATEGORIES_PATH).unwrap_or_else(|e| {
+        eprintln!("{}", describe_categories_error(&catalog, &e));
+        std::process::exit(2);
+    });
+
+    // Categories nobody has labeled into yet have no file
+    let label_files: Vec<String> = if args.label_files.is_empty() {
+        categories
+            .files()
+            .iter()
+            .filter(|path| path.exists())
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    } else {
+        args.label_files.clone()
+    };
+
+    let labels: Vec<Vec<LabelRecord>> = label_files
+        .iter()
+        .map(|path| parse_label_file(path, &categories))
+        .collect::<io::Result<_>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", catalog.format("sheet-failed", &[("error", &e)]));
+            std::process::exit(1);
+        });
+
+    if labels.iter().all(Vec::is_empty) {
+        eprintln!("{}", catalog.get("sheet-empty"));
+        std::process::exit(1);
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| {
+        if args.png {
+            "contact-sheets".to_owned()
+        } else {
+            "contact-sheet.pdf".to_owned()
+        }
+    });
+
+    match write_contact_sheets(&labels, &args, &output, &catalog) {
+        Ok(pages) => println!(
+            "{}",
+            catalog.format("sheet-saved", &[("pages", &pages), ("path", &output)])
+        ),
+        Err(e) => {
+            eprintln!("{}", catalog.format("sheet-failed", &[("error", &e)]));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `scan-classification embed`: write the metrics and fingerprint of every
+/// scan to a CSV file for clustering
+fn embed(args: impl Iterator<Item = String>) {
+    let args = EmbedArgs::parse(args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, EMBED_USAGE);
+        std::process::exit(2);
+    });
+
+    if args.help {
+        println!("{}", EMBED_USAGE);
+        return;
+    }
+
+    let catalog = Catalog::from_env();
+    let sources = SourceRegistry::default();
+    let queue = list_queue(&sources, args.queue_root.as_deref().unwrap(), &catalog);
+
+    match write_embeddings(&sources, &queue, &args.output, &catalog) {
+        Ok(count) => println!(
+            "{}",
+            catalog.format("embed-saved", &[("count", &count), ("path", &args.output)])
+        ),
+        Err(e) => {
+            eprintln!("{}", catalog.format("embed-failed", &[("error", &e)]));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Measure each scan of `queue` and write it as a row of the CSV file at
+/// `output`. Scans that cannot be read are left out. Returns the number of
+/// rows written
+fn write_embeddings(
+    sources: &SourceRegistry,
+    queue: &[String],
+    output: &str,
+    catalog: &Catalog,
+) -> io::Result<usize> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut scratch = ScanScratch::new();
+    let mut contents = Vec::new();
+    let mut count = 0;
+
+    writeln!(writer, "{}", ScanEmbedding::header())?;
+
+    for path in queue {
+        let loaded = read_scan(sources, path, None, &mut contents)
+            .map_err(MeshError::from)
+            .and_then(|()| Mesh::from_bytes_in(&contents, Some(path.as_str()), &mut scratch));
+
+        let mesh = match loaded {
+            Ok(mesh) => mesh,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    catalog.format("embed-skipped", &[("path", &path), ("error", &e)])
+                );
+                continue;
+            }
+        };
+
+        // Measured as while labeling, see `PreparedScan::new`
+        let welded = WeldedMesh::new_in(&mesh, &mut scratch);
+        let metrics = MeshMetrics::from_welded(&mesh, &welded);
+        let embedding = ScanEmbedding::new(&metrics, ShapeFingerprint::new(&welded));
+
+        embedding.write_row(&mut writer, path)?;
+        count += 1;
+
+        scratch.recycle(mesh);
+    }
+
+    writer.flush()?;
+
+    Ok(count)
+}
+
+/// Render and lay out the scans of each label, writing pages as they fill.
+/// Returns the number of pages written
+fn write_contact_sheets(
+    labels: &[Vec<LabelRecord>],
+    args: &SheetArgs,
+    output: &str,
+    catalog: &Catalog,
+) -> io::Result<usize> {
+    let layout = args.layout;
+    let size = layout.thumbnail_size();
+
+    // Printed on white, so scans are drawn darker than on screen
+    let options = RenderOptions {
+        width: size,
+        height: size,
+        supersampling: 2,
+        object_color: Color::new(0.6, 0.6, 0.6),
+        background: Color::WHITE,
+        ..RenderOptions::default()
+    };
+
+    let mut renderer = match OffscreenRenderer::new() {
+        Ok(renderer) => Some(renderer),
+        Err(e) => {
+            eprintln!("{}", catalog.format("sheet-software", &[("error", &e)]));
+            None
+        }
+    };
+
+    let sources = SourceRegistry::default();
+    let atlas = FontAtlas::default_font();
+    let mut scratch = ScanScratch::new();
+    let mut contents = Vec::new();
+
+    let mut render = |path: &str| -> Result<RgbaImage, RenderError> {
+        read_scan(&sources, path, None, &mut contents).map_err(MeshError::from)?;
+        let mesh = Mesh::from_bytes_in(&contents, Some(path), &mut scratch)?;
+
+        let image = match &mut renderer {
+            Some(renderer) => renderer.render(&mesh, &options)?,
+            None => render_software(&mesh, &options),
+        };
+
+        scratch.recycle(mesh);
+
+        Ok(image)
+    };
+
+    let mut pdf = if args.png {
+        fs::create_dir_all(output)?;
+        None
+    } else {
+        Some(PdfWriter::new(BufWriter::new(File::create(output)?))?)
+    };
+
+    let mut pages = 0;
+
+    let mut save = |label: &str, page: RgbaImage| -> io::Result<()> {
+        pages += 1;
+
+        match &mut pdf {
+            Some(pdf) => pdf.add_page(&page, layout.paper),
+            None => {
+                // Numbered first so the files sort in the order they print
+                let path = Path::new(output).join(format!("{:03}-{}.png", pages, label));
+                let mut writer = BufWriter::new(File::create(path)?);
+
+                page.write_png(&mut writer)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                writer.flush()
+            }
+        }
+    };
+
+    for records in labels {
+        let label = match records.first() {
+            Some(record) => &record.label,
+            None => continue,
+        };
+
+        let mut sheet = ContactSheet::new(layout, &atlas, label.as_str());
+
+        for record in records {
+            let thumbnail = match render(&record.path) {
+                Ok(thumbnail) => thumbnail,
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        catalog.format("sheet-skipped", &[("path", &record.path), ("error", &e)])
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(page) = sheet.push(&record.path, &thumbnail) {
+                save(label, page)?;
+            }
+        }
+
+        if let Some(page) = sheet.finish() {
+            save(label, page)?;
+        }
+    }
+
+    if let Some(pdf) = pdf {
+        pdf.finish()?;
+    }
+
+    Ok(pages)
+}
+
+fn describe_context_failure(catalog: &Catalog, failure: &ContextFailure) -> String {
+    let mut message = catalog.get("context-failure");
+
+    for (attempt, reason) in &failure.attempts {
+        message.push_str("\n  ");
+        message.push_str(&catalog.format(
+            "context-failure-attempt",
+            &[("attempt", attempt), ("reason", reason)],
+        ));
+    }
+
+    message.push('\n');
+    message.push_str(&catalog.get("context-failure-hint"));
+
+    message
+}
+
+fn describe_categories_error(catalog: &Catalog, error: &CategoriesError) -> String {
+    match error {
+        CategoriesError::Io(e) => catalog.format("categories-unreadable", &[("error", e)]),
+        CategoriesError::Parse(e) => catalog.format("categories-invalid", &[("error", e)]),
+        CategoriesError::Invalid(reason) => {
+            catalog.format("categories-invalid", &[("error", reason)])
+        }
+    }
+}
+
+fn describe_manifest_error(catalog: &Catalog, error: &ManifestError) -> String {
+    match error {
+        ManifestError::Io(e) => catalog.format("manifest-unreadable", &[("error", e)]),
+        ManifestError::Parse(e) => catalog.format("manifest-invalid", &[("error", e)]),
+    }
+}
+
+/// For tables read by `sample` and cluster assignments, which share a
+/// reader
+fn describe_table_error(catalog: &Catalog, error: &SampleError) -> String {
+    match error {
+        SampleError::Io(e) => catalog.format("table-unreadable", &[("error", e)]),
+        SampleError::MissingColumn(column) => {
+            catalog.format("table-missing-column", &[("column", column)])
+        }
+    }
+}
+
+/// Draw through Direct3D, Vulkan, or Metal if any works, and on the CPU
+/// otherwise
+fn fallback_backend(catalog: &Catalog, theme: &Theme) -> Box<dyn PixelBackend> {
+    let (width, height) = (FallbackViewer::WIDTH as u32, FallbackViewer::HEIGHT as u32);
+
+    match WgpuBackend::new(width, height, &Light::white(), theme.object_color()) {
+        Ok(backend) => {
+            eprintln!(
+                "{}",
+                catalog.format("gpu-fallback", &[("api", &backend.api())])
+            );
+
+            Box::new(backend)
+        }
+        Err(e) => {
+            eprintln!("{}", catalog.format("gpu-unavailable", &[("error", &e)]));
+            eprintln!("{}", catalog.get("software-fallback"));
+
+            Box::new(SoftwareBackend::new(
+                width,
+                height,
+                &Light::white(),
+                theme.object_color(),
+            ))
+        }
+    }
+}
+
+fn mesh_cache(config: &CacheConfig, catalog: &Catalog) -> Option<MeshCache> {
+    let directory = config.directory.clone()?;
+
+    Some(MeshCache::new(
+        directory,
+        config.max_bytes(),
+        catalog.clone(),
+    ))
+}
+
+/// The scans in `queue` whose metrics match `filter`, in order
+///
+/// Metrics are taken from the metrics cache when the cache is configured
+/// and the scan has not changed, and measured (and cached) otherwise.
+/// Scans that cannot be loaded never match
+fn filter_queue(
+    queue: Vec<String>,
+    filter: &Filter,
+    sources: &SourceRegistry,
+    config: &Config,
+    catalog: &Catalog,
+) -> Vec<String> {
+    let cache = mesh_cache(&config.cache, catalog);
+    let mut metrics_cache = config
+        .cache
+        .directory
+        .as_ref()
+        .map(|directory| MetricsCache::open(directory, catalog));
+
+    let total = queue.len();
+
+    let mut scratch = ScanScratch::new();
+    let mut contents = Vec::new();
+
+    let mut measure = |path: &str| -> Result<MeshMetrics, MeshError> {
+        read_scan(
+            sources,
+            path,
+            config.loading.max_bytes_per_sec(),
+            &mut contents,
+        )?;
+
+        let mesh = match &cache {
+            Some(cache) => cache.parse(&contents, Some(path))?,
+            None => Mesh::from_bytes_in(&contents, Some(path), &mut scratch)?,
+        };
+
+        let metrics = MeshMetrics::from_welded(&mesh, &WeldedMesh::new_in(&mesh, &mut scratch));
+        scratch.recycle(mesh);
+
+        Ok(metrics)
+    };
+
+    let matched: Vec<String> = queue
+        .into_iter()
+        .filter(|path| {
+            let metadata = sources.metadata(path).unwrap_or_default();
+
+            let cached = metrics_cache
+                .as_ref()
+                .and_then(|cache| cache.get(path, &metadata));
+
+            let metrics = match cached {
+                Some(metrics) => metrics,
+                None => match measure(path) {
+                    Ok(metrics) => {
+                        if let Some(cache) = &mut metrics_cache {
+                            cache.insert(path, &metadata, metrics);
+                        }
+
+                        metrics
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "{}",
+                            catalog.format("filter-skipped", &[("path", path), ("error", &e)])
+                        );
+                        return false;
+                    }
+                },
+            };
+
+            filter.matches(&metrics)
+        })
+        .collect();
+
+    if let Some(cache) = &metrics_cache {
+        if let Err(e) = cache.save() {
+            eprintln!(
+                "{}",
+                catalog.format("metrics-cache-failed", &[("error", &e)])
+            );
+        }
+    }
+
+    println!(
+        "{}",
+        catalog.format(
+            "filter-summary",
+            &[("matched", &matched.len()), ("total", &total)]
+        )
+    );
+
+    matched
+}
+
+/// Read a scan into `contents`, replacing what it held, no faster than
+/// `max_bytes_per_sec` if given
+fn read_scan(
+    sources: &SourceRegistry,
+    uri: &str,
+    max_bytes_per_sec: Option<u64>,
+    contents: &mut Vec<u8>,
+) -> io::Result<()> {
+    let reader = sources.fetch(uri)?;
+    contents.clear();
+
+    match max_bytes_per_sec {
+        Some(rate) => read_decompressed(ThrottledReader::new(reader, rate), contents)?,
+        None => read_decompressed(reader, contents)?,
+    };
+
+    Ok(())
+}
+
+/// The reference template at `path`, with its frame to line it up with
+/// scans
+fn load_reference(path: &str) -> Result<(Mesh, PrincipalFrame), MeshError> {
+    let mesh = Mesh::open(path)?;
+    let positions: Vec<Vector3<f32>> = (0..mesh.vertex_count() as u32)
+        .map(|idx| mesh.position(idx))
+        .collect();
+
+    match PrincipalFrame::new(&positions) {
+        Some(frame) => Ok((mesh, frame)),
+        None => Err(MeshError::Parse(String::from(
+            "too few vertices to line up with scans",
+        ))),
+    }
+}
+
+/// Stop for the supervisor to try again later when labels cannot be written,
+/// e.g. the disk is full or a network mount dropped, rather than carry on
+/// without them
+fn labels_unwritable(catalog: &Catalog, error: &io::Error) -> ! {
+    eprintln!(
+        "{}",
+        catalog.format("labels-unwritable", &[("error", error)])
+    );
+    std::process::exit(EXIT_LABELS_UNWRITABLE);
+}
+
+/// Every scan under `root`, exiting for the supervisor to try again later if
+/// it cannot be listed
+fn list_queue(sources: &SourceRegistry, root: &str, catalog: &Catalog) -> Vec<String> {
+    sources.list(root).unwrap_or_else(|e| {
+        eprintln!(
+            "{}",
+            catalog.format("queue-unavailable", &[("path", &root), ("error", &e)])
+        );
+        std::process::exit(EXIT_QUEUE_UNAVAILABLE);
+    })
+}
+
+/// `path_queue` without the scans already recorded for the tab `tab`, or
+/// for the main queue
+fn resume_queue(
+    path_queue: Vec<String>,
+    output: &LabelOutput,
+    categories: &Categories,
+    tab: Option<&str>,
+    catalog: &Catalog,
+) -> Vec<String> {
+    let recorded = recorded_paths(output, categories, tab);
+    let queue_len = path_queue.len();
+    let remaining: Vec<String> = path_queue
+        .into_iter()
+        .filter(|path| !recorded.contains(path))
+        .collect();
+
+    println!(
+        "{}",
+        catalog.format(
+            "kiosk-resumed",
+            &[
+                ("recorded", &(queue_len - remaining.len())),
+                ("remaining", &remaining.len())
+            ]
+        )
+    );
+
+    remaining
+}
+
+/// Pick `session` up where the last session over the same queue, in the tab
+/// `tab` or the main queue, left off
+fn resume_progress(
+    path_loader: &PathLoader,
+    session: &mut Session,
+    tab: Option<&str>,
+    catalog: &Catalog,
+) {
+    let progress = match path_loader.load_progress() {
+        Some(progress) => progress,
+        None => return,
+    };
+
+    let message = if session.resume(&progress) {
+        catalog.format(
+            "progress-resumed",
+            &[
+                ("index", &(progress.cursor + 1)),
+                ("recorded", &progress.recorded),
+            ],
+        )
+    } else {
+        catalog.get("progress-stale")
+    };
+
+    match tab {
+        Some(tab) => println!(
+            "{}",
+            catalog.format("tab-title", &[("tab", &tab), ("title", &message)])
+        ),
+        None => println!("{}", message),
+    }
+}
+
+/// Every scan labeled or in the invalid file of the tab `tab`, or of the
+/// main queue, so a restarted kiosk does not show them again
+fn recorded_paths(
+    output: &LabelOutput,
+    categories: &Categories,
+    tab: Option<&str>,
+) -> HashSet<String> {
+    let invalid = read_label_file(tab_path(tab, INVALID_LABEL_PATH), "").unwrap_or_default();
+
+    output
+        .recorded(categories, tab)
+        .into_iter()
+        .chain(invalid)
+        .map(|record| record.path)
+        .collect()
+}
+
+fn scan_title(catalog: &Catalog, path: &str, metrics: &MeshMetrics) -> String {
+    let mut title = catalog.format(
+        "scan-title",
+        &[
+            ("path", &path),
+            ("triangles", &metrics.triangle_count),
+            ("volume", &format!("{:.1}", metrics.volume)),
+            ("area", &format!("{:.1}", metrics.surface_area)),
+            ("holes", &metrics.hole_count),
+            ("shells", &metrics.shell_count),
+            ("noise", &format!("{:.1}", metrics.noise)),
+        ],
+    );
+
+    if metrics.degenerate_triangle_count + metrics.duplicate_triangle_count > 0 {
+        title.push_str(", ");
+        title.push_str(&catalog.format(
+            "redundant-triangles",
+            &[
+                ("degenerate", &metrics.degenerate_triangle_count),
+                ("duplicate", &metrics.duplicate_triangle_count),
+            ],
+        ));
+    }
+
+    if metrics.inconsistent_triangle_count > 0 {
+        title.push_str(", ");
+        title.push_str(&catalog.format(
+            "inconsistent-triangles",
+            &[("count", &metrics.inconsistent_triangle_count)],
+        ));
+    }
+
+    title
+}
+
+/// The smallest of 1, 2, or 5 times a power of ten that is at least `step`,
+/// so gridlines fall on round distances
+fn grid_spacing(step: f32) -> f32 {
+    let magnitude = 10.0_f32.powi(step.max(f32::MIN_POSITIVE).log10().floor() as i32);
+
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|&spacing| spacing >= step)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Where `position` falls in the window, in pixels from the top left, or
+/// `None` if it is behind the camera
+fn to_screen(
+    transform: &Matrix4<f32>,
+    dimensions: PhysicalSize<u32>,
+    position: Vector3<f32>,
+) -> Option<Vector2<f32>> {
+    let clip = transform * Point3::from(position).to_homogeneous();
+
+    if clip.w <= f32::EPSILON {
+        return None;
+    }
+
+    Some(Vector2::new(
+        (clip.x / clip.w + 1.0) / 2.0 * dimensions.width as f32,
+        (1.0 - clip.y / clip.w) / 2.0 * dimensions.height as f32,
+    ))
+}
+
+/// The letter or digit `key` types, for labeling with categories bound to it
+fn key_char(key: VirtualKeyCode) -> Option<char> {
+    use VirtualKeyCode::*;
+
+    const KEYS: [VirtualKeyCode; 36] = [
+        Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+
+    KEYS.iter()
+        .position(|&other| other == key)
+        .and_then(|index| std::char::from_digit(index as u32, 36))
+}
+
+/// The key of the software viewer's window that types `key`, a letter or
+/// digit
+fn software_key(key: char) -> Option<Key> {
+    use Key::*;
+
+    const KEYS: [Key; 36] = [
+        Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+
+    key.to_digit(36).map(|index| KEYS[index as usize])
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Hand `url` to the desktop's handler for links
+fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        // The empty argument is the window title `start` expects first
+        command.args(&["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(url).spawn().map(drop)
+}
+
+struct PathLoader {
+    invalid_file: File,
+    axis_file: File,
+    sink: LabelSink,
+    urgent: Option<Urgent>,
+    links: Option<Links>,
+    webhook: Option<Webhook>,
+    skipped_file: Option<File>,
+    fingerprints: Option<Fingerprints>,
+    progress_path: PathBuf,
+    /// How long each file was before each scan committed this session was
+    /// written, most recent last, so commits can be taken back
+    commits: Vec<Vec<(LabelFile, u64)>>,
+}
+
+/// One of the files [`PathLoader`] appends to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LabelFile {
+    Category(ScanKind),
+    Invalid,
+    Axis,
+    Links,
+    Expedited,
+    Skipped,
+    Fingerprints,
+    Csv,
+    Decisions,
+}
+
+impl LabelFile {
+    /// Every file but the categories'
+    const OTHERS: [Self; 8] = [
+        Self::Invalid,
+        Self::Axis,
+        Self::Links,
+        Self::Expedited,
+        Self::Skipped,
+        Self::Fingerprints,
+        Self::Csv,
+        Self::Decisions,
+    ];
+}
+
+/// Where labels are written, chosen on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LabelOutput {
+    /// A line in the file of each scan's category
+    CategoryFiles,
+    /// A row of the CSV file at the path, given with `--csv`, instead
+    Csv(String),
+    /// A line of the JSON Lines file at the path, given with `--jsonl`,
+    /// instead, with every other decision
+    Decisions { path: String, session_id: String },
+}
+
+impl LabelOutput {
+    /// Decisions written with `--jsonl` are marked with `session_id`
+    fn new(args: &Args, session_id: String) -> Self {
+        match (&args.label_csv, &args.jsonl) {
+            (Some(csv), _) => Self::Csv(csv.clone()),
+            (None, Some(jsonl)) => Self::Decisions {
+                path: jsonl.clone(),
+                session_id,
+            },
+            (None, None) => Self::CategoryFiles,
+        }
+    }
+
+    /// The files labels are written to in the tab `tab`, or the main queue,
+    /// created if missing
+    fn open(
+        &self,
+        categories: &Categories,
+        annotator: Option<String>,
+        tab: Option<&str>,
+    ) -> LabelSink {
+        match self {
+            Self::CategoryFiles => LabelSink::CategoryFiles(
+                categories
+                    .files()
+                    .iter()
+                    .map(|path| {
+                        OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(tab_path(tab, path))
+                            .unwrap()
+                    })
+                    .collect(),
+            ),
+            Self::Csv(csv) => {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(tab_path(tab, csv))
+                    .unwrap();
+
+                if file
+                    .metadata()
+                    .map_or(false, |metadata| metadata.len() == 0)
+                {
+                    file.write_all(b"path,label,timestamp,annotator,duration_ms\n")
+                        .unwrap();
+                }
+
+                LabelSink::Csv(LabelCsv { file, annotator })
+            }
+            Self::Decisions { path, session_id } => LabelSink::Decisions(DecisionLog {
+                file: OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(tab_path(tab, path))
+                    .unwrap(),
+                session_id: session_id.clone(),
+            }),
+        }
+    }
+
+    /// Every scan labeled so far in the tab `tab`, or the main queue. Files
+    /// that do not exist yet have none
+    fn recorded(&self, categories: &Categories, tab: Option<&str>) -> Vec<LabelRecord> {
+        match self {
+            Self::CategoryFiles => categories
+                .iter()
+                .filter_map(|category| {
+                    read_label_file(tab_path(tab, category.file()), &category.name).ok()
+                })
+                .flatten()
+                .collect(),
+            Self::Csv(csv) => parse_label_csv(tab_path(tab, csv)).unwrap_or_default(),
+            Self::Decisions { path, .. } => {
+                parse_decisions(tab_path(tab, path)).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// The open files of a [`LabelOutput`]
+enum LabelSink {
+    /// Indexed like the categories
+    CategoryFiles(Vec<File>),
+    Csv(LabelCsv),
+    Decisions(DecisionLog),
+}
+
+/// Every label in one CSV file, with when, by whom, and how quickly it was
+/// given
+struct LabelCsv {
+    file: File,
+    annotator: Option<String>,
+}
+
+/// Every decision as a line of JSON, see [`Decision`]
+struct DecisionLog {
+    file: File,
+    session_id: String,
+}
+
+/// Shapes of the scans labeled, see [`FingerprintIndex`]
+struct Fingerprints {
+    index: FingerprintIndex,
+    file: File,
+}
+
+/// Links to each scan's case, see [`LinkTemplate`]
+struct Links {
+    template: LinkTemplate,
+    file: File,
+}
+
+/// Scans that preempt the queue, see [`Session::promote_urgent`]
+struct Urgent {
+    queue: UrgentQueue,
+    expedited_file: File,
+    last_poll: Option<Instant>,
+}
+
+impl PathLoader {
+    /// Append labels to `sink`
+    pub fn new(
+        sink: LabelSink,
+        invalid_path: impl AsRef<Path>,
+        axis_path: impl AsRef<Path>,
+        progress_path: impl AsRef<Path>,
+    ) -> Self {
+        let invalid_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(invalid_path)
+            .unwrap();
+        let mut axis_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(axis_path)
+            .unwrap();
+
+        if axis_file
+            .metadata()
+            .map_or(false, |metadata| metadata.len() == 0)
+        {
+            axis_file.write_all(b"path,x,y,z\n").unwrap();
+        }
+
+        Self {
+            sink,
+            invalid_file,
+            axis_file,
+            urgent: None,
+            links: None,
+            webhook: None,
+            skipped_file: None,
+            fingerprints: None,
+            progress_path: progress_path.as_ref().to_owned(),
+            commits: Vec::new(),
+        }
+    }
+
+    /// The progress saved by the last session over these files, if any
+    pub fn load_progress(&self) -> Option<Progress> {
+        Progress::parse(&fs::read_to_string(&self.progress_path).ok()?)
+    }
+
+    /// Replace the saved progress with `progress`. Written to a temporary
+    /// file first, so a crash while saving leaves the previous progress
+    pub fn save_progress(&self, progress: &Progress) -> io::Result<()> {
+        let temporary = self.progress_path.with_extension("tmp");
+
+        fs::write(&temporary, progress.to_string())?;
+        fs::rename(&temporary, &self.progress_path)
+    }
+
+    fn file_mut(&mut self, file: LabelFile) -> Option<&mut File> {
+        match file {
+            LabelFile::Category(scan_kind) => match &mut self.sink {
+                LabelSink::CategoryFiles(files) => files.get_mut(scan_kind.index()),
+                LabelSink::Csv(_) | LabelSink::Decisions(_) => None,
+            },
+            LabelFile::Invalid => Some(&mut self.invalid_file),
+            LabelFile::Axis => Some(&mut self.axis_file),
+            LabelFile::Links => self.links.as_mut().map(|links| &mut links.file),
+            LabelFile::Expedited => self
+                .urgent
+                .as_mut()
+                .map(|urgent| &mut urgent.expedited_file),
+            LabelFile::Skipped => self.skipped_file.as_mut(),
+            LabelFile::Fingerprints => self
+                .fingerprints
+                .as_mut()
+                .map(|fingerprints| &mut fingerprints.file),
+            LabelFile::Csv => match &mut self.sink {
+                LabelSink::Csv(csv) => Some(&mut csv.file),
+                LabelSink::CategoryFiles(_) | LabelSink::Decisions(_) => None,
+            },
+            LabelFile::Decisions => match &mut self.sink {
+                LabelSink::Decisions(log) => Some(&mut log.file),
+                LabelSink::CategoryFiles(_) | LabelSink::Csv(_) => None,
+            },
+        }
+    }
+
+    /// Note how long every file is before a scan is committed to them, so
+    /// [`PathLoader::undo`] can take it back
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let categories = match &self.sink {
+            LabelSink::CategoryFiles(files) => files.len(),
+            LabelSink::Csv(_) | LabelSink::Decisions(_) => 0,
+        };
+        let files: Vec<LabelFile> = (0..categories)
+            .map(|index| LabelFile::Category(ScanKind(index)))
+            .chain(LabelFile::OTHERS.iter().copied())
+            .collect();
+        let mut lengths = Vec::with_capacity(files.len());
+
+        for file in files {
+            if let Some(handle) = self.file_mut(file) {
+                lengths.push((file, handle.metadata()?.len()));
+            }
+        }
+
+        self.commits.push(lengths);
+
+        Ok(())
+    }
+
+    /// Cut every file back to how long it was at the last checkpoint
+    pub fn undo(&mut self) -> io::Result<()> {
+        let lengths = match self.commits.pop() {
+            Some(lengths) => lengths,
+            None => return Ok(()),
+        };
+
+        for (file, len) in lengths {
+            if let Some(handle) = self.file_mut(file) {
+                handle.set_len(len)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The label files of the tab `name`, in a directory of that name,
+    /// created along with them if missing
+    pub fn for_tab(
+        name: &str,
+        output: &LabelOutput,
+        categories: &Categories,
+        annotator: Option<String>,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(name)?;
+
+        Ok(Self::new(
+            output.open(categories, annotator, Some(name)),
+            tab_path(Some(name), INVALID_LABEL_PATH),
+            tab_path(Some(name), INSERTION_AXIS_PATH),
+            tab_path(Some(name), PROGRESS_PATH),
+        ))
+    }
+
+    /// Poll `queue` for urgent scans, and record them in the file at
+    /// `expedited_path` once labeled
+    pub fn with_urgent(mut self, queue: UrgentQueue, expedited_path: impl AsRef<Path>) -> Self {
+        let expedited_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(expedited_path)
+            .unwrap();
+
+        self.urgent = Some(Urgent {
+            queue,
+            expedited_file,
+            last_poll: None,
+        });
+
+        self
+    }
+
+    /// Record a link to each scan's case, built from `template`, in the file
+    /// at `links_path` as it is labeled
+    pub fn with_links(mut self, template: LinkTemplate, links_path: impl AsRef<Path>) -> Self {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(links_path)
+            .unwrap();
+
+        if file
+            .metadata()
+            .map_or(false, |metadata| metadata.len() == 0)
+        {
+            file.write_all(b"path,label,url\n").unwrap();
+        }
+
+        self.links = Some(Links { template, file });
+
+        self
+    }
+
+    /// Record scans passed over without a label in the file at
+    /// `skipped_path`
+    pub fn with_skipped(mut self, skipped_path: impl AsRef<Path>) -> Self {
+        self.skipped_file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(skipped_path)
+                .unwrap(),
+        );
+
+        self
+    }
+
+    /// Record the shape of each scan labeled in the file at
+    /// `fingerprints_path`, after reading back those recorded before
+    pub fn with_fingerprints(mut self, fingerprints_path: impl AsRef<Path>) -> Self {
+        let fingerprints_path = fingerprints_path.as_ref();
+        let index = FingerprintIndex::load(fingerprints_path).unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(fingerprints_path)
+            .unwrap();
+
+        if file
+            .metadata()
+            .map_or(false, |metadata| metadata.len() == 0)
+        {
+            writeln!(file, "{}", FingerprintIndex::header()).unwrap();
+        }
+
+        self.fingerprints = Some(Fingerprints { index, file });
+
+        self
+    }
+
+    /// Tell `webhook` when scans fail to load and when the queue runs out
+    pub fn with_webhook(mut self, webhook: Webhook) -> Self {
+        self.webhook = Some(webhook);
+
+        self
+    }
+}
+
+struct Program {
+    window: ContextWrapper<PossiblyCurrent, Window>,
+    stationary: StationaryCamera,
+    scene: Scene,
+    /// Rotated by the arrow keys and mouse
+    scan_node: NodeId,
+    /// Child of the scan, toggled with B
+    bounding_box_node: NodeId,
+    camera: FlightCamera,
+    window_state: WindowState,
+    control_flow: ControlFlow,
+    backend: GlBackend,
+    stl_context: StlContext,
+    config: Config,
+    config_path: String,
+    context_attempt: ContextAttempt,
+    capabilities: GlCapabilities,
+    line_renderer: LineRenderer,
+    overlay: OverlayPass,
+    /// Metrics of the current scan, kept for redrawing the title
+    metrics: Option<MeshMetrics>,
+    /// Connected pieces of the current scan
+    shells: Option<Shells>,
+    /// Shells clicked for removal from the cleaned export, indexed like
+    /// `shells`
+    removed_shells: Vec<bool>,
+    /// Toggled with E; shells are spread apart to reveal floating debris
+    exploded: bool,
+    /// Triangles of the scan as displayed, exploded or not, for picking the
+    /// one under the cursor every frame
+    hover_bvh: Option<TriangleBvh>,
+    /// The current scan welded, which painted regions are indexed by
+    welded: Option<WeldedMesh>,
+    /// Regions of the current scan painted with the brush
+    selection: SelectionMask,
+    /// Triangles in `selection` and their area, updated as it is painted
+    selected_area: (usize, f64),
+    /// Toggled with M; dragging paints regions instead of rotating
+    brush_mode: bool,
+    /// Relative to the diagonal of the scan's bounding box
+    brush_radius: f32,
+    /// While dragging the brush, whether it selects or deselects
+    painting: Option<bool>,
+    /// Toggled with H; the scan is colored by its distance from its
+    /// occlusal plane
+    show_heatmap: bool,
+    /// Distances of the current scan, measured when the heatmap is first
+    /// shown
+    deviation: Option<PlaneDeviation>,
+    /// Toggled with O; the scan's antagonist from its manifest entry is
+    /// drawn in articulation with it, and the scan colored by the gap
+    show_bite: bool,
+    /// Measured when the bite is first shown, `None` if the scan has no
+    /// antagonist or it could not be read
+    bite: Option<BiteDistances>,
+    /// Toggled with V; the configured reference template is drawn over the
+    /// scan, lined up with it. Off while the bite is shown, which draws over
+    /// the scan in the same way
+    show_reference: bool,
+    /// Read once at startup, `None` if not configured or unreadable
+    reference: Option<(Mesh, PrincipalFrame)>,
+    /// Toggled with J; the scan is drawn as the blocks of its occupancy
+    /// grid instead of its surface
+    show_voxels: bool,
+    /// The current scan's occupancy grid, found when first shown or saved
+    voxels: Option<VoxelGrid>,
+    /// Toggled with U; triangles hidden along the scan's insertion axis are
+    /// highlighted as undercuts
+    show_undercuts: bool,
+    /// Found when undercuts are first shown
+    undercuts: Option<Undercuts>,
+    /// Direction undercuts are found along, in the scan's coordinates. Set
+    /// to the default when undercuts are first shown, turned with Ctrl and
+    /// the arrow keys or by dragging with Ctrl held, and written out with
+    /// the scan's label
+    insertion_axis: Option<Vector3<f32>>,
+    /// The current scan as loaded, for finding undercuts again as the axis
+    /// turns
+    undercut_bvh: Option<TriangleBvh>,
+    /// Set while dragging with Ctrl held to turn the insertion axis
+    aiming_axis: bool,
+    /// When undercuts were last found while dragging
+    undercuts_found: Option<Instant>,
+    /// Toggled with L; plots of the current scan's edge lengths and triangle
+    /// areas are shown in the bottom left
+    show_histograms: bool,
+    /// Computed when the plots are first shown
+    histograms: Option<MeshHistograms>,
+    /// Toggled with K; the current scan's cross section is shown in the top
+    /// right
+    show_section: bool,
+    section: Option<Section>,
+    /// Cycled with Z through slices across x, y, and z; a slice of the
+    /// current scan's signed distance field is shown in the top left
+    distance_axis: Option<usize>,
+    /// Grid points along `distance_axis` to the slice shown, stepped with
+    /// Page Up and Page Down
+    distance_slice: usize,
+    /// Sampled when a slice is first shown
+    distance_field: Option<DistanceField>,
+    /// Shown as a hint at the top of the window
+    arch: Option<ArchEstimate>,
+    teeth: Option<ToothCount>,
+    /// Toggled with T; each tooth counted is marked on the scan
+    show_teeth: bool,
+    /// Of the current scan, recorded with its label
+    fingerprint: Option<ShapeFingerprint>,
+    /// The path and label of a scan labeled before that the current scan
+    /// has nearly the same shape as
+    near_duplicate: Option<(String, String)>,
+    /// Cycled with G; the shape fitted to the painted region
+    fit_kind: Option<PrimitiveKind>,
+    /// Updated as the region is painted
+    fit: Option<Fit>,
+    /// Of the current scan's welded vertices, computed for the first
+    /// cylinder fit
+    vertex_normals: Option<Vec<Vector3<f32>>>,
+    catalog: Catalog,
+    /// Set by Ctrl+B; the bundle is written after the next frame is drawn so
+    /// the screenshot matches what is on screen
+    bug_report_requested: bool,
+    /// A bug report waiting on its screenshot to be read back from the GPU
+    pending_bug_report: Option<(BugReport, PendingReadback)>,
+    /// Set by Ctrl+E; the view is read back after the next frame's 3D pass,
+    /// before the HUD is drawn over it
+    export_requested: bool,
+    pending_export: Option<(ImageStamp, PendingReadback)>,
+    /// Category color and the time the last label was committed
+    flash: Option<(Color, Instant)>,
+    /// A label waiting out the configured commit delay, and when it was
+    /// given
+    pending_label: Option<(ScanKind, Instant)>,
+    /// A label the config asks to confirm, waiting for Y or N
+    confirming_label: Option<ScanKind>,
+    /// When recent labels were committed, oldest first
+    label_times: VecDeque<Instant>,
+    /// Set when the last label was given faster than the configured pace
+    labeling_too_fast: bool,
+    /// Toggled with I; the scan pass's draw counters are drawn over the scan
+    show_stats: bool,
+    /// Counters of the last frame drawn
+    frame_stats: RenderStats,
+    /// Served to monitoring, if `monitoring.metrics_address` is set
+    station_metrics: Option<MetricsServer>,
+    /// Set with `--kiosk`, when nobody is around to retry loads and the
+    /// usual quit keys are disabled
+    kiosk: bool,
+    watchdog: Watchdog,
+    /// The next scan, while it loads
+    loading: Option<Loading>,
+    /// Drawn instead of the current scan if it cannot be shown
+    placeholder: Option<Placeholder>,
+    /// Prepares the next scan in the queue while the current one is shown
+    prefetcher: Prefetcher<Result<PreparedScan, MeshError>>,
+    /// Set by input; in power-saving mode frames are only drawn when this is
+    /// set or an animation is running
+    needs_redraw: bool,
+    /// When the last frame was presented, for the frame-rate cap
+    last_frame: Option<Instant>,
+    /// The current scan's decimated proxy, kept to hand to the backend again
+    /// after the exploded view
+    lod_proxy: Option<Mesh>,
+    /// Where the scan was drawn last frame, combining the model, view, and
+    /// projection
+    last_view: Option<Matrix4<f32>>,
+    /// When the view last moved, until it has held still for
+    /// `lod_settle_ms`
+    view_moved: Option<Instant>,
+}
+
+struct StlContext {
+    path_loader: PathLoader,
+    session: Session,
+    current: Option<Mesh>,
+    /// When the current scan was opened, for how long it took to label
+    opened: Option<Instant>,
+    /// Of the current scan, recorded with decisions about it
+    metrics: Option<MeshMetrics>,
+    stl_buffer: Vec<u8>,
+    /// Used instead of parsing if configured
+    cache: Option<MeshCache>,
+    sources: SourceRegistry,
+    /// Per-scan render settings
+    manifest: Arc<Manifest>,
+    /// Labels given this session to scans whose manifest entry names where
+    /// they came from
+    breakdown: SourceBreakdown,
+    /// Of the queue open, if it was given with `--tab`
+    tab_name: Option<String>,
+    /// The other queues open, in the order Ctrl+Tab goes through them
+    tabs: VecDeque<QueueTab>,
+    /// Shared by every tab
+    categories: Categories,
+    catalog: Catalog,
+}
+
+/// A queue set aside in another tab, with everything that is its own.
+/// Rendering, prefetching, and the scan cache are shared by every tab
+struct QueueTab {
+    /// `None` for the queue given without `--tab`
+    name: Option<String>,
+    path_loader: PathLoader,
+    session: Session,
+    breakdown: SourceBreakdown,
+}
+
+/// A scan being loaded on the prefetch thread
+#[derive(Debug)]
+struct Loading {
+    path: String,
+    started: Instant,
+    /// Set once the load takes longer than the configured timeout, after
+    /// which it can be retried or skipped
+    timed_out: bool,
+    /// In kiosk mode, when to read the scan again after its source could
+    /// not be reached
+    retry_at: Option<Instant>,
+}
+
+/// Why the current scan is replaced by a placeholder
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Placeholder {
+    /// No triangles, or none left after stripping degenerate ones
+    Empty,
+    /// The file could not be read or parsed. Must be explicitly recorded as
+    /// invalid or skipped rather than labeled
+    Corrupt(String),
+}
+
+impl Placeholder {
+    /// Shown in the title bar in place of the scan's metrics
+    pub fn title(&self, catalog: &Catalog, path: &str) -> String {
+        match self {
+            Self::Empty => catalog.format("empty-scan", &[("path", &path)]),
+            Self::Corrupt(error) => {
+                catalog.format("corrupt-scan", &[("path", &path), ("error", error)])
+            }
+        }
+    }
+}
+
+/// One of the [`Categories`] a scan can be labeled with
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct ScanKind(usize);
+
+impl ScanKind {
+    /// Position of the category in the categories file, and in the theme's
+    /// palette
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl StlContext {
+    /// `path_loader` must write to the files of `categories`
+    pub fn new(
+        path_loader: PathLoader,
+        session: Session,
+        categories: Categories,
+        cache: Option<MeshCache>,
+        sources: SourceRegistry,
+        manifest: Arc<Manifest>,
+        catalog: Catalog,
+    ) -> Self {
+        Self {
+            path_loader,
+            session,
+            categories,
+            catalog,
+            stl_buffer: Vec::new(),
+            cache,
+            sources,
+            manifest,
+            current: None,
+            opened: None,
+            metrics: None,
+            breakdown: SourceBreakdown::default(),
+            tab_name: None,
+            tabs: VecDeque::new(),
+        }
+    }
+
+    pub fn category(&self, scan_kind: ScanKind) -> &Category {
+        &self.categories[scan_kind.index()]
+    }
+
+    /// The label the current scan's cluster suggests, with its category,
+    /// unless that is no longer one of the categories
+    pub fn suggestion(&self) -> Option<(Suggestion, ScanKind)> {
+        let suggestion = self.session.suggestion()?;
+        let index = self
+            .categories
+            .iter()
+            .position(|category| category.name == suggestion.label)?;
+
+        Some((suggestion, ScanKind(index)))
+    }
+
+    /// The category labeled with `key`, if any
+    pub fn category_for_key(&self, key: char) -> Option<ScanKind> {
+        self.categories.by_key(key).map(ScanKind)
+    }
+
+    /// Open each of `tabs` alongside the main queue
+    pub fn with_tabs(mut self, tabs: Vec<QueueTab>) -> Self {
+        self.tabs.extend(tabs);
+
+        self
+    }
+
+    pub fn has_tabs(&self) -> bool {
+        !self.tabs.is_empty()
+    }
+
+    /// Of the queue open, or `None` for the main queue
+    pub fn tab_name(&self) -> Option<&str> {
+        self.tab_name.as_deref()
+    }
+
+    /// Set the queue open aside and open the next tab. A scan not yet
+    /// labeled is opened again when its tab comes back around. Returns
+    /// whether there was another tab
+    pub fn switch_tab(&mut self) -> bool {
+        let mut next = match self.tabs.pop_front() {
+            Some(next) => next,
+            None => return false,
+        };
+
+        self.session.shelve();
+
+        // Notifications are about the station, not one queue
+        next.path_loader.webhook = self.path_loader.webhook.take();
+
+        let previous = QueueTab {
+            name: std::mem::replace(&mut self.tab_name, next.name),
+            path_loader: std::mem::replace(&mut self.path_loader, next.path_loader),
+            session: std::mem::replace(&mut self.session, next.session),
+            breakdown: std::mem::replace(&mut self.breakdown, next.breakdown),
+        };
+        self.tabs.push_back(previous);
+
+        true
+    }
+
+    /// Once the queue open runs out, switch to the next tab with scans left.
+    /// Returns whether there was one
+    pub fn switch_to_unfinished_tab(&mut self) -> bool {
+        for _ in 0..self.tabs.len() {
+            self.switch_tab();
+
+            if self.next_path().is_some() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Append the current scan to the file for `scan_kind`, its insertion
+    /// axis, if one was set, to the axis file, and its fingerprint, if
+    /// given, to the fingerprints. Returns what was written, or `None` if
+    /// the scan was already committed, e.g. as invalid because it is empty
+    pub fn label(
+        &mut self,
+        scan_kind: ScanKind,
+        insertion_axis: Option<Vector3<f32>>,
+        fingerprint: Option<&ShapeFingerprint>,
+    ) -> io::Result<Option<Record>> {
+        self.poll_urgent(true);
+
+        if let Some(Record::Labeled {
+            path,
+            label,
+            expedited,
+        }) = self.session.label(&self.categories[scan_kind.index()].name)
+        {
+            self.path_loader.checkpoint()?;
+
+            if let Some(entry) = self.manifest.entry(&path) {
+                for (kind, source) in entry.sources() {
+                    self.breakdown.push(kind, source, &label);
+                }
+            }
+
+            match &mut self.path_loader.sink {
+                LabelSink::CategoryFiles(files) => {
+                    let file = &mut files[scan_kind.index()];
+
+                    file.write_all(path.as_bytes())?;
+                    file.write_all(&[b'\n'])?;
+                }
+                LabelSink::Csv(csv) => {
+                    let duration = self.opened.map_or(0, |opened| opened.elapsed().as_millis());
+
+                    writeln!(
+                        csv.file,
+                        "{},{},{},{},{}",
+                        csv_escape(&path),
+                        csv_escape(&label),
+                        format_utc(unix_now()),
+                        csv_escape(csv.annotator.as_deref().unwrap_or_default()),
+                        duration
+                    )?;
+                }
+                // With the decision, below
+                LabelSink::Decisions(_) => {}
+            }
+
+            if let Some(links) = &mut self.path_loader.links {
+                writeln!(
+                    links.file,
+                    "{},{},{}",
+                    csv_escape(&path),
+                    csv_escape(&label),
+                    csv_escape(&links.template.url(&path))
+                )?;
+            }
+
+            if let Some(axis) = insertion_axis {
+                writeln!(
+                    self.path_loader.axis_file,
+                    "{},{},{},{}",
+                    csv_escape(&path),
+                    axis.x,
+                    axis.y,
+                    axis.z
+                )?;
+            }
+
+            if let (true, Some(urgent)) = (expedited, &mut self.path_loader.urgent) {
+                urgent.expedited_file.write_all(path.as_bytes())?;
+                urgent.expedited_file.write_all(&[b'\n'])?;
+            }
+
+            if let (Some(fingerprint), Some(fingerprints)) =
+                (fingerprint, &mut self.path_loader.fingerprints)
+            {
+                let entry = LabeledFingerprint {
+                    path: path.clone(),
+                    label: label.clone(),
+                    fingerprint: fingerprint.clone(),
+                };
+
+                fingerprints.index.push(entry, &mut fingerprints.file)?;
+            }
+
+            let record = Record::Labeled {
+                path,
+                label,
+                expedited,
+            };
+
+            self.log_decision(&record)?;
+            self.path_loader.save_progress(&self.session.progress())?;
+
+            return Ok(Some(record));
+        }
+
+        Ok(None)
+    }
+
+    /// The scan labeled before whose shape is nearest `fingerprint`, the
+    /// current scan's, if it is within `max_distance`
+    pub fn near_duplicate(
+        &self,
+        fingerprint: &ShapeFingerprint,
+        max_distance: f32,
+    ) -> Option<&LabeledFingerprint> {
+        let fingerprints = self.path_loader.fingerprints.as_ref()?;
+
+        fingerprints
+            .index
+            .nearest(fingerprint, self.current_path()?, max_distance)
+            .map(|(entry, _)| entry)
+    }
+
+    /// Append `record` to the decisions, if `--jsonl` was given, with the
+    /// current scan's metrics
+    fn log_decision(&mut self, record: &Record) -> io::Result<()> {
+        if let LabelSink::Decisions(log) = &mut self.path_loader.sink {
+            Decision::new(record, &log.session_id, unix_now(), self.metrics.as_ref())
+                .write_line(&mut log.file)?;
+        }
+
+        Ok(())
+    }
+
+    /// The link to the current scan's case, if `--link-template` was given
+    pub fn current_link(&self) -> Option<String> {
+        let links = self.path_loader.links.as_ref()?;
+
+        Some(links.template.url(self.current_path()?))
+    }
+
+    /// Print the labels given this session and, if the manifest names where
+    /// scans came from, write them broken down by source to
+    /// [`SOURCE_REPORT_PATH`], or the same file in each tab's directory
+    pub fn report_session(&self, catalog: &Catalog) {
+        let mut queues: Vec<_> = self
+            .tabs
+            .iter()
+            .map(|tab| (tab.name.as_deref(), &tab.session, &tab.breakdown))
+            .collect();
+        queues.push((self.tab_name(), &self.session, &self.breakdown));
+        queues.sort_by_key(|&(name, _, _)| name);
+
+        for (name, session, breakdown) in queues {
+            report_queue(catalog, name, session, breakdown);
+        }
+    }
+
+    /// Append the current scan to the invalid file, for scans that cannot be
+    /// shown or labeled
+    pub fn record_invalid(&mut self) -> io::Result<()> {
+        self.poll_urgent(true);
+
+        if let Some(record) = self.session.record_invalid() {
+            self.path_loader.checkpoint()?;
+            let file = &mut self.path_loader.invalid_file;
+
+            file.write_all(record.path().as_bytes())?;
+            file.write_all(&[b'\n'])?;
+
+            self.log_decision(&record)?;
+            self.path_loader.save_progress(&self.session.progress())?;
+        }
+
+        Ok(())
+    }
+
+    /// Take back the last scan labeled or recorded as invalid, removing it
+    /// from the files it was written to, so it is the next scan opened.
+    /// Returns what was taken back
+    pub fn undo(&mut self) -> io::Result<Option<Record>> {
+        let record = match self.session.undo() {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        self.path_loader.undo()?;
+        self.path_loader.save_progress(&self.session.progress())?;
+
+        if let Some(fingerprints) = &mut self.path_loader.fingerprints {
+            fingerprints.index.pop(record.path());
+        }
+
+        if let (Record::Labeled { label, .. }, Some(entry)) =
+            (&record, self.manifest.entry(record.path()))
+        {
+            for (kind, source) in entry.sources() {
+                self.breakdown.remove(kind, source, label);
+            }
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Check the urgent queue for new scans if it is due, or if `force` is
+    /// set. Returns whether any were found
+    pub fn poll_urgent(&mut self, force: bool) -> bool {
+        let urgent = match &mut self.path_loader.urgent {
+            Some(urgent) => urgent,
+            None => return false,
+        };
+
+        let is_due = urgent.last_poll.map_or(true, |last_poll| {
+            last_poll.elapsed() >= URGENT_POLL_INTERVAL
+        });
+
+        if !force && !is_due {
+            return false;
+        }
+
+        urgent.last_poll = Some(Instant::now());
+
+        match urgent.queue.poll() {
+            Ok(added) => {
+                let found = !added.is_empty();
+                self.session.add_urgent(added);
+
+                found
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    self.catalog.format(
+                        "urgent-queue-failed",
+                        &[("path", &urgent.queue.path().display()), ("error", &e)]
+                    )
+                );
+                false
+            }
+        }
+    }
+
+    /// Whether the current scan came from the urgent queue
+    pub fn is_expedited(&self) -> bool {
+        self.session.is_expedited()
+    }
+
+    /// Urgent scans found that will be shown once the current one is
+    /// committed
+    pub fn urgent_waiting(&self) -> usize {
+        self.session.urgent_waiting()
+    }
+
+    pub fn current_path(&self) -> Option<&str> {
+        self.current.as_ref()?;
+        self.session.current_path()
+    }
+
+    /// The manifest's settings for the current scan, if it has any
+    pub fn current_entry(&self) -> Option<&ManifestEntry> {
+        self.manifest.entry(self.current_path()?)
+    }
+
+    pub fn next_path(&self) -> Option<&str> {
+        self.session.next_path()
+    }
+
+    pub fn read(&mut self, path: &str) -> Result<Mesh, MeshError> {
+        let reader = self.sources.fetch(path)?;
+
+        self.stl_buffer.clear();
+        read_decompressed(reader, &mut self.stl_buffer)?;
+
+        match &self.cache {
+            Some(cache) => cache.parse(&self.stl_buffer, Some(path)),
+            None => Mesh::from_bytes(&self.stl_buffer, Some(path)),
+        }
+    }
+
+    /// Make `mesh`, loaded from [`StlContext::next_path`], the current scan
+    pub fn advance(&mut self, mesh: Mesh) -> &Mesh {
+        self.session.advance();
+        self.current = Some(mesh);
+        self.opened = Some(Instant::now());
+        self.metrics = None;
+
+        if let Some(webhook) = &mut self.path_loader.webhook {
+            webhook.scan_opened(false);
+        }
+
+        self.current.as_ref().unwrap()
+    }
+
+    /// Record `metrics` with decisions about the current scan
+    pub fn set_metrics(&mut self, metrics: MeshMetrics) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Move past [`StlContext::next_path`] after it failed to load, leaving
+    /// an empty mesh current so it can still be recorded as invalid
+    pub fn advance_failed(&mut self, error: &MeshError) {
+        self.session.advance_failed(error);
+        self.current = Some(Mesh::new(Vec::new(), Vec::new()));
+        self.opened = Some(Instant::now());
+        self.metrics = None;
+
+        if let Some(webhook) = &mut self.path_loader.webhook {
+            webhook.scan_opened(true);
+        }
+    }
+
+    /// Move past [`StlContext::next_path`] without loading it, keeping the
+    /// current mesh
+    pub fn skip_next(&mut self) {
+        self.session.skip_next();
+    }
+
+    /// Pass over the current scan without a label, recording it in the
+    /// skipped file if there is one
+    pub fn skip(&mut self) -> io::Result<()> {
+        if let Some(record) = self.session.skip() {
+            self.path_loader.checkpoint()?;
+
+            if let Some(file) = &mut self.path_loader.skipped_file {
+                file.write_all(record.path().as_bytes())?;
+                file.write_all(&[b'\n'])?;
+            }
+
+            self.log_decision(&record)?;
+            self.path_loader.save_progress(&self.session.progress())?;
+        }
+
+        Ok(())
+    }
+
+    /// Notify the webhook, if configured, that the queue ran out
+    pub fn finish_queue(&mut self) {
+        let labeled = self.session.report().total;
+        let scans = self.session.queue().len();
+
+        if let Some(webhook) = &mut self.path_loader.webhook {
+            webhook.queue_finished(labeled, scans);
+        }
+    }
+
+    pub fn load_next(&mut self) -> Option<Result<&Mesh, MeshError>> {
+        let next_path = self.next_path()?.to_owned();
+
+        Some(match self.read(&next_path) {
+            Ok(mesh) => Ok(self.advance(mesh)),
+            Err(e) => {
+                self.advance_failed(&e);
+                Err(e)
+            }
+        })
+    }
+}
+
+/// Print the labels one queue was given and write its source report, see
+/// [`StlContext::report_session`]
+fn report_queue(
+    catalog: &Catalog,
+    name: Option<&str>,
+    session: &Session,
+    breakdown: &SourceBreakdown,
+) {
+    let report = session.report();
+
+    if report.total == 0 {
+        return;
+    }
+
+    if let Some(name) = name {
+        println!("{}", catalog.format("tab-report", &[("tab", &name)]));
+    }
+
+    print!("{}", report);
+
+    if breakdown.is_empty() {
+        return;
+    }
+
+    print!("{}", breakdown);
+
+    let path = tab_path(name, SOURCE_REPORT_PATH);
+    let result = File::create(&path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        breakdown.write_csv(report, &mut writer)?;
+        writer.flush()
+    });
+
+    match result {
+        Ok(()) => println!(
+            "{}",
+            catalog.format("source-report-saved", &[("path", &path.display())])
+        ),
+        Err(e) => eprintln!(
+            "{}",
+            catalog.format("source-report-failed", &[("error", &e)])
+        ),
+    }
+}
+
+/// `path`, one of the files written next to the label files, for the tab
+/// `name`, which keeps its files in a directory of that name
+fn tab_path(name: Option<&str>, path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+
+    match (name, path.file_name()) {
+        (Some(name), Some(file_name)) => Path::new(name).join(file_name),
+        _ => path.to_owned(),
+    }
+}
+
+/// A scan parsed, analyzed, and repaired according to the config, ready to
+/// be shown
+struct PreparedScan {
+    mesh: Mesh,
+    metrics: MeshMetrics,
+    shells: Shells,
+    /// Set when prepared ahead of time on a context shared with the window's
+    uploaded: Option<UploadedMesh>,
+    bvh: TriangleBvh,
+    welded: WeldedMesh,
+    /// Through the whole scan, before `clip` is applied
+    section: Section,
+    arch: Option<ArchEstimate>,
+    teeth: Option<ToothCount>,
+    /// Drawn while the view moves, for scans large enough to need one
+    proxy: Option<Mesh>,
+    /// Of the whole scan, before `clip` is applied
+    fingerprint: ShapeFingerprint,
+}
+
+impl PreparedScan {
+    /// Metrics are measured before `clip` is applied. Meshes replaced along
+    /// the way are returned to `scratch`. A proxy of about `lod_triangles`
+    /// is made if the scan has more than twice as many
+    pub fn new(
+        mesh: Mesh,
+        analysis: &AnalysisConfig,
+        clip: Option<ClipPreset>,
+        lod_triangles: Option<usize>,
+        scratch: &mut ScanScratch,
+    ) -> Self {
+        let mut welded = WeldedMesh::new_in(&mesh, scratch);
+        let metrics = MeshMetrics::from_welded(&mesh, &welded);
+        let fingerprint = ShapeFingerprint::new(&welded);
+
+        // Cut where the scan is clipped, or level through its middle
+        let bbox = metrics.bounding_box;
+        let plane = clip.map_or(
+            Plane {
+                point: bbox.center(),
+                normal: Vector3::z(),
+            },
+            |clip| clip.plane(&bbox),
+        );
+        let section = Section::new(&welded, plane);
+        let arch = ArchEstimate::new(&welded);
+        let teeth = arch.and_then(|arch| ToothCount::new(&welded, &arch));
+
+        let mut mesh = mesh;
+        let mut changed = false;
+
+        if analysis.strip_redundant_triangles
+            && metrics.degenerate_triangle_count + metrics.duplicate_triangle_count > 0
+        {
+            let stripped = strip_redundant_triangles_in(&mesh, &welded, scratch).0;
+            scratch.recycle(std::mem::replace(&mut mesh, stripped));
+            changed = true;
+        }
+
+        if analysis.repair_normals && metrics.inconsistent_triangle_count > 0 {
+            if changed {
+                welded = WeldedMesh::new_in(&mesh, scratch);
+            }
+
+            scratch.recycle(std::mem::replace(&mut mesh, welded.reoriented().0));
+            changed = true;
+        }
+
+        if let Some(clip) = clip {
+            let clipped = clip.apply(&mesh);
+            scratch.recycle(std::mem::replace(&mut mesh, clipped));
+            changed = true;
+        }
+
+        if changed {
+            welded = WeldedMesh::new_in(&mesh, scratch);
+        }
+
+        let shells = Shells::from_welded(&welded);
+        let bvh = TriangleBvh::new(&mesh);
+        let proxy = lod_triangles
+            .filter(|&triangles| mesh.triangle_count() > 2 * triangles)
+            .map(|triangles| decimate(&mesh, triangles));
+
+        Self {
+            mesh,
+            metrics,
+            shells,
+            uploaded: None,
+            bvh,
+            welded,
+            section,
+            arch,
+            teeth,
+            proxy,
+            fingerprint,
+        }
+    }
+}
+
+impl Program {
+    /// `gl_window` must be current, with GL function pointers loaded
+    /// `upload_context` should share objects with `gl_window`; scans are
+    /// uploaded on the prefetch thread with it if given
+    pub fn init(
+        gl_window: ContextWrapper<PossiblyCurrent, Window>,
+        context_attempt: ContextAttempt,
+        upload_context: Option<Context<NotCurrent>>,
+        path_loader: PathLoader,
+        session: Session,
+        categories: Categories,
+        sources: SourceRegistry,
+        manifest: Arc<Manifest>,
+        config: Config,
+        config_path: String,
+        catalog: Catalog,
+        kiosk: bool,
+        tabs: Vec<QueueTab>,
+    ) -> Self {
+        let dimensions = gl_window.window().inner_size();
+
+        unsafe {
+            gl::Viewport(0, 0, dimensions.width as i32, dimensions.height as i32);
+        };
+
+        let capabilities = GlCapabilities::probe();
+        println!("{}", capabilities);
+
+        if cfg!(debug_assertions) && capabilities.supports_debug_output() {
+            enable_debug_output();
+        }
+
+        let mut scene = Scene::new();
+        let scan_node = scene.add("scan", StationaryCamera::default_model(), None);
+        let bounding_box_node = scene.add("bounding box", Matrix4::identity(), Some(scan_node));
+        scene.node_mut(bounding_box_node).visible = false;
+
+        let camera = FlightCamera::new(50.0_f32);
+        let stationary = StationaryCamera::new();
+
+        let light = Light::white();
+
+        let mut backend = GlBackend::new(&light, config.theme.object_color());
+        let vertex_format = config.display.vertex_format();
+        backend.set_vertex_format(vertex_format);
+
+        let analysis = config.analysis;
+        let lod_triangles = config.display.lod_target();
+        let loading = config.loading;
+        let cache = mesh_cache(&config.cache, &catalog);
+        let prefetch_cache = cache.clone();
+        let prefetch_sources = sources.clone();
+        let prefetch_manifest = manifest.clone();
+        let prefetch_catalog = catalog.clone();
+
+        // Only the first prefetch thread gets the shared context; threads
+        // started after a cancelled load upload on the main thread
+        let upload_context = Mutex::new(upload_context);
+
+        let prefetcher = Prefetcher::new(move || {
+            let cache = prefetch_cache.clone();
+            let sources = prefetch_sources.clone();
+            let manifest = prefetch_manifest.clone();
+            let upload_context = upload_context.lock().unwrap().take().and_then(|context| {
+                match unsafe { context.make_current() } {
+                    Ok(context) => Some(context),
+                    Err((_, e)) => {
+                        eprintln!(
+                            "{}",
+                            prefetch_catalog.format("shared-context-unavailable", &[("error", &e)])
+                        );
+                        None
+                    }
+                }
+            });
+
+            // Reused for every scan this thread prepares
+            let mut scratch = ScanScratch::new();
+
+            move |path: &str| -> Result<PreparedScan, MeshError> {
+                let rate = loading.max_bytes_per_sec();
+                read_scan(&sources, path, rate, &mut scratch.contents)?;
+
+                // Parsed into buffers from `scratch`, so the contents are
+                // moved out while it is borrowed
+                let contents = std::mem::take(&mut scratch.contents);
+                let mesh = match &cache {
+                    Some(cache) => cache.parse(&contents, Some(path)),
+                    None => Mesh::from_bytes_in(&contents, Some(path), &mut scratch),
+                };
+                scratch.contents = contents;
+                let mesh = mesh?;
+
+                let clip = manifest.entry(path).and_then(|entry| entry.clip);
+                let mut scan =
+                    PreparedScan::new(mesh, &analysis, clip, lod_triangles, &mut scratch);
+
+                if upload_context.is_some() {
+                    scan.uploaded = Some(UploadedMesh::with_format(&scan.mesh, vertex_format));
+                }
+
+                Ok(scan)
+            }
+        });
+
+        let reference =
+            config
+                .reference
+                .path
+                .as_ref()
+                .and_then(|path| match load_reference(path) {
+                    Ok(reference) => Some(reference),
+                    Err(e) => {
+                        eprintln!(
+                            "{}",
+                            catalog.format("reference-failed", &[("path", path), ("error", &e)])
+                        );
+                        None
+                    }
+                });
+
+        let station_metrics = match &config.monitoring.metrics_address {
+            Some(address) => match MetricsServer::start(address.as_str()) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        catalog.format(
+                            "metrics-server-failed",
+                            &[("address", address), ("error", &e)]
+                        )
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Self {
+            window: gl_window,
+            camera,
+            stationary,
+            scene,
+            scan_node,
+            bounding_box_node,
+            backend,
+            window_state: WindowState::new(),
+            control_flow: ControlFlow::Wait,
+            stl_context: StlContext::new(
+                path_loader,
+                session,
+                categories,
+                cache,
+                sources,
+                manifest,
+                catalog.clone(),
+            )
+            .with_tabs(tabs),
+            config,
+            config_path,
+            context_attempt,
+            capabilities,
+            line_renderer: LineRenderer::new(),
+            overlay: OverlayPass::new(FontAtlas::default_font()),
+            metrics: None,
+            shells: None,
+            removed_shells: Vec::new(),
+            exploded: false,
+            hover_bvh: None,
+            welded: None,
+            selection: SelectionMask::default(),
+            selected_area: (0, 0.0),
+            brush_mode: false,
+            brush_radius: BRUSH_RADIUS,
+            painting: None,
+            show_heatmap: false,
+            deviation: None,
+            show_bite: false,
+            bite: None,
+            show_reference: false,
+            reference,
+            show_voxels: false,
+            voxels: None,
+            show_undercuts: false,
+            undercuts: None,
+            insertion_axis: None,
+            undercut_bvh: None,
+            aiming_axis: false,
+            undercuts_found: None,
+            show_histograms: false,
+            histograms: None,
+            show_section: false,
+            section: None,
+            distance_axis: None,
+            distance_slice: 0,
+            distance_field: None,
+            arch: None,
+            teeth: None,
+            show_teeth: false,
+            fingerprint: None,
+            near_duplicate: None,
+            fit_kind: None,
+            fit: None,
+            vertex_normals: None,
+            catalog,
+            bug_report_requested: false,
+            pending_bug_report: None,
+            export_requested: false,
+            pending_export: None,
+            flash: None,
+            pending_label: None,
+            confirming_label: None,
+            label_times: VecDeque::new(),
+            labeling_too_fast: false,
+            show_stats: false,
+            frame_stats: RenderStats::default(),
+            station_metrics,
+            kiosk,
+            watchdog: Watchdog::from_env(),
+            loading: None,
+            placeholder: None,
+            prefetcher,
+            needs_redraw: true,
+            last_frame: None,
+            lod_proxy: None,
+            last_view: None,
+            view_moved: None,
+        }
+    }
+
+    fn dimensions(&self) -> PhysicalSize<u32> {
+        self.window.window().inner_size()
+    }
+
+    pub fn run(mut self, event_loop: EventLoop<()>) -> ! {
+        self.load_next_stl();
+        self.watchdog.ready();
+
+        event_loop.run(move |event, _, control_flow| {
+            match event {
+                Event::LoopDestroyed => return,
+                Event::WindowEvent { event, .. } => {
+                    self.needs_redraw = true;
+                    self.handle_window_event(event);
+                }
+                Event::DeviceEvent { event, .. } => {
+                    self.needs_redraw = true;
+                    self.handle_device_event(event);
+                }
+                Event::MainEventsCleared => {
+                    self.watchdog.poll();
+
+                    if self.stl_context.poll_urgent(false) {
+                        self.update_title();
+                    }
+
+                    self.poll_pending_label();
+                    self.poll_loading();
+                    self.poll_bug_report();
+                    self.poll_export();
+                    self.schedule_redraw();
+                }
+                Event::RedrawRequested(_) => {
+                    let frame_start = Instant::now();
+
+                    // Draw once more after the flash ends so it fades fully
+                    self.needs_redraw = self.is_flashing();
+
+                    let background = self.background();
+                    let projection = self.projection();
+
+                    self.track_view(&projection);
+
+                    self.backend.draw_scan(&ScanFrame {
+                        stationary: &self.stationary,
+                        model: self.scene.world(self.scan_node),
+                        projection,
+                        background,
+                    });
+
+                    if self.scene.is_visible(self.bounding_box_node) {
+                        self.draw_bounding_box(&projection);
+                    }
+
+                    self.draw_axis_gadget();
+                    if let Some(placeholder) = &self.placeholder {
+                        let is_corrupt = matches!(placeholder, Placeholder::Corrupt(_));
+                        self.draw_placeholder(is_corrupt, &projection);
+                    }
+
+                    if self.export_requested {
+                        self.export_requested = false;
+                        self.start_export();
+                    }
+
+                    // Last, so the 2D pass is over everything in the 3D one
+                    self.draw_hud();
+
+                    if self.bug_report_requested {
+                        self.bug_report_requested = false;
+                        self.start_bug_report();
+                    }
+
+                    self.window.swap_buffers().unwrap();
+                    self.last_frame = Some(Instant::now());
+
+                    if let Some(server) = &self.station_metrics {
+                        server.update(|metrics| metrics.record_frame(frame_start.elapsed()));
+                    }
+
+                    self.frame_stats = self.backend.take_stats();
+                }
+                _ => {}
+            }
+
+            *control_flow = self.control_flow;
+        });
+    }
+
+    /// Request a redraw if one is due under the display config, otherwise
+    /// sleep until it is
+    fn schedule_redraw(&mut self) {
+        if self.control_flow == ControlFlow::Exit {
+            return;
+        }
+
+        let display = self.config.display;
+        // Loads are polled once per frame
+        let continuous = !display.power_saving
+            || self.is_flashing()
+            || self.loading.is_some()
+            || self.pending_label.is_some()
+            || self.pending_bug_report.is_some()
+            || self.pending_export.is_some()
+            || self.view_moved.is_some();
+
+        if !continuous && !self.needs_redraw {
+            self.control_flow = match self.watchdog.next_ping() {
+                Some(ping) => ControlFlow::WaitUntil(ping),
+                None => ControlFlow::Wait,
+            };
+            return;
+        }
+
+        let next_frame = match (self.last_frame, display.frame_interval()) {
+            (Some(last_frame), Some(interval)) => last_frame + interval,
+            _ => Instant::now(),
+        };
+
+        if next_frame > Instant::now() {
+            self.control_flow = ControlFlow::WaitUntil(next_frame);
+        } else {
+            self.window.window().request_redraw();
+            self.control_flow = if continuous {
+                ControlFlow::Poll
+            } else {
+                ControlFlow::Wait
+            };
+        }
+    }
+
+    /// Draw the proxy while the view is moving, and the full scan once it
+    /// has held still for `lod_settle_ms`
+    fn track_view(&mut self, projection: &Matrix4<f32>) {
+        if !self.backend.has_proxy() {
+            self.view_moved = None;
+            self.backend.set_interacting(false);
+            return;
+        }
+
+        let view = self.stl_context.current.as_ref().map(|mesh| {
+            projection
+                * self.stationary.view(mesh.bounding_box())
+                * self.scene.world(self.scan_node)
+        });
+
+        // Not when a new scan is shown
+        if view != self.last_view && self.last_view.is_some() {
+            self.view_moved = Some(Instant::now());
+        }
+        self.last_view = view;
+
+        let settle = self.config.display.lod_settle();
+        self.view_moved = self.view_moved.filter(|moved| moved.elapsed() < settle);
+
+        self.backend.set_interacting(self.view_moved.is_some());
+    }
+
+    fn projection(&self) -> Matrix4<f32> {
+        let dimensions = self.dimensions();
+
+        nalgebra_glm::perspective(
+            dimensions.width as f32 / dimensions.height as f32,
+            self.camera.fov(),
+            1.0,
+            1000.0,
+        )
+    }
+
+    fn handle_window_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => self.quit(false),
+            WindowEvent::ModifiersChanged(state) => {
+                self.window_state.modifiers = state;
+            }
+            WindowEvent::Resized(new_dimensions) => {
+                self.backend
+                    .resize(new_dimensions.width, new_dimensions.height);
+            }
+            WindowEvent::Focused(focused) => {
+                self.window_state.is_window_focused = focused;
+            }
+            WindowEvent::CursorEntered { .. } => {
+                self.window_state.is_window_hovered = true;
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.window_state.is_window_hovered = false;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let previous = self.window_state.cursor;
+                self.window_state.cursor = (position.x as f32, position.y as f32);
+
+                self.paint_under_cursor();
+
+                if self.window_state.is_mouse_pressed && self.window_state.is_panning {
+                    self.pan(
+                        self.window_state.cursor.0 - previous.0,
+                        self.window_state.cursor.1 - previous.1,
+                    );
+                }
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => {
+                self.window_state.is_panning =
+                    button == MouseButton::Middle || self.window_state.modifiers.shift();
+
+                self.aiming_axis = self.window_state.modifiers.ctrl()
+                    && self.show_undercuts
+                    && self.insertion_axis.is_some()
+                    && !self.window_state.is_panning;
+
+                if self.brush_mode && !self.window_state.is_panning && !self.aiming_axis {
+                    self.painting = match button {
+                        MouseButton::Left => Some(true),
+                        MouseButton::Right => Some(false),
+                        _ => None,
+                    };
+                }
+
+                self.window
+                    .window()
+                    .set_cursor_icon(if self.window_state.is_panning {
+                        CursorIcon::Move
+                    } else if self.painting.is_some() {
+                        CursorIcon::Crosshair
+                    } else {
+                        CursorIcon::Grabbing
+                    });
+                self.window_state.is_mouse_pressed = true;
+                self.window_state.press_position = self.window_state.cursor;
+
+                self.paint_under_cursor();
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                ..
+            } => {
+                self.window.window().set_cursor_icon(CursorIcon::Default);
+                self.window_state.is_mouse_pressed = false;
+                let was_painting = self.painting.take().is_some();
+
+                if self.aiming_axis {
+                    self.aiming_axis = false;
+                    self.undercuts_found = None;
+                    self.refresh_undercuts();
+                }
+
+                if self.window_state.is_click()
+                    && !was_painting
+                    && !self.window_state.is_panning
+                    && !self.snap_to_axis_under_cursor()
+                {
+                    self.toggle_shell_under_cursor();
+                }
+
+                self.window_state.is_panning = false;
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                match (input.virtual_keycode, input.state) {
+                    (Some(VirtualKeyCode::Left), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.step_insertion_axis(-1.0, 0.0);
+                    }
+                    (Some(VirtualKeyCode::Right), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.step_insertion_axis(1.0, 0.0);
+                    }
+                    (Some(VirtualKeyCode::Up), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.step_insertion_axis(0.0, -1.0);
+                    }
+                    (Some(VirtualKeyCode::Down), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.step_insertion_axis(0.0, 1.0);
+                    }
+                    (Some(VirtualKeyCode::Left), ElementState::Pressed) => {
+                        let scale = self.window_state.step_scale();
+                        self.stationary.left(&mut self.scene, self.scan_node, scale);
+                    }
+                    (Some(VirtualKeyCode::Right), ElementState::Pressed) => {
+                        let scale = self.window_state.step_scale();
+                        self.stationary
+                            .right(&mut self.scene, self.scan_node, scale);
+                    }
+                    (Some(VirtualKeyCode::Up), ElementState::Pressed) => {
+                        let scale = self.window_state.step_scale();
+                        self.stationary.up(&mut self.scene, self.scan_node, scale);
+                    }
+                    (Some(VirtualKeyCode::Down), ElementState::Pressed) => {
+                        let scale = self.window_state.step_scale();
+                        self.stationary.down(&mut self.scene, self.scan_node, scale);
+                    }
+                    (Some(VirtualKeyCode::P), ElementState::Pressed) => {
+                        self.window_state.toggle_paused();
+                    }
+                    (Some(VirtualKeyCode::Home), ElementState::Pressed) => {
+                        self.reset_view();
+                    }
+                    (Some(VirtualKeyCode::F), ElementState::Pressed) => {
+                        self.focus_under_cursor();
+                    }
+                    (Some(VirtualKeyCode::Escape), ElementState::Pressed)
+                    | (Some(VirtualKeyCode::N), ElementState::Pressed) => {
+                        self.cancel_pending_label();
+                    }
+                    (Some(VirtualKeyCode::Y), ElementState::Pressed) => {
+                        self.confirm_label();
+                    }
+                    (Some(VirtualKeyCode::Return), ElementState::Pressed) => {
+                        self.accept_suggestion();
+                    }
+                    (Some(VirtualKeyCode::Q), ElementState::Pressed) => {
+                        let modifiers = self.window_state.modifiers;
+                        self.quit(modifiers.ctrl() && modifiers.alt() && modifiers.shift());
+                    }
+                    (Some(VirtualKeyCode::C), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.quit(false);
+                    }
+                    (Some(VirtualKeyCode::C), ElementState::Pressed) => {
+                        self.mark_corrupt();
+                    }
+                    (Some(VirtualKeyCode::Space), ElementState::Pressed) => {
+                        self.skip();
+                    }
+                    (Some(VirtualKeyCode::R), ElementState::Pressed) => {
+                        self.retry_loading();
+                    }
+                    (Some(VirtualKeyCode::Tab), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.switch_tab();
+                    }
+                    (Some(VirtualKeyCode::Z), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.undo_label();
+                    }
+                    (Some(VirtualKeyCode::Z), ElementState::Pressed) => {
+                        self.cycle_distance_axis();
+                    }
+                    (Some(VirtualKeyCode::PageUp), ElementState::Pressed) => {
+                        self.step_distance_slice(1);
+                    }
+                    (Some(VirtualKeyCode::PageDown), ElementState::Pressed) => {
+                        self.step_distance_slice(-1);
+                    }
+                    (Some(VirtualKeyCode::B), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.bug_report_requested = true;
+                    }
+                    (Some(VirtualKeyCode::B), ElementState::Pressed) => {
+                        self.scene.toggle_visible(self.bounding_box_node);
+                        self.update_title();
+                    }
+                    (Some(VirtualKeyCode::E), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.export_requested = true;
+                    }
+                    (Some(VirtualKeyCode::E), ElementState::Pressed) => {
+                        self.toggle_exploded();
+                    }
+                    (Some(VirtualKeyCode::G), ElementState::Pressed) => {
+                        self.fit_kind = PrimitiveKind::next(self.fit_kind);
+                        self.update_fit();
+                    }
+                    (Some(VirtualKeyCode::H), ElementState::Pressed) => {
+                        self.toggle_heatmap();
+                    }
+                    (Some(VirtualKeyCode::U), ElementState::Pressed) => {
+                        self.toggle_undercuts();
+                    }
+                    (Some(VirtualKeyCode::O), ElementState::Pressed)
+                        if self.window_state.modifiers.ctrl() =>
+                    {
+                        self.open_link();
+                    }
+                    (Some(VirtualKeyCode::O), ElementState::Pressed) => {
+                        self.toggle_bite();
+                    }
+                    (Some(VirtualKeyCode::V), ElementState::Pressed) => {
+                        self.toggle_reference();
+                    }
+                    (Some(VirtualKeyCode::J), ElementState::Pressed) => {
+                        self.show_voxels = !self.show_voxels;
+                        self.show_current_scan(None);
+                    }
+                    (Some(VirtualKeyCode::K), ElementState::Pressed) => {
+                        self.show_section = !self.show_section;
+                    }
+                    (Some(VirtualKeyCode::T), ElementState::Pressed) => {
+                        self.show_teeth = !self.show_teeth;
+                    }
+                    (Some(VirtualKeyCode::L), ElementState::Pressed) => {
+                        self.show_histograms = !self.show_histograms;
+
+                        if self.show_histograms && self.histograms.is_none() {
+                            self.measure_histograms();
+                        }
+                    }
+                    (Some(VirtualKeyCode::M), ElementState::Pressed) => {
+                        self.brush_mode = !self.brush_mode;
+                    }
+                    (Some(VirtualKeyCode::LBracket), ElementState::Pressed) => {
+                        self.resize_brush(1.0 / BRUSH_RADIUS_STEP);
+                    }
+                    (Some(VirtualKeyCode::RBracket), ElementState::Pressed) => {
+                        self.resize_brush(BRUSH_RADIUS_STEP);
+                    }
+                    (Some(VirtualKeyCode::I), ElementState::Pressed) => {
+                        self.show_stats = !self.show_stats;
+                    }
+                    (Some(VirtualKeyCode::X), ElementState::Pressed) => {
+                        self.export_cleaned();
+                    }
+                    (Some(key), ElementState::Pressed) => {
+                        let scan_kind =
+                            key_char(key).and_then(|key| self.stl_context.category_for_key(key));
+
+                        if let Some(scan_kind) = scan_kind {
+                            self.label(scan_kind);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_device_event(&mut self, event: DeviceEvent) {
+        match event {
+            DeviceEvent::MouseWheel { delta } => {
+                self.zoom(delta);
+            }
+            DeviceEvent::MouseMotion { delta } => {
+                if !self.window_state.is_paused && self.window_state.is_window_focused {
+                    if self.window_state.is_mouse_pressed && self.aiming_axis {
+                        self.tilt_insertion_axis(
+                            delta.0 as f32 * AXIS_DRAG_RATE,
+                            delta.1 as f32 * AXIS_DRAG_RATE,
+                        );
+
+                        let is_due = self
+                            .undercuts_found
+                            .map_or(true, |found| found.elapsed() >= UNDERCUT_REFRESH);
+
+                        if is_due {
+                            self.undercuts_found = Some(Instant::now());
+                            self.refresh_undercuts();
+                        }
+                    } else if self.window_state.is_mouse_pressed
+                        && !self.window_state.is_panning
+                        && self.painting.is_none()
+                    {
+                        self.stationary.move_mouse(
+                            &mut self.scene,
+                            self.scan_node,
+                            delta.0 as f32,
+                            -delta.1 as f32,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Slide the scan by a cursor movement of `dx` and `dy` physical pixels,
+    /// so the point under the cursor stays under it
+    fn pan(&mut self, dx: f32, dy: f32) {
+        let height = self.dimensions().height.max(1) as f32;
+        let per_pixel = 2.0 * (self.camera.fov() / 2.0).tan() / height;
+
+        self.stationary.pan(dx * per_pixel, -dy * per_pixel);
+    }
+
+    /// Home: undo panning, zooming, and rotation, returning to the
+    /// orientation the scan was opened with
+    fn reset_view(&mut self) {
+        self.stationary.reset_pan();
+        self.camera.reset_zoom();
+
+        self.scene.node_mut(self.scan_node).local = self
+            .stl_context
+            .current_entry()
+            .and_then(ManifestEntry::model)
+            .unwrap_or_else(StationaryCamera::default_model);
+    }
+
+    /// Start loading the next scan on the prefetch thread, if it is not
+    /// already, keeping the current one on screen until it is ready
+    fn load_next_stl(&mut self) {
+        let path = match self.stl_context.next_path() {
+            Some(path) => path.to_owned(),
+            None if self.stl_context.switch_to_unfinished_tab() => return self.load_next_stl(),
+            None => {
+                self.stl_context.finish_queue();
+                self.watchdog.stopping();
+                self.control_flow = ControlFlow::Exit;
+                return;
+            }
+        };
+
+        if !self.prefetcher.is_pending(&path) {
+            self.prefetcher.request(&path);
+        }
+
+        self.loading = Some(Loading {
+            path,
+            started: Instant::now(),
+            timed_out: false,
+            retry_at: None,
+        });
+
+        self.poll_loading();
+        self.update_title();
+    }
+
+    /// Show the scan being loaded if it is ready, or offer to retry or skip
+    /// it once it has taken longer than the configured timeout
+    fn poll_loading(&mut self) {
+        let path = match &mut self.loading {
+            Some(Loading {
+                retry_at: Some(retry_at),
+                ..
+            }) if Instant::now() < *retry_at => return,
+            Some(loading) if loading.retry_at.is_some() => {
+                loading.retry_at = None;
+                loading.started = Instant::now();
+                self.prefetcher.request(&loading.path);
+                return;
+            }
+            Some(loading) => loading.path.clone(),
+            None => return,
+        };
+
+        let scan = match self.prefetcher.try_take(&path) {
+            Some(scan) => scan,
+            None if self.prefetcher.is_pending(&path) => {
+                let timeout = self.config.loading.timeout();
+                let loading = self.loading.as_mut().unwrap();
+
+                if !loading.timed_out && loading.started.elapsed() >= timeout {
+                    loading.timed_out = true;
+                    self.update_title();
+
+                    // Nobody is at a kiosk to press R
+                    if self.kiosk {
+                        self.retry_loading();
+                    }
+                }
+
+                return;
+            }
+            // The prefetch thread panicked
+            None => {
+                self.prefetcher.cancel();
+                Err(MeshError::Parse(String::from("the loader crashed")))
+            }
+        };
+
+        // Network sources come back, so keep trying rather than record the
+        // scan as invalid
+        if let (true, Err(MeshError::Io(e))) = (self.kiosk, &scan) {
+            self.stl_context
+                .session
+                .note(format!("unable to reach {}, retrying: {}", path, e));
+
+            if let Some(loading) = &mut self.loading {
+                loading.retry_at = Some(Instant::now() + RECONNECT_DELAY);
+            }
+
+            return;
+        }
+
+        let latency = self.loading.take().map(|loading| loading.started.elapsed());
+
+        if let (Some(server), Some(latency)) = (&self.station_metrics, latency) {
+            let session = &self.stl_context.session;
+            // Counting the scan about to be opened
+            let depth = session.queue().len().saturating_sub(session.cursor() + 1);
+
+            server.update(|metrics| {
+                metrics.record_load(latency, scan.is_err());
+                metrics.set_queue_depth(depth);
+            });
+        }
+
+        match scan {
+            Ok(scan) => self.show_scan(scan),
+            Err(e) => self.show_load_failure(&e),
+        }
+    }
+
+    /// Q, Ctrl+C, or closing the window. In kiosk mode only Ctrl+Alt+Shift+Q,
+    /// `guarded`, quits, so a stray key does not stop the station
+    fn quit(&mut self, guarded: bool) {
+        if self.kiosk && !guarded {
+            return;
+        }
+
+        self.watchdog.stopping();
+        self.control_flow = ControlFlow::Exit;
+    }
+
+    /// R: start a load that timed out over on a new thread
+    fn retry_loading(&mut self) {
+        let loading = match &mut self.loading {
+            Some(loading) if loading.timed_out => loading,
+            _ => return,
+        };
+
+        loading.started = Instant::now();
+        loading.timed_out = false;
+
+        self.prefetcher.cancel();
+        self.prefetcher.request(&loading.path);
+
+        self.stl_context
+            .session
+            .note(format!("retried loading {}", loading.path));
+
+        self.update_title();
+    }
+
+    /// Ctrl+Z: take back the last label, or a label still waiting out its
+    /// delay, and open that scan again to label it over
+    fn undo_label(&mut self) {
+        if self.pending_label.is_some() || self.confirming_label.is_some() {
+            self.cancel_pending_label();
+            return;
+        }
+
+        let record = match self
+            .stl_context
+            .undo()
+            .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e))
+        {
+            Some(record) => record,
+            None => return,
+        };
+
+        if let Some(loading) = self.loading.take() {
+            self.prefetcher.cancel();
+            self.stl_context
+                .session
+                .note(format!("stopped loading {} to undo", loading.path));
+        }
+
+        // Skipped and invalid scans were never timed
+        if let Record::Labeled { .. } = record {
+            self.label_times.pop_back();
+        }
+
+        eprintln!(
+            "{}",
+            self.catalog
+                .format("label-undone", &[("path", &record.path())])
+        );
+
+        self.load_next_stl();
+    }
+
+    /// Ctrl+Tab: set the queue open aside, scan and all, and open the next
+    fn switch_tab(&mut self) {
+        if !self.stl_context.has_tabs() {
+            return;
+        }
+
+        // A label given in one queue is not carried into the next
+        self.confirming_label = None;
+        self.pending_label = None;
+
+        if let Some(loading) = self.loading.take() {
+            self.prefetcher.cancel();
+            self.stl_context
+                .session
+                .note(format!("stopped loading {} to switch tabs", loading.path));
+        }
+
+        self.stl_context.switch_tab();
+        self.load_next_stl();
+    }
+
+    /// Space: move on without a label, from the scan on screen or a load
+    /// that timed out. Scans shown are recorded in the skipped file if
+    /// configured
+    fn skip(&mut self) {
+        match &self.loading {
+            Some(loading) if loading.timed_out => {
+                self.prefetcher.cancel();
+                self.stl_context.skip_next();
+                self.load_next_stl();
+            }
+            Some(_) => {}
+            None => {
+                // A label waiting out its delay is dropped, not written
+                self.confirming_label = None;
+                self.pending_label = None;
+
+                self.stl_context
+                    .skip()
+                    .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+                self.load_next_stl();
+            }
+        }
+    }
+
+    fn show_scan(&mut self, scan: PreparedScan) {
+        let is_empty = scan.mesh.is_empty();
+
+        self.stl_context.advance(scan.mesh);
+        self.stl_context.set_metrics(scan.metrics);
+        self.prefetch_next();
+        self.stationary.reset_pan();
+        self.apply_manifest_entry();
+
+        self.placeholder = None;
+
+        if is_empty {
+            self.stl_context
+                .record_invalid()
+                .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+            self.placeholder = Some(Placeholder::Empty);
+        }
+
+        self.metrics = Some(scan.metrics);
+        self.removed_shells = vec![false; scan.shells.count()];
+        self.shells = Some(scan.shells);
+        self.exploded = false;
+        self.hover_bvh = Some(scan.bvh);
+        self.selection = SelectionMask::new(&scan.welded);
+        self.selected_area = (0, 0.0);
+        self.deviation = None;
+        self.bite = None;
+        self.voxels = None;
+        self.undercuts = None;
+        self.insertion_axis = None;
+        self.undercut_bvh = None;
+        self.histograms = None;
+        self.distance_field = None;
+        self.section = Some(scan.section);
+        self.arch = scan.arch;
+        self.teeth = scan.teeth;
+        // Fingerprints are only kept with a distance set
+        self.near_duplicate = self
+            .stl_context
+            .near_duplicate(
+                &scan.fingerprint,
+                self.config.labeling.near_duplicate_distance,
+            )
+            .map(|entry| (entry.path.clone(), entry.label.clone()));
+        self.fingerprint = Some(scan.fingerprint);
+        self.fit = None;
+        self.vertex_normals = None;
+        self.welded = Some(scan.welded);
+        self.lod_proxy = scan.proxy;
+        self.last_view = None;
+
+        if self.show_heatmap {
+            self.measure_deviation();
+        }
+
+        if self.distance_axis.is_some() {
+            self.measure_distance_field();
+        }
+
+        if self.show_undercuts {
+            self.find_undercuts();
+        }
+
+        // After the welded scan is in place, which distances are measured on
+        if self.show_bite {
+            self.measure_bite();
+        } else if self.show_reference {
+            self.overlay_reference();
+        } else {
+            self.backend.set_comparison(None, 0.0);
+        }
+
+        if self.show_histograms {
+            self.measure_histograms();
+        }
+
+        self.show_current_scan(scan.uploaded);
+        self.update_title();
+    }
+
+    /// Open the current scan with the orientation and shading its manifest
+    /// entry asks for. Scans without an entry keep the previous orientation
+    fn apply_manifest_entry(&mut self) {
+        let entry = self.stl_context.current_entry();
+
+        if let Some(model) = entry.and_then(ManifestEntry::model) {
+            self.scene.node_mut(self.scan_node).local = model;
+        }
+
+        self.backend.set_shading(
+            entry
+                .and_then(|entry| entry.shading)
+                .unwrap_or(ShadingMode::Shaded),
+        );
+    }
+
+    /// Queue up to `prefetch_ahead` scans after the current one
+    fn prefetch_next(&mut self) {
+        let start = self.stl_context.session.cursor();
+        let end = start + self.config.loading.prefetch_ahead;
+
+        for path in self
+            .stl_context
+            .session
+            .queue()
+            .iter()
+            .take(end)
+            .skip(start)
+        {
+            if !self.prefetcher.is_pending(path) {
+                self.prefetcher.request(path);
+            }
+        }
+    }
+
+    /// Replace the scan that failed to load with a red X until it is recorded
+    /// as invalid or skipped
+    fn show_load_failure(&mut self, error: &MeshError) {
+        self.stl_context.advance_failed(error);
+        self.prefetch_next();
+
+        self.placeholder = Some(Placeholder::Corrupt(error.to_string()));
+        self.metrics = None;
+        self.shells = None;
+        self.removed_shells.clear();
+        self.exploded = false;
+        self.hover_bvh = None;
+        self.welded = None;
+        self.selection = SelectionMask::default();
+        self.selected_area = (0, 0.0);
+        self.deviation = None;
+        self.bite = None;
+        self.voxels = None;
+        self.undercuts = None;
+        self.insertion_axis = None;
+        self.undercut_bvh = None;
+        self.histograms = None;
+        self.distance_field = None;
+        self.section = None;
+        self.arch = None;
+        self.teeth = None;
+        self.fingerprint = None;
+        self.near_duplicate = None;
+        self.fit = None;
+        self.vertex_normals = None;
+        self.lod_proxy = None;
+
+        if let Some(mesh) = &self.stl_context.current {
+            self.backend.load_scan(mesh);
+        }
+        self.backend.set_highlights(&[]);
+        self.backend.set_comparison(None, 0.0);
+
+        self.update_title();
+    }
+
+    /// C: record a scan that failed to load as invalid and move on
+    fn mark_corrupt(&mut self) {
+        if self.loading.is_some() {
+            return;
+        }
+
+        if let Some(Placeholder::Corrupt(_)) = self.placeholder {
+            self.stl_context
+                .record_invalid()
+                .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+            self.load_next_stl();
+        }
+    }
+
+    fn toggle_exploded(&mut self) {
+        let (mesh, shells) = match (&self.stl_context.current, &self.shells) {
+            (Some(mesh), Some(shells)) => (mesh, shells),
+            _ => return,
+        };
+
+        self.exploded = !self.exploded;
+        self.hover_bvh = Some(if self.exploded {
+            TriangleBvh::new(&shells.explode(mesh, EXPLODE_FACTOR))
+        } else {
+            TriangleBvh::new(mesh)
+        });
+
+        self.show_current_scan(None);
+    }
+
+    /// Upload the current scan, exploded if toggled, with small shells
+    /// highlighted. `uploaded` is used instead of uploading the scan again
+    /// if it is not exploded. Blocks are uploaded instead while they are
+    /// toggled on
+    fn show_current_scan(&mut self, uploaded: Option<UploadedMesh>) {
+        if self.show_voxels {
+            if let Some(grid) = self.voxel_grid() {
+                let blocks = grid.to_mesh();
+
+                self.backend.load_scan(&blocks);
+                self.backend.set_proxy(None);
+                self.backend.set_highlights(&[]);
+                return;
+            }
+        }
+
+        let (mesh, shells) = match (&self.stl_context.current, &self.shells) {
+            (Some(mesh), Some(shells)) => (mesh, shells),
+            _ => return,
+        };
+
+        let exploded;
+        let displayed = if self.exploded {
+            exploded = shells.explode(mesh, EXPLODE_FACTOR);
+            &exploded
+        } else {
+            mesh
+        };
+
+        match uploaded {
+            Some(uploaded) if !self.exploded => self.backend.load_uploaded(uploaded),
+            _ => self.backend.load_scan(displayed),
+        }
+
+        // The proxy matches the scan as loaded, not exploded
+        if !self.exploded {
+            self.backend.set_proxy(self.lod_proxy.as_ref());
+        }
+
+        let highlights = self.highlights(displayed);
+        self.backend.set_highlights(&highlights);
+    }
+
+    /// The current scan's occupancy grid, found the first time it is asked
+    /// for
+    fn voxel_grid(&mut self) -> Option<&VoxelGrid> {
+        let mesh = match (&self.stl_context.current, &self.placeholder) {
+            (Some(mesh), None) => mesh,
+            _ => return None,
+        };
+        let resolution = self.config.voxels.resolution;
+
+        Some(
+            self.voxels
+                .get_or_insert_with(|| VoxelGrid::new(mesh, resolution)),
+        )
+    }
+
+    /// Tint the current scan again after marking shells or painting
+    /// regions, without uploading the scan itself
+    fn update_highlights(&mut self) {
+        // Blocks are drawn untinted
+        if self.show_voxels && self.voxels.is_some() {
+            return;
+        }
+
+        let (mesh, shells) = match (&self.stl_context.current, &self.shells) {
+            (Some(mesh), Some(shells)) => (mesh, shells),
+            _ => return,
+        };
+
+        let exploded;
+        let displayed = if self.exploded {
+            exploded = shells.explode(mesh, EXPLODE_FACTOR);
+            &exploded
+        } else {
+            mesh
+        };
+
+        let highlights = self.highlights(displayed);
+        self.backend.set_highlights(&highlights);
+    }
+
+    /// Small shells, shells marked for removal, and painted regions of the
+    /// current scan as `displayed`, each in its color
+    fn highlights(&self, displayed: &Mesh) -> Vec<(Mesh, Color)> {
+        let shells = match &self.shells {
+            Some(shells) => shells,
+            None => return Vec::new(),
+        };
+
+        let theme = &self.config.theme;
+        let mut highlights = Vec::new();
+
+        match (&self.bite, &self.undercuts, &self.deviation, &self.welded) {
+            // Contacts matter more than the scan's shape while checking the
+            // bite, so the bite wins over the heatmap
+            (Some(bite), _, _, _) if self.show_bite => {
+                let bands = bite.bands(HEATMAP_BANDS);
+
+                // The nearest warm and the farthest cool
+                for band in 0..HEATMAP_BANDS {
+                    let keep: Vec<bool> = bands.iter().map(|&b| b == Some(band)).collect();
+                    let heat = 1.0 - (band as f32 + 0.5) / HEATMAP_BANDS as f32;
+
+                    highlights.push((displayed.retain_triangles(&keep), theme.heatmap(heat)));
+                }
+
+                let penetrating: Vec<bool> =
+                    bands.iter().map(|&b| b == Some(HEATMAP_BANDS)).collect();
+                highlights.push((
+                    displayed.retain_triangles(&penetrating),
+                    theme.warning_color(),
+                ));
+            }
+            (_, Some(undercuts), _, _) if self.show_undercuts => {
+                highlights.push((
+                    displayed.retain_triangles(&undercuts.faces),
+                    theme.warning_color(),
+                ));
+            }
+            // Debris would be hidden under the heatmap anyway
+            (_, _, Some(deviation), Some(welded)) if self.show_heatmap => {
+                let bands = deviation.face_bands(welded, HEATMAP_BANDS);
+
+                for band in 0..HEATMAP_BANDS {
+                    let keep: Vec<bool> = bands.iter().map(|&b| b == band).collect();
+                    let heat = (band as f32 + 0.5) / HEATMAP_BANDS as f32;
+
+                    highlights.push((displayed.retain_triangles(&keep), theme.heatmap(heat)));
+                }
+            }
+            _ => {
+                let small: Vec<bool> = shells
+                    .small_shells(self.config.analysis.small_shell_fraction)
+                    .into_iter()
+                    .zip(&self.removed_shells)
+                    .map(|(is_small, &is_removed)| is_small && !is_removed)
+                    .collect();
+
+                highlights.push((shells.extract(displayed, &small), theme.warning_color()));
+            }
+        }
+
+        highlights.push((
+            shells.extract(displayed, &self.removed_shells),
+            theme.removal_color(),
+        ));
+
+        if let Some(welded) = &self.welded {
+            if !self.selection.is_empty() {
+                let faces = self.selection.faces(welded);
+                highlights.push((displayed.retain_triangles(&faces), theme.selection_color()));
+            }
+        }
+
+        highlights
+    }
+
+    /// Paint or erase the region under the cursor while dragging the brush.
+    /// Regions are painted on the scan as loaded, so not while it is exploded
+    fn paint_under_cursor(&mut self) {
+        let selected = match self.painting {
+            Some(selected) if !self.exploded => selected,
+            _ => return,
+        };
+
+        let (bbox, welded, bvh) = match (&self.stl_context.current, &self.welded, &self.hover_bvh) {
+            (Some(mesh), Some(welded), Some(bvh)) => (mesh.bounding_box(), welded, bvh),
+            _ => return,
+        };
+
+        let hit = match self.cursor_ray(bbox) {
+            Some(ray) => bvh.pick(&ray).map(|hit| ray.at(hit.t)),
+            None => return,
+        };
+
+        let center = match hit {
+            Some(center) => center,
+            None => return,
+        };
+
+        let radius = self.brush_radius * bbox.delta().norm();
+
+        if self.selection.paint(welded, bvh, center, radius, selected) {
+            let faces = self.selection.faces(welded);
+            self.selected_area = (
+                faces.iter().filter(|&&face| face).count(),
+                self.selection.area(welded),
+            );
+
+            self.update_fit();
+            self.update_highlights();
+        }
+    }
+
+    /// H: color the scan by its distance from its occlusal plane, or stop
+    fn toggle_heatmap(&mut self) {
+        self.show_heatmap = !self.show_heatmap;
+
+        if self.show_heatmap && self.deviation.is_none() {
+            self.measure_deviation();
+        }
+
+        self.update_highlights();
+    }
+
+    /// Ctrl+O: open the current scan's case in the default browser
+    fn open_link(&self) {
+        let url = match self.stl_context.current_link() {
+            Some(url) => url,
+            None => {
+                eprintln!("{}", self.catalog.get("link-unavailable"));
+                return;
+            }
+        };
+
+        if let Err(e) = open_in_browser(&url) {
+            eprintln!(
+                "{}",
+                self.catalog
+                    .format("link-failed", &[("url", &url), ("error", &e)])
+            );
+        }
+    }
+
+    /// O: show the current scan's antagonist in articulation with it,
+    /// colored by the gap between them, or stop
+    fn toggle_bite(&mut self) {
+        self.show_bite = !self.show_bite;
+
+        if self.show_bite {
+            self.show_reference = false;
+            self.measure_bite();
+        } else {
+            self.backend.set_comparison(None, 0.0);
+        }
+
+        self.update_highlights();
+    }
+
+    /// Read the current scan's antagonist, draw it over the scan, and
+    /// measure the gap
+    fn measure_bite(&mut self) {
+        self.bite = None;
+        self.backend.set_comparison(None, 0.0);
+
+        let antagonist = match self
+            .stl_context
+            .current_entry()
+            .and_then(|entry| entry.antagonist.clone())
+        {
+            Some(antagonist) => antagonist,
+            None => {
+                if let Some(path) = self.stl_context.current_path() {
+                    eprintln!(
+                        "{}",
+                        self.catalog.format("bite-unavailable", &[("path", &path)])
+                    );
+                }
+                return;
+            }
+        };
+
+        let mesh = match self.stl_context.read(&antagonist.path) {
+            Ok(mesh) => mesh.transformed(&antagonist.transform()),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    self.catalog
+                        .format("bite-failed", &[("path", &antagonist.path), ("error", &e)])
+                );
+                return;
+            }
+        };
+
+        if let Some(welded) = &self.welded {
+            self.bite = Some(BiteDistances::new(
+                welded,
+                &mesh,
+                self.config.analysis.bite_range,
+            ));
+        }
+
+        self.backend.set_comparison(Some(&mesh), ANTAGONIST_ALPHA);
+    }
+
+    /// V: draw the reference template over the scan, or stop
+    fn toggle_reference(&mut self) {
+        if self.reference.is_none() {
+            eprintln!("{}", self.catalog.get("reference-unavailable"));
+            return;
+        }
+
+        self.show_reference = !self.show_reference;
+
+        if self.show_reference {
+            if self.show_bite {
+                self.show_bite = false;
+                self.update_highlights();
+            }
+
+            self.overlay_reference();
+        } else {
+            self.backend.set_comparison(None, 0.0);
+        }
+
+        self.update_title();
+    }
+
+    /// Line the reference template up with the current scan and draw it
+    /// translucent over it
+    fn overlay_reference(&mut self) {
+        self.backend.set_comparison(None, 0.0);
+
+        let (reference, frame) = match &self.reference {
+            Some(reference) => reference,
+            None => return,
+        };
+
+        let target = match self
+            .welded
+            .as_ref()
+            .and_then(|welded| PrincipalFrame::new(&welded.positions))
+        {
+            Some(target) => target,
+            None => return,
+        };
+
+        let aligned = reference.transformed(&frame.onto(&target));
+
+        self.backend
+            .set_comparison(Some(&aligned), self.config.reference.alpha);
+    }
+
+    /// U: highlight the parts of the scan hidden along its insertion axis,
+    /// or stop
+    fn toggle_undercuts(&mut self) {
+        self.show_undercuts = !self.show_undercuts;
+
+        if self.show_undercuts && self.undercuts.is_none() {
+            self.find_undercuts();
+        }
+
+        self.update_highlights();
+    }
+
+    fn find_undercuts(&mut self) {
+        let (mesh, welded) = match (&self.stl_context.current, &self.welded) {
+            (Some(mesh), Some(welded)) => (mesh, welded),
+            _ => {
+                self.undercuts = None;
+                return;
+            }
+        };
+
+        let axis = *self
+            .insertion_axis
+            .get_or_insert_with(|| Undercuts::default_axis(welded));
+        let bvh = self
+            .undercut_bvh
+            .get_or_insert_with(|| TriangleBvh::new(mesh));
+
+        self.undercuts = Some(Undercuts::new(welded, bvh, axis));
+    }
+
+    /// Turn the insertion axis by `yaw` degrees about the screen's vertical
+    /// and `pitch` degrees about its horizontal, so its tip follows the
+    /// arrow keys or the cursor
+    fn tilt_insertion_axis(&mut self, yaw: f32, pitch: f32) {
+        let (axis, bbox) = match (self.insertion_axis, &self.stl_context.current) {
+            (Some(axis), Some(mesh)) if self.show_undercuts => (axis, mesh.bounding_box()),
+            _ => return,
+        };
+
+        let view = self.stationary.view(bbox) * self.scene.world(self.scan_node);
+        let linear: Matrix3<f32> = view.fixed_slice::<U3, U3>(0, 0).into_owned();
+        let inverse = match linear.try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        // The screen's axes in the scan's coordinates
+        let right = Unit::new_normalize(inverse * Vector3::x());
+        let up = Unit::new_normalize(inverse * Vector3::y());
+        let rotation = Rotation3::from_axis_angle(&up, yaw.to_radians())
+            * Rotation3::from_axis_angle(&right, pitch.to_radians());
+
+        self.insertion_axis = Some((rotation * axis).normalize());
+    }
+
+    /// Ctrl and an arrow key: turn the insertion axis a step and find
+    /// undercuts along it
+    fn step_insertion_axis(&mut self, yaw: f32, pitch: f32) {
+        let step = AXIS_TILT_STEP * self.window_state.step_scale();
+
+        self.tilt_insertion_axis(yaw * step, pitch * step);
+        self.refresh_undercuts();
+    }
+
+    /// Find undercuts along the insertion axis as it is now
+    fn refresh_undercuts(&mut self) {
+        if self.show_undercuts {
+            self.find_undercuts();
+            self.update_highlights();
+        }
+    }
+
+    fn measure_deviation(&mut self) {
+        self.deviation = self.welded.as_ref().and_then(PlaneDeviation::occlusal);
+    }
+
+    /// Z: show a slice of the signed distance field across the next axis,
+    /// or stop after z
+    fn cycle_distance_axis(&mut self) {
+        self.distance_axis = match self.distance_axis {
+            None => Some(0),
+            Some(axis) if axis < 2 => Some(axis + 1),
+            Some(_) => None,
+        };
+
+        if self.distance_field.is_none() {
+            self.measure_distance_field();
+        } else {
+            self.center_distance_slice();
+        }
+    }
+
+    /// Page Up or Page Down: move the distance slice along its axis
+    fn step_distance_slice(&mut self, step: isize) {
+        let (axis, field) = match (self.distance_axis, &self.distance_field) {
+            (Some(axis), Some(field)) => (axis, field),
+            _ => return,
+        };
+
+        let last = field.dims[axis] as isize - 1;
+        self.distance_slice = (self.distance_slice as isize + step).max(0).min(last) as usize;
+    }
+
+    fn measure_distance_field(&mut self) {
+        let resolution = self.config.analysis.distance_resolution;
+        self.distance_field = self
+            .welded
+            .as_ref()
+            .filter(|welded| !welded.triangles.is_empty())
+            .map(|welded| DistanceField::new(welded, resolution));
+        self.center_distance_slice();
+    }
+
+    /// Show the slice through the middle of the scan
+    fn center_distance_slice(&mut self) {
+        if let (Some(axis), Some(field)) = (self.distance_axis, &self.distance_field) {
+            self.distance_slice = field.dims[axis] / 2;
+        }
+    }
+
+    fn measure_histograms(&mut self) {
+        self.histograms = self
+            .welded
+            .as_ref()
+            .and_then(|welded| MeshHistograms::new(welded, HISTOGRAM_BINS));
+    }
+
+    /// Fit the shape chosen with G to the painted region again
+    fn update_fit(&mut self) {
+        self.fit = None;
+
+        let (kind, welded) = match (self.fit_kind, &self.welded) {
+            (Some(kind), Some(welded)) => (kind, welded),
+            _ => return,
+        };
+
+        let selected: Vec<usize> = self
+            .selection
+            .vertices()
+            .iter()
+            .enumerate()
+            .filter(|(_, &selected)| selected)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let points: Vec<Vector3<f32>> = selected.iter().map(|&idx| welded.positions[idx]).collect();
+        let normals = match kind {
+            PrimitiveKind::Cylinder => {
+                let normals = self
+                    .vertex_normals
+                    .get_or_insert_with(|| welded.vertex_normals());
+
+                selected.iter().map(|&idx| normals[idx]).collect()
+            }
+            _ => Vec::new(),
+        };
+
+        self.fit = Fit::new(kind, &points, &normals);
+    }
+
+    /// The HUD line describing the fit of `kind`, or how to make one
+    fn fit_summary(&self, kind: PrimitiveKind) -> String {
+        let shape = self.catalog.get(match kind {
+            PrimitiveKind::Plane => "fit-plane",
+            PrimitiveKind::Sphere => "fit-sphere",
+            PrimitiveKind::Cylinder => "fit-cylinder",
+        });
+
+        let fit = match &self.fit {
+            Some(fit) => fit,
+            None => return self.catalog.format("fit-unavailable", &[("shape", &shape)]),
+        };
+
+        let radius = fit
+            .primitive
+            .radius()
+            .map_or_else(|| "-".to_owned(), |radius| format!("{:.2}", radius));
+
+        self.catalog.format(
+            "fit-summary",
+            &[
+                ("shape", &shape),
+                ("points", &fit.point_count),
+                ("radius", &radius),
+                ("rms", &format!("{:.3}", fit.residuals.rms)),
+                ("max", &format!("{:.3}", fit.residuals.max)),
+            ],
+        )
+    }
+
+    /// `[` and `]`: shrink or grow the brush by `factor`
+    fn resize_brush(&mut self, factor: f32) {
+        let (min, max) = BRUSH_RADIUS_RANGE;
+        self.brush_radius = (self.brush_radius * factor).max(min).min(max);
+    }
+
+    /// The ray through the cursor in the model space of a scan bounded by
+    /// `bbox`
+    fn cursor_ray(&self, bbox: BoundingBox) -> Option<Ray> {
+        let dimensions = self.dimensions();
+        let transform =
+            self.projection() * self.stationary.view(bbox) * self.scene.world(self.scan_node);
+
+        Ray::from_screen(
+            self.window_state.cursor,
+            (dimensions.width, dimensions.height),
+            &transform,
+        )
+    }
+
+    /// Zoom by a wheel movement, toward the point under the cursor if it is
+    /// over the window, so that point stays put on screen
+    fn zoom(&mut self, delta: MouseScrollDelta) {
+        let before = self.camera.fov();
+        self.camera.scroll(delta, self.window_state.step_scale());
+        let after = self.camera.fov();
+
+        let mesh = match &self.stl_context.current {
+            Some(mesh) if self.window_state.is_window_hovered && !mesh.is_empty() => mesh,
+            _ => return,
+        };
+
+        let bbox = mesh.bounding_box();
+
+        let ray = match self.cursor_ray(bbox) {
+            Some(ray) => ray,
+            None => return,
+        };
+
+        // Over the background, zoom toward the point level with the center
+        // of the scan. Exploded shells are not picked, which only matters
+        // for where the zoom is centered
+        let target = pick_point(mesh, &ray).unwrap_or_else(|| ray.closest_point(bbox.center()));
+
+        // Narrowing the field of view scales every point's offset from the
+        // center of the screen by the ratio of tangents; panning by the
+        // difference keeps the target where it was
+        let view = self.stationary.view(bbox) * self.scene.world(self.scan_node);
+        let target = view.transform_point(&Point3::from(target));
+        let ratio = (after / 2.0).tan() / (before / 2.0).tan();
+
+        self.stationary
+            .pan_view_space(bbox, Vector2::new(target.x, target.y) * (ratio - 1.0));
+    }
+
+    /// The point on the displayed scan under the cursor, in model space
+    fn point_under_cursor(&self) -> Option<Vector3<f32>> {
+        let mesh = self.stl_context.current.as_ref()?;
+        let ray = self.cursor_ray(mesh.bounding_box())?;
+
+        match &self.hover_bvh {
+            Some(bvh) => bvh.pick(&ray).map(|hit| ray.at(hit.t)),
+            None => pick_point(mesh, &ray),
+        }
+    }
+
+    /// Center the view on the point under the cursor and zoom in close
+    /// around it
+    fn focus_under_cursor(&mut self) {
+        let point = match self.point_under_cursor() {
+            Some(point) => point,
+            None => return,
+        };
+
+        let bbox = match &self.stl_context.current {
+            Some(mesh) => mesh.bounding_box(),
+            None => return,
+        };
+
+        let view = self.stationary.view(bbox) * self.scene.world(self.scan_node);
+        let point = view.transform_point(&Point3::from(point));
+
+        self.stationary
+            .pan_view_space(bbox, -Vector2::new(point.x, point.y));
+
+        // The camera looks down -z in view space
+        let depth = -point.z;
+
+        if depth > f32::EPSILON {
+            let radius = FOCUS_RADIUS * bbox.delta().norm();
+            self.camera.zoom_to(2.0 * (radius / depth).atan());
+        }
+    }
+
+    /// Mark or unmark the shell under the cursor for removal
+    fn toggle_shell_under_cursor(&mut self) {
+        let (mesh, shells) = match (&self.stl_context.current, &self.shells) {
+            (Some(mesh), Some(shells)) => (mesh, shells),
+            _ => return,
+        };
+
+        let ray = match self.cursor_ray(mesh.bounding_box()) {
+            Some(ray) => ray,
+            None => return,
+        };
+
+        // Exploding moves shells without reordering their triangles
+        let triangle = match &self.hover_bvh {
+            Some(bvh) => bvh.pick(&ray).map(|hit| hit.triangle),
+            None if self.exploded => pick_triangle(&shells.explode(mesh, EXPLODE_FACTOR), &ray),
+            None => pick_triangle(mesh, &ray),
+        };
+
+        if let Some(triangle) = triangle {
+            let shell = shells.triangle_shells[triangle] as usize;
+            self.removed_shells[shell] = !self.removed_shells[shell];
+
+            self.update_highlights();
+        }
+    }
+
+    /// Save the current scan without the shells marked for removal, next to
+    /// the original as `<name>.cleaned.stl`
+    fn export_cleaned(&self) {
+        let (mesh, shells, path) = match (
+            &self.stl_context.current,
+            &self.shells,
+            self.stl_context.current_path(),
+        ) {
+            (Some(mesh), Some(shells), Some(path)) => (mesh, shells, path),
+            _ => return,
+        };
+
+        // Only part of the scan is loaded, so saving it would lose the rest
+        if self
+            .stl_context
+            .current_entry()
+            .map_or(false, |entry| entry.clip.is_some())
+        {
+            eprintln!("{}", self.catalog.get("cleaned-scan-clipped"));
+            return;
+        }
+
+        let kept: Vec<bool> = self.removed_shells.iter().map(|&r| !r).collect();
+        let removed = self.removed_shells.iter().filter(|&&r| r).count();
+
+        let output = Path::new(path).with_extension("cleaned.stl");
+
+        match shells.extract(mesh, &kept).save_stl(&output) {
+            Ok(()) => println!(
+                "{}",
+                self.catalog.format(
+                    "cleaned-scan-saved",
+                    &[("path", &output.display()), ("removed", &removed)],
+                )
+            ),
+            Err(e) => eprintln!(
+                "{}",
+                self.catalog.format("cleaned-scan-failed", &[("error", &e)])
+            ),
+        }
+    }
+
+    /// Save the regions painted on the current scan, if any, as it is
+    /// labeled
+    fn save_selection(&self) {
+        let (welded, path) = match (&self.welded, self.stl_context.current_path()) {
+            (Some(welded), Some(path)) if !self.selection.is_empty() => (welded, path),
+            _ => return,
+        };
+
+        let masks = &self.config.masks;
+        let output = masks.path(path);
+
+        let result = fs::create_dir_all(&masks.directory)
+            .and_then(|()| File::create(&output))
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                self.selection
+                    .write(welded, path, masks.indexing, masks.format, &mut writer)?;
+                writer.flush()
+            });
+
+        match result {
+            Ok(()) => println!(
+                "{}",
+                self.catalog
+                    .format("mask-saved", &[("path", &output.display())])
+            ),
+            Err(e) => eprintln!("{}", self.catalog.format("mask-failed", &[("error", &e)])),
+        }
+    }
+
+    /// Set the window title, naming the queue open if there are tabs
+    fn set_title(&self, title: &str) {
+        if !self.stl_context.has_tabs() {
+            self.window.window().set_title(title);
+            return;
+        }
+
+        let tab = self
+            .stl_context
+            .tab_name()
+            .map_or_else(|| self.catalog.get("tab-main"), str::to_owned);
+
+        self.window.window().set_title(
+            &self
+                .catalog
+                .format("tab-title", &[("tab", &tab), ("title", &title)]),
+        );
+    }
+
+    fn update_title(&self) {
+        if let Some(loading) = &self.loading {
+            let title = if loading.timed_out {
+                self.catalog.format(
+                    "loading-timed-out",
+                    &[
+                        ("path", &loading.path),
+                        ("seconds", &loading.started.elapsed().as_secs()),
+                    ],
+                )
+            } else {
+                self.catalog
+                    .format("loading-scan", &[("path", &loading.path)])
+            };
+
+            self.set_title(&title);
+            return;
+        }
+
+        let path = self.stl_context.current_path().unwrap_or_default();
+
+        if let Some(scan_kind) = self.confirming_label {
+            let title = self.catalog.format(
+                "label-confirm",
+                &[
+                    ("path", &path),
+                    ("category", &self.stl_context.category(scan_kind).caption()),
+                ],
+            );
+
+            self.set_title(&title);
+            return;
+        }
+
+        if let Some((scan_kind, _)) = self.pending_label {
+            let title = self.catalog.format(
+                "label-pending",
+                &[
+                    ("path", &path),
+                    ("category", &self.stl_context.category(scan_kind).caption()),
+                ],
+            );
+
+            self.set_title(&title);
+            return;
+        }
+
+        if let Some(placeholder) = &self.placeholder {
+            let title = placeholder.title(&self.catalog, path);
+
+            self.set_title(&title);
+            return;
+        }
+
+        let metrics = match &self.metrics {
+            Some(metrics) => metrics,
+            None => return,
+        };
+
+        let mut title = scan_title(&self.catalog, path, metrics);
+
+        if self.stl_context.is_expedited() {
+            title = format!("{} | {}", self.catalog.get("expedited-scan"), title);
+        }
+
+        if self.labeling_too_fast {
+            title = format!("{} | {}", self.catalog.get("labeling-too-fast"), title);
+        }
+
+        if let Some((duplicate, label)) = &self.near_duplicate {
+            let warning = self
+                .catalog
+                .format("near-duplicate", &[("path", duplicate), ("label", label)]);
+            title = format!("{} | {}", warning, title);
+        }
+
+        if let Some((suggestion, scan_kind)) = self.stl_context.suggestion() {
+            let hint = self.catalog.format(
+                "cluster-suggestion",
+                &[
+                    ("cluster", &suggestion.cluster),
+                    ("exemplar", &suggestion.exemplar),
+                    ("category", &self.stl_context.category(scan_kind).caption()),
+                ],
+            );
+            title = format!("{} | {}", hint, title);
+        }
+
+        if self.scene.is_visible(self.bounding_box_node) {
+            let size = metrics.bounding_box.delta();
+
+            title.push_str(" | ");
+            title.push_str(&self.catalog.format(
+                "bounding-box-size",
+                &[
+                    ("x", &format!("{:.1}", size.x)),
+                    ("y", &format!("{:.1}", size.y)),
+                    ("z", &format!("{:.1}", size.z)),
+                ],
+            ));
+        }
+
+        let labeling = &self.config.labeling;
+
+        for drift in self.stl_context.session.report().drift(
+            &labeling.priors,
+            labeling.prior_tolerance,
+            labeling.prior_min_labels,
+        ) {
+            title.push_str(" | ");
+            title.push_str(&self.catalog.format(
+                "category-drift",
+                &[
+                    ("category", &drift.label),
+                    ("share", &format!("{:.0}", drift.fraction * 100.0)),
+                    ("expected", &format!("{:.0}", drift.expected * 100.0)),
+                ],
+            ));
+        }
+
+        let waiting = self.stl_context.urgent_waiting();
+
+        if waiting > 0 {
+            title.push_str(" | ");
+            title.push_str(
+                &self
+                    .catalog
+                    .format("urgent-waiting", &[("count", &waiting)]),
+            );
+        }
+
+        self.set_title(&title);
+    }
+
+    /// Softly shade the patch of the scan under the cursor and mark the
+    /// cursor with a crosshair, showing exactly where a click will land
+    fn draw_hover(&self, batch: &mut OverlayBatch) {
+        if !(self.config.display.hover_highlight || self.brush_mode)
+            || !self.window_state.is_window_hovered
+            || self.loading.is_some()
+            || self.placeholder.is_some()
+        {
+            return;
+        }
+
+        let (bbox, bvh) = match (&self.stl_context.current, &self.hover_bvh) {
+            (Some(mesh), Some(bvh)) => (mesh.bounding_box(), bvh),
+            _ => return,
+        };
+
+        let ray = match self.cursor_ray(bbox) {
+            Some(ray) => ray,
+            None => return,
+        };
+
+        let hit = match bvh.pick(&ray) {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        let dimensions = self.dimensions();
+        let theme = &self.config.theme;
+        let color = theme.overlay_color();
+
+        let transform = self.screen_transform(bbox);
+        let to_screen = |position| to_screen(&transform, dimensions, position);
+
+        let center = ray.at(hit.t);
+        // In brush mode, the patch shows what a stroke would paint
+        let radius = if self.brush_mode {
+            self.brush_radius
+        } else {
+            HOVER_RADIUS
+        } * bbox.delta().norm();
+        let hit_side = ray.direction.dot(&hit.normal);
+
+        bvh.triangles_near(center, radius, |_, [a, b, c]| {
+            let normal = (b - a).cross(&(c - a));
+
+            // Skip the far side of thin walls within the radius
+            if ray.direction.dot(&normal) * hit_side < 0.0 {
+                return;
+            }
+
+            let falloff = 1.0 - ((a + b + c) / 3.0 - center).norm() / radius;
+
+            if let (Some(a), Some(b), Some(c)) = (to_screen(a), to_screen(b), to_screen(c)) {
+                batch.triangle(a, b, c, color, HOVER_ALPHA * falloff);
+            }
+        });
+
+        let cursor = Vector2::new(self.window_state.cursor.0, self.window_state.cursor.1);
+        let arm = CROSSHAIR_ARM * theme.hud_scale();
+        let gap = CROSSHAIR_GAP * theme.hud_scale();
+        let width = theme.outline_width();
+
+        for &direction in &[
+            Vector2::new(1.0, 0.0),
+            Vector2::new(-1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(0.0, -1.0),
+        ] {
+            let near = cursor + direction * gap;
+            let far = cursor + direction * (gap + arm);
+            let across = Vector2::new(direction.y, direction.x) * (width / 2.0);
+
+            batch.rect(
+                near.inf(&far) - across,
+                (far - near).abs() + across * 2.0,
+                color,
+                1.0,
+            );
+        }
+    }
+
+    /// The 2D pass over the scan: the hover highlight, the last frame's
+    /// render counters when toggled with I, the painted area while using the
+    /// brush, the heatmap's legend, and the category of a label waiting to be committed or
+    /// confirmed, large enough to catch a wrong key at a glance
+    fn draw_hud(&mut self) {
+        let dimensions = self.dimensions();
+        let mut batch = OverlayBatch::new();
+
+        self.draw_hover(&mut batch);
+
+        let theme = &self.config.theme;
+        let atlas = self.overlay.atlas();
+        // Of the free space in the top left, below the counters if shown
+        let mut top_left = HUD_MARGIN * theme.hud_scale();
+
+        if self.show_stats {
+            let stats = &self.frame_stats;
+            let text = self.catalog.format(
+                "render-stats",
+                &[
+                    ("draws", &stats.draw_calls),
+                    ("triangles", &stats.triangles),
+                    ("changes", &stats.state_changes()),
+                    ("uniforms", &stats.uniform_uploads),
+                ],
+            );
+
+            let margin = HUD_MARGIN * theme.hud_scale();
+            let layout = atlas.layout(&text, theme.text_size());
+            let padding = margin / 2.0;
+
+            // A translucent backing keeps the counters legible over the scan
+            batch.rect(
+                Vector2::repeat(padding),
+                Vector2::new(layout.width, layout.height) + Vector2::repeat(padding * 2.0),
+                Color::BLACK,
+                0.5,
+            );
+            batch.layout(&layout, Vector2::new(margin, margin), Color::WHITE, 1.0);
+            top_left += layout.height + margin;
+        }
+
+        if let Some(mesh) = &self.stl_context.current {
+            let mut lines = Vec::new();
+
+            if self.brush_mode || self.selected_area.0 > 0 {
+                let radius = self.brush_radius * mesh.bounding_box().delta().norm();
+                let (triangles, area) = self.selected_area;
+
+                lines.push(self.catalog.format(
+                    "selection-area",
+                    &[
+                        ("radius", &format!("{:.2}", radius)),
+                        ("area", &format!("{:.1}", area)),
+                        ("triangles", &triangles),
+                    ],
+                ));
+            }
+
+            if let Some(kind) = self.fit_kind {
+                lines.push(self.fit_summary(kind));
+            }
+
+            if let Some(undercuts) = self.undercuts.as_ref().filter(|_| self.show_undercuts) {
+                lines.push(self.catalog.format(
+                    "undercut-summary",
+                    &[
+                        ("area", &format!("{:.2}", undercuts.area)),
+                        ("percent", &format!("{:.1}", undercuts.fraction * 100.0)),
+                    ],
+                ));
+            }
+
+            if let Some(bite) = self.bite.as_ref().filter(|_| self.show_bite) {
+                lines.push(match bite.closest() {
+                    Some(closest) => self.catalog.format(
+                        "bite-summary",
+                        &[
+                            ("closest", &format!("{:.2}", closest)),
+                            ("penetrating", &bite.penetrating()),
+                        ],
+                    ),
+                    None => self
+                        .catalog
+                        .format("bite-apart", &[("range", &format!("{:.2}", bite.range))]),
+                });
+            }
+
+            if !lines.is_empty() {
+                let text = lines.join("\n");
+                let margin = HUD_MARGIN * theme.hud_scale();
+                let layout = atlas.layout(&text, theme.text_size());
+                let position = Vector2::new(
+                    dimensions.width as f32 - margin - layout.width,
+                    dimensions.height as f32 - margin - layout.height,
+                );
+
+                batch.layout(&layout, position, theme.selection_color(), 1.0);
+            }
+        }
+
+        if let Some(deviation) = self.deviation.as_ref().filter(|_| self.show_heatmap) {
+            let scale = theme.hud_scale();
+            let margin = HUD_MARGIN * scale;
+            let size = Vector2::new(LEGEND_SIZE.0, LEGEND_SIZE.1) * scale;
+            let top_left = Vector2::new(
+                dimensions.width as f32 - margin - size.x,
+                (dimensions.height as f32 - size.y) / 2.0,
+            );
+            let band_height = size.y / HEATMAP_BANDS as f32;
+
+            // The occlusal side at the top
+            for band in 0..HEATMAP_BANDS {
+                let heat = (band as f32 + 0.5) / HEATMAP_BANDS as f32;
+                let y = top_left.y + (HEATMAP_BANDS - 1 - band) as f32 * band_height;
+
+                batch.rect(
+                    Vector2::new(top_left.x, y),
+                    Vector2::new(size.x, band_height),
+                    theme.heatmap(heat),
+                    1.0,
+                );
+            }
+
+            for &(distance, y) in &[
+                (deviation.range, top_left.y),
+                (0.0, top_left.y + size.y / 2.0),
+                (-deviation.range, top_left.y + size.y),
+            ] {
+                let layout = atlas.layout(&format!("{:+.2}", distance), theme.text_size());
+                let position = Vector2::new(
+                    top_left.x - margin / 2.0 - layout.width,
+                    y - layout.height / 2.0,
+                );
+
+                batch.layout(&layout, position, Color::WHITE, 1.0);
+            }
+
+            let caption = self.catalog.format(
+                "heatmap-legend",
+                &[
+                    ("rms", &format!("{:.2}", deviation.residuals.rms)),
+                    ("max", &format!("{:.2}", deviation.residuals.max)),
+                ],
+            );
+            let layout = atlas.layout(&caption, theme.text_size());
+            let position = Vector2::new(
+                dimensions.width as f32 - margin - layout.width,
+                top_left.y - margin - layout.height,
+            );
+
+            batch.layout(&layout, position, Color::WHITE, 1.0);
+        }
+
+        if let Some(histograms) = self.histograms.as_ref().filter(|_| self.show_histograms) {
+            let margin = HUD_MARGIN * theme.hud_scale();
+            let mut bottom = dimensions.height as f32 - margin;
+
+            // Areas below edge lengths, each caption above its plot
+            for (histogram, caption) in &[
+                (&histograms.triangle_areas, "histogram-areas"),
+                (&histograms.edge_lengths, "histogram-edges"),
+            ] {
+                bottom = self.draw_histogram(&mut batch, histogram, caption, margin, bottom);
+                bottom -= margin;
+            }
+        }
+
+        if self.show_section {
+            self.draw_section(&mut batch);
+        }
+
+        if self.distance_axis.is_some() {
+            self.draw_distance_slice(&mut batch, top_left);
+        }
+
+        if self.show_undercuts {
+            self.draw_insertion_axis(&mut batch);
+        }
+
+        let arch = self
+            .arch
+            .filter(|arch| self.config.labeling.arch_hint && arch.is_confident());
+
+        let margin = HUD_MARGIN * theme.hud_scale();
+        let mut top = margin;
+
+        if let Some(arch) = arch {
+            let key = match arch.arch {
+                Arch::Upper => "arch-upper",
+                Arch::Lower => "arch-lower",
+            };
+
+            let layout = atlas.layout(&self.catalog.get(key), theme.text_size() * 1.5);
+            let position = Vector2::new((dimensions.width as f32 - layout.width) / 2.0, top);
+            top += layout.height;
+
+            // Fainter the less sure the guess is
+            batch.layout(
+                &layout,
+                position,
+                theme.overlay_color(),
+                0.5 + arch.score / 2.0,
+            );
+        }
+
+        let labeling = &self.config.labeling;
+
+        if let Some(teeth) = self.teeth.as_ref().filter(|_| labeling.tooth_count) {
+            let count = teeth.count();
+            let (key, color) = if count < labeling.partial_arch_teeth {
+                ("teeth-partial", theme.warning_color())
+            } else {
+                ("teeth-count", theme.overlay_color())
+            };
+
+            let text = self.catalog.format(key, &[("count", &count)]);
+            let layout = atlas.layout(&text, theme.text_size());
+            let position = Vector2::new((dimensions.width as f32 - layout.width) / 2.0, top);
+
+            batch.layout(&layout, position, color, 1.0);
+
+            if self.show_teeth {
+                self.draw_teeth(&mut batch, teeth);
+            }
+        }
+
+        let category = self
+            .pending_label
+            .map(|(scan_kind, _)| scan_kind)
+            .or(self.confirming_label);
+
+        if let Some(scan_kind) = category {
+            let caption = self.stl_context.category(scan_kind).caption();
+            let layout = atlas.layout(&caption, theme.text_size() * CATEGORY_TEXT_SCALE);
+            let viewport = Vector2::new(dimensions.width as f32, dimensions.height as f32);
+            let position = (viewport - Vector2::new(layout.width, layout.height)) / 2.0;
+
+            batch.layout(
+                &layout,
+                position,
+                theme.category_color(scan_kind.index()),
+                1.0,
+            );
+        }
+
+        let style = TextStyle {
+            outline_width: if theme.high_contrast { 6.0 } else { 3.0 },
+            ..TextStyle::default()
+        };
+
+        self.overlay
+            .draw(&batch, (dimensions.width, dimensions.height), &style);
+    }
+
+    /// From the current scan's coordinates to clip space
+    fn screen_transform(&self, bbox: BoundingBox) -> Matrix4<f32> {
+        self.projection() * self.stationary.view(bbox) * self.scene.world(self.scan_node)
+    }
+
+    /// An arrow from the center of the scan along the insertion axis
+    fn draw_insertion_axis(&self, batch: &mut OverlayBatch) {
+        let (axis, bbox) = match (self.insertion_axis, &self.stl_context.current) {
+            (Some(axis), Some(mesh)) => (axis, mesh.bounding_box()),
+            _ => return,
+        };
+
+        let dimensions = self.dimensions();
+        let theme = &self.config.theme;
+        let transform = self.screen_transform(bbox);
+        let start = bbox.center();
+        let end = start + axis * AXIS_ARROW_LENGTH * bbox.delta().norm();
+
+        let (start, end) = match (
+            to_screen(&transform, dimensions, start),
+            to_screen(&transform, dimensions, end),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+
+        let width = AXIS_ARROW_WIDTH * theme.hud_scale();
+        let color = theme.selection_color();
+        batch.line(start, end, width, color, 1.0);
+
+        // The head, unless the axis points almost straight at the viewer
+        if let Some(back) = (start - end).try_normalize(f32::EPSILON) {
+            let side = Vector2::new(-back.y, back.x);
+            let head = width * 4.0;
+
+            batch.line(end, end + (back + side) * head, width, color, 1.0);
+            batch.line(end, end + (back - side) * head, width, color, 1.0);
+        }
+    }
+
+    /// A numbered marker on each tooth counted, so a miscount can be seen
+    fn draw_teeth(&self, batch: &mut OverlayBatch, teeth: &ToothCount) {
+        let bbox = match &self.stl_context.current {
+            Some(mesh) => mesh.bounding_box(),
+            None => return,
+        };
+
+        let dimensions = self.dimensions();
+        let theme = &self.config.theme;
+        let atlas = self.overlay.atlas();
+        let transform = self.screen_transform(bbox);
+        let radius = TOOTH_MARKER_RADIUS * theme.hud_scale();
+
+        for (number, &tooth) in teeth.teeth.iter().enumerate() {
+            let center = match to_screen(&transform, dimensions, tooth) {
+                Some(center) => center,
+                None => continue,
+            };
+
+            batch.rect(
+                center - Vector2::repeat(radius),
+                Vector2::repeat(radius * 2.0),
+                theme.overlay_color(),
+                0.8,
+            );
+
+            let layout = atlas.layout(&(number + 1).to_string(), theme.text_size());
+            let size = Vector2::new(layout.width, layout.height);
+            batch.layout(&layout, center - size / 2.0, Color::WHITE, 1.0);
+        }
+    }
+
+    /// The shown slice of the current scan's signed distance field in an
+    /// inset at the left, with its top at `top`. Points in front of the
+    /// surface are drawn warm and behind it cool
+    fn draw_distance_slice(&self, batch: &mut OverlayBatch, top: f32) {
+        let (axis, field) = match (self.distance_axis, &self.distance_field) {
+            (Some(axis), Some(field)) => (axis, field),
+            _ => return,
+        };
+
+        let slice = field.slice(axis, self.distance_slice);
+        let range = field.range().max(f32::EPSILON);
+
+        let theme = &self.config.theme;
+        let scale = theme.hud_scale();
+        let margin = HUD_MARGIN * scale;
+        let padding = margin / 2.0;
+        let size = Vector2::new(DISTANCE_SIZE.0, DISTANCE_SIZE.1) * scale;
+        let [columns, rows] = slice.dims;
+        let cell = (size.x / columns as f32).min(size.y / rows as f32);
+        let drawn = Vector2::new(columns as f32, rows as f32) * cell;
+        let top_left = Vector2::new(margin, top);
+
+        batch.rect(
+            top_left - Vector2::repeat(padding),
+            drawn + Vector2::repeat(padding * 2.0),
+            Color::BLACK,
+            0.6,
+        );
+
+        // The second axis up, as the scan is seen from the end of the first
+        for column in 0..columns {
+            for row in 0..rows {
+                let heat = 0.5 + slice.at([column, row]) / (2.0 * range);
+                let position =
+                    top_left + Vector2::new(column as f32, (rows - 1 - row) as f32) * cell;
+
+                batch.rect(position, Vector2::repeat(cell), theme.heatmap(heat), 1.0);
+            }
+        }
+
+        let [across, up] = slice.axes();
+        let caption = self.catalog.format(
+            "distance-caption",
+            &[
+                ("axis", &AXIS_NAMES[axis]),
+                ("position", &format!("{:.1}", slice.position)),
+                ("across", &AXIS_NAMES[across]),
+                ("up", &AXIS_NAMES[up]),
+                ("range", &format!("{:.1}", range)),
+                ("inside", &format!("{:.0}", field.border_inside() * 100.0)),
+            ],
+        );
+        let layout = self.overlay.atlas().layout(&caption, theme.text_size());
+
+        batch.layout(
+            &layout,
+            Vector2::new(margin, top_left.y + drawn.y + padding * 2.0),
+            Color::WHITE,
+            1.0,
+        );
+    }
+
+    /// The current scan's cross section in an inset at the top right, on a
+    /// grid in the scan's units
+    fn draw_section(&self, batch: &mut OverlayBatch) {
+        let (section, bbox) = match (&self.section, &self.metrics) {
+            (Some(section), Some(metrics)) => (section, metrics.bounding_box),
+            _ => return,
+        };
+
+        let theme = &self.config.theme;
+        let dimensions = self.dimensions();
+        let scale = theme.hud_scale();
+        let margin = HUD_MARGIN * scale;
+        let padding = margin / 2.0;
+        let size = Vector2::new(SECTION_SIZE.0, SECTION_SIZE.1) * scale;
+        let top_left = Vector2::new(dimensions.width as f32 - margin - size.x, margin);
+
+        // Framed by the whole scan, so the view holds still as the cut
+        // changes from one scan to the next
+        let corners = (0..8).map(|corner| {
+            let pick = |bit: usize, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+
+            section.project(Vector3::new(
+                pick(1, bbox.min.x, bbox.max.x),
+                pick(2, bbox.min.y, bbox.max.y),
+                pick(4, bbox.min.z, bbox.max.z),
+            ))
+        });
+        let (min, max) = corners.fold(
+            (
+                Vector2::repeat(f32::INFINITY),
+                Vector2::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), corner| (min.inf(&corner), max.sup(&corner)),
+        );
+
+        let extent = (max - min).map(|e| e.max(f32::EPSILON));
+        let pixels_per_unit = (size.x / extent.x).min(size.y / extent.y);
+        let center = (min + max) / 2.0;
+
+        // y up, as the profile is seen from the front of the plane
+        let to_screen = |point: Vector2<f32>| {
+            let offset = (point - center) * pixels_per_unit;
+            top_left + size / 2.0 + Vector2::new(offset.x, -offset.y)
+        };
+
+        batch.rect(
+            top_left - Vector2::repeat(padding),
+            size + Vector2::repeat(padding * 2.0),
+            Color::BLACK,
+            0.6,
+        );
+
+        let spacing = grid_spacing(extent.x.max(extent.y) / SECTION_GRID_LINES);
+        let visible = |value: f32, low: f32, high: f32| value >= low && value <= high;
+        let (low, high) = (
+            center - size / (2.0 * pixels_per_unit),
+            center + size / (2.0 * pixels_per_unit),
+        );
+
+        let mut x = (low.x / spacing).ceil() * spacing;
+        while visible(x, low.x, high.x) {
+            let (top, bottom) = (
+                to_screen(Vector2::new(x, high.y)),
+                to_screen(Vector2::new(x, low.y)),
+            );
+            batch.line(top, bottom, 1.0, Color::WHITE, 0.2);
+            x += spacing;
+        }
+
+        let mut y = (low.y / spacing).ceil() * spacing;
+        while visible(y, low.y, high.y) {
+            let (left, right) = (
+                to_screen(Vector2::new(low.x, y)),
+                to_screen(Vector2::new(high.x, y)),
+            );
+            batch.line(left, right, 1.0, Color::WHITE, 0.2);
+            y += spacing;
+        }
+
+        let width = 1.5 * scale;
+
+        for polyline in &section.polylines {
+            for pair in polyline.windows(2) {
+                batch.line(
+                    to_screen(pair[0]),
+                    to_screen(pair[1]),
+                    width,
+                    theme.overlay_color(),
+                    1.0,
+                );
+            }
+        }
+
+        let caption = self.catalog.format(
+            "section-caption",
+            &[
+                ("contours", &section.polylines.len()),
+                ("length", &format!("{:.1}", section.length())),
+                ("grid", &spacing),
+            ],
+        );
+        let layout = self.overlay.atlas().layout(&caption, theme.text_size());
+        let position = Vector2::new(
+            dimensions.width as f32 - margin - layout.width,
+            top_left.y + size.y + padding * 2.0,
+        );
+
+        batch.layout(&layout, position, Color::WHITE, 1.0);
+    }
+
+    /// Plot `histogram` with its left edge at `left` and its bottom at
+    /// `bottom`, captioned with the `caption` message. Returns the top of
+    /// the caption
+    fn draw_histogram(
+        &self,
+        batch: &mut OverlayBatch,
+        histogram: &Histogram,
+        caption: &str,
+        left: f32,
+        bottom: f32,
+    ) -> f32 {
+        let theme = &self.config.theme;
+        let scale = theme.hud_scale();
+        let size = Vector2::new(HISTOGRAM_SIZE.0, HISTOGRAM_SIZE.1) * scale;
+        let padding = HUD_MARGIN * scale / 2.0;
+
+        // One bar per bin, then a gap and the overflow
+        let bar_width = size.x / (histogram.counts.len() + 2) as f32;
+        let tallest = histogram.tallest().max(1) as f32;
+        let top = bottom - size.y;
+
+        let text = self.catalog.format(
+            caption,
+            &[
+                ("median", &format!("{:.3}", histogram.median)),
+                ("tail", &format!("{:.1}", histogram.tail * 100.0)),
+                ("factor", &TAIL_FACTOR),
+                ("peaks", &histogram.peaks()),
+            ],
+        );
+        let layout = self.overlay.atlas().layout(&text, theme.text_size());
+        let caption_top = top - padding - layout.height;
+
+        batch.rect(
+            Vector2::new(left - padding, caption_top - padding),
+            Vector2::new(size.x.max(layout.width), bottom - caption_top)
+                + Vector2::repeat(padding * 2.0),
+            Color::BLACK,
+            0.5,
+        );
+
+        let bars = histogram
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(idx, &count)| (idx, count, theme.overlay_color()))
+            .chain(std::iter::once((
+                histogram.counts.len() + 1,
+                histogram.overflow,
+                theme.warning_color(),
+            )));
+
+        for (idx, count, color) in bars {
+            let height = size.y * count as f32 / tallest;
+
+            batch.rect(
+                Vector2::new(left + idx as f32 * bar_width, bottom - height),
+                Vector2::new(bar_width, height),
+                color,
+                1.0,
+            );
+        }
+
+        batch.layout(&layout, Vector2::new(left, caption_top), Color::WHITE, 1.0);
+
+        caption_top - padding
+    }
+
+    fn draw_bounding_box(&mut self, projection: &Matrix4<f32>) {
+        let bbox = match &self.metrics {
+            Some(metrics) => metrics.bounding_box,
+            None => return,
+        };
+
+        let dimensions = self.dimensions();
+
+        let mut batch = LineBatch::new();
+        batch.bounding_box(&bbox, self.config.theme.overlay_color());
+
+        let transform =
+            projection * self.stationary.view(bbox) * self.scene.world(self.bounding_box_node);
+
+        self.line_renderer.draw(
+            &batch,
+            &transform,
+            (dimensions.width, dimensions.height),
+            &LineStyle {
+                line_width: self.config.theme.outline_width() * 1.5,
+                ..LineStyle::default()
+            },
+        );
+    }
+
+    /// The axis gadget, and the view of the current scan it reflects
+    fn axis_gadget(&self) -> Option<(AxisGadget, Matrix4<f32>)> {
+        let bbox = self.metrics.as_ref()?.bounding_box;
+        let gadget = AxisGadget::new(self.dimensions().height, self.config.theme.hud_scale());
+
+        Some((gadget, self.stationary.view(bbox)))
+    }
+
+    /// The scan's axes in the bottom left corner, with the ends pointing
+    /// away from the camera drawn as faded dots
+    fn draw_axis_gadget(&mut self) {
+        let (gadget, view) = match self.axis_gadget() {
+            Some(gadget) => gadget,
+            None => return,
+        };
+
+        let dimensions = self.dimensions();
+        let theme = &self.config.theme;
+
+        // Drawn directly in clip space, just past the near plane so the scan
+        // never covers it, with ends toward the camera in front
+        let to_clip = |position: Vector2<f32>, depth: f32| {
+            Vector3::new(
+                position.x / dimensions.width as f32 * 2.0 - 1.0,
+                1.0 - position.y / dimensions.height as f32 * 2.0,
+                -0.99 - 0.005 * depth,
+            )
+        };
+
+        let mut batch = LineBatch::new();
+        let center = to_clip(gadget.center, 0.0);
+        let model_view = view * self.scene.world(self.scan_node);
+
+        for (end, position, depth) in gadget.project(&model_view) {
+            let color = theme.axis_color(end.axis);
+            let tip = to_clip(position, depth);
+
+            if end.positive {
+                batch.line(center, tip, color);
+                batch.point(tip, color);
+            } else {
+                batch.point(tip, color.mix(theme.background(), 0.5));
+            }
+        }
+
+        self.line_renderer.draw(
+            &batch,
+            &Matrix4::identity(),
+            (dimensions.width, dimensions.height),
+            &LineStyle {
+                line_width: theme.outline_width() * 2.0,
+                point_size: LineStyle::default().point_size * theme.hud_scale(),
+            },
+        );
+    }
+
+    /// Turn the end of the axis clicked in the gadget toward the camera,
+    /// returning whether the cursor was over one
+    fn snap_to_axis_under_cursor(&mut self) -> bool {
+        let (gadget, view) = match self.axis_gadget() {
+            Some(gadget) => gadget,
+            None => return false,
+        };
+
+        let model_view = view * self.scene.world(self.scan_node);
+
+        match gadget.hit(&model_view, self.window_state.cursor) {
+            Some(end) => {
+                self.scene.node_mut(self.scan_node).local = end.facing(&view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// An outline in the warning color where the scan would be, or a large
+    /// X across the window if it could not be loaded at all
+    fn draw_placeholder(&mut self, is_corrupt: bool, projection: &Matrix4<f32>) {
+        let dimensions = self.dimensions();
+        let color = self.config.theme.warning_color();
+
+        let mut batch = LineBatch::new();
+
+        let transform = if is_corrupt {
+            // Drawn directly in clip space, so it faces the camera
+            batch.line(
+                Vector3::new(-0.6, -0.6, 0.0),
+                Vector3::new(0.6, 0.6, 0.0),
+                color,
+            );
+            batch.line(
+                Vector3::new(-0.6, 0.6, 0.0),
+                Vector3::new(0.6, -0.6, 0.0),
+                color,
+            );
+
+            Matrix4::identity()
+        } else {
+            let bbox = BoundingBox {
+                min: Vector3::repeat(-1.0),
+                max: Vector3::repeat(1.0),
+            };
+
+            batch.bounding_box(&bbox, color);
+
+            projection * self.stationary.view(bbox) * self.scene.world(self.scan_node)
+        };
+
+        self.line_renderer.draw(
+            &batch,
+            &transform,
+            (dimensions.width, dimensions.height),
+            &LineStyle {
+                line_width: self.config.theme.outline_width() * if is_corrupt { 6.0 } else { 1.5 },
+                ..LineStyle::default()
+            },
+        );
+    }
+
+    fn label(&mut self, scan_kind: ScanKind) {
+        // The scan on screen during a load has already been labeled
+        if self.loading.is_some() {
+            return;
+        }
+
+        if let Some(Placeholder::Corrupt(_)) = self.placeholder {
+            return;
+        }
+
+        // Another key while asked to confirm answers no and labels with it
+        // instead
+        self.confirming_label = None;
+
+        self.labeling_too_fast = self.is_labeling_too_fast();
+
+        let labeling = &self.config.labeling;
+
+        if labeling.requires_confirmation(&self.stl_context.category(scan_kind).name)
+            || (self.labeling_too_fast && labeling.confirm_when_fast)
+        {
+            self.confirming_label = Some(scan_kind);
+            self.update_title();
+            return;
+        }
+
+        self.accept_label(scan_kind);
+    }
+
+    /// Whether a label given now would make the last few average less than
+    /// the configured minimum time apiece
+    fn is_labeling_too_fast(&self) -> bool {
+        let window = self.config.labeling.pace_window;
+        let floor = self.config.labeling.min_time_per_label();
+
+        if window == 0 || floor == Duration::from_secs(0) || self.label_times.len() < window {
+            return false;
+        }
+
+        let start = self.label_times[self.label_times.len() - window];
+
+        start.elapsed() < floor * window as u32
+    }
+
+    /// Y: go ahead with a label waiting for confirmation
+    fn confirm_label(&mut self) {
+        if let Some(scan_kind) = self.confirming_label.take() {
+            self.accept_label(scan_kind);
+        }
+    }
+
+    /// Enter: label the scan as the exemplar of its cluster was labeled
+    fn accept_suggestion(&mut self) {
+        if let Some((_, scan_kind)) = self.stl_context.suggestion() {
+            self.label(scan_kind);
+        }
+    }
+
+    /// Commit a label, after the configured delay if there is one
+    fn accept_label(&mut self, scan_kind: ScanKind) {
+        if self.config.labeling.commit_delay() > Duration::from_secs(0) {
+            // Another key during the delay changes the label and restarts it
+            self.pending_label = Some((scan_kind, Instant::now()));
+            self.update_title();
+            return;
+        }
+
+        self.commit_label(scan_kind);
+    }
+
+    fn commit_label(&mut self, scan_kind: ScanKind) {
+        self.save_selection();
+
+        if self.config.voxels.export {
+            self.save_voxels();
+        }
+
+        let record = self
+            .stl_context
+            .label(scan_kind, self.insertion_axis, self.fingerprint.as_ref())
+            .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+
+        // Nothing was written, so there is nothing to count
+        if record.is_some() {
+            if let Some(server) = &self.station_metrics {
+                let category = &self.stl_context.category(scan_kind).name;
+                server.update(|metrics| metrics.record_label(category));
+            }
+
+            self.label_times.push_back(Instant::now());
+
+            while self.label_times.len() > self.config.labeling.pace_window {
+                self.label_times.pop_front();
+            }
 
-mod buffer;
-mod camera;
-mod renderer;
-mod shader;
-mod texture;
-mod vertex_array;
+            // Flash a warning instead of the category when labeling too fast
+            let color = if self.labeling_too_fast {
+                self.config.theme.warning_color()
+            } else {
+                self.config.theme.category_color(scan_kind.index())
+            };
+            self.flash = Some((color, Instant::now()));
+        }
 
-pub fn clear_error() {
-    while unsafe { gl::GetError() } != gl::NO_ERROR {}
-}
+        self.load_next_stl();
+    }
 
-#[track_caller]
-pub fn check_error() {
-    assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR)
-}
+    /// Save the current scan's occupancy grid as it is labeled
+    fn save_voxels(&mut self) {
+        let path = match self.stl_context.current_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let output = self.config.voxels.path(&path);
+        let directory = self.config.voxels.directory.clone();
 
-#[macro_export]
-macro_rules! check {
-    ($call:expr) => {{
-        #[cfg(debug_assertions)]
-        {
-            crate::clear_error();
-            let x = $call;
-            crate::check_error();
-            x
+        let grid = match self.voxel_grid() {
+            Some(grid) => grid,
+            None => return,
+        };
+
+        let result = fs::create_dir_all(&directory)
+            .and_then(|()| File::create(&output))
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                grid.write_npy(&mut writer)?;
+                writer.flush()
+            });
+
+        if let Err(e) = result {
+            eprintln!(
+                "{}",
+                self.catalog.format(
+                    "voxels-failed",
+                    &[("path", &output.display()), ("error", &e)]
+                )
+            );
         }
+    }
 
-        #[cfg(not(debug_assertions))]
-        {
-            $call
+    /// Commit the pending label once its delay is up
+    fn poll_pending_label(&mut self) {
+        match self.pending_label {
+            Some((scan_kind, start)) if start.elapsed() >= self.config.labeling.commit_delay() => {
+                self.pending_label = None;
+                self.commit_label(scan_kind);
+            }
+            _ => {}
         }
-    }};
-}
+    }
 
-struct Color {
-    red: f32,
-    green: f32,
-    blue: f32,
-}
+    /// Esc or N: take back a label waiting for confirmation or still
+    /// waiting out its delay
+    fn cancel_pending_label(&mut self) {
+        if self.confirming_label.take().is_some() {
+            self.update_title();
+        }
 
-struct Light {
-    color: Color,
-}
+        if let Some((scan_kind, _)) = self.pending_label.take() {
+            let entry = format!(
+                "canceled label {} for {}",
+                self.stl_context.category(scan_kind).name,
+                self.stl_context.current_path().unwrap_or_default()
+            );
+            self.stl_context.session.note(entry);
 
-impl Light {
-    pub const fn white() -> Self {
-        Light {
-            color: Color {
-                red: 1.0,
-                green: 1.0,
-                blue: 1.0,
-            },
+            self.update_title();
         }
     }
-}
 
-struct StationaryCamera {
-    model: nalgebra::Matrix4<f32>,
-    speed: f32,
-}
+    /// Bundle the session log, label files, GL info, config, and a
+    /// screenshot of the back buffer. Must be called after drawing and before
+    /// swapping buffers. Where the driver allows, the screenshot is read back
+    /// over the next few frames rather than stalling this one
+    fn start_bug_report(&mut self) {
+        let dimensions = self.dimensions();
+        let report = self.collect_bug_report();
 
-impl StationaryCamera {
-    pub fn new(model: nalgebra::Matrix4<f32>) -> Self {
-        Self {
-            model,
-            speed: 1.0_f32.to_radians(),
+        if self.capabilities.supports_async_readback() {
+            let readback = read_default_framebuffer_async(dimensions.width, dimensions.height);
+            self.pending_bug_report = Some((report, readback));
+        } else {
+            let screenshot = read_default_framebuffer(dimensions.width, dimensions.height);
+            self.save_bug_report(report, screenshot);
         }
     }
 
-    pub fn left(&mut self) {
-        self.model = nalgebra_glm::rotate(&self.model, -self.speed, &Vector3::y_axis());
-    }
+    /// Save the bug report once its screenshot has been read back
+    fn poll_bug_report(&mut self) {
+        match &self.pending_bug_report {
+            Some((_, readback)) if readback.is_ready() => {}
+            _ => return,
+        }
 
-    pub fn right(&mut self) {
-        self.model = nalgebra_glm::rotate(&self.model, self.speed, &Vector3::y_axis());
+        let (report, readback) = self.pending_bug_report.take().unwrap();
+        self.save_bug_report(report, readback.finish());
     }
 
-    pub fn up(&mut self) {
-        self.model = nalgebra_glm::rotate(&self.model, -self.speed, &Vector3::x_axis());
-    }
+    /// Read back the view of the current scan to save as a PNG, stamped
+    /// with the scan, its label, and who exported it
+    fn start_export(&mut self) {
+        let path = match self.stl_context.current_path() {
+            Some(path) => path,
+            None => return,
+        };
 
-    pub fn down(&mut self) {
-        self.model = nalgebra_glm::rotate(&self.model, self.speed, &Vector3::x_axis());
-    }
+        let mut stamp = ImageStamp::new(path);
+        stamp.label = self
+            .pending_label
+            .map(|(scan_kind, _)| scan_kind)
+            .or(self.confirming_label)
+            .map(|scan_kind| self.stl_context.category(scan_kind).name.clone());
+        stamp.annotator = self.config.export.annotator();
 
-    pub fn model(&self) -> &nalgebra::Matrix4<f32> {
-        &self.model
-    }
+        let dimensions = self.dimensions();
 
-    pub fn move_mouse(&mut self, x_offset: f32, y_offset: f32) {
-        self.model =
-            nalgebra_glm::rotate(&self.model, x_offset.to_radians() / 2.0, &Vector3::y_axis());
-        self.model = nalgebra_glm::rotate(
-            &self.model,
-            -y_offset.to_radians() / 2.0,
-            &Vector3::x_axis(),
-        );
+        if self.capabilities.supports_async_readback() {
+            let readback = read_default_framebuffer_async(dimensions.width, dimensions.height);
+            self.pending_export = Some((stamp, readback));
+        } else {
+            let image = read_default_framebuffer(dimensions.width, dimensions.height);
+            self.save_export(stamp, image);
+        }
     }
 
-    pub fn pos(&self, bbox: stl::BoundingBox) -> [f32; 3] {
-        let dimensions = bbox.delta();
+    fn poll_export(&mut self) {
+        match &self.pending_export {
+            Some((_, readback)) if readback.is_ready() => {}
+            _ => return,
+        }
 
-        [dimensions.x * 2.0, dimensions.y * 2.0, dimensions.z * 2.0]
+        let (stamp, readback) = self.pending_export.take().unwrap();
+        self.save_export(stamp, readback.finish());
     }
 
-    pub fn view(&self, bbox: stl::BoundingBox) -> nalgebra::Matrix4<f32> {
-        let center = bbox.center();
-        let dimensions = bbox.delta();
+    /// Stamp the image, then encode and write it on another thread
+    fn save_export(&self, stamp: ImageStamp, mut image: RgbaImage) {
+        let export = &self.config.export;
 
-        nalgebra_glm::look_at(
-            &(vec3(dimensions.x, dimensions.y, dimensions.z) * 2.0),
-            &vec3(center.x, center.y, center.z),
-            &Vector3::new(0.0, 1.0, 0.0),
-        )
-    }
-}
+        if export.stamp {
+            stamp.draw(&mut image, self.overlay.atlas());
+        }
 
-fn main() {
-    let event_loop = EventLoop::new();
+        let directory = export.directory.clone();
+        let output = export.path(&stamp.scan, stamp.unix_seconds());
+        let catalog = self.catalog.clone();
 
-    let path_queue = vec![
-        "Eiffel_tower_sample.stl".to_owned(),
-        "Utah_teapot_(solid).stl".to_owned(),
-    ];
+        std::thread::spawn(move || {
+            let chunks = stamp.text_chunks();
+            let text: Vec<(&str, &str)> = chunks
+                .iter()
+                .map(|(keyword, value)| (*keyword, value.as_str()))
+                .collect();
 
-    let path_loader = PathLoader::new(path_queue, "./w", "./a", "./s", "./d");
+            let result = fs::create_dir_all(&directory)
+                .and_then(|()| File::create(&output))
+                .and_then(|file| {
+                    let mut writer = BufWriter::new(file);
+                    image
+                        .write_png_with_text(&mut writer, &text)
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                    writer.flush()
+                });
 
-    let program = Program::init(&event_loop, path_loader);
+            match result {
+                Ok(()) => println!(
+                    "{}",
+                    catalog.format("export-saved", &[("path", &output.display())])
+                ),
+                Err(e) => eprintln!("{}", catalog.format("export-failed", &[("error", &e)])),
+            }
+        });
+    }
 
-    program.run(event_loop);
-}
+    fn collect_bug_report(&self) -> BugReport {
+        let mut report = BugReport::new();
 
-struct PathLoader {
-    w_file: File,
-    a_file: File,
-    s_file: File,
-    d_file: File,
-    queue: Vec<String>,
-}
+        report.add("session.log", self.stl_context.session.log().join("\n"));
+        report.add(
+            "checkpoint.txt",
+            self.stl_context.session.checkpoint().to_string(),
+        );
 
-impl PathLoader {
-    pub fn new(
-        queue: Vec<String>,
-        w_path: impl AsRef<Path>,
-        a_path: impl AsRef<Path>,
-        s_path: impl AsRef<Path>,
-        d_path: impl AsRef<Path>,
-    ) -> Self {
-        let w_file = OpenOptions::new().append(true).open(w_path).unwrap();
-        let a_file = OpenOptions::new().append(true).open(a_path).unwrap();
-        let s_file = OpenOptions::new().append(true).open(s_path).unwrap();
-        let d_file = OpenOptions::new().append(true).open(d_path).unwrap();
+        let label_files = self
+            .stl_context
+            .categories
+            .files()
+            .into_iter()
+            .chain(std::iter::once(PathBuf::from(INVALID_LABEL_PATH)));
 
-        Self {
-            w_file,
-            a_file,
-            s_file,
-            d_file,
-            queue,
+        for path in label_files {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            report.add_file(format!("labels/{}", name), &path);
         }
-    }
-}
 
-struct Program {
-    window: ContextWrapper<PossiblyCurrent, Window>,
-    stationary: StationaryCamera,
-    camera: FlightCamera,
-    window_state: WindowState,
-    control_flow: ControlFlow,
-    renderer: Renderer,
-    stl_context: StlContext,
-    shader: Shader,
-    buffer_context: Option<BufferContext>,
-}
+        report.add_gl_info("gl.txt", &self.capabilities);
+        report.add("context.txt", self.context_attempt.to_string());
+        report.add_file("config.toml", &self.config_path);
+        report.add("config-resolved.txt", format!("{:#?}", self.config));
 
-struct StlContext {
-    path_loader: PathLoader,
-    cursor: usize,
-    current: Option<stl::StlFile>,
-    stl_buffer: Vec<u8>,
-}
+        report
+    }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum ScanKind {
-    W,
-    A,
-    S,
-    D,
-}
+    /// Encode the screenshot and write the archive on another thread, since
+    /// compressing a full-window PNG takes a noticeable fraction of a second
+    fn save_bug_report(&self, mut report: BugReport, screenshot: RgbaImage) {
+        let catalog = self.catalog.clone();
 
-impl StlContext {
-    pub fn new(path_loader: PathLoader) -> Self {
-        Self {
-            path_loader,
-            stl_buffer: Vec::new(),
-            current: None,
-            cursor: 0,
-        }
-    }
+        std::thread::spawn(move || {
+            let path = BugReport::default_file_name();
 
-    pub fn label(&mut self, scan_kind: ScanKind) -> io::Result<()> {
-        if self.current.is_some() {
-            if let Some(path) = self.path_loader.queue.get(self.cursor.saturating_sub(1)) {
-                let file = match scan_kind {
-                    ScanKind::W => &mut self.path_loader.w_file,
-                    ScanKind::A => &mut self.path_loader.a_file,
-                    ScanKind::S => &mut self.path_loader.s_file,
-                    ScanKind::D => &mut self.path_loader.d_file,
-                };
+            let result = report
+                .add_image("screenshot.png", &screenshot)
+                .and_then(|()| report.write(&path));
 
-                file.write_all(path.as_bytes())?;
-                file.write_all(&[b'\n'])?;
+            match result {
+                Ok(()) => println!("{}", catalog.format("bug-report-saved", &[("path", &path)])),
+                Err(e) => eprintln!("{}", catalog.format("bug-report-failed", &[("error", &e)])),
             }
-        }
-
-        Ok(())
+        });
     }
 
-    pub fn load_next(&mut self) -> Option<&StlFile> {
-        let next_path = self.path_loader.queue.get(self.cursor)?;
+    fn is_flashing(&self) -> bool {
+        matches!(self.flash, Some((_, start)) if start.elapsed() < FLASH_DURATION)
+    }
 
-        let mut file = File::open(next_path).unwrap();
+    /// The background color, tinted with a pending or unconfirmed label's
+    /// category color, or fading from the last committed one's
+    fn background(&self) -> Color {
+        let tint = self
+            .pending_label
+            .map(|(scan_kind, _)| scan_kind)
+            .or(self.confirming_label);
 
-        self.stl_buffer.clear();
-        file.read_to_end(&mut self.stl_buffer).unwrap();
+        if let Some(scan_kind) = tint {
+            return self
+                .config
+                .theme
+                .category_color(scan_kind.index())
+                .mix(self.config.theme.background(), 0.5);
+        }
 
-        self.cursor += 1;
+        match self.flash {
+            Some((color, start)) if start.elapsed() < FLASH_DURATION => {
+                let t = start.elapsed().as_secs_f32() / FLASH_DURATION.as_secs_f32();
 
-        let file = StlFile::parse(&self.stl_buffer).unwrap();
+                color.mix(self.config.theme.background(), t)
+            }
+            _ => self.config.theme.background(),
+        }
+    }
+}
 
-        self.current = Some(file);
+impl Drop for Program {
+    fn drop(&mut self) {
+        self.stl_context.report_session(&self.catalog);
 
-        self.current.as_ref()
+        println!(
+            "{}",
+            self.catalog.format(
+                "stopped-at",
+                &[("index", &self.stl_context.session.cursor())]
+            )
+        );
     }
 }
 
-#[derive(Debug)]
-struct BufferContext {
-    bbox: stl::BoundingBox,
-    ib: IndexBuffer,
-    va: VertexArray,
+/// A minimal viewer for machines without a usable GL context, drawing with
+/// [`WgpuBackend`] or [`SoftwareBackend`] into a plain window
+///
+/// Only labeling, arrow-key rotation, and quitting are supported
+struct FallbackViewer {
+    window: minifb::Window,
+    backend: Box<dyn PixelBackend>,
+    stationary: StationaryCamera,
+    scene: Scene,
+    scan_node: NodeId,
+    stl_context: StlContext,
+    theme: Theme,
+    catalog: Catalog,
+    /// Set if the current scan is empty or failed to load
+    placeholder: Option<Placeholder>,
+    /// Set with `--kiosk`, as for [`Program`]
+    kiosk: bool,
+    watchdog: Watchdog,
 }
 
-impl Program {
-    pub fn init(event_loop: &EventLoop<()>, path_loader: PathLoader) -> Self {
-        let window = glutin::window::WindowBuilder::new().with_title("");
-        let gl_window = glutin::ContextBuilder::new()
-            .with_vsync(true)
-            .with_multisampling(4)
-            .build_windowed(window, event_loop)
-            .unwrap();
+impl FallbackViewer {
+    const WIDTH: usize = 1024;
+    const HEIGHT: usize = 768;
 
-        let gl_window = unsafe { gl_window.make_current() }.unwrap();
+    pub fn new(
+        backend: Box<dyn PixelBackend>,
+        path_loader: PathLoader,
+        session: Session,
+        categories: Categories,
+        sources: SourceRegistry,
+        manifest: Arc<Manifest>,
+        theme: Theme,
+        display: DisplayConfig,
+        cache: Option<MeshCache>,
+        catalog: Catalog,
+        kiosk: bool,
+        tabs: Vec<QueueTab>,
+    ) -> Result<Self, minifb::Error> {
+        let mut window = minifb::Window::new(
+            "",
+            Self::WIDTH,
+            Self::HEIGHT,
+            WindowOptions {
+                resize: true,
+                ..WindowOptions::default()
+            },
+        )?;
 
-        gl::load_with(|symbol| gl_window.get_proc_address(symbol));
+        window.limit_update_rate(Some(
+            display
+                .frame_interval()
+                .unwrap_or_else(|| Duration::from_millis(16)),
+        ));
 
-        let dimensions = gl_window.window().inner_size();
+        let mut scene = Scene::new();
+        let scan_node = scene.add("scan", StationaryCamera::default_model(), None);
 
-        unsafe {
-            gl::Viewport(0, 0, dimensions.width as i32, dimensions.height as i32);
-        };
+        Ok(Self {
+            window,
+            backend,
+            stationary: StationaryCamera::new(),
+            scene,
+            scan_node,
+            stl_context: StlContext::new(
+                path_loader,
+                session,
+                categories,
+                cache,
+                sources,
+                manifest,
+                catalog.clone(),
+            )
+            .with_tabs(tabs),
+            theme,
+            catalog,
+            placeholder: None,
+            kiosk,
+            watchdog: Watchdog::from_env(),
+        })
+    }
 
-        check!(unsafe { gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) });
-        check!(unsafe { gl::Enable(gl::BLEND) });
-        check!(unsafe { gl::Enable(gl::DEPTH_TEST) });
-        check!(unsafe { gl::Enable(gl::MULTISAMPLE) });
+    pub fn run(mut self) {
+        if !self.load_next_stl() {
+            return;
+        }
 
-        let model: nalgebra::Matrix4<f32> = nalgebra_glm::rotate(
-            &nalgebra_glm::one(),
-            -55.0_f32.to_radians(),
-            &Vector3::x_axis(),
-        );
+        self.watchdog.ready();
 
-        let camera = FlightCamera::new(50.0_f32);
-        let stationary = StationaryCamera::new(model);
+        while self.window.is_open() {
+            self.watchdog.poll();
 
-        let projection = nalgebra_glm::perspective(
-            dimensions.width as f32 / dimensions.height as f32,
-            camera.fov(),
-            0.1,
-            100.0,
-        );
+            if !self.handle_keys() {
+                return;
+            }
 
-        let light = Light::white();
+            let (width, height) = self.window.get_size();
 
-        let renderer = Renderer::new();
+            if width == 0 || height == 0 {
+                self.window.update();
+                continue;
+            }
 
-        let shader = Self::init_shaders(&model, &projection, &light);
+            if (width as u32, height as u32) != (self.backend.width(), self.backend.height()) {
+                self.backend.resize(width as u32, height as u32);
+            }
 
-        Self {
-            window: gl_window,
-            camera,
-            stationary,
-            renderer,
-            shader,
-            buffer_context: None,
-            window_state: WindowState::new(),
-            control_flow: ControlFlow::Wait,
-            stl_context: StlContext::new(path_loader),
+            self.backend.draw_scan(&ScanFrame {
+                stationary: &self.stationary,
+                model: self.scene.world(self.scan_node),
+                projection: nalgebra_glm::perspective(
+                    width as f32 / height as f32,
+                    45.0_f32.to_radians(),
+                    1.0,
+                    1000.0,
+                ),
+                background: self.theme.background(),
+            });
+
+            self.window
+                .update_with_buffer(self.backend.pixels(), width, height)
+                .unwrap();
         }
     }
 
-    fn dimensions(&self) -> PhysicalSize<u32> {
-        self.window.window().inner_size()
-    }
+    /// Returns `false` once the viewer should exit
+    fn handle_keys(&mut self) -> bool {
+        let window = &self.window;
 
-    pub fn run(mut self, event_loop: EventLoop<()>) {
-        self.load_next_stl();
+        let scale = step_scale(
+            window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift),
+            window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt),
+        );
 
-        event_loop.run(move |event, _, control_flow| {
-            *control_flow = self.control_flow;
+        if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+            self.stationary.left(&mut self.scene, self.scan_node, scale);
+        }
+        if window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+            self.stationary
+                .right(&mut self.scene, self.scan_node, scale);
+        }
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            self.stationary.up(&mut self.scene, self.scan_node, scale);
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            self.stationary.down(&mut self.scene, self.scan_node, scale);
+        }
 
-            match event {
-                Event::LoopDestroyed => return,
-                Event::WindowEvent { event, .. } => self.handle_window_event(event),
-                Event::DeviceEvent { event, .. } => self.handle_device_event(event),
-                Event::RedrawRequested(_) => {
-                    self.renderer.clear();
+        if window.is_key_pressed(Key::Home, KeyRepeat::No) {
+            self.scene.node_mut(self.scan_node).local = self
+                .stl_context
+                .current_entry()
+                .and_then(ManifestEntry::model)
+                .unwrap_or_else(StationaryCamera::default_model);
+        }
 
-                    let buffer_context = match &self.buffer_context {
-                        Some(b) => b,
-                        None => return,
-                    };
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
 
-                    let dimensions = self.dimensions();
-
-                    self.renderer.draw(
-                        &buffer_context.va,
-                        &buffer_context.ib,
-                        &mut Material::new(
-                            &mut self.shader,
-                            &[
-                                Uniform::MatrixFourFv {
-                                    name: "model",
-                                    matrix: self.stationary.model(),
-                                },
-                                Uniform::MatrixFourFv {
-                                    name: "view",
-                                    matrix: &self.stationary.view(buffer_context.bbox),
-                                },
-                                Uniform::MatrixFourFv {
-                                    name: "projection",
-                                    matrix: &nalgebra_glm::perspective(
-                                        dimensions.width as f32 / dimensions.height as f32,
-                                        self.camera.fov(),
-                                        1.0,
-                                        1000.0,
-                                    ),
-                                },
-                                Uniform::ThreeFloat {
-                                    name: "light_pos",
-                                    v0: self.stationary.pos(buffer_context.bbox)[0],
-                                    v1: self.stationary.pos(buffer_context.bbox)[1],
-                                    v2: self.stationary.pos(buffer_context.bbox)[2],
-                                },
-                            ],
-                        ),
-                    );
+        // As in `Program::quit`, a kiosk only quits on Ctrl+Alt+Shift+Q
+        if window.is_key_pressed(Key::Q, KeyRepeat::No) {
+            let alt = window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt);
+            let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
 
-                    self.window.swap_buffers().unwrap();
-                }
-                _ => {}
+            if !self.kiosk || (ctrl && alt && shift) {
+                return false;
             }
+        }
 
-            self.window.window().request_redraw();
-        });
-    }
+        if window.is_key_pressed(Key::Tab, KeyRepeat::No) && ctrl && self.stl_context.switch_tab() {
+            return self.load_next_stl();
+        }
 
-    fn handle_window_event(&mut self, event: WindowEvent) {
-        match event {
-            WindowEvent::CloseRequested => self.control_flow = ControlFlow::Exit,
-            WindowEvent::ModifiersChanged(state) => {
-                self.window_state.modifiers = state;
-            }
-            WindowEvent::Resized(new_dimensions) => {
-                unsafe {
-                    gl::Viewport(
-                        0,
-                        0,
-                        new_dimensions.width as i32,
-                        new_dimensions.height as i32,
-                    );
-                };
+        if window.is_key_pressed(Key::Z, KeyRepeat::No)
+            && ctrl
+            && self
+                .stl_context
+                .undo()
+                .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e))
+                .is_some()
+        {
+            return self.load_next_stl();
+        }
 
-                self.renderer.clear();
-            }
-            WindowEvent::Focused(focused) => {
-                self.window_state.is_window_focused = focused;
-            }
-            WindowEvent::CursorEntered { .. } => {
-                self.window_state.is_window_hovered = true;
-            }
-            WindowEvent::CursorLeft { .. } => {
-                self.window_state.is_window_hovered = false;
-            }
-            WindowEvent::MouseInput {
-                state: ElementState::Pressed,
-                ..
-            } => {
-                self.window.window().set_cursor_icon(CursorIcon::Grabbing);
-                self.window_state.is_mouse_pressed = true;
-            }
-            WindowEvent::MouseInput {
-                state: ElementState::Released,
-                ..
-            } => {
-                self.window.window().set_cursor_icon(CursorIcon::Default);
-                self.window_state.is_mouse_pressed = false;
-            }
-            WindowEvent::KeyboardInput { input, .. } => {
-                match (input.virtual_keycode, input.state) {
-                    (Some(VirtualKeyCode::Left), ElementState::Pressed) => {
-                        self.stationary.left();
-                    }
-                    (Some(VirtualKeyCode::Right), ElementState::Pressed) => {
-                        self.stationary.right();
-                    }
-                    (Some(VirtualKeyCode::Up), ElementState::Pressed) => {
-                        self.stationary.up();
-                    }
-                    (Some(VirtualKeyCode::Down), ElementState::Pressed) => {
-                        self.stationary.down();
-                    }
-                    (Some(VirtualKeyCode::P), ElementState::Pressed) => {
-                        self.window_state.toggle_paused();
-                    }
-                    (Some(VirtualKeyCode::Q), ElementState::Pressed) => {
-                        self.control_flow = ControlFlow::Exit;
-                    }
-                    (Some(VirtualKeyCode::C), ElementState::Pressed)
-                        if self.window_state.modifiers.ctrl() =>
-                    {
-                        self.control_flow = ControlFlow::Exit;
-                    }
-                    (Some(VirtualKeyCode::W), ElementState::Pressed) => {
-                        self.label(ScanKind::W);
-                    }
-                    (Some(VirtualKeyCode::A), ElementState::Pressed) => {
-                        self.label(ScanKind::A);
-                    }
-                    (Some(VirtualKeyCode::S), ElementState::Pressed) => {
-                        self.label(ScanKind::S);
-                    }
-                    (Some(VirtualKeyCode::D), ElementState::Pressed) => {
-                        self.label(ScanKind::D);
-                    }
-                    _ => (),
-                }
-            }
-            _ => (),
+        if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            self.stl_context
+                .skip()
+                .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+            return self.load_next_stl();
         }
-    }
 
-    fn handle_device_event(&mut self, event: DeviceEvent) {
-        match event {
-            DeviceEvent::MouseWheel { delta } => {
-                self.camera.scroll(delta);
-            }
-            DeviceEvent::MouseMotion { delta } => {
-                if !self.window_state.is_paused && self.window_state.is_window_focused {
-                    if self.window_state.is_mouse_pressed {
-                        self.stationary.move_mouse(delta.0 as f32, -delta.1 as f32);
-                    }
-                }
+        if let Some(Placeholder::Corrupt(_)) = self.placeholder {
+            if window.is_key_pressed(Key::C, KeyRepeat::No) {
+                self.stl_context
+                    .record_invalid()
+                    .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+                return self.load_next_stl();
             }
-            _ => {}
-        }
-    }
 
-    fn init_shaders(
-        model: &nalgebra::Matrix4<f32>,
-        projection: &nalgebra::Matrix4<f32>,
-        light: &Light,
-    ) -> Shader {
-        let mut shader = Shader::new("src/shaders/basic-vs.shader", "src/shaders/basic-fs.shader");
-        let uniforms = vec![];
+            return true;
+        }
 
-        let mut material = Material::new(&mut shader, &uniforms);
-        material.bind();
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            if let Some((_, scan_kind)) = self.stl_context.suggestion() {
+                self.stl_context
+                    .label(scan_kind, None, None)
+                    .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+                return self.load_next_stl();
+            }
+        }
 
-        shader.set_uniform(&Uniform::MatrixFourFv {
-            name: "model",
-            matrix: model,
-        });
-        shader.set_uniform(&Uniform::MatrixFourFv {
-            name: "view",
-            matrix: &nalgebra::Matrix4::identity(),
-        });
-        shader.set_uniform(&Uniform::MatrixFourFv {
-            name: "projection",
-            matrix: projection,
-        });
-        shader.set_uniform(&Uniform::ThreeFloat {
-            name: "object_color",
-            v0: 0.8,
-            v1: 0.8,
-            v2: 0.8,
-        });
-        shader.set_uniform(&Uniform::ThreeFloat {
-            name: "light_color",
-            v0: light.color.red,
-            v1: light.color.green,
-            v2: light.color.blue,
-        });
-        shader.set_uniform(&Uniform::ThreeFloat {
-            name: "light_pos",
-            v0: 0.0,
-            v1: 0.0,
-            v2: 0.0,
+        let pressed = self.stl_context.categories.iter().position(|category| {
+            software_key(category.key)
+                .map_or(false, |key| window.is_key_pressed(key, KeyRepeat::No))
         });
 
-        shader.unbind();
+        let scan_kind = match pressed {
+            Some(index) => ScanKind(index),
+            None => return true,
+        };
+
+        self.stl_context
+            .label(scan_kind, None, None)
+            .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
 
-        shader
+        self.load_next_stl()
     }
 
-    fn load_next_stl(&mut self) {
-        let stl_file = match self.stl_context.load_next() {
-            Some(f) => f,
+    /// Returns `false` if the queue is exhausted
+    ///
+    /// Manifest orientations and clipping apply here too, but scans are
+    /// always shaded
+    fn load_next_stl(&mut self) -> bool {
+        let (metrics, placeholder) = match self.stl_context.load_next() {
+            Some(Ok(_)) => {
+                let mesh = self.stl_context.current.as_ref().unwrap();
+                let entry = self.stl_context.current_entry();
+
+                match entry.and_then(|entry| entry.clip) {
+                    Some(clip) => self.backend.load_scan(&clip.apply(mesh)),
+                    None => self.backend.load_scan(mesh),
+                }
+
+                if let Some(model) = entry.and_then(ManifestEntry::model) {
+                    self.scene.node_mut(self.scan_node).local = model;
+                }
+
+                let placeholder = if mesh.is_empty() {
+                    Some(Placeholder::Empty)
+                } else {
+                    None
+                };
+
+                (Some(MeshMetrics::new(mesh)), placeholder)
+            }
+            Some(Err(e)) => (None, Some(Placeholder::Corrupt(e.to_string()))),
+            None if self.stl_context.switch_to_unfinished_tab() => return self.load_next_stl(),
             None => {
-                self.control_flow = ControlFlow::Exit;
-                return;
+                self.stl_context.finish_queue();
+                return false;
             }
         };
 
-        let index = stl_file.index_buffer_vertex_and_normal();
-        let positions = index.vertices();
-        let indices = index.indices();
-
-        let mut va = VertexArray::new();
-        let vb = VertexBuffer::new(&positions);
-        let mut layout = VertexBufferLayout::new();
+        // Clear the previous scan
+        if let (Some(Placeholder::Corrupt(_)), Some(mesh)) =
+            (&placeholder, &self.stl_context.current)
+        {
+            self.backend.load_scan(mesh);
+        }
 
-        layout.push(BufferElementType::Float, 3, false);
-        layout.push(BufferElementType::Float, 3, false);
-        va.add_buffer(&vb, &layout);
+        if let Some(metrics) = metrics {
+            self.stl_context.set_metrics(metrics);
+        }
 
-        let ib = IndexBuffer::new(indices);
+        if placeholder == Some(Placeholder::Empty) {
+            self.stl_context
+                .record_invalid()
+                .unwrap_or_else(|e| labels_unwritable(&self.catalog, &e));
+        }
 
-        ib.unbind();
-        va.unbind();
-        vb.unbind();
+        let path = self.stl_context.current_path().unwrap_or_default();
 
-        let buffer_context = BufferContext {
-            va,
-            ib,
-            bbox: stl_file.bounding_box(),
+        let title = match (&placeholder, &metrics) {
+            (Some(placeholder), _) => placeholder.title(&self.catalog, path),
+            (None, Some(metrics)) => scan_title(&self.catalog, path, metrics),
+            (None, None) => String::new(),
         };
 
-        self.buffer_context = Some(buffer_context);
-    }
+        self.window.set_title(&title);
+        self.placeholder = placeholder;
 
-    fn label(&mut self, scan_kind: ScanKind) {
-        self.stl_context.label(scan_kind).unwrap();
-        self.load_next_stl();
+        true
     }
 }
 
-impl Drop for Program {
+impl Drop for FallbackViewer {
     fn drop(&mut self) {
-        println!("Stopped at file #{}", self.stl_context.cursor);
+        self.watchdog.stopping();
+        self.stl_context.report_session(&self.catalog);
+
+        println!(
+            "{}",
+            self.catalog.format(
+                "stopped-at",
+                &[("index", &self.stl_context.session.cursor())]
+            )
+        );
     }
 }
 
@@ -592,7 +5910,12 @@ struct WindowState {
     is_window_focused: bool,
     is_window_hovered: bool,
     is_mouse_pressed: bool,
+    /// Set while the mouse is pressed if dragging pans rather than rotates
+    is_panning: bool,
     modifiers: ModifiersState,
+    /// In physical pixels from the top left of the window
+    cursor: (f32, f32),
+    press_position: (f32, f32),
 }
 
 impl WindowState {
@@ -602,11 +5925,28 @@ impl WindowState {
             is_window_focused: false,
             is_window_hovered: false,
             is_mouse_pressed: false,
+            is_panning: false,
             modifiers: ModifiersState::empty(),
+            cursor: (0.0, 0.0),
+            press_position: (0.0, 0.0),
         }
     }
 
+    /// Whether the mouse was released close enough to where it was pressed
+    /// to count as a click rather than a drag
+    pub fn is_click(&self) -> bool {
+        let dx = self.cursor.0 - self.press_position.0;
+        let dy = self.cursor.1 - self.press_position.1;
+
+        dx * dx + dy * dy <= 4.0 * 4.0
+    }
+
     pub fn toggle_paused(&mut self) {
         self.is_paused = !self.is_paused;
     }
+
+    /// Shift for coarse rotation and zoom steps, Alt for fine ones
+    pub fn step_scale(&self) -> f32 {
+        step_scale(self.modifiers.shift(), self.modifiers.alt())
+    }
 }
@@ -0,0 +1,251 @@
+//! Queue manifests: a JSON list of scans to label, each optionally opened
+//! with its own render settings
+//!
+//! ```json
+//! {
+//!     "scans": [
+//!         { "path": "scans/a.stl" },
+//!         { "path": "scans/b.stl", "orientation": [90, 0, 0], "shading": "wireframe", "clip": "upper-half" },
+//!         { "path": "scans/c-upper.stl", "antagonist": { "path": "scans/c-lower.stl" } }
+//!     ]
+//! }
+//! ```
+
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+use nalgebra::{Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::StationaryCamera,
+    fitting::Plane,
+    mesh::{BoundingBox, Mesh},
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub scans: Vec<ManifestEntry>,
+    #[serde(skip)]
+    index: HashMap<String, usize>,
+}
+
+/// A scan in the queue, and the settings it is opened with. Settings left
+/// out keep their usual behavior
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    pub path: String,
+    /// Rotation in degrees about the x, y, and z axes, applied in that
+    /// order on top of [`StationaryCamera::default_model`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<[f32; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shading: Option<ShadingMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clip: Option<ClipPreset>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub antagonist: Option<Antagonist>,
+    /// Scanner model the scan was captured with, for breaking labels down
+    /// by source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scanner: Option<String>,
+    /// Clinic the scan came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clinic: Option<String>,
+}
+
+/// The opposing arch of a scan, shown in articulation with it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Antagonist {
+    pub path: String,
+    /// Row-major transform from the antagonist's coordinates into the
+    /// scan's, as recorded by the bite registration. Left out if both were
+    /// exported in the same frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bite: Option<[[f32; 4]; 4]>,
+}
+
+impl Antagonist {
+    pub fn transform(&self) -> Matrix4<f32> {
+        match self.bite {
+            Some(rows) => Matrix4::from_fn(|row, column| rows[row][column]),
+            None => Matrix4::identity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShadingMode {
+    /// Lit, filled triangles
+    Shaded,
+    /// Triangle edges only, so overlapping layers can be told apart
+    Wireframe,
+}
+
+impl Default for ShadingMode {
+    fn default() -> Self {
+        Self::Shaded
+    }
+}
+
+/// Half of a scan to hide, so the other half can be judged unobstructed
+///
+/// Halves are split at the center of the scan's bounding box, in the scan's
+/// own coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipPreset {
+    /// Keep triangles above the center along z
+    UpperHalf,
+    LowerHalf,
+    /// Keep triangles to the left of the center along x
+    LeftHalf,
+    RightHalf,
+}
+
+impl ClipPreset {
+    /// A copy of `mesh` with only the triangles whose centroid lies in the
+    /// kept half
+    pub fn apply(self, mesh: &Mesh) -> Mesh {
+        let center = mesh.bounding_box().center();
+
+        let keep: Vec<bool> = mesh
+            .triangles()
+            .map(|[a, b, c]| {
+                let centroid = (mesh.position(a) + mesh.position(b) + mesh.position(c)) / 3.0;
+
+                match self {
+                    Self::UpperHalf => centroid.z >= center.z,
+                    Self::LowerHalf => centroid.z < center.z,
+                    Self::LeftHalf => centroid.x < center.x,
+                    Self::RightHalf => centroid.x >= center.x,
+                }
+            })
+            .collect();
+
+        mesh.retain_triangles(&keep)
+    }
+
+    /// The plane between the halves of a scan with bounding box `bbox`, its
+    /// normal pointing into the kept half
+    pub fn plane(self, bbox: &BoundingBox) -> Plane {
+        let normal = match self {
+            Self::UpperHalf => Vector3::z(),
+            Self::LowerHalf => -Vector3::z(),
+            Self::LeftHalf => -Vector3::x(),
+            Self::RightHalf => Vector3::x(),
+        };
+
+        Plane {
+            point: bbox.center(),
+            normal,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read manifest: {}", e),
+            Self::Parse(e) => write!(f, "invalid manifest: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl Manifest {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let contents = fs::read_to_string(path).map_err(ManifestError::Io)?;
+
+        Self::parse(&contents)
+    }
+
+    /// A manifest listing `queue` with default settings
+    pub fn from_queue(queue: Vec<String>) -> Self {
+        let scans = queue
+            .into_iter()
+            .map(|path| ManifestEntry {
+                path,
+                ..ManifestEntry::default()
+            })
+            .collect();
+
+        let mut manifest = Self {
+            scans,
+            index: HashMap::new(),
+        };
+        manifest.reindex();
+
+        manifest
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+
+        fs::write(path, contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ManifestError> {
+        let mut manifest: Self = serde_json::from_str(contents).map_err(ManifestError::Parse)?;
+        manifest.reindex();
+
+        Ok(manifest)
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .scans
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.path.clone(), idx))
+            .collect();
+    }
+
+    /// Every scan's path, in order
+    pub fn queue(&self) -> Vec<String> {
+        self.scans.iter().map(|entry| entry.path.clone()).collect()
+    }
+
+    /// The entry for `path`, if it is listed. If a path is listed more than
+    /// once, the last entry wins
+    pub fn entry(&self, path: &str) -> Option<&ManifestEntry> {
+        self.index.get(path).map(|&idx| &self.scans[idx])
+    }
+}
+
+impl ManifestEntry {
+    /// The kind and name of each source the scan is listed as coming from
+    pub fn sources(&self) -> Vec<(&'static str, &str)> {
+        let mut sources = Vec::new();
+        sources.extend(self.scanner.as_deref().map(|scanner| ("scanner", scanner)));
+        sources.extend(self.clinic.as_deref().map(|clinic| ("clinic", clinic)));
+        sources
+    }
+
+    /// The model transform the scan is first shown with, if overridden
+    pub fn model(&self) -> Option<Matrix4<f32>> {
+        let [x, y, z] = self.orientation?;
+
+        let mut model = StationaryCamera::default_model();
+
+        for (angle, axis) in &[
+            (x, Vector3::x_axis()),
+            (y, Vector3::y_axis()),
+            (z, Vector3::z_axis()),
+        ] {
+            model = nalgebra_glm::rotate(&model, angle.to_radians(), axis);
+        }
+
+        Some(model)
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3, U3};
+use serde::{Deserialize, Serialize};
+use stl::StlFile;
+
+use crate::{compression::read_decompressed, format::ScanFormat, scratch::ScanScratch};
+
+/// Number of `f32`s per vertex: a position followed by a normal
+pub const VERTEX_STRIDE: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl BoundingBox {
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Self {
+        let mut min = Vector3::repeat(f32::INFINITY);
+        let mut max = Vector3::repeat(f32::NEG_INFINITY);
+
+        for point in points {
+            min = min.inf(&point);
+            max = max.sup(&point);
+        }
+
+        if min.x > max.x {
+            return Self {
+                min: Vector3::zeros(),
+                max: Vector3::zeros(),
+            };
+        }
+
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn delta(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    Parse(String),
+    /// The contents were recognized, but cannot be parsed yet
+    UnsupportedFormat(ScanFormat),
+    /// The contents did not match any known format
+    UnknownFormat,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read mesh: {}", e),
+            Self::Parse(msg) => write!(f, "unable to parse mesh: {}", msg),
+            Self::UnsupportedFormat(ScanFormat::Zip) => {
+                write!(f, "scan is a zip archive; open it with a zip:// URI")
+            }
+            Self::UnsupportedFormat(format) => write!(f, "{} scans are not supported", format),
+            Self::UnknownFormat => write!(f, "unrecognized scan format"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl From<io::Error> for MeshError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// An indexed triangle mesh with interleaved positions and normals, ready to
+/// be uploaded to the GPU
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    vertices: Vec<f32>,
+    indices: Vec<u32>,
+    bounding_box: BoundingBox,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<f32>, indices: Vec<u32>) -> Self {
+        let bounding_box = BoundingBox::from_points(
+            vertices
+                .chunks_exact(VERTEX_STRIDE)
+                .map(|v| Vector3::new(v[0], v[1], v[2])),
+        );
+
+        Self {
+            vertices,
+            indices,
+            bounding_box,
+        }
+    }
+
+    /// Read and parse the scan at `path`, decompressing it if it is
+    /// compressed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MeshError> {
+        let path = path.as_ref();
+        let mut buffer = Vec::new();
+        read_decompressed(fs::File::open(path)?, &mut buffer)?;
+
+        Self::from_bytes(&buffer, path.to_str())
+    }
+
+    /// Parse a scan in any supported format, detected from its contents.
+    /// `name`'s extension is only consulted if the contents are not
+    /// recognized
+    pub fn from_bytes(buffer: &[u8], name: Option<&str>) -> Result<Self, MeshError> {
+        Self::from_bytes_in(buffer, name, &mut ScanScratch::new())
+    }
+
+    /// Like [`Mesh::from_bytes`], building the mesh in buffers taken from
+    /// `scratch`. With the `reconstruction` feature, point clouds come back
+    /// with a rough surface through their points, see the `reconstruct`
+    /// module
+    pub fn from_bytes_in(
+        buffer: &[u8],
+        name: Option<&str>,
+        scratch: &mut ScanScratch,
+    ) -> Result<Self, MeshError> {
+        let format = ScanFormat::sniff(buffer, name).ok_or(MeshError::UnknownFormat)?;
+
+        let mesh = match format.loader() {
+            Some(loader) => loader.load(buffer, scratch)?,
+            None => return Err(MeshError::UnsupportedFormat(format)),
+        };
+
+        #[cfg(feature = "reconstruction")]
+        let mesh = if crate::reconstruct::is_point_cloud(&mesh) {
+            crate::reconstruct::reconstruct(&mesh)
+        } else {
+            mesh
+        };
+
+        Ok(mesh)
+    }
+
+    pub fn from_stl(buffer: &[u8]) -> Result<Self, MeshError> {
+        Self::from_stl_in(buffer, &mut ScanScratch::new())
+    }
+
+    pub fn from_stl_in(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Self, MeshError> {
+        let file = StlFile::parse(buffer).map_err(|e| MeshError::Parse(format!("{:?}", e)))?;
+
+        let index = file.index_buffer_vertex_and_normal();
+
+        let mut vertices = scratch.vertices.take(index.vertices().len());
+        vertices.extend_from_slice(index.vertices());
+
+        let mut indices = scratch.indices.take(index.indices().len());
+        indices.extend_from_slice(index.indices());
+
+        Ok(Self::new(vertices, indices))
+    }
+
+    /// The vertex and index buffers, e.g. to reuse them with
+    /// [`ScanScratch::recycle`]
+    pub fn into_buffers(self) -> (Vec<f32>, Vec<u32>) {
+        (self.vertices, self.indices)
+    }
+
+    /// Interleaved `[x, y, z, nx, ny, nz]` vertex data
+    pub fn vertices(&self) -> &[f32] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.bounding_box
+    }
+
+    /// Whether the mesh has no triangles to draw
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len() / VERTEX_STRIDE
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn position(&self, idx: u32) -> Vector3<f32> {
+        let start = idx as usize * VERTEX_STRIDE;
+
+        Vector3::new(
+            self.vertices[start],
+            self.vertices[start + 1],
+            self.vertices[start + 2],
+        )
+    }
+
+    pub fn normal(&self, idx: u32) -> Vector3<f32> {
+        let start = idx as usize * VERTEX_STRIDE + 3;
+
+        Vector3::new(
+            self.vertices[start],
+            self.vertices[start + 1],
+            self.vertices[start + 2],
+        )
+    }
+
+    /// Write the mesh as a binary STL file, with facet normals computed from
+    /// the winding of each triangle
+    pub fn write_stl(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&[0; 80])?;
+        writer.write_all(&(self.triangle_count() as u32).to_le_bytes())?;
+
+        for [a, b, c] in self.triangles() {
+            let (a, b, c) = (self.position(a), self.position(b), self.position(c));
+            let normal = (b - a)
+                .cross(&(c - a))
+                .try_normalize(0.0)
+                .unwrap_or_else(Vector3::zeros);
+
+            for vector in &[normal, a, b, c] {
+                for component in vector.iter() {
+                    writer.write_all(&component.to_le_bytes())?;
+                }
+            }
+
+            // attribute byte count, unused
+            writer.write_all(&[0, 0])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_stl(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+
+        self.write_stl(&mut writer)?;
+
+        writer.flush()
+    }
+
+    /// A copy containing only the triangles for which `keep` is true,
+    /// sharing the full vertex buffer
+    pub fn retain_triangles(&self, keep: &[bool]) -> Self {
+        self.retain_triangles_in(keep, &mut ScanScratch::new())
+    }
+
+    /// Like [`Mesh::retain_triangles`], building the copy in buffers taken
+    /// from `scratch`
+    pub fn retain_triangles_in(&self, keep: &[bool], scratch: &mut ScanScratch) -> Self {
+        let mut indices = scratch.indices.take(self.indices.len());
+        indices.extend(
+            self.triangles()
+                .zip(keep)
+                .filter(|(_, &keep)| keep)
+                .flat_map(|(tri, _)| tri.to_vec()),
+        );
+        let mut vertices = scratch.vertices.take(self.vertices.len());
+        vertices.extend_from_slice(&self.vertices);
+
+        Self {
+            vertices,
+            indices,
+            bounding_box: self.bounding_box,
+        }
+    }
+
+    /// A copy moved by `transform`, which may rotate, scale, and translate
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Self {
+        // Normals stay perpendicular to the surface under the inverse
+        // transpose, even if the scaling is uneven
+        let linear: Matrix3<f32> = transform.fixed_slice::<U3, U3>(0, 0).into_owned();
+        let normal_transform = linear
+            .try_inverse()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(linear);
+
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+
+        for idx in 0..self.vertex_count() as u32 {
+            let position = transform.transform_point(&Point3::from(self.position(idx)));
+            let normal = (normal_transform * self.normal(idx))
+                .try_normalize(0.0)
+                .unwrap_or_else(Vector3::zeros);
+
+            vertices.extend(position.coords.iter().chain(normal.iter()));
+        }
+
+        Self::new(vertices, self.indices.clone())
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]])
+    }
+}
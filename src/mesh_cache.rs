@@ -0,0 +1,226 @@
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compression::read_decompressed,
+    i18n::Catalog,
+    mesh::{Mesh, MeshError},
+};
+
+/// Identifies the entry format. Change this whenever the layout, or the
+/// vertex data produced by the parser, changes, so stale entries are ignored
+const MAGIC: &[u8; 8] = b"SCMESH01";
+
+/// Parsed meshes stored on disk, keyed by a hash of the file they were
+/// parsed from, so reopening a scan skips parsing it
+///
+/// If `max_bytes` is set, the oldest entries are deleted whenever a new one
+/// pushes the cache over it; otherwise entries are never evicted
+#[derive(Debug, Clone)]
+pub struct MeshCache {
+    directory: PathBuf,
+    max_bytes: Option<u64>,
+    /// For reporting entries that cannot be written
+    catalog: Catalog,
+}
+
+impl MeshCache {
+    pub fn new(directory: impl Into<PathBuf>, max_bytes: Option<u64>, catalog: Catalog) -> Self {
+        Self {
+            directory: directory.into(),
+            max_bytes,
+            catalog,
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Like [`Mesh::open`], but reads the parsed mesh from the cache if the
+    /// file has been opened before. Failing to write the cache is not an
+    /// error
+    pub fn open(&self, path: impl AsRef<Path>) -> Result<Mesh, MeshError> {
+        let path = path.as_ref();
+
+        let mut buffer = Vec::new();
+        read_decompressed(fs::File::open(path)?, &mut buffer)?;
+
+        self.parse(&buffer, path.to_str())
+    }
+
+    /// Like [`Mesh::from_bytes`], for files already read into memory
+    pub fn parse(&self, contents: &[u8], name: Option<&str>) -> Result<Mesh, MeshError> {
+        let entry = self.entry_path(contents);
+
+        if let Ok(mesh) = read_entry(&entry) {
+            return Ok(mesh);
+        }
+
+        let mesh = Mesh::from_bytes(contents, name)?;
+
+        if let Err(e) = self.store(&entry, &mesh) {
+            eprintln!(
+                "{}",
+                self.catalog.format(
+                    "mesh-cache-store-failed",
+                    &[("path", &entry.display()), ("error", &e)],
+                )
+            );
+        }
+
+        if let Err(e) = self.trim() {
+            eprintln!(
+                "{}",
+                self.catalog.format(
+                    "mesh-cache-trim-failed",
+                    &[("path", &self.directory.display()), ("error", &e)],
+                )
+            );
+        }
+
+        Ok(mesh)
+    }
+
+    fn entry_path(&self, contents: &[u8]) -> PathBuf {
+        self.directory
+            .join(format!("{}.mesh", blake3::hash(contents).to_hex()))
+    }
+
+    fn store(&self, entry: &Path, mesh: &Mesh) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+
+        // Written under a temporary name first so a concurrent or
+        // interrupted write never leaves a truncated entry behind
+        let partial = entry.with_extension("partial");
+
+        let mut writer = BufWriter::new(fs::File::create(&partial)?);
+        write_entry(&mut writer, mesh)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&partial, entry)
+    }
+
+    /// Delete the oldest entries until the cache fits in `max_bytes`
+    fn trim(&self) -> io::Result<()> {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(()),
+        };
+
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+
+            if path.extension() != Some("mesh".as_ref()) {
+                continue;
+            }
+
+            let metadata = fs::metadata(&path)?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+
+        entries.sort();
+
+        for (_, len, path) in entries {
+            if total <= max_bytes {
+                break;
+            }
+
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_entry(writer: &mut impl Write, mesh: &Mesh) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(mesh.vertices().len() as u64).to_le_bytes())?;
+    writer.write_all(&(mesh.indices().len() as u64).to_le_bytes())?;
+
+    if cfg!(target_endian = "little") {
+        writer.write_all(bytemuck::cast_slice(mesh.vertices()))?;
+        writer.write_all(bytemuck::cast_slice(mesh.indices()))?;
+    } else {
+        for value in mesh.vertices() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        for index in mesh.indices() {
+            writer.write_all(&index.to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_entry(path: &Path) -> io::Result<Mesh> {
+    let file = fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unknown mesh cache format",
+        ));
+    }
+
+    let vertex_len = read_u64(&mut reader)?;
+    let index_len = read_u64(&mut reader)?;
+
+    // Checked before allocating, so a corrupt header cannot request an
+    // enormous buffer
+    if Some(file_len)
+        != vertex_len
+            .checked_add(index_len)
+            .and_then(|len| len.checked_mul(4))
+            .and_then(|len| len.checked_add(24))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "mesh cache entry has the wrong length",
+        ));
+    }
+
+    let (vertex_len, index_len) = (vertex_len as usize, index_len as usize);
+
+    // Read straight into the final buffers, then fix up byte order on the
+    // rare big-endian host
+    let mut vertices = vec![0_f32; vertex_len];
+    reader.read_exact(bytemuck::cast_slice_mut(&mut vertices))?;
+
+    let mut indices = vec![0_u32; index_len];
+    reader.read_exact(bytemuck::cast_slice_mut(&mut indices))?;
+
+    if cfg!(target_endian = "big") {
+        for value in &mut vertices {
+            *value = f32::from_bits(u32::from_le(value.to_bits()));
+        }
+
+        for index in &mut indices {
+            *index = u32::from_le(*index);
+        }
+    }
+
+    Ok(Mesh::new(vertices, indices))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
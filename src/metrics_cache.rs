@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::MeshMetrics, i18n::Catalog, source::ScanMetadata};
+
+/// Name of the file metrics are stored in, inside the cache directory.
+/// Versioned, so metrics measured before [`MeshMetrics`] gained a field are
+/// measured again rather than read without it
+const METRICS_FILE: &str = "metrics-v2.json";
+
+/// Metrics of scans analyzed before, keyed by their URI and invalidated when
+/// their size or modification time changes
+///
+/// Unlike [`MeshCache`](crate::MeshCache), looking up an entry does not
+/// read the scan, so filtering a large queue over a slow link is cheap once
+/// it has been analyzed
+#[derive(Debug, Clone)]
+pub struct MetricsCache {
+    path: PathBuf,
+    entries: HashMap<String, CachedMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedMetrics {
+    size: u64,
+    /// Since the Unix epoch
+    modified: Duration,
+    metrics: MeshMetrics,
+}
+
+impl MetricsCache {
+    /// Read the metrics stored in `directory`, starting empty if there are
+    /// none or they cannot be read
+    pub fn open(directory: impl AsRef<Path>, catalog: &Catalog) -> Self {
+        let path = directory.as_ref().join(METRICS_FILE);
+
+        let entries = match read_entries(&path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    catalog.format(
+                        "metrics-cache-unreadable",
+                        &[("path", &path.display()), ("error", &e)],
+                    )
+                );
+                HashMap::new()
+            }
+        };
+
+        Self { path, entries }
+    }
+
+    /// The metrics stored for `uri`, if they were measured from a scan with
+    /// the same size and modification time
+    pub fn get(&self, uri: &str, metadata: &ScanMetadata) -> Option<MeshMetrics> {
+        let (size, modified) = key(metadata)?;
+        let entry = self.entries.get(uri)?;
+
+        if entry.size == size && entry.modified == modified {
+            Some(entry.metrics)
+        } else {
+            None
+        }
+    }
+
+    /// Store `metrics` for `uri`. Scans whose size or modification time is
+    /// unknown are not cached
+    pub fn insert(&mut self, uri: &str, metadata: &ScanMetadata, metrics: MeshMetrics) {
+        if let Some((size, modified)) = key(metadata) {
+            self.entries.insert(
+                uri.to_owned(),
+                CachedMetrics {
+                    size,
+                    modified,
+                    metrics,
+                },
+            );
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        // Written under a temporary name first, like mesh cache entries
+        let partial = self.path.with_extension("partial");
+
+        let mut writer = BufWriter::new(fs::File::create(&partial)?);
+        serde_json::to_writer(&mut writer, &self.entries)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&partial, &self.path)
+    }
+}
+
+fn key(metadata: &ScanMetadata) -> Option<(u64, Duration)> {
+    let modified = metadata.modified?.duration_since(UNIX_EPOCH).ok()?;
+
+    Some((metadata.size?, modified))
+}
+
+fn read_entries(path: &Path) -> io::Result<HashMap<String, CachedMetrics>> {
+    let reader = BufReader::new(fs::File::open(path)?);
+
+    Ok(serde_json::from_reader(reader)?)
+}
@@ -0,0 +1,237 @@
+//! A Prometheus metrics endpoint, so stations labeling all day can be
+//! watched from the same dashboards as everything else
+//!
+//! Metrics are served in the text exposition format from `/metrics` on a
+//! background thread. Only one request is handled at a time, which is plenty
+//! for a scraper polling every few seconds
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Upper bounds of the load latency buckets, in seconds
+const LOAD_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Upper bounds of the frame time buckets, in seconds. 60 Hz is about 0.016
+const FRAME_BUCKETS: &[f64] = &[0.004, 0.008, 0.016, 0.033, 0.066, 0.1, 0.25, 1.0];
+
+/// How long a scraper has to send its request before it is dropped, so a
+/// stalled connection does not block the next
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Observations counted into buckets, as Prometheus histograms are
+#[derive(Debug, Clone, PartialEq)]
+struct Buckets {
+    bounds: &'static [f64],
+    /// Per bound, observations at or under it and over the one before
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Buckets {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) = self.bounds.iter().position(|&bound| value <= bound) {
+            self.counts[bucket] += 1;
+        }
+
+        self.count += 1;
+        self.sum += value;
+    }
+
+    /// The `_bucket`, `_sum`, and `_count` lines, with bucket counts made
+    /// cumulative
+    fn write(&self, name: &str, out: &mut String) {
+        let mut cumulative = 0;
+
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+/// Everything the endpoint reports, updated as the session goes
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationMetrics {
+    /// Labels committed this session, by category
+    labels: BTreeMap<String, u64>,
+    load_failures: u64,
+    load_seconds: Buckets,
+    frame_seconds: Buckets,
+    /// Scans left in the queue after the current one
+    queue_depth: usize,
+}
+
+impl Default for StationMetrics {
+    fn default() -> Self {
+        Self {
+            labels: BTreeMap::new(),
+            load_failures: 0,
+            load_seconds: Buckets::new(LOAD_BUCKETS),
+            frame_seconds: Buckets::new(FRAME_BUCKETS),
+            queue_depth: 0,
+        }
+    }
+}
+
+impl StationMetrics {
+    pub fn record_label(&mut self, label: &str) {
+        *self.labels.entry(label.to_owned()).or_insert(0) += 1;
+    }
+
+    /// A scan finished loading, or failed to, `latency` after it was
+    /// requested
+    pub fn record_load(&mut self, latency: Duration, failed: bool) {
+        self.load_seconds.observe(latency.as_secs_f64());
+
+        if failed {
+            self.load_failures += 1;
+        }
+    }
+
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        self.frame_seconds.observe(frame_time.as_secs_f64());
+    }
+
+    pub fn set_queue_depth(&mut self, depth: usize) {
+        self.queue_depth = depth;
+    }
+
+    /// In the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP scan_classification_labels_total Scans labeled, by category\n");
+        out.push_str("# TYPE scan_classification_labels_total counter\n");
+        for (label, count) in &self.labels {
+            let _ = writeln!(
+                out,
+                "scan_classification_labels_total{{label=\"{}\"}} {}",
+                escape_label_value(label),
+                count
+            );
+        }
+
+        out.push_str("# HELP scan_classification_load_failures_total Scans that failed to load\n");
+        out.push_str("# TYPE scan_classification_load_failures_total counter\n");
+        let _ = writeln!(
+            out,
+            "scan_classification_load_failures_total {}",
+            self.load_failures
+        );
+
+        out.push_str(
+            "# HELP scan_classification_load_seconds Time from requesting a scan to showing it\n",
+        );
+        out.push_str("# TYPE scan_classification_load_seconds histogram\n");
+        self.load_seconds
+            .write("scan_classification_load_seconds", &mut out);
+
+        out.push_str("# HELP scan_classification_frame_seconds Time spent drawing each frame\n");
+        out.push_str("# TYPE scan_classification_frame_seconds histogram\n");
+        self.frame_seconds
+            .write("scan_classification_frame_seconds", &mut out);
+
+        out.push_str("# HELP scan_classification_queue_depth Scans left in the queue\n");
+        out.push_str("# TYPE scan_classification_queue_depth gauge\n");
+        let _ = writeln!(out, "scan_classification_queue_depth {}", self.queue_depth);
+
+        out
+    }
+}
+
+/// Serves [`StationMetrics`] until the process exits
+#[derive(Debug, Clone)]
+pub struct MetricsServer {
+    metrics: Arc<Mutex<StationMetrics>>,
+}
+
+impl MetricsServer {
+    /// Listen on `address`, e.g. `"127.0.0.1:9464"`
+    pub fn start(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let metrics = Arc::new(Mutex::new(StationMetrics::default()));
+
+        let served = Arc::clone(&metrics);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A scraper hanging up early is its own problem
+                let _ = respond(stream, &served);
+            }
+        });
+
+        Ok(Self { metrics })
+    }
+
+    /// Update the metrics served
+    pub fn update(&self, update: impl FnOnce(&mut StationMetrics)) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            update(&mut metrics);
+        }
+    }
+}
+
+fn respond(stream: TcpStream, metrics: &Mutex<StationMetrics>) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Headers are read and ignored, so the client sees its whole request
+    // consumed before the response
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            metrics
+                .lock()
+                .map(|metrics| metrics.render())
+                .unwrap_or_default(),
+        ),
+        _ => ("404 Not Found", String::from("not found, try /metrics\n")),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Backslashes, quotes, and newlines escaped, as label values require
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,155 @@
+//! Reading Wavefront OBJ scans
+//!
+//! Only geometry is read: positions, normals, and faces. Texture
+//! coordinates, groups, and materials are skipped, and polygons are split
+//! into fans of triangles. Files without normals on every face get smooth
+//! normals averaged from the faces around each vertex
+
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use crate::{
+    loader::smooth_normals,
+    mesh::{Mesh, MeshError, VERTEX_STRIDE},
+    scratch::ScanScratch,
+};
+
+/// A corner of a face, as indices into the file's positions and normals
+type Corner = (u32, Option<u32>);
+
+pub fn parse(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+    let text = String::from_utf8_lossy(buffer);
+
+    let mut positions: Vec<Vector3<f32>> = Vec::new();
+    let mut normals: Vec<Vector3<f32>> = Vec::new();
+    // Three per triangle
+    let mut corners: Vec<Corner> = Vec::new();
+    let mut face: Vec<Corner> = Vec::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let number = idx + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+
+        match fields.next() {
+            Some("v") => positions.push(vector(fields, number)?),
+            Some("vn") => normals.push(vector(fields, number)?),
+            Some("f") => {
+                face.clear();
+
+                for field in fields {
+                    face.push(corner(field, positions.len(), normals.len(), number)?);
+                }
+
+                if face.len() < 3 {
+                    return Err(error(number, "face with fewer than 3 corners"));
+                }
+
+                for idx in 1..face.len() - 1 {
+                    corners.extend_from_slice(&[face[0], face[idx], face[idx + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let has_normals = corners.iter().all(|&(_, normal)| normal.is_some());
+    let smoothed = if has_normals {
+        Vec::new()
+    } else {
+        let triangles: Vec<u32> = corners.iter().map(|&(position, _)| position).collect();
+        smooth_normals(&positions, &triangles)
+    };
+
+    // Each distinct corner becomes one vertex, so faces sharing a position
+    // and normal share a vertex as they do in STL meshes
+    let mut remap: HashMap<Corner, u32> = HashMap::with_capacity(positions.len());
+    let mut vertices = scratch.vertices.take(positions.len() * VERTEX_STRIDE);
+    let mut indices = scratch.indices.take(corners.len());
+
+    for &(position, normal) in &corners {
+        let key = if has_normals {
+            (position, normal)
+        } else {
+            (position, None)
+        };
+        let next = remap.len() as u32;
+
+        let vertex = *remap.entry(key).or_insert_with(|| {
+            let normal = match normal {
+                Some(normal) if has_normals => normals[normal as usize]
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros),
+                _ => smoothed[position as usize],
+            };
+
+            vertices.extend(positions[position as usize].iter().chain(normal.iter()));
+            next
+        });
+
+        indices.push(vertex);
+    }
+
+    Ok(Mesh::new(vertices, indices))
+}
+
+/// The three coordinates after a `v` or `vn`. A fourth, the weight some
+/// exporters add to positions, is ignored
+fn vector<'a>(
+    mut fields: impl Iterator<Item = &'a str>,
+    number: usize,
+) -> Result<Vector3<f32>, MeshError> {
+    let mut vector = Vector3::zeros();
+
+    for component in vector.iter_mut() {
+        *component = fields
+            .next()
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(|| error(number, "expected three coordinates"))?;
+    }
+
+    Ok(vector)
+}
+
+/// A face corner written as `v`, `v/vt`, `v//vn`, or `v/vt/vn`, with
+/// indices counted from 1, or back from the last one seen if negative
+fn corner(
+    field: &str,
+    positions: usize,
+    normals: usize,
+    number: usize,
+) -> Result<Corner, MeshError> {
+    let mut parts = field.split('/');
+
+    let position = resolve(parts.next().unwrap_or_default(), positions)
+        .ok_or_else(|| error(number, "invalid position index"))?;
+
+    let normal = match parts.nth(1) {
+        Some(normal) if !normal.is_empty() => {
+            Some(resolve(normal, normals).ok_or_else(|| error(number, "invalid normal index"))?)
+        }
+        _ => None,
+    };
+
+    Ok((position, normal))
+}
+
+fn resolve(index: &str, count: usize) -> Option<u32> {
+    let index: i64 = index.parse().ok()?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+
+    if index != 0 && (0..count as i64).contains(&resolved) {
+        Some(resolved as u32)
+    } else {
+        None
+    }
+}
+
+fn error(number: usize, message: &str) -> MeshError {
+    MeshError::Parse(format!("OBJ line {}: {}", number, message))
+}
@@ -0,0 +1,275 @@
+use std::{fmt, path::Path};
+
+use glutin::{
+    dpi::PhysicalSize, event_loop::EventLoop, Context, ContextBuilder, ContextError, CreationError,
+    PossiblyCurrent,
+};
+use nalgebra::Matrix4;
+
+use crate::{
+    backend::{GlBackend, RenderBackend, ScanFrame},
+    camera::StationaryCamera,
+    color::Color,
+    framebuffer::Framebuffer,
+    image::RgbaImage,
+    mesh::{Mesh, MeshError},
+    renderer::Light,
+    software::SoftwareBackend,
+};
+
+/// Most samples per side [`RenderOptions::supersampling`] renders
+pub const MAX_SUPERSAMPLING: u32 = 4;
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Size of the returned image
+    pub width: u32,
+    pub height: u32,
+    /// Render at this many times `width` and `height` and average down, for
+    /// smoother edges than multisampling gives. 1 to
+    /// [`MAX_SUPERSAMPLING`]; values outside are clamped
+    pub supersampling: u32,
+    /// Orientation of the scan, defaults to the orientation used when a scan
+    /// is first opened in the interactive tool
+    pub model: Matrix4<f32>,
+    /// Vertical field of view, in degrees
+    pub fov: f32,
+    pub object_color: Color,
+    pub background: Color,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            supersampling: 1,
+            model: StationaryCamera::default_model(),
+            fov: 45.0,
+            object_color: Color {
+                red: 0.8,
+                green: 0.8,
+                blue: 0.8,
+            },
+            background: Color {
+                red: 0.0,
+                green: 0.0,
+                blue: 0.0,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Context(CreationError),
+    MakeCurrent(ContextError),
+    IncompleteFramebuffer(u32),
+    Mesh(MeshError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Context(e) => write!(f, "unable to create headless context: {}", e),
+            Self::MakeCurrent(e) => write!(f, "unable to make context current: {}", e),
+            Self::IncompleteFramebuffer(status) => {
+                write!(f, "framebuffer incomplete (status {:#x})", status)
+            }
+            Self::Mesh(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl RenderOptions {
+    fn supersampling(&self) -> u32 {
+        self.supersampling.clamp(1, MAX_SUPERSAMPLING)
+    }
+
+    /// Size of the image actually rendered, before downsampling
+    fn render_size(&self) -> (u32, u32) {
+        let factor = self.supersampling();
+
+        (self.width * factor, self.height * factor)
+    }
+}
+
+impl From<MeshError> for RenderError {
+    fn from(e: MeshError) -> Self {
+        Self::Mesh(e)
+    }
+}
+
+/// Renders scans without a window, using the same shader pipeline as the
+/// interactive tool
+///
+/// The GL context is made current on creation, so an `OffscreenRenderer`
+/// should not be shared with other GL code on the same thread
+pub struct OffscreenRenderer {
+    // GL resources must be dropped before the context that owns them
+    framebuffer: Option<Framebuffer>,
+    backend: GlBackend,
+    _context: Context<PossiblyCurrent>,
+    _event_loop: EventLoop<()>,
+}
+
+impl OffscreenRenderer {
+    pub fn new() -> Result<Self, RenderError> {
+        let event_loop = new_event_loop();
+
+        let context = ContextBuilder::new()
+            .build_headless(&event_loop, PhysicalSize::new(1, 1))
+            .map_err(RenderError::Context)?;
+
+        let context =
+            unsafe { context.make_current() }.map_err(|(_, e)| RenderError::MakeCurrent(e))?;
+
+        gl::load_with(|symbol| context.get_proc_address(symbol));
+
+        let backend = GlBackend::new(&Light::white(), RenderOptions::default().object_color);
+
+        Ok(Self {
+            framebuffer: None,
+            backend,
+            _context: context,
+            _event_loop: event_loop,
+        })
+    }
+
+    pub fn render_path(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &RenderOptions,
+    ) -> Result<RgbaImage, RenderError> {
+        let mesh = Mesh::open(path)?;
+
+        self.render(&mesh, options)
+    }
+
+    pub fn render(
+        &mut self,
+        mesh: &Mesh,
+        options: &RenderOptions,
+    ) -> Result<RgbaImage, RenderError> {
+        let (width, height) = options.render_size();
+        let framebuffer = self.framebuffer(width, height)?;
+        framebuffer.bind();
+
+        let stationary = StationaryCamera::new();
+
+        let projection = nalgebra_glm::perspective(
+            options.width as f32 / options.height as f32,
+            options.fov.to_radians(),
+            1.0,
+            1000.0,
+        );
+
+        self.backend.load_scan(mesh);
+        self.backend.set_object_color(options.object_color);
+        self.backend.draw_scan(&ScanFrame {
+            stationary: &stationary,
+            model: options.model,
+            projection,
+            background: options.background,
+        });
+
+        let framebuffer = self.framebuffer.as_ref().unwrap();
+        let mut image = RgbaImage::from_raw(
+            framebuffer.width,
+            framebuffer.height,
+            framebuffer.read_pixels(),
+        );
+        image.flip_vertical();
+
+        Ok(image.downsample(options.supersampling()))
+    }
+
+    fn framebuffer(&mut self, width: u32, height: u32) -> Result<&Framebuffer, RenderError> {
+        let is_stale = match &self.framebuffer {
+            Some(fb) => fb.width != width || fb.height != height,
+            None => true,
+        };
+
+        if is_stale {
+            self.framebuffer = None;
+            self.framebuffer =
+                Some(Framebuffer::new(width, height).map_err(RenderError::IncompleteFramebuffer)?);
+        }
+
+        Ok(self.framebuffer.as_ref().unwrap())
+    }
+}
+
+/// Render the scan at `path` to an image, falling back to the CPU if no GL
+/// context can be created
+///
+/// This creates a new GL context on every call; prefer [`OffscreenRenderer`]
+/// when rendering many scans
+pub fn render_scan_to_image(
+    path: impl AsRef<Path>,
+    options: RenderOptions,
+) -> Result<RgbaImage, RenderError> {
+    match OffscreenRenderer::new() {
+        Ok(mut renderer) => renderer.render_path(path, &options),
+        Err(RenderError::Context(..)) => Ok(render_software(&Mesh::open(path)?, &options)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Render a scan with [`SoftwareBackend`]. Much slower than the GL path and
+/// flat shaded, but works without a GPU
+pub fn render_software(mesh: &Mesh, options: &RenderOptions) -> RgbaImage {
+    let (width, height) = options.render_size();
+    let mut backend = SoftwareBackend::new(width, height, &Light::white(), options.object_color);
+
+    let stationary = StationaryCamera::new();
+
+    backend.load_scan(mesh);
+    backend.draw_scan(&ScanFrame {
+        stationary: &stationary,
+        model: options.model,
+        projection: nalgebra_glm::perspective(
+            options.width as f32 / options.height as f32,
+            options.fov.to_radians(),
+            1.0,
+            1000.0,
+        ),
+        background: options.background,
+    });
+
+    backend.to_image().downsample(options.supersampling())
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+fn new_event_loop() -> EventLoop<()> {
+    use glutin::platform::unix::EventLoopExtUnix;
+
+    EventLoop::new_any_thread()
+}
+
+#[cfg(target_os = "windows")]
+fn new_event_loop() -> EventLoop<()> {
+    use glutin::platform::windows::EventLoopExtWindows;
+
+    EventLoop::new_any_thread()
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "windows"
+)))]
+fn new_event_loop() -> EventLoop<()> {
+    EventLoop::new()
+}
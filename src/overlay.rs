@@ -0,0 +1,287 @@
+//! The 2D pass drawn over the scan
+//!
+//! HUD text, chips, buttons, and thumbnails are laid out in pixels from the
+//! top left of the window and drawn after the 3D pass with an orthographic
+//! projection, no depth test, and premultiplied alpha, so nothing they do
+//! depends on the scan's camera or depth buffer
+
+use nalgebra::{Matrix4, Vector2};
+
+use crate::{
+    buffer::{BufferElementType, VertexBuffer, VertexBufferLayout},
+    check,
+    color::Color,
+    font::{FontAtlas, TextLayout},
+    shader::{Shader, Uniform},
+    text::{TextBatch, TextRenderer, TextStyle},
+    texture::Texture,
+    vertex_array::VertexArray,
+};
+
+const QUADS_VERTEX_SHADER: &str = include_str!("shaders/quads-vs.shader");
+const QUADS_FRAGMENT_SHADER: &str = include_str!("shaders/quads-fs.shader");
+
+/// Number of `f32`s per vertex: a position, texture coordinates, and a
+/// color with alpha
+const STRIDE: usize = 8;
+
+/// Everything to draw in one 2D pass. Rectangles and triangles are drawn
+/// first, then images, then text, each in the order added
+#[derive(Default)]
+pub struct OverlayBatch<'a> {
+    shapes: Vec<f32>,
+    images: Vec<(&'a Texture, Vec<f32>)>,
+    text: TextBatch,
+}
+
+impl<'a> OverlayBatch<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.images.clear();
+        self.text.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.images.is_empty() && self.text.is_empty()
+    }
+
+    /// A solid rectangle, e.g. the background of a chip or button
+    pub fn rect(&mut self, min: Vector2<f32>, size: Vector2<f32>, color: Color, alpha: f32) {
+        push_quad(
+            &mut self.shapes,
+            min,
+            min + size,
+            Vector2::zeros(),
+            Vector2::zeros(),
+            color,
+            alpha,
+        );
+    }
+
+    /// A solid triangle, e.g. part of a highlight projected from the scan
+    pub fn triangle(
+        &mut self,
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+        c: Vector2<f32>,
+        color: Color,
+        alpha: f32,
+    ) {
+        for &corner in &[a, b, c] {
+            self.shapes.extend_from_slice(&[
+                corner.x,
+                corner.y,
+                0.0,
+                0.0,
+                color.red,
+                color.green,
+                color.blue,
+                alpha,
+            ]);
+        }
+    }
+
+    /// A segment `width` pixels wide, e.g. part of a plotted profile or a
+    /// gridline. Nothing is drawn if its ends coincide
+    pub fn line(&mut self, a: Vector2<f32>, b: Vector2<f32>, width: f32, color: Color, alpha: f32) {
+        let side = match (b - a).try_normalize(f32::EPSILON) {
+            Some(direction) => Vector2::new(-direction.y, direction.x) * width / 2.0,
+            None => return,
+        };
+
+        self.triangle(a + side, b + side, b - side, color, alpha);
+        self.triangle(a + side, b - side, a - side, color, alpha);
+    }
+
+    /// `texture`, e.g. a thumbnail, stretched over a rectangle and faded by
+    /// `alpha`. The texture's first row is drawn at the top
+    pub fn image(
+        &mut self,
+        texture: &'a Texture,
+        min: Vector2<f32>,
+        size: Vector2<f32>,
+        alpha: f32,
+    ) {
+        let mut vertices = Vec::with_capacity(6 * STRIDE);
+
+        push_quad(
+            &mut vertices,
+            min,
+            min + size,
+            Vector2::zeros(),
+            Vector2::new(1.0, 1.0),
+            Color::WHITE,
+            alpha,
+        );
+
+        self.images.push((texture, vertices));
+    }
+
+    /// See [`TextBatch::text`]
+    pub fn text(
+        &mut self,
+        atlas: &FontAtlas,
+        text: &str,
+        position: Vector2<f32>,
+        size: f32,
+        color: Color,
+        alpha: f32,
+    ) -> Vector2<f32> {
+        self.text.text(atlas, text, position, size, color, alpha)
+    }
+
+    /// See [`TextBatch::layout`]
+    pub fn layout(
+        &mut self,
+        layout: &TextLayout,
+        position: Vector2<f32>,
+        color: Color,
+        alpha: f32,
+    ) {
+        self.text.layout(layout, position, color, alpha);
+    }
+}
+
+fn push_quad(
+    data: &mut Vec<f32>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+    uv_min: Vector2<f32>,
+    uv_max: Vector2<f32>,
+    color: Color,
+    alpha: f32,
+) {
+    let corners = [
+        (min.x, min.y, uv_min.x, uv_min.y),
+        (max.x, min.y, uv_max.x, uv_min.y),
+        (max.x, max.y, uv_max.x, uv_max.y),
+        (min.x, max.y, uv_min.x, uv_max.y),
+    ];
+
+    for &idx in &[0, 1, 2, 0, 2, 3] {
+        let (x, y, u, v) = corners[idx];
+
+        data.extend_from_slice(&[x, y, u, v, color.red, color.green, color.blue, alpha]);
+    }
+}
+
+/// Draws an [`OverlayBatch`] over whatever is already in the framebuffer
+pub struct OverlayPass {
+    shader: Shader,
+    va: VertexArray,
+    vb: VertexBuffer,
+    text: TextRenderer,
+}
+
+impl OverlayPass {
+    /// Requires a current GL context with loaded function pointers
+    pub fn new(atlas: FontAtlas) -> Self {
+        let mut va = VertexArray::new();
+        let vb = VertexBuffer::dynamic(&[]);
+        let mut layout = VertexBufferLayout::new();
+
+        layout.push(BufferElementType::Float, 2, false);
+        layout.push(BufferElementType::Float, 2, false);
+        layout.push(BufferElementType::Float, 4, false);
+        va.add_buffer(&vb, &layout);
+
+        va.unbind();
+        vb.unbind();
+
+        Self {
+            shader: Shader::from_source(QUADS_VERTEX_SHADER, QUADS_FRAGMENT_SHADER),
+            va,
+            vb,
+            text: TextRenderer::new(atlas),
+        }
+    }
+
+    /// For laying out text to draw in this pass
+    pub fn atlas(&self) -> &FontAtlas {
+        self.text.atlas()
+    }
+
+    /// Maps pixels from the top left of a viewport to clip space
+    pub fn projection(viewport_size: (u32, u32)) -> Matrix4<f32> {
+        Matrix4::new_orthographic(
+            0.0,
+            viewport_size.0 as f32,
+            viewport_size.1 as f32,
+            0.0,
+            -1.0,
+            1.0,
+        )
+    }
+
+    /// Draw `batch`, restoring the 3D pass's depth test and blending after
+    pub fn draw(
+        &mut self,
+        batch: &OverlayBatch,
+        viewport_size: (u32, u32),
+        text_style: &TextStyle,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let projection = Self::projection(viewport_size);
+
+        unsafe {
+            check!(gl::Disable(gl::DEPTH_TEST));
+            check!(gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA));
+        }
+
+        self.va.bind();
+        self.shader.bind();
+        self.shader.set_uniform(&Uniform::MatrixFourFv {
+            name: "projection",
+            matrix: &projection,
+        });
+        self.shader.set_uniform(&Uniform::OneInteger {
+            name: "image",
+            v0: 0,
+        });
+
+        if !batch.shapes.is_empty() {
+            self.shader.set_uniform(&Uniform::OneInteger {
+                name: "textured",
+                v0: 0,
+            });
+            self.draw_quads(&batch.shapes);
+        }
+
+        if !batch.images.is_empty() {
+            self.shader.set_uniform(&Uniform::OneInteger {
+                name: "textured",
+                v0: 1,
+            });
+
+            for (texture, vertices) in &batch.images {
+                texture.bind(0);
+                self.draw_quads(vertices);
+                texture.unbind();
+            }
+        }
+
+        self.va.unbind();
+        self.vb.unbind();
+
+        self.text.draw(&batch.text, &projection, text_style);
+
+        unsafe {
+            check!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+            check!(gl::Enable(gl::DEPTH_TEST));
+        }
+    }
+
+    fn draw_quads(&self, vertices: &[f32]) {
+        self.vb.update(vertices);
+
+        let count = (vertices.len() / STRIDE) as i32;
+        check!(unsafe { gl::DrawArrays(gl::TRIANGLES, 0, count) });
+    }
+}
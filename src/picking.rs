@@ -0,0 +1,340 @@
+//! Finding what is under the cursor by casting rays against meshes on the CPU
+
+use std::cmp::Ordering;
+
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+use crate::mesh::{BoundingBox, Mesh};
+
+/// Most triangles in a leaf of a [`TriangleBvh`]
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// Not necessarily normalized
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// The ray through the pixel at `cursor`, measured from the top left of
+    /// a `viewport`-sized window, in the space `transform` maps to clip
+    /// space, e.g. model space for `projection * view * model`
+    ///
+    /// Returns `None` if `transform` is not invertible
+    pub fn from_screen(
+        cursor: (f32, f32),
+        viewport: (u32, u32),
+        transform: &Matrix4<f32>,
+    ) -> Option<Self> {
+        let inverse = transform.try_inverse()?;
+
+        let x = cursor.0 / viewport.0 as f32 * 2.0 - 1.0;
+        let y = 1.0 - cursor.1 / viewport.1 as f32 * 2.0;
+
+        let unproject = |z: f32| {
+            let point = inverse * Vector4::new(x, y, z, 1.0);
+
+            point.xyz() / point.w
+        };
+
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+
+        Some(Self {
+            origin: near,
+            direction: far - near,
+        })
+    }
+
+    /// The point `t` multiples of `direction` along the ray
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        self.origin + self.direction * t
+    }
+
+    /// The point on the ray closest to `point`, which may be behind its
+    /// origin
+    pub fn closest_point(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let length = self.direction.norm_squared();
+
+        if length <= f32::EPSILON {
+            return self.origin;
+        }
+
+        self.at((point - self.origin).dot(&self.direction) / length)
+    }
+
+    /// Distance along the ray, in multiples of `direction`, at which it
+    /// crosses the triangle `[a, b, c]` from either side
+    pub fn intersect_triangle(&self, [a, b, c]: [Vector3<f32>; 3]) -> Option<f32> {
+        // Möller-Trumbore
+        let ab = b - a;
+        let ac = c - a;
+
+        let p = self.direction.cross(&ac);
+        let determinant = ab.dot(&p);
+
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let to_origin = self.origin - a;
+
+        let u = to_origin.dot(&p) * inverse;
+
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(&ab);
+        let v = self.direction.dot(&q) * inverse;
+
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = ac.dot(&q) * inverse;
+
+        if t >= 0.0 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+/// Index of the nearest triangle of `mesh` hit by `ray`
+pub fn pick_triangle(mesh: &Mesh, ray: &Ray) -> Option<usize> {
+    pick(mesh, ray).map(|(idx, _)| idx)
+}
+
+/// The nearest point on `mesh` hit by `ray`, in the ray's space
+pub fn pick_point(mesh: &Mesh, ray: &Ray) -> Option<Vector3<f32>> {
+    pick(mesh, ray).map(|(_, t)| ray.at(t))
+}
+
+/// The nearest triangle hit, and how far along the ray
+fn pick(mesh: &Mesh, ray: &Ray) -> Option<(usize, f32)> {
+    mesh.triangles()
+        .enumerate()
+        .filter_map(|(idx, [a, b, c])| {
+            ray.intersect_triangle([mesh.position(a), mesh.position(b), mesh.position(c)])
+                .map(|t| (idx, t))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// A triangle hit by a ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// Index of the triangle in the mesh
+    pub triangle: usize,
+    /// Distance along the ray, in multiples of its direction
+    pub t: f32,
+    /// The triangle's normal by its winding, not normalized
+    pub normal: Vector3<f32>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bbox: BoundingBox,
+    /// For leaves, the first of `count` triangles. For interior nodes, the
+    /// right child; the left one follows the node
+    start: u32,
+    /// 0 for interior nodes
+    count: u32,
+}
+
+/// A bounding volume hierarchy over a mesh's triangles, for picking every
+/// frame without testing each triangle. Holds its own copy of the positions,
+/// so it does not borrow the mesh
+#[derive(Debug, Clone)]
+pub struct TriangleBvh {
+    /// Depth first, the root first
+    nodes: Vec<Node>,
+    /// Corners of each triangle, in leaf order
+    triangles: Vec<[Vector3<f32>; 3]>,
+    /// Index in the mesh of each triangle in `triangles`
+    indices: Vec<u32>,
+}
+
+impl TriangleBvh {
+    pub fn new(mesh: &Mesh) -> Self {
+        let positions: Vec<[Vector3<f32>; 3]> = mesh
+            .triangles()
+            .map(|[a, b, c]| [mesh.position(a), mesh.position(b), mesh.position(c)])
+            .collect();
+        let centroids: Vec<Vector3<f32>> = positions
+            .iter()
+            .map(|[a, b, c]| (a + b + c) / 3.0)
+            .collect();
+
+        let mut order: Vec<u32> = (0..positions.len() as u32).collect();
+        let mut nodes = Vec::new();
+
+        if !order.is_empty() {
+            build(&positions, &centroids, &mut order, 0, &mut nodes);
+        }
+
+        Self {
+            nodes,
+            triangles: order.iter().map(|&idx| positions[idx as usize]).collect(),
+            indices: order,
+        }
+    }
+
+    /// The nearest triangle hit by `ray`, which must be in the mesh's space
+    pub fn pick(&self, ray: &Ray) -> Option<Hit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inverse = ray.direction.map(|d| 1.0 / d);
+        let mut nearest: Option<Hit> = None;
+        let mut stack = vec![0];
+
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            let limit = nearest.map_or(f32::INFINITY, |hit| hit.t);
+
+            match intersect_box(ray, &inverse, &node.bbox) {
+                Some(t) if t <= limit => {}
+                _ => continue,
+            }
+
+            if node.count == 0 {
+                stack.push(node.start as usize);
+                stack.push(idx + 1);
+                continue;
+            }
+
+            let start = node.start as usize;
+
+            for leaf in start..start + node.count as usize {
+                let [a, b, c] = self.triangles[leaf];
+
+                if let Some(t) = ray.intersect_triangle([a, b, c]) {
+                    if nearest.map_or(true, |hit| t < hit.t) {
+                        nearest = Some(Hit {
+                            triangle: self.indices[leaf] as usize,
+                            t,
+                            normal: (b - a).cross(&(c - a)),
+                        });
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+
+    /// Call `f` with the index and corners of each triangle whose centroid
+    /// is within `radius` of `center`
+    pub fn triangles_near(
+        &self,
+        center: Vector3<f32>,
+        radius: f32,
+        mut f: impl FnMut(usize, [Vector3<f32>; 3]),
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let radius_squared = radius * radius;
+        let mut stack = vec![0];
+
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            let closest = center.sup(&node.bbox.min).inf(&node.bbox.max);
+
+            if (closest - center).norm_squared() > radius_squared {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.start as usize);
+                stack.push(idx + 1);
+                continue;
+            }
+
+            let start = node.start as usize;
+
+            for leaf in start..start + node.count as usize {
+                let [a, b, c] = self.triangles[leaf];
+
+                if ((a + b + c) / 3.0 - center).norm_squared() <= radius_squared {
+                    f(self.indices[leaf] as usize, [a, b, c]);
+                }
+            }
+        }
+    }
+}
+
+/// Append the subtree over `order`, which starts `offset` triangles into the
+/// full order, to `nodes`. Returns the index of its root
+fn build(
+    positions: &[[Vector3<f32>; 3]],
+    centroids: &[Vector3<f32>],
+    order: &mut [u32],
+    offset: usize,
+    nodes: &mut Vec<Node>,
+) -> usize {
+    let bbox = BoundingBox::from_points(
+        order
+            .iter()
+            .flat_map(|&idx| positions[idx as usize].iter().copied()),
+    );
+
+    let root = nodes.len();
+    nodes.push(Node {
+        bbox,
+        start: offset as u32,
+        count: order.len() as u32,
+    });
+
+    if order.len() <= LEAF_SIZE {
+        return root;
+    }
+
+    // Split at the median centroid along the axis they spread the most on
+    let spread = BoundingBox::from_points(order.iter().map(|&idx| centroids[idx as usize])).delta();
+    let axis = spread.imax();
+
+    if spread[axis] <= 0.0 {
+        return root;
+    }
+
+    order.sort_unstable_by(|&a, &b| {
+        centroids[a as usize][axis]
+            .partial_cmp(&centroids[b as usize][axis])
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let middle = order.len() / 2;
+    let (left, right) = order.split_at_mut(middle);
+
+    build(positions, centroids, left, offset, nodes);
+    let right = build(positions, centroids, right, offset + middle, nodes);
+
+    nodes[root].start = right as u32;
+    nodes[root].count = 0;
+
+    root
+}
+
+/// Distance along `ray` at which it enters `bbox`, 0 if it starts inside.
+/// `inverse` is the reciprocal of each component of its direction
+fn intersect_box(ray: &Ray, inverse: &Vector3<f32>, bbox: &BoundingBox) -> Option<f32> {
+    let to_min = (bbox.min - ray.origin).component_mul(inverse);
+    let to_max = (bbox.max - ray.origin).component_mul(inverse);
+
+    let enter = to_min.inf(&to_max).max().max(0.0);
+    let exit = to_min.sup(&to_max).min();
+
+    if enter <= exit {
+        Some(enter)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,391 @@
+//! Reading Stanford PLY scans, in ASCII or binary of either byte order
+//!
+//! The header lists elements, each a count of records with typed
+//! properties. Positions and, if present, normals are read from the `vertex`
+//! element and triangles from the `face` element, with larger polygons split
+//! into fans. Every other element and property, such as colors, is skipped
+
+use nalgebra::Vector3;
+
+use crate::{
+    loader::smooth_normals,
+    mesh::{Mesh, MeshError, VERTEX_STRIDE},
+    scratch::ScanScratch,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Property {
+    Scalar(Scalar),
+    /// The type of the item count, then of each item
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, Property)>,
+}
+
+impl Element {
+    /// The fewest bytes a record can take, so no more records are reserved
+    /// for than the data could hold, whatever the header claims
+    fn min_record_size(&self, encoding: Encoding) -> usize {
+        let size = |scalar: Scalar| match encoding {
+            // A digit, then whitespace
+            Encoding::Ascii => 2,
+            _ => scalar.size(),
+        };
+
+        self.properties
+            .iter()
+            .map(|(_, property)| match *property {
+                Property::Scalar(scalar) | Property::List(scalar, _) => size(scalar),
+            })
+            .sum::<usize>()
+            .max(1)
+    }
+
+    /// Index of the scalar property `name`
+    fn scalar(&self, name: &str) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|(property, kind)| property == name && matches!(kind, Property::Scalar(_)))
+    }
+}
+
+pub fn parse(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+    let (encoding, elements, body) = parse_header(buffer)?;
+    let body_len = body.len();
+    let mut values = Values::new(encoding, body)?;
+
+    let mut positions: Vec<Vector3<f32>> = Vec::new();
+    let mut normals: Option<Vec<Vector3<f32>>> = None;
+    let mut indices = scratch.indices.take(0);
+    let mut polygon: Vec<u32> = Vec::new();
+
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let position = [
+                    element.scalar("x"),
+                    element.scalar("y"),
+                    element.scalar("z"),
+                ];
+                let position = match position {
+                    [Some(x), Some(y), Some(z)] => [x, y, z],
+                    _ => return Err(error("vertices without x, y, and z")),
+                };
+                let normal = match [
+                    element.scalar("nx"),
+                    element.scalar("ny"),
+                    element.scalar("nz"),
+                ] {
+                    [Some(x), Some(y), Some(z)] => Some([x, y, z]),
+                    _ => None,
+                };
+
+                let capacity = element
+                    .count
+                    .min(body_len / element.min_record_size(encoding));
+                let mut record = vec![0.0; element.properties.len()];
+                positions.reserve(capacity);
+                let mut read_normals = normal.map(|_| Vec::with_capacity(capacity));
+
+                for _ in 0..element.count {
+                    for (value, (_, property)) in record.iter_mut().zip(&element.properties) {
+                        match *property {
+                            Property::Scalar(scalar) => *value = values.read(scalar)?,
+                            Property::List(count, item) => values.skip_list(count, item)?,
+                        }
+                    }
+
+                    let vector = |[x, y, z]: [usize; 3]| {
+                        Vector3::new(record[x] as f32, record[y] as f32, record[z] as f32)
+                    };
+
+                    positions.push(vector(position));
+
+                    if let (Some(normal), Some(read)) = (normal, &mut read_normals) {
+                        read.push(
+                            vector(normal)
+                                .try_normalize(f32::EPSILON)
+                                .unwrap_or_else(Vector3::zeros),
+                        );
+                    }
+                }
+
+                normals = read_normals;
+            }
+            "face" => {
+                let corners = element
+                    .properties
+                    .iter()
+                    .position(|(name, property)| {
+                        matches!(property, Property::List(..))
+                            && (name == "vertex_indices" || name == "vertex_index")
+                    })
+                    .ok_or_else(|| error("faces without vertex_indices"))?;
+
+                for _ in 0..element.count {
+                    for (idx, (_, property)) in element.properties.iter().enumerate() {
+                        match *property {
+                            Property::List(count, item) if idx == corners => {
+                                polygon.clear();
+
+                                for _ in 0..values.read(count)? as usize {
+                                    let vertex = values.read(item)?;
+
+                                    if vertex < 0.0 || vertex as usize >= positions.len() {
+                                        return Err(error("face refers to a missing vertex"));
+                                    }
+
+                                    polygon.push(vertex as u32);
+                                }
+
+                                for corner in 1..polygon.len().saturating_sub(1) {
+                                    indices.extend_from_slice(&[
+                                        polygon[0],
+                                        polygon[corner],
+                                        polygon[corner + 1],
+                                    ]);
+                                }
+                            }
+                            Property::List(count, item) => values.skip_list(count, item)?,
+                            Property::Scalar(scalar) => {
+                                values.read(scalar)?;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => values.skip_element(element)?,
+        }
+    }
+
+    let normals = normals.unwrap_or_else(|| smooth_normals(&positions, &indices));
+
+    let mut vertices = scratch.vertices.take(positions.len() * VERTEX_STRIDE);
+
+    for (position, normal) in positions.iter().zip(&normals) {
+        vertices.extend(position.iter().chain(normal.iter()));
+    }
+
+    Ok(Mesh::new(vertices, indices))
+}
+
+/// The encoding, the elements in order, and the data after the header
+fn parse_header(buffer: &[u8]) -> Result<(Encoding, Vec<Element>, &[u8]), MeshError> {
+    // At the start of a line, so comments mentioning it do not end the
+    // header early
+    const END: &[u8] = b"\nend_header";
+
+    let end = buffer
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| error("no end_header"))?;
+
+    let mut body = &buffer[end + END.len()..];
+    if body.starts_with(b"\r") {
+        body = &body[1..];
+    }
+    if body.starts_with(b"\n") {
+        body = &body[1..];
+    }
+
+    let header = std::str::from_utf8(&buffer[..end]).map_err(|_| error("header is not text"))?;
+    let mut lines = header.lines().map(str::trim);
+
+    if lines.next() != Some("ply") {
+        return Err(error("missing ply magic"));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        match fields.as_slice() {
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(error(&format!("unknown format `{}`", format))),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: (*name).to_owned(),
+                count: count.parse().map_err(|_| error("invalid element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = match (Scalar::parse(count), Scalar::parse(item)) {
+                    (Some(count), Some(item)) => Property::List(count, item),
+                    _ => return Err(error(&format!("unknown type of list `{}`", name))),
+                };
+
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?
+                    .properties
+                    .push(((*name).to_owned(), property));
+            }
+            ["property", kind, name] => {
+                let property = Scalar::parse(kind)
+                    .ok_or_else(|| error(&format!("unknown type `{}`", kind)))?;
+
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?
+                    .properties
+                    .push(((*name).to_owned(), Property::Scalar(property)));
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(error(&format!("unexpected header line `{}`", line))),
+        }
+    }
+
+    let encoding = encoding.ok_or_else(|| error("no format line"))?;
+
+    Ok((encoding, elements, body))
+}
+
+/// The values of the records after the header, read in order
+enum Values<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl<'a> Values<'a> {
+    fn new(encoding: Encoding, body: &'a [u8]) -> Result<Self, MeshError> {
+        Ok(match encoding {
+            Encoding::Ascii => Self::Ascii(
+                std::str::from_utf8(body)
+                    .map_err(|_| error("ASCII data is not text"))?
+                    .split_ascii_whitespace(),
+            ),
+            Encoding::LittleEndian => Self::Binary {
+                bytes: body,
+                big_endian: false,
+            },
+            Encoding::BigEndian => Self::Binary {
+                bytes: body,
+                big_endian: true,
+            },
+        })
+    }
+
+    fn read(&mut self, scalar: Scalar) -> Result<f64, MeshError> {
+        let (bytes, big_endian) = match self {
+            Self::Ascii(tokens) => {
+                return tokens
+                    .next()
+                    .and_then(|token| token.parse().ok())
+                    .ok_or_else(|| error("missing or invalid value"))
+            }
+            Self::Binary { bytes, big_endian } => (bytes, *big_endian),
+        };
+
+        let size = scalar.size();
+
+        if bytes.len() < size {
+            return Err(error("data ends early"));
+        }
+
+        // Little-endian from here on
+        let data = *bytes;
+        let mut raw = [0; 8];
+        raw[..size].copy_from_slice(&data[..size]);
+        *bytes = &data[size..];
+
+        if big_endian {
+            raw[..size].reverse();
+        }
+
+        let [a, b, c, d, ..] = raw;
+
+        Ok(match scalar {
+            Scalar::I8 => a as i8 as f64,
+            Scalar::U8 => a as f64,
+            Scalar::I16 => i16::from_le_bytes([a, b]) as f64,
+            Scalar::U16 => u16::from_le_bytes([a, b]) as f64,
+            Scalar::I32 => i32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::U32 => u32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F32 => f32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F64 => f64::from_le_bytes(raw),
+        })
+    }
+
+    fn skip_list(&mut self, count: Scalar, item: Scalar) -> Result<(), MeshError> {
+        for _ in 0..self.read(count)? as usize {
+            self.read(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn skip_element(&mut self, element: &Element) -> Result<(), MeshError> {
+        for _ in 0..element.count {
+            for (_, property) in &element.properties {
+                match *property {
+                    Property::Scalar(scalar) => {
+                        self.read(scalar)?;
+                    }
+                    Property::List(count, item) => self.skip_list(count, item)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn error(message: &str) -> MeshError {
+    MeshError::Parse(format!("PLY: {}", message))
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+/// Runs a loader on a background thread so scans are ready by the time they
+/// are shown
+///
+/// Paths are loaded in the order they are requested. Dropping a `Prefetcher`
+/// does not wait for a load in progress
+pub struct Prefetcher<T> {
+    start: Box<dyn Fn() -> Worker<T>>,
+    worker: Worker<T>,
+    pending: VecDeque<String>,
+}
+
+struct Worker<T> {
+    requests: Sender<String>,
+    results: Receiver<(String, T)>,
+}
+
+impl<T: Send + 'static> Prefetcher<T> {
+    /// `init` runs on each background thread, e.g. to make a GL context
+    /// current there, and returns the loader. A new thread is started after
+    /// [`Prefetcher::cancel`]
+    pub fn new<I, F>(init: I) -> Self
+    where
+        I: Fn() -> F + Send + Sync + 'static,
+        F: FnMut(&str) -> T,
+    {
+        let init = Arc::new(init);
+        let start = move || spawn_worker(Arc::clone(&init));
+
+        Self {
+            worker: start(),
+            start: Box::new(start),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Start loading `path` once earlier requests are done
+    pub fn request(&mut self, path: &str) {
+        if self.worker.requests.send(path.to_owned()).is_ok() {
+            self.pending.push_back(path.to_owned());
+        }
+    }
+
+    pub fn is_pending(&self, path: &str) -> bool {
+        self.pending.iter().any(|pending| pending == path)
+    }
+
+    /// Wait for `path` to finish loading, discarding anything requested
+    /// before it
+    ///
+    /// Returns `None` if `path` was never requested, or if the loader
+    /// panicked
+    pub fn take(&mut self, path: &str) -> Option<T> {
+        if !self.is_pending(path) {
+            return None;
+        }
+
+        while self.pending.pop_front().is_some() {
+            let (loaded, result) = self.worker.results.recv().ok()?;
+
+            if loaded == path {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+
+    /// Like [`Prefetcher::take`], but returns `None` immediately if `path`
+    /// is still loading
+    pub fn try_take(&mut self, path: &str) -> Option<T> {
+        while self.is_pending(path) {
+            let (loaded, result) = match self.worker.results.try_recv() {
+                Ok(loaded) => loaded,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.pending.clear();
+                    return None;
+                }
+            };
+
+            self.pending.pop_front();
+
+            if loaded == path {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+
+    /// Abandon every request. A load in progress cannot be interrupted, so it
+    /// is left to finish on its own and its result is dropped; later requests
+    /// are handled by a new thread
+    pub fn cancel(&mut self) {
+        self.worker = (self.start)();
+        self.pending.clear();
+    }
+}
+
+fn spawn_worker<T, I, F>(init: Arc<I>) -> Worker<T>
+where
+    T: Send + 'static,
+    I: Fn() -> F + Send + Sync + 'static,
+    F: FnMut(&str) -> T,
+{
+    let (requests, request_receiver) = mpsc::channel::<String>();
+    let (result_sender, results) = mpsc::channel();
+
+    thread::Builder::new()
+        .name("prefetch".to_owned())
+        .spawn(move || {
+            let mut load = init();
+
+            for path in request_receiver {
+                let result = load(&path);
+
+                if result_sender.send((path, result)).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("unable to spawn prefetch thread");
+
+    Worker { requests, results }
+}
@@ -0,0 +1,203 @@
+//! Simple meshes for markers and gizmos drawn on top of scans
+//!
+//! Every primitive is centered on the origin and fits within `[-1, 1]` on
+//! each axis, so it can be placed with a single scale and translation
+
+use std::f32::consts::TAU;
+
+use nalgebra::Vector3;
+
+use crate::mesh::{Mesh, VERTEX_STRIDE};
+
+#[derive(Debug, Default)]
+struct MeshBuilder {
+    vertices: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    fn vertex(&mut self, position: Vector3<f32>, normal: Vector3<f32>) -> u32 {
+        let idx = (self.vertices.len() / VERTEX_STRIDE) as u32;
+
+        self.vertices.extend_from_slice(position.as_slice());
+        self.vertices.extend_from_slice(normal.as_slice());
+
+        idx
+    }
+
+    /// Counterclockwise when viewed from the front
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    /// A flat disc of `radius` at height `z`, facing `+z` if `up`
+    fn disc(&mut self, radius: f32, z: f32, segments: u32, up: bool) {
+        let normal = Vector3::new(0.0, 0.0, if up { 1.0 } else { -1.0 });
+
+        let center = self.vertex(Vector3::new(0.0, 0.0, z), normal);
+        self.vertex(Vector3::new(radius, 0.0, z), normal);
+
+        for segment in 1..=segments {
+            let (x, y) = circle(segment, segments);
+            let current = self.vertex(Vector3::new(x * radius, y * radius, z), normal);
+
+            if up {
+                self.triangle(center, current - 1, current);
+            } else {
+                self.triangle(center, current, current - 1);
+            }
+        }
+    }
+
+    /// The side of a cone frustum around the z axis, from `(bottom_radius,
+    /// bottom)` to `(top_radius, top)`, with smooth normals
+    fn frustum(
+        &mut self,
+        bottom_radius: f32,
+        bottom: f32,
+        top_radius: f32,
+        top: f32,
+        segments: u32,
+    ) {
+        let slope = (bottom_radius - top_radius) / (top - bottom);
+        let start = (self.vertices.len() / VERTEX_STRIDE) as u32;
+
+        for segment in 0..=segments {
+            let (x, y) = circle(segment, segments);
+            let normal = Vector3::new(x, y, slope).normalize();
+
+            self.vertex(
+                Vector3::new(x * bottom_radius, y * bottom_radius, bottom),
+                normal,
+            );
+            self.vertex(Vector3::new(x * top_radius, y * top_radius, top), normal);
+        }
+
+        for segment in 0..segments {
+            let bottom_left = start + segment * 2;
+
+            self.quad(
+                bottom_left,
+                bottom_left + 2,
+                bottom_left + 3,
+                bottom_left + 1,
+            );
+        }
+    }
+
+    fn build(self) -> Mesh {
+        Mesh::new(self.vertices, self.indices)
+    }
+}
+
+/// The point at `segment` of `segments` around the unit circle
+fn circle(segment: u32, segments: u32) -> (f32, f32) {
+    let angle = segment as f32 / segments as f32 * TAU;
+
+    (angle.cos(), angle.sin())
+}
+
+/// A UV sphere of radius 1. `rings` and `segments` are clamped to at least
+/// 2 and 3 respectively
+pub fn sphere(rings: u32, segments: u32) -> Mesh {
+    let rings = rings.max(2);
+    let segments = segments.max(3);
+
+    let mut builder = MeshBuilder::default();
+
+    for ring in 0..=rings {
+        let polar = ring as f32 / rings as f32 * std::f32::consts::PI;
+
+        for segment in 0..=segments {
+            let (x, y) = circle(segment, segments);
+            let position = Vector3::new(x * polar.sin(), y * polar.sin(), polar.cos());
+
+            builder.vertex(position, position);
+        }
+    }
+
+    let row = segments + 1;
+
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let top = ring * row + segment;
+            let bottom = top + row;
+
+            builder.quad(top, bottom, bottom + 1, top + 1);
+        }
+    }
+
+    builder.build()
+}
+
+/// An axis-aligned cube from `-1` to `1`, with flat normals
+pub fn cube() -> Mesh {
+    let mut builder = MeshBuilder::default();
+
+    for axis in 0..3 {
+        for &sign in &[1.0_f32, -1.0] {
+            let mut normal = Vector3::zeros();
+            normal[axis] = sign;
+
+            // Two axes spanning the face, ordered so the winding faces out
+            let mut u = Vector3::zeros();
+            let mut v = Vector3::zeros();
+            u[(axis + 1) % 3] = sign;
+            v[(axis + 2) % 3] = 1.0;
+
+            let corners = [
+                normal - u - v,
+                normal + u - v,
+                normal + u + v,
+                normal - u + v,
+            ];
+
+            let [a, b, c, d] = [
+                builder.vertex(corners[0], normal),
+                builder.vertex(corners[1], normal),
+                builder.vertex(corners[2], normal),
+                builder.vertex(corners[3], normal),
+            ];
+
+            builder.quad(a, b, c, d);
+        }
+    }
+
+    builder.build()
+}
+
+/// A closed disc of radius 1 in the xy plane, facing `+z`, with a back face
+/// so it is visible from either side
+pub fn disc(segments: u32) -> Mesh {
+    let segments = segments.max(3);
+
+    let mut builder = MeshBuilder::default();
+
+    builder.disc(1.0, 0.0, segments, true);
+    builder.disc(1.0, 0.0, segments, false);
+
+    builder.build()
+}
+
+/// An arrow from `z = -1` to `z = 1`: a shaft with a cone for its head
+pub fn arrow(segments: u32) -> Mesh {
+    const SHAFT_RADIUS: f32 = 0.08;
+    const HEAD_RADIUS: f32 = 0.2;
+    const HEAD_START: f32 = 0.5;
+
+    let segments = segments.max(3);
+
+    let mut builder = MeshBuilder::default();
+
+    builder.disc(SHAFT_RADIUS, -1.0, segments, false);
+    builder.frustum(SHAFT_RADIUS, -1.0, SHAFT_RADIUS, HEAD_START, segments);
+    builder.disc(HEAD_RADIUS, HEAD_START, segments, false);
+    builder.frustum(HEAD_RADIUS, HEAD_START, 0.0, 1.0, segments);
+
+    builder.build()
+}
@@ -0,0 +1,85 @@
+//! Python bindings, built as the `scan_classification` extension module with
+//! `maturin build --features python`
+
+use std::collections::BTreeMap;
+
+use pyo3::{exceptions::PyValueError, prelude::*, wrap_pyfunction};
+
+use crate::{
+    categories::{Categories, CATEGORIES_PATH},
+    labels::{self, DatasetSplit, LabelRecord, SessionReport},
+    queue,
+};
+
+/// The categories of `labels.toml` in the working directory, or the
+/// defaults, which label files are matched to by their path
+fn categories() -> PyResult<Categories> {
+    Categories::load(CATEGORIES_PATH).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn into_tuples(records: Vec<LabelRecord>) -> Vec<(String, String)> {
+    records
+        .into_iter()
+        .map(|record| (record.path, record.label))
+        .collect()
+}
+
+/// build_queue(root)
+/// --
+///
+/// All scans below `root`, in the order they would be labeled
+#[pyfunction]
+fn build_queue(root: &str) -> PyResult<Vec<String>> {
+    Ok(queue::build_queue(root)?)
+}
+
+/// parse_label_file(path)
+/// --
+///
+/// `(scan_path, label)` pairs from a single label file, labeled with the
+/// name of its category in `labels.toml`
+#[pyfunction]
+fn parse_label_file(path: &str) -> PyResult<Vec<(String, String)>> {
+    Ok(into_tuples(labels::parse_label_file(path, &categories()?)?))
+}
+
+/// session_report(label_files)
+/// --
+///
+/// Number of scans assigned to each label across `label_files`
+#[pyfunction]
+fn session_report(label_files: Vec<String>) -> PyResult<BTreeMap<String, usize>> {
+    let records = labels::parse_label_files(&label_files, &categories()?)?;
+
+    Ok(SessionReport::new(&records).counts)
+}
+
+/// export_split(label_files, out_dir, validation, test)
+/// --
+///
+/// Write train/validation/test CSVs for `label_files` to `out_dir`, returning
+/// the number of scans in each split
+#[pyfunction]
+fn export_split(
+    label_files: Vec<String>,
+    out_dir: &str,
+    validation: f64,
+    test: f64,
+) -> PyResult<(usize, usize, usize)> {
+    let records = labels::parse_label_files(&label_files, &categories()?)?;
+    let split = DatasetSplit::new(&records, validation, test);
+
+    split.export(out_dir)?;
+
+    Ok((split.train.len(), split.validation.len(), split.test.len()))
+}
+
+#[pymodule]
+fn scan_classification(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(build_queue, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_label_file, m)?)?;
+    m.add_function(wrap_pyfunction!(session_report, m)?)?;
+    m.add_function(wrap_pyfunction!(export_split, m)?)?;
+
+    Ok(())
+}
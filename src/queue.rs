@@ -0,0 +1,120 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{compression::strip_compressed_extension, format::ScanFormat};
+
+/// File extensions, compared case-insensitively, that are picked up when
+/// building a queue from a directory
+pub const SCAN_EXTENSIONS: &[&str] = &["stl", "obj", "ply", "glb", "3mf"];
+
+/// Also true of compressed scans, e.g. `scan.stl.gz`
+pub fn is_scan_path(path: &Path) -> bool {
+    let name = match path.to_str() {
+        Some(name) => strip_compressed_extension(name),
+        None => return false,
+    };
+
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| {
+            SCAN_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Recursively collect every scan below `root`, sorted so that the same
+/// directory always produces the same queue
+///
+/// Files with a scan extension are always included; other files are
+/// included if their contents are recognized as a supported format, so
+/// scans with a missing or wrong extension are not overlooked
+///
+/// If `root` is a file it is returned as a single-entry queue
+pub fn build_queue(root: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    let root = root.as_ref();
+    let mut queue = Vec::new();
+
+    if root.is_file() {
+        queue.push(root.to_string_lossy().into_owned());
+        return Ok(queue);
+    }
+
+    visit(root, &mut queue)?;
+
+    queue.sort();
+
+    Ok(queue)
+}
+
+/// Whether a file without a scan extension looks like a scan anyway.
+/// Unreadable files are skipped rather than failing the whole queue
+fn is_unlabeled_scan(path: &Path) -> bool {
+    match ScanFormat::sniff_file(path) {
+        Ok(format) => format.map_or(false, ScanFormat::is_supported),
+        Err(..) => false,
+    }
+}
+
+fn visit(dir: &Path, queue: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            visit(&path, queue)?;
+        } else if is_scan_path(&path) || is_unlabeled_scan(&path) {
+            queue.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// A file of scan paths, one per line, that may grow during a session with
+/// scans to label before the rest of the queue
+///
+/// Each path is only returned once, however often the file is polled
+#[derive(Debug, Clone)]
+pub struct UrgentQueue {
+    path: PathBuf,
+    seen: HashSet<String>,
+}
+
+impl UrgentQueue {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Paths added to the file since the last poll, in the order they are
+    /// listed. A missing file is treated as empty
+    pub fn poll(&mut self) -> io::Result<Vec<String>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut added = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+
+            if !line.is_empty() && self.seen.insert(line.to_owned()) {
+                added.push(line.to_owned());
+            }
+        }
+
+        Ok(added)
+    }
+}
@@ -0,0 +1,229 @@
+//! A rough surface for point clouds, so raw scanner output can be looked at
+//! and labeled like any other scan
+//!
+//! This is a preview, not a watertight reconstruction. Each point's nearest
+//! neighbors are found on a uniform grid, a normal is fitted to them and
+//! turned to face away from the middle of the cloud, and the neighbors are
+//! joined into a fan around the point in the order they lie about it. Gaps
+//! wider than [`MAX_FAN_GAP`] are left open, so the edges of the scan and
+//! holes in it stay visible instead of being bridged. Triangles found from
+//! more than one of their corners are kept once
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+};
+
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+
+use crate::mesh::{BoundingBox, Mesh, VERTEX_STRIDE};
+
+/// Neighbors each point is joined to
+const NEIGHBORS: usize = 8;
+
+/// Grid cells are sized so a surface sampled evenly would have about this
+/// many points in each
+const POINTS_PER_CELL: f32 = 4.0;
+
+/// Widest angle between consecutive neighbors around a point that is still
+/// closed with a triangle
+const MAX_FAN_GAP: f32 = PI / 2.0;
+
+/// Whether `mesh` is a point cloud: vertices without triangles
+pub fn is_point_cloud(mesh: &Mesh) -> bool {
+    mesh.is_empty() && mesh.vertex_count() >= 3
+}
+
+/// Triangulate the vertices of `mesh`, ignoring any triangles it has
+pub fn reconstruct(mesh: &Mesh) -> Mesh {
+    let points: Vec<Vector3<f32>> = (0..mesh.vertex_count() as u32)
+        .map(|idx| mesh.position(idx))
+        .collect();
+
+    let grid = Grid::new(&points, mesh.bounding_box());
+    let center = mesh.bounding_box().center();
+
+    let mut normals = vec![Vector3::zeros(); points.len()];
+    let mut triangles = HashSet::new();
+
+    for (idx, &point) in points.iter().enumerate() {
+        let neighbors = grid.nearest(&points, idx, NEIGHBORS);
+
+        let normal = match fit_normal(point, neighbors.iter().map(|&other| points[other])) {
+            Some(normal) if normal.dot(&(point - center)) < 0.0 => -normal,
+            Some(normal) => normal,
+            None => continue,
+        };
+        normals[idx] = normal;
+
+        for [a, b] in fan(&points, point, normal, &neighbors) {
+            let mut key = [idx as u32, a as u32, b as u32];
+            key.sort_unstable();
+            triangles.insert(key);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(triangles.len() * 3);
+
+    for key in triangles {
+        let [a, b, c] = [key[0] as usize, key[1] as usize, key[2] as usize];
+        let winding = (points[b] - points[a]).cross(&(points[c] - points[a]));
+        // Wound to face the same way as the normals at its corners
+        let facing = normals[a] + normals[b] + normals[c];
+
+        if winding.dot(&facing) < 0.0 {
+            indices.extend_from_slice(&[key[0], key[2], key[1]]);
+        } else {
+            indices.extend_from_slice(&key);
+        }
+    }
+
+    let mut vertices = Vec::with_capacity(points.len() * VERTEX_STRIDE);
+
+    for (point, normal) in points.iter().zip(&normals) {
+        vertices.extend(point.iter().chain(normal.iter()));
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+/// The unit normal of the plane through `point` closest to `neighbors`, or
+/// `None` if they do not span a plane
+fn fit_normal(
+    point: Vector3<f32>,
+    neighbors: impl Iterator<Item = Vector3<f32>>,
+) -> Option<Vector3<f32>> {
+    let mut count = 0;
+    let covariance = neighbors.fold(Matrix3::zeros(), |sum, neighbor| {
+        count += 1;
+        let offset = neighbor - point;
+        sum + offset * offset.transpose()
+    });
+
+    if count < 2 {
+        return None;
+    }
+
+    let eigen = SymmetricEigen::new(covariance);
+    let smallest = (0..3).min_by(|&a, &b| {
+        eigen.eigenvalues[a]
+            .partial_cmp(&eigen.eigenvalues[b])
+            .unwrap_or(Ordering::Equal)
+    })?;
+
+    eigen
+        .eigenvectors
+        .column(smallest)
+        .into_owned()
+        .try_normalize(f32::EPSILON)
+}
+
+/// Pairs of `neighbors` consecutive about `point`, counterclockwise seen
+/// from the side `normal` points to, that are close enough in angle to join
+fn fan(
+    points: &[Vector3<f32>],
+    point: Vector3<f32>,
+    normal: Vector3<f32>,
+    neighbors: &[usize],
+) -> Vec<[usize; 2]> {
+    // Any direction not parallel to the normal gives a tangent basis
+    let reference = if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let u = normal.cross(&reference).normalize();
+    let v = normal.cross(&u);
+
+    let mut around: Vec<(f32, usize)> = neighbors
+        .iter()
+        .map(|&other| {
+            let offset = points[other] - point;
+            (offset.dot(&v).atan2(offset.dot(&u)), other)
+        })
+        .collect();
+    around.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    let mut pairs = Vec::with_capacity(around.len());
+
+    for (idx, &(angle, other)) in around.iter().enumerate() {
+        let (next_angle, next) = around[(idx + 1) % around.len()];
+        let mut gap = next_angle - angle;
+
+        if gap <= 0.0 {
+            gap += 2.0 * PI;
+        }
+
+        if around.len() > 1 && gap <= MAX_FAN_GAP {
+            pairs.push([other, next]);
+        }
+    }
+
+    pairs
+}
+
+/// Points bucketed into cubes, for finding each one's neighbors
+struct Grid {
+    cell: f32,
+    origin: Vector3<f32>,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl Grid {
+    fn new(points: &[Vector3<f32>], bbox: BoundingBox) -> Self {
+        // A surface of n evenly spaced points spans about n * spacing²
+        let spacing = bbox.delta().norm() / (points.len() as f32).sqrt();
+        let cell = (spacing * POINTS_PER_CELL.sqrt()).max(f32::EPSILON);
+
+        let mut grid = Self {
+            cell,
+            origin: bbox.min,
+            cells: HashMap::new(),
+        };
+
+        for (idx, &point) in points.iter().enumerate() {
+            let key = grid.key(point);
+            grid.cells.entry(key).or_insert_with(Vec::new).push(idx);
+        }
+
+        grid
+    }
+
+    fn key(&self, point: Vector3<f32>) -> [i32; 3] {
+        let offset = (point - self.origin) / self.cell;
+
+        [
+            offset.x.floor() as i32,
+            offset.y.floor() as i32,
+            offset.z.floor() as i32,
+        ]
+    }
+
+    /// Up to `count` of the points nearest to `points[idx]`, from its cell
+    /// and the cells around it, nearest first
+    fn nearest(&self, points: &[Vector3<f32>], idx: usize, count: usize) -> Vec<usize> {
+        let point = points[idx];
+        let [x, y, z] = self.key(point);
+        let mut candidates = Vec::new();
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(cell) = self.cells.get(&[x + dx, y + dy, z + dz]) {
+                        candidates.extend(
+                            cell.iter()
+                                .filter(|&&other| other != idx)
+                                .map(|&other| ((points[other] - point).norm_squared(), other)),
+                        );
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        candidates.truncate(count);
+
+        candidates.into_iter().map(|(_, other)| other).collect()
+    }
+}
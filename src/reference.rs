@@ -0,0 +1,81 @@
+//! Lining a reference template, such as an ideal arch, up with a scan
+//!
+//! Both are reduced to their centroid and principal axes, the directions
+//! their vertices spread along the most, and the template is turned and
+//! moved so its axes land on the scan's. Each axis is pointed toward the
+//! side its vertices are skewed to, so the template is not flipped end for
+//! end. The template is never scaled; its size is part of what the scan is
+//! judged against
+
+use std::cmp::Ordering;
+
+use nalgebra::{Matrix3, Matrix4, SymmetricEigen, Vector3};
+
+/// Where a set of points is centered and how it is oriented
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrincipalFrame {
+    pub centroid: Vector3<f32>,
+    /// Columns are the principal axes, from the longest spread to the
+    /// shortest, forming a right-handed basis
+    pub axes: Matrix3<f32>,
+}
+
+impl PrincipalFrame {
+    /// `None` for fewer than 3 points, or points all on one line
+    pub fn new(points: &[Vector3<f32>]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        let centroid = points.iter().sum::<Vector3<f32>>() / points.len() as f32;
+
+        let covariance = points.iter().fold(Matrix3::zeros(), |sum, point| {
+            let offset = point - centroid;
+            sum + offset * offset.transpose()
+        }) / points.len() as f32;
+
+        let eigen = SymmetricEigen::new(covariance);
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| {
+            eigen.eigenvalues[b]
+                .partial_cmp(&eigen.eigenvalues[a])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        if eigen.eigenvalues[order[1]] <= f32::EPSILON {
+            return None;
+        }
+
+        let skewed = |axis: Vector3<f32>| {
+            let skew: f32 = points
+                .iter()
+                .map(|point| (point - centroid).dot(&axis).powi(3))
+                .sum();
+
+            if skew < 0.0 {
+                -axis
+            } else {
+                axis
+            }
+        };
+
+        let first = skewed(eigen.eigenvectors.column(order[0]).into_owned());
+        let second = skewed(eigen.eigenvectors.column(order[1]).into_owned());
+        // The shortest spread is usually the thinnest and least skewed, so it
+        // is left to the others
+        let third = first.cross(&second);
+
+        Some(Self {
+            centroid,
+            axes: Matrix3::from_columns(&[first, second, third]),
+        })
+    }
+
+    /// The rigid transform taking points in this frame onto `target`
+    pub fn onto(&self, target: &Self) -> Matrix4<f32> {
+        let rotation = target.axes * self.axes.transpose();
+        let translation = target.centroid - rotation * self.centroid;
+
+        Matrix4::new_translation(&translation) * rotation.to_homogeneous()
+    }
+}
@@ -1,24 +1,445 @@
-use crate::{buffer::IndexBuffer, check, shader::Material, vertex_array::VertexArray};
+use std::cell::Cell;
 
-pub struct Renderer {}
+use nalgebra::Matrix4;
+
+use crate::{
+    buffer::{BufferElementType, IndexBuffer, VertexBuffer, VertexBufferLayout},
+    camera::StationaryCamera,
+    check,
+    color::Color,
+    mesh::{BoundingBox, Mesh},
+    shader::{Material, Shader, Uniform},
+    vertex_array::VertexArray,
+    vertex_format::{NormalFormat, PositionFormat, VertexFormat},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub color: Color,
+}
+
+impl Light {
+    pub const fn white() -> Self {
+        Light {
+            color: Color {
+                red: 1.0,
+                green: 1.0,
+                blue: 1.0,
+            },
+        }
+    }
+}
+
+/// The attribute layout of vertices encoded in `format`
+fn scan_layout(format: VertexFormat) -> VertexBufferLayout {
+    let mut layout = VertexBufferLayout::new();
+
+    match format.positions {
+        PositionFormat::Float => layout.push(BufferElementType::Float, 3, false),
+        PositionFormat::Half => layout.push(BufferElementType::HalfFloat, 4, false),
+    }
+
+    match format.normals {
+        NormalFormat::Float => layout.push(BufferElementType::Float, 3, false),
+        NormalFormat::Half => layout.push(BufferElementType::HalfFloat, 4, false),
+        NormalFormat::Packed => layout.push(BufferElementType::Int2101010Rev, 4, true),
+    }
+
+    layout
+}
+
+/// GPU-side buffers for a single loaded scan
+#[derive(Debug)]
+pub struct BufferContext {
+    pub bbox: BoundingBox,
+    pub ib: IndexBuffer,
+    pub va: VertexArray,
+    /// Takes the stored positions to model space, see
+    /// [`VertexFormat::encode`]
+    pub dequantize: Matrix4<f32>,
+}
+
+impl BufferContext {
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::with_format(mesh, VertexFormat::default())
+    }
+
+    /// Upload `mesh` with its vertices encoded in `format`
+    pub fn with_format(mesh: &Mesh, format: VertexFormat) -> Self {
+        let encoded = format.encode(mesh);
+
+        let mut va = VertexArray::new();
+        let vb = VertexBuffer::new(&encoded.data);
+        va.add_buffer(&vb, &scan_layout(format));
+
+        let ib = IndexBuffer::new(mesh.indices());
+
+        ib.unbind();
+        va.unbind();
+        vb.unbind();
+
+        Self {
+            va,
+            ib,
+            bbox: mesh.bounding_box(),
+            dequantize: encoded.dequantize,
+        }
+    }
+
+    /// Finish setting up buffers uploaded ahead of time. Vertex arrays
+    /// cannot be shared between contexts, so this must be called on the
+    /// context that will draw
+    pub fn from_uploaded(uploaded: UploadedMesh) -> Self {
+        let mut va = VertexArray::new();
+        va.add_buffer(&uploaded.vb, &scan_layout(uploaded.format));
+
+        uploaded.ib.bind();
+
+        va.unbind();
+        uploaded.vb.unbind();
+
+        Self {
+            va,
+            ib: uploaded.ib,
+            bbox: uploaded.bbox,
+            dequantize: uploaded.dequantize,
+        }
+    }
+}
+
+/// Vertex and index buffers for a scan, uploaded ahead of time, possibly on
+/// another thread with a context sharing objects with the window's
+///
+/// See [`create_shared_context`](crate::create_shared_context)
+#[derive(Debug)]
+pub struct UploadedMesh {
+    vb: VertexBuffer,
+    ib: IndexBuffer,
+    bbox: BoundingBox,
+    format: VertexFormat,
+    dequantize: Matrix4<f32>,
+}
+
+impl UploadedMesh {
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::with_format(mesh, VertexFormat::default())
+    }
+
+    /// Upload `mesh` with its vertices encoded in `format`
+    pub fn with_format(mesh: &Mesh, format: VertexFormat) -> Self {
+        let encoded = format.encode(mesh);
+
+        let vb = VertexBuffer::new(&encoded.data);
+        vb.unbind();
+
+        let ib = IndexBuffer::new(mesh.indices());
+        ib.unbind();
+
+        // Other contexts may only use the buffers once the upload completes
+        check!(unsafe { gl::Finish() });
+
+        Self {
+            vb,
+            ib,
+            bbox: mesh.bounding_box(),
+            format,
+            dequantize: encoded.dequantize,
+        }
+    }
+}
+
+/// Placement and color of one copy of an [`InstancedMesh`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    pub model: Matrix4<f32>,
+    pub color: Color,
+}
+
+impl Instance {
+    /// Number of `f32`s per instance: a column-major matrix followed by a color
+    const STRIDE: usize = 16 + 3;
+}
+
+/// A mesh drawn many times in a single call, e.g. landmark markers, with a
+/// model matrix and color per copy
+///
+/// Draw with [`Renderer::draw_instanced`] and [`Shader::instanced`]
+#[derive(Debug)]
+pub struct InstancedMesh {
+    pub ib: IndexBuffer,
+    pub va: VertexArray,
+    // Kept alive for the VAO, which refers to it
+    _vb: VertexBuffer,
+    instances: VertexBuffer,
+    instance_count: u32,
+}
+
+impl InstancedMesh {
+    pub fn new(mesh: &Mesh) -> Self {
+        let mut va = VertexArray::new();
+        let vb = VertexBuffer::new(mesh.vertices());
+        let mut layout = VertexBufferLayout::new();
+
+        layout.push(BufferElementType::Float, 3, false);
+        layout.push(BufferElementType::Float, 3, false);
+        va.add_buffer(&vb, &layout);
+
+        let instances = VertexBuffer::dynamic(&[]);
+        let mut instance_layout = VertexBufferLayout::new();
+
+        // One attribute per matrix column, then the color
+        for _ in 0..4 {
+            instance_layout.push(BufferElementType::Float, 4, false);
+        }
+        instance_layout.push(BufferElementType::Float, 3, false);
+        va.add_instance_buffer(&instances, &instance_layout, 2);
+
+        let ib = IndexBuffer::new(mesh.indices());
+
+        ib.unbind();
+        va.unbind();
+        vb.unbind();
+
+        Self {
+            ib,
+            va,
+            _vb: vb,
+            instances,
+            instance_count: 0,
+        }
+    }
+
+    /// Replace every instance
+    pub fn set_instances(&mut self, instances: &[Instance]) {
+        let mut data = Vec::with_capacity(instances.len() * Instance::STRIDE);
+
+        for instance in instances {
+            data.extend_from_slice(instance.model.as_slice());
+            data.extend_from_slice(&[
+                instance.color.red,
+                instance.color.green,
+                instance.color.blue,
+            ]);
+        }
+
+        self.instances.update(&data);
+        self.instances.unbind();
+
+        self.instance_count = instances.len() as u32;
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+}
+
+/// Work submitted by a renderer since its counters were last taken, so
+/// batching and sorting can be checked by numbers rather than by eye
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    /// Triangles submitted, counting each instance
+    pub triangles: u64,
+    /// Shader programs bound. Draws reusing the program left bound by the
+    /// draw before do not bind it again
+    pub program_changes: u32,
+    /// Vertex arrays bound, likewise
+    pub vertex_array_changes: u32,
+    pub uniform_uploads: u32,
+}
+
+impl RenderStats {
+    /// Program and vertex array changes, the switches sorting draws avoids
+    pub fn state_changes(&self) -> u32 {
+        self.program_changes + self.vertex_array_changes
+    }
+}
+
+/// One draw in a [`DrawList`]
+pub struct DrawCommand<'a> {
+    /// Lower layers are drawn first, whatever their state, e.g. so overlays
+    /// land on top. Within a layer draws are reordered to share state
+    pub layer: u32,
+    /// Index of the shader in the slice given to [`Renderer::draw_list`]
+    pub shader: usize,
+    /// Identifies the material within its shader. `material_uniforms` are
+    /// only uploaded when the material changes
+    pub material: u32,
+    pub material_uniforms: &'a [Uniform<'a>],
+    /// Uploaded for every draw, e.g. the model matrix
+    pub uniforms: &'a [Uniform<'a>],
+    pub va: &'a VertexArray,
+    pub ib: &'a IndexBuffer,
+}
+
+/// Draws collected over a frame and submitted together, so those sharing a
+/// shader, material, or vertex array run back to back
+#[derive(Default)]
+pub struct DrawList<'a> {
+    commands: Vec<DrawCommand<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: DrawCommand<'a>) {
+        self.commands.push(command);
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// By layer, then shader, then material, then vertex array. The sort is
+    /// stable, so otherwise equal draws keep the order they were pushed in
+    fn sort(&mut self) {
+        self.commands.sort_by_key(|command| {
+            (
+                command.layer,
+                command.shader,
+                command.material,
+                command.va.id(),
+            )
+        });
+    }
+}
+
+/// The per-draw uniforms of the shader from [`Renderer::scan_shader`] for
+/// one buffer context
+#[derive(Debug, Clone, Copy)]
+pub struct ScanUniforms {
+    model: Matrix4<f32>,
+    view: Matrix4<f32>,
+    projection: Matrix4<f32>,
+    light_pos: [f32; 3],
+}
+
+impl ScanUniforms {
+    pub fn new(
+        buffer_context: &BufferContext,
+        stationary: &StationaryCamera,
+        model: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+    ) -> Self {
+        Self {
+            model: model * buffer_context.dequantize,
+            view: stationary.view(buffer_context.bbox),
+            projection: *projection,
+            light_pos: stationary.pos(buffer_context.bbox),
+        }
+    }
+
+    pub fn uniforms(&self) -> [Uniform; 4] {
+        [
+            Uniform::MatrixFourFv {
+                name: "model",
+                matrix: &self.model,
+            },
+            Uniform::MatrixFourFv {
+                name: "view",
+                matrix: &self.view,
+            },
+            Uniform::MatrixFourFv {
+                name: "projection",
+                matrix: &self.projection,
+            },
+            Uniform::ThreeFloat {
+                name: "light_pos",
+                v0: self.light_pos[0],
+                v1: self.light_pos[1],
+                v2: self.light_pos[2],
+            },
+        ]
+    }
+}
+
+pub struct Renderer {
+    stats: Cell<RenderStats>,
+    /// Program and vertex array left bound by the last draw, or 0 if unknown
+    bound: Cell<(u32, u32)>,
+}
 
 impl Renderer {
+    /// Requires a current GL context with loaded function pointers
     pub fn new() -> Self {
-        Self {}
+        check!(unsafe { gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) });
+        check!(unsafe { gl::Enable(gl::BLEND) });
+        check!(unsafe { gl::Enable(gl::DEPTH_TEST) });
+        check!(unsafe { gl::Enable(gl::MULTISAMPLE) });
+
+        Self {
+            stats: Cell::new(RenderStats::default()),
+            bound: Cell::new((0, 0)),
+        }
+    }
+
+    /// The counters accumulated since the last call, which resets them. Call
+    /// once per frame
+    pub fn take_stats(&self) -> RenderStats {
+        self.stats.take()
+    }
+
+    /// Forget which program and vertex array are bound, so the next draw
+    /// binds both. Call whenever code outside the renderer may have bound
+    /// others, e.g. at the start of a frame
+    pub fn invalidate_bindings(&self) {
+        self.bound.set((0, 0));
+    }
+
+    /// Bind `shader` and `va` unless the last draw left them bound
+    fn bind(&self, shader: &Shader, va: &VertexArray, stats: &mut RenderStats) {
+        let (program, vertex_array) = self.bound.get();
+
+        if shader.id() != program {
+            shader.bind();
+            stats.program_changes += 1;
+        }
+
+        if va.id() != vertex_array {
+            va.bind();
+            stats.vertex_array_changes += 1;
+        }
+
+        self.bound.set((shader.id(), va.id()));
+    }
+
+    fn upload(shader: &mut Shader, uniforms: &[Uniform], stats: &mut RenderStats) {
+        for uniform in uniforms {
+            shader.set_uniform(uniform);
+        }
+
+        stats.uniform_uploads += uniforms.len() as u32;
     }
 
     pub fn clear(&self) {
+        self.clear_color(Color {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+        });
+    }
+
+    pub fn clear_color(&self, color: Color) {
         unsafe {
-            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::ClearColor(color.red, color.green, color.blue, 1.0);
 
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
     }
 
     pub fn draw(&self, va: &VertexArray, ib: &IndexBuffer, material: &mut Material) {
-        material.bind();
+        let mut stats = self.stats.get();
+
+        self.bind(material.shader(), va, &mut stats);
+        material.upload_uniforms();
+        stats.uniform_uploads += material.uniforms().len() as u32;
 
-        va.bind();
         ib.bind();
 
         check!(unsafe {
@@ -29,5 +450,198 @@ impl Renderer {
                 std::ptr::null(),
             )
         });
+
+        stats.draw_calls += 1;
+        stats.triangles += ib.count as u64 / 3;
+        self.stats.set(stats);
+    }
+
+    /// Draw every instance of `mesh` with a single `glDrawElementsInstanced`
+    pub fn draw_instanced(&self, mesh: &InstancedMesh, material: &mut Material) {
+        if mesh.instance_count == 0 {
+            return;
+        }
+
+        let mut stats = self.stats.get();
+
+        self.bind(material.shader(), &mesh.va, &mut stats);
+        material.upload_uniforms();
+        stats.uniform_uploads += material.uniforms().len() as u32;
+
+        mesh.ib.bind();
+
+        check!(unsafe {
+            gl::DrawElementsInstanced(
+                gl::TRIANGLES,
+                mesh.ib.count as i32,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+                mesh.instance_count as i32,
+            )
+        });
+
+        stats.draw_calls += 1;
+        stats.triangles += mesh.ib.count as u64 / 3 * mesh.instance_count as u64;
+        self.stats.set(stats);
+    }
+
+    /// Sort `list` to minimize state changes, then draw it. Programs,
+    /// vertex arrays, and material uniforms are only bound or uploaded when
+    /// they differ from the draw before
+    pub fn draw_list(&self, mut list: DrawList, shaders: &mut [&mut Shader]) {
+        list.sort();
+
+        let mut stats = self.stats.get();
+        let mut material = None;
+
+        for command in &list.commands {
+            let shader = &mut *shaders[command.shader];
+
+            self.bind(shader, command.va, &mut stats);
+
+            // Uniforms belong to the program, so a material stays uploaded
+            // until another with the same shader replaces it
+            if material != Some((command.shader, command.material)) {
+                Self::upload(shader, command.material_uniforms, &mut stats);
+                material = Some((command.shader, command.material));
+            }
+
+            Self::upload(shader, command.uniforms, &mut stats);
+
+            command.ib.bind();
+
+            check!(unsafe {
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    command.ib.count as i32,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null(),
+                )
+            });
+
+            stats.draw_calls += 1;
+            stats.triangles += command.ib.count as u64 / 3;
+        }
+
+        self.stats.set(stats);
+    }
+
+    /// Draw a scan with the lit shader created by [`Renderer::scan_shader`]
+    pub fn draw_scan(
+        &self,
+        shader: &mut Shader,
+        buffer_context: &BufferContext,
+        stationary: &StationaryCamera,
+        model: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+    ) {
+        let uniforms = ScanUniforms::new(buffer_context, stationary, model, projection);
+
+        self.draw(
+            &buffer_context.va,
+            &buffer_context.ib,
+            &mut Material::new(shader, &uniforms.uniforms()),
+        );
+    }
+
+    /// Draw a scan at `alpha` over whatever has already been drawn
+    ///
+    /// Blending with the depth test alone would show back faces through
+    /// front faces in an order that depends on triangle order. Instead, a
+    /// depth-only pass first finds the nearest surface of the scan, and the
+    /// color pass then only blends fragments on that surface. Opaque
+    /// geometry must be drawn first
+    pub fn draw_scan_transparent(
+        &self,
+        shader: &mut Shader,
+        buffer_context: &BufferContext,
+        stationary: &StationaryCamera,
+        model: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+        alpha: f32,
+    ) {
+        unsafe {
+            check!(gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE));
+        }
+
+        self.draw_scan(shader, buffer_context, stationary, model, projection);
+
+        unsafe {
+            check!(gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE));
+            check!(gl::DepthMask(gl::FALSE));
+            check!(gl::DepthFunc(gl::LEQUAL));
+        }
+
+        shader.bind();
+        shader.set_uniform(&Uniform::OneFloat {
+            name: "alpha",
+            v0: alpha,
+        });
+
+        self.draw_scan(shader, buffer_context, stationary, model, projection);
+
+        shader.set_uniform(&Uniform::OneFloat {
+            name: "alpha",
+            v0: 1.0,
+        });
+
+        unsafe {
+            check!(gl::DepthMask(gl::TRUE));
+            check!(gl::DepthFunc(gl::LESS));
+        }
+    }
+
+    /// Compile the lit shader used to display scans and set its initial
+    /// uniforms
+    pub fn scan_shader(
+        model: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+        light: &Light,
+        object_color: Color,
+    ) -> Shader {
+        let mut shader = Shader::basic();
+        let uniforms = vec![];
+
+        let mut material = Material::new(&mut shader, &uniforms);
+        material.bind();
+
+        shader.set_uniform(&Uniform::MatrixFourFv {
+            name: "model",
+            matrix: model,
+        });
+        shader.set_uniform(&Uniform::MatrixFourFv {
+            name: "view",
+            matrix: &Matrix4::identity(),
+        });
+        shader.set_uniform(&Uniform::MatrixFourFv {
+            name: "projection",
+            matrix: projection,
+        });
+        shader.set_uniform(&Uniform::ThreeFloat {
+            name: "object_color",
+            v0: object_color.red,
+            v1: object_color.green,
+            v2: object_color.blue,
+        });
+        shader.set_uniform(&Uniform::ThreeFloat {
+            name: "light_color",
+            v0: light.color.red,
+            v1: light.color.green,
+            v2: light.color.blue,
+        });
+        shader.set_uniform(&Uniform::ThreeFloat {
+            name: "light_pos",
+            v0: 0.0,
+            v1: 0.0,
+            v2: 0.0,
+        });
+        shader.set_uniform(&Uniform::OneFloat {
+            name: "alpha",
+            v0: 1.0,
+        });
+
+        shader.unbind();
+
+        shader
     }
 }
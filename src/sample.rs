@@ -0,0 +1,160 @@
+//! Stratified sampling of scans, so a labeling batch is balanced across
+//! predicted classes, clinics, or scanner models rather than dominated by
+//! whichever is most common
+
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+
+use crate::labels::fnv1a;
+
+/// A CSV table with a header row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum SampleError {
+    Io(io::Error),
+    MissingColumn(String),
+}
+
+impl fmt::Display for SampleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read table: {}", e),
+            Self::MissingColumn(column) => write!(f, "table has no `{}` column", column),
+        }
+    }
+}
+
+impl std::error::Error for SampleError {}
+
+impl From<io::Error> for SampleError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl Table {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse comma-separated values, with fields optionally quoted as in
+    /// the CSVs written by [`DatasetSplit::export`](crate::DatasetSplit::export)
+    pub fn parse(contents: &str) -> Self {
+        let mut records = parse_csv(contents).into_iter();
+
+        Self {
+            columns: records.next().unwrap_or_default(),
+            rows: records.collect(),
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Result<usize, SampleError> {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| SampleError::MissingColumn(name.to_owned()))
+    }
+}
+
+/// Scans picked from each group, by the value of the grouping column
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StratifiedSample {
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+impl StratifiedSample {
+    /// Pick up to `per_group` paths from each distinct value of the `by`
+    /// column of `table`
+    ///
+    /// Paths are picked by hashing them with `seed`, so the same table and
+    /// seed always produce the same sample, and a scan picked once stays
+    /// picked as rows are added to its group
+    pub fn new(table: &Table, by: &str, per_group: usize, seed: u64) -> Result<Self, SampleError> {
+        let path_column = table.column("path")?;
+        let group_column = table.column(by)?;
+
+        let mut candidates: BTreeMap<String, Vec<(u64, String)>> = BTreeMap::new();
+
+        for row in &table.rows {
+            let (path, group) = match (row.get(path_column), row.get(group_column)) {
+                (Some(path), Some(group)) if !path.is_empty() => (path, group),
+                _ => continue,
+            };
+
+            let mut key = seed.to_le_bytes().to_vec();
+            key.extend_from_slice(path.as_bytes());
+
+            candidates
+                .entry(group.clone())
+                .or_default()
+                .push((fnv1a(&key), path.clone()));
+        }
+
+        let groups = candidates
+            .into_iter()
+            .map(|(group, mut paths)| {
+                paths.sort();
+                paths.dedup_by(|a, b| a.1 == b.1);
+                paths.truncate(per_group);
+
+                (group, paths.into_iter().map(|(_, path)| path).collect())
+            })
+            .collect();
+
+        Ok(Self { groups })
+    }
+
+    /// Every picked path, alternating between groups so any prefix of the
+    /// queue is balanced too
+    pub fn queue(&self) -> Vec<String> {
+        let longest = self.groups.values().map(Vec::len).max().unwrap_or(0);
+
+        (0..longest)
+            .flat_map(|idx| self.groups.values().filter_map(move |paths| paths.get(idx)))
+            .cloned()
+            .collect()
+    }
+}
+
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+
+                if record.iter().any(|field| !field.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            c => field.push(c),
+        }
+    }
+
+    record.push(field);
+
+    if record.iter().any(|field| !field.is_empty()) {
+        records.push(record);
+    }
+
+    records
+}
@@ -0,0 +1,109 @@
+//! A flat scene graph: nodes with a local transform, an optional parent, and
+//! a visibility flag
+//!
+//! Nodes are never removed, so a [`NodeId`] stays valid for the life of the
+//! scene
+
+use nalgebra::{Matrix4, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    /// Relative to the parent, or to the world for root nodes
+    pub local: Matrix4<f32>,
+    pub visible: bool,
+    parent: Option<NodeId>,
+}
+
+impl Node {
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a visible node. Panics if `parent` belongs to another scene
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        local: Matrix4<f32>,
+        parent: Option<NodeId>,
+    ) -> NodeId {
+        if let Some(NodeId(parent)) = parent {
+            assert!(parent < self.nodes.len(), "parent is not in this scene");
+        }
+
+        self.nodes.push(Node {
+            name: name.into(),
+            local,
+            visible: true,
+            parent,
+        });
+
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .map(NodeId)
+    }
+
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == Some(id))
+            .map(|(idx, _)| NodeId(idx))
+    }
+
+    /// The transform from the node's space to world space, composing every
+    /// ancestor's local transform
+    pub fn world(&self, id: NodeId) -> Matrix4<f32> {
+        let node = self.node(id);
+
+        match node.parent {
+            Some(parent) => self.world(parent) * node.local,
+            None => node.local,
+        }
+    }
+
+    /// Whether the node and all of its ancestors are visible
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        let node = self.node(id);
+
+        node.visible && node.parent.map_or(true, |parent| self.is_visible(parent))
+    }
+
+    pub fn toggle_visible(&mut self, id: NodeId) {
+        let node = self.node_mut(id);
+        node.visible = !node.visible;
+    }
+
+    /// Rotate the node about `axis` in its local space by `angle` radians
+    pub fn rotate(&mut self, id: NodeId, angle: f32, axis: &Vector3<f32>) {
+        let node = self.node_mut(id);
+        node.local = nalgebra_glm::rotate(&node.local, angle, axis);
+    }
+}
@@ -0,0 +1,137 @@
+//! Buffers reused from one scan to the next
+//!
+//! Parsing and analyzing a scan allocates several vectors the size of the
+//! mesh and frees them moments later. Over a long session that churn
+//! fragments the heap and keeps peak memory well above what any one scan
+//! needs, so the prefetch thread keeps these buffers in a [`ScanScratch`]
+//! and hands them back out for the next scan
+
+use std::collections::HashMap;
+
+use crate::mesh::Mesh;
+
+/// How many returned buffers a [`BufferPool`] keeps; more are dropped
+const MAX_RETAINED: usize = 4;
+
+/// Empty vectors of one element type, keeping their capacity between uses
+#[derive(Debug)]
+pub struct BufferPool<T> {
+    free: Vec<Vec<T>>,
+}
+
+impl<T> Default for BufferPool<T> {
+    fn default() -> Self {
+        Self { free: Vec::new() }
+    }
+}
+
+impl<T> BufferPool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty vector with room for at least `capacity` elements. The
+    /// smallest returned buffer that fits is reused; failing that, the
+    /// largest is grown so the pool settles on the size of the biggest scans
+    pub fn take(&mut self, capacity: usize) -> Vec<T> {
+        let fitting = self
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(idx, _)| idx);
+
+        let largest = || {
+            self.free
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, buffer)| buffer.capacity())
+                .map(|(idx, _)| idx)
+        };
+
+        let mut buffer = match fitting.or_else(largest) {
+            Some(idx) => self.free.swap_remove(idx),
+            None => Vec::new(),
+        };
+
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    /// Return `buffer` for a later [`BufferPool::take`]. Its contents are
+    /// dropped, its capacity kept. When the pool is full the smallest buffer
+    /// is freed instead
+    pub fn give(&mut self, mut buffer: Vec<T>) {
+        buffer.clear();
+
+        if buffer.capacity() == 0 {
+            return;
+        }
+
+        self.free.push(buffer);
+
+        if self.free.len() > MAX_RETAINED {
+            let smallest = self
+                .free
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, buffer)| buffer.capacity())
+                .map(|(idx, _)| idx)
+                .unwrap();
+
+            self.free.swap_remove(smallest);
+        }
+    }
+
+    /// Memory held by returned buffers
+    pub fn retained_bytes(&self) -> usize {
+        self.free
+            .iter()
+            .map(|buffer| buffer.capacity() * std::mem::size_of::<T>())
+            .sum()
+    }
+}
+
+/// The transient buffers used while loading a scan
+///
+/// Meshes built with [`Mesh::from_bytes_in`] take their vertex and index
+/// buffers from here, and [`ScanScratch::recycle`] returns them once a mesh
+/// is replaced. Welding reuses the same lookup table and remapping for every
+/// scan, see [`crate::WeldedMesh::new_in`]
+#[derive(Debug, Default)]
+pub struct ScanScratch {
+    /// File contents as read from the source
+    pub contents: Vec<u8>,
+    /// Interleaved vertex data for parsed meshes
+    pub vertices: BufferPool<f32>,
+    /// Index data for parsed meshes
+    pub indices: BufferPool<u32>,
+    /// Welded index of each position, see [`crate::WeldedMesh`]
+    pub(crate) weld_lookup: HashMap<[u32; 3], u32>,
+    /// Welded index of each render vertex
+    pub(crate) weld_remap: Vec<u32>,
+}
+
+impl ScanScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the buffers of a mesh that is no longer needed
+    pub fn recycle(&mut self, mesh: Mesh) {
+        let (vertices, indices) = mesh.into_buffers();
+
+        self.vertices.give(vertices);
+        self.indices.give(indices);
+    }
+
+    /// Memory held between scans
+    pub fn retained_bytes(&self) -> usize {
+        self.contents.capacity()
+            + self.vertices.retained_bytes()
+            + self.indices.retained_bytes()
+            + self.weld_lookup.capacity() * std::mem::size_of::<([u32; 3], u32)>()
+            + self.weld_remap.capacity() * std::mem::size_of::<u32>()
+    }
+}
@@ -0,0 +1,294 @@
+//! Signed distance from a scan's surface, sampled on a coarse grid
+//!
+//! Distances are positive on the side triangles face and negative behind
+//! them, so a closed scan facing outward is negative inside and positive all
+//! around it. One turned inside out reads negative all around instead, and a
+//! hole or non-manifold region shows as the sign flipping across empty space
+//! rather than at the surface. That makes the field objective evidence for
+//! labeling a scan inside out or non-manifold.
+//!
+//! Distances are exact for grid points within a cell of a triangle. Farther
+//! out, each point takes the nearest surface point among those its
+//! neighbors found, which is close enough at this resolution
+
+use nalgebra::Vector3;
+
+use crate::{analysis::WeldedMesh, bite::closest_point, mesh::BoundingBox};
+
+/// Empty cells added around the scan on every side, so there is a border
+/// to read the outside's sign from
+const PADDING: usize = 2;
+
+/// Grid points this many cells from a triangle's bounding box are measured
+/// to it exactly
+const BAND: f32 = 1.0;
+
+/// Rounds of sweeping nearest surface points out from the band. Each is a
+/// pass forward and a pass back through the grid
+const SWEEPS: usize = 2;
+
+/// Signed distances at the corners of a grid of cubes around a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceField {
+    /// Position of grid point `[0, 0, 0]`
+    pub origin: Vector3<f32>,
+    /// Distance between neighboring grid points
+    pub cell: f32,
+    /// Grid points along x, y, and z
+    pub dims: [usize; 3],
+    /// Indexed by x, then y, then z
+    distances: Vec<f32>,
+}
+
+/// One axis-aligned plane of grid points of a [`DistanceField`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSlice {
+    /// The axis the slice is across, 0 for x
+    pub axis: usize,
+    /// Where the slice is along `axis`
+    pub position: f32,
+    /// Grid points along the two other axes, in order
+    pub dims: [usize; 2],
+    /// Indexed by the first other axis, then the second
+    pub distances: Vec<f32>,
+}
+
+impl DistanceField {
+    /// Sample the distance from `welded` with `resolution` cells along the
+    /// longest side of its bounding box
+    pub fn new(welded: &WeldedMesh, resolution: usize) -> Self {
+        let bbox = BoundingBox::from_points(welded.positions.iter().copied());
+        let delta = bbox.delta();
+        let longest = delta.x.max(delta.y).max(delta.z);
+        let cell = if longest > 0.0 {
+            longest / resolution.max(1) as f32
+        } else {
+            1.0
+        };
+
+        let cells = |extent: f32| (extent / cell).ceil() as usize + 1 + 2 * PADDING;
+        let dims = [cells(delta.x), cells(delta.y), cells(delta.z)];
+        let origin = bbox.min - Vector3::repeat(PADDING as f32 * cell);
+        let len = dims[0] * dims[1] * dims[2];
+
+        let point = |idx: usize| {
+            let (x, rest) = (idx / (dims[1] * dims[2]), idx % (dims[1] * dims[2]));
+            let (y, z) = (rest / dims[2], rest % dims[2]);
+
+            origin + Vector3::new(x as f32, y as f32, z as f32) * cell
+        };
+
+        // The nearest surface point found for each grid point, with the
+        // unit normal of its triangle
+        let mut nearest: Vec<Option<(Vector3<f32>, Vector3<f32>)>> = vec![None; len];
+        let mut nearest_distance = vec![f32::INFINITY; len];
+
+        for tri in &welded.triangles {
+            let corners = welded.corners(tri);
+            let [a, b, c] = corners;
+            let normal = match (b - a).cross(&(c - a)).try_normalize(f32::EPSILON) {
+                Some(normal) => normal,
+                None => continue,
+            };
+
+            let low = a.inf(&b).inf(&c) - Vector3::repeat(BAND * cell) - origin;
+            let high = a.sup(&b).sup(&c) + Vector3::repeat(BAND * cell) - origin;
+            let range = |axis: usize| {
+                let start = (low[axis] / cell).floor().max(0.0) as usize;
+                let end = ((high[axis] / cell).ceil() as usize).min(dims[axis] - 1);
+                start..=end
+            };
+
+            for x in range(0) {
+                for y in range(1) {
+                    for z in range(2) {
+                        let idx = (x * dims[1] + y) * dims[2] + z;
+                        let at = point(idx);
+                        let closest = closest_point(at, corners);
+                        let offset = at - closest;
+                        let distance = offset.norm();
+
+                        // Points nearest an edge or corner are as near every
+                        // triangle sharing it, and the triangle the offset
+                        // leans toward tells their side best
+                        let better = match nearest[idx] {
+                            _ if distance < nearest_distance[idx] - f32::EPSILON => true,
+                            Some((_, other))
+                                if distance <= nearest_distance[idx] + f32::EPSILON =>
+                            {
+                                offset.dot(&normal).abs() > offset.dot(&other).abs()
+                            }
+                            _ => false,
+                        };
+
+                        if better {
+                            nearest[idx] = Some((closest, normal));
+                            nearest_distance[idx] = distance;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut neighbors = Vec::with_capacity(26);
+
+        for dx in -1..=1_isize {
+            for dy in -1..=1_isize {
+                for dz in -1..=1_isize {
+                    let offset = (dx * dims[1] as isize + dy) * dims[2] as isize + dz;
+
+                    if offset != 0 {
+                        neighbors.push(([dx, dy, dz], offset));
+                    }
+                }
+            }
+        }
+
+        for _ in 0..SWEEPS {
+            for &backward in &[false, true] {
+                for step in 0..len {
+                    let idx = if backward { len - 1 - step } else { step };
+                    let at = point(idx);
+                    let voxel = [
+                        idx / (dims[1] * dims[2]),
+                        idx / dims[2] % dims[1],
+                        idx % dims[2],
+                    ];
+
+                    for &(delta, offset) in &neighbors {
+                        // Only points this pass has already been through
+                        let passed = if backward { offset > 0 } else { offset < 0 };
+                        let in_grid = (0..3).all(|axis| {
+                            let moved = voxel[axis] as isize + delta[axis];
+                            moved >= 0 && (moved as usize) < dims[axis]
+                        });
+
+                        if !passed || !in_grid {
+                            continue;
+                        }
+
+                        let neighbor = (idx as isize + offset) as usize;
+
+                        if let Some((closest, normal)) = nearest[neighbor] {
+                            let distance = (at - closest).norm();
+
+                            if distance < nearest_distance[idx] {
+                                nearest[idx] = Some((closest, normal));
+                                nearest_distance[idx] = distance;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let distances = (0..len)
+            .map(|idx| match nearest[idx] {
+                Some((closest, normal)) => {
+                    let offset = point(idx) - closest;
+
+                    if offset.dot(&normal) < 0.0 {
+                        -offset.norm()
+                    } else {
+                        offset.norm()
+                    }
+                }
+                None => 0.0,
+            })
+            .collect();
+
+        Self {
+            origin,
+            cell,
+            dims,
+            distances,
+        }
+    }
+
+    pub fn at(&self, [x, y, z]: [usize; 3]) -> f32 {
+        self.distances[(x * self.dims[1] + y) * self.dims[2] + z]
+    }
+
+    /// The largest distance from the surface anywhere on the grid
+    pub fn range(&self) -> f32 {
+        self.distances
+            .iter()
+            .fold(0.0, |range: f32, distance| range.max(distance.abs()))
+    }
+
+    /// Share of grid points on the outside of the grid, well clear of the
+    /// scan, that read as behind the surface. Near 0 for a closed scan
+    /// facing outward, near 1 for one inside out, and in between for open
+    /// or non-manifold scans
+    pub fn border_inside(&self) -> f32 {
+        let mut border = 0;
+        let mut inside = 0;
+
+        for x in 0..self.dims[0] {
+            for y in 0..self.dims[1] {
+                for z in 0..self.dims[2] {
+                    let on_border = [x, y, z]
+                        .iter()
+                        .zip(&self.dims)
+                        .any(|(&at, &dim)| at == 0 || at == dim - 1);
+
+                    if on_border {
+                        border += 1;
+
+                        if self.at([x, y, z]) < 0.0 {
+                            inside += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        inside as f32 / border.max(1) as f32
+    }
+
+    /// The grid points `index` cells along `axis`, clamped to the grid
+    pub fn slice(&self, axis: usize, index: usize) -> FieldSlice {
+        let index = index.min(self.dims[axis] - 1);
+        let [u, v] = other_axes(axis);
+        let mut distances = Vec::with_capacity(self.dims[u] * self.dims[v]);
+
+        for a in 0..self.dims[u] {
+            for b in 0..self.dims[v] {
+                let mut at = [0; 3];
+                at[axis] = index;
+                at[u] = a;
+                at[v] = b;
+
+                distances.push(self.at(at));
+            }
+        }
+
+        FieldSlice {
+            axis,
+            position: self.origin[axis] + index as f32 * self.cell,
+            dims: [self.dims[u], self.dims[v]],
+            distances,
+        }
+    }
+}
+
+impl FieldSlice {
+    /// The two axes the slice runs along, in x, y, z order
+    pub fn axes(&self) -> [usize; 2] {
+        other_axes(self.axis)
+    }
+
+    pub fn at(&self, [a, b]: [usize; 2]) -> f32 {
+        self.distances[a * self.dims[1] + b]
+    }
+}
+
+/// The axes other than `axis`, in x, y, z order, so slices read the same
+/// way as the scan's axes
+fn other_axes(axis: usize) -> [usize; 2] {
+    match axis {
+        0 => [1, 2],
+        1 => [0, 2],
+        _ => [0, 1],
+    }
+}
@@ -0,0 +1,221 @@
+//! Surface regions painted with the brush, for annotating defects that
+//! cover part of a scan rather than all of it
+//!
+//! The mask is kept per vertex of the [`WeldedMesh`], so painting across the
+//! seam between two render vertices at one position selects both sides.
+//! Exported masks use the same indexing, making them segmentation ground
+//! truth for the welded scan
+
+use std::io::{self, Write};
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::WeldedMesh, picking::TriangleBvh};
+
+/// Start of binary mask files, followed by the format version
+const BINARY_MAGIC: &[u8; 4] = b"SMSK";
+const BINARY_VERSION: u32 = 1;
+
+/// What an exported mask has an entry for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaskIndexing {
+    /// Each welded triangle, in the scan's order
+    Faces,
+    /// Each welded vertex, in order of first use
+    Vertices,
+}
+
+impl Default for MaskIndexing {
+    fn default() -> Self {
+        Self::Faces
+    }
+}
+
+/// How an exported mask is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaskFormat {
+    /// The scan's path, the indexing, the number of triangles and vertices
+    /// to check the indexing against, and the selected indices in order
+    Json,
+    /// `SMSK`, then little-endian `u32`s for the version, the indexing (0
+    /// for faces, 1 for vertices), and the number of entries, then one byte
+    /// per entry, 1 if selected. The 16-byte header makes it easy to load,
+    /// e.g. with `numpy.fromfile(path, numpy.uint8, offset=16)`
+    Binary,
+}
+
+impl Default for MaskFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+impl MaskFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "mask.json",
+            Self::Binary => "mask.bin",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonMask<'a> {
+    scan: &'a str,
+    indexing: MaskIndexing,
+    triangle_count: usize,
+    vertex_count: usize,
+    area: f64,
+    selected: Vec<u32>,
+}
+
+/// Which welded vertices are selected
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionMask {
+    vertices: Vec<bool>,
+}
+
+impl SelectionMask {
+    /// Nothing selected on `welded`
+    pub fn new(welded: &WeldedMesh) -> Self {
+        Self {
+            vertices: vec![false; welded.positions.len()],
+        }
+    }
+
+    /// Indexed like [`WeldedMesh::positions`]
+    pub fn vertices(&self) -> &[bool] {
+        &self.vertices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.vertices.iter().any(|&selected| selected)
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices
+            .iter_mut()
+            .for_each(|selected| *selected = false);
+    }
+
+    /// Select, or deselect if `selected` is false, the vertices within
+    /// `radius` of `center`. `bvh` must be built from the mesh `welded` was,
+    /// unexploded. Returns whether anything changed
+    pub fn paint(
+        &mut self,
+        welded: &WeldedMesh,
+        bvh: &TriangleBvh,
+        center: Vector3<f32>,
+        radius: f32,
+        selected: bool,
+    ) -> bool {
+        let radius_squared = radius * radius;
+        let vertices = &mut self.vertices;
+        let mut changed = false;
+
+        // Triangles are found by their centroids, so look a little further
+        // for vertices of large triangles straddling the edge
+        bvh.triangles_near(center, radius * 2.0, |triangle, _| {
+            for &vertex in &welded.triangles[triangle] {
+                let vertex = vertex as usize;
+
+                if vertices[vertex] != selected
+                    && (welded.positions[vertex] - center).norm_squared() <= radius_squared
+                {
+                    vertices[vertex] = selected;
+                    changed = true;
+                }
+            }
+        });
+
+        changed
+    }
+
+    /// Whether each triangle of `welded` is selected, which takes all three
+    /// of its vertices
+    pub fn faces(&self, welded: &WeldedMesh) -> Vec<bool> {
+        welded
+            .triangles
+            .iter()
+            .map(|triangle| {
+                triangle
+                    .iter()
+                    .all(|&vertex| self.vertices[vertex as usize])
+            })
+            .collect()
+    }
+
+    /// One entry per face or vertex of `welded`
+    pub fn entries(&self, welded: &WeldedMesh, indexing: MaskIndexing) -> Vec<bool> {
+        match indexing {
+            MaskIndexing::Faces => self.faces(welded),
+            MaskIndexing::Vertices => self.vertices.clone(),
+        }
+    }
+
+    /// Export the mask of the scan at `scan`, whose welded mesh is `welded`
+    pub fn write(
+        &self,
+        welded: &WeldedMesh,
+        scan: &str,
+        indexing: MaskIndexing,
+        format: MaskFormat,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        let entries = self.entries(welded, indexing);
+
+        match format {
+            MaskFormat::Json => {
+                let mask = JsonMask {
+                    scan,
+                    indexing,
+                    triangle_count: welded.triangles.len(),
+                    vertex_count: welded.positions.len(),
+                    area: self.area(welded),
+                    selected: entries
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &selected)| selected)
+                        .map(|(idx, _)| idx as u32)
+                        .collect(),
+                };
+
+                serde_json::to_writer(&mut writer, &mask)?;
+                writer.write_all(b"\n")
+            }
+            MaskFormat::Binary => {
+                let indexing = match indexing {
+                    MaskIndexing::Faces => 0_u32,
+                    MaskIndexing::Vertices => 1,
+                };
+
+                writer.write_all(BINARY_MAGIC)?;
+                writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+                writer.write_all(&indexing.to_le_bytes())?;
+                writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+
+                let bytes: Vec<u8> = entries.iter().map(|&selected| selected as u8).collect();
+                writer.write_all(&bytes)
+            }
+        }
+    }
+
+    /// Total area of the selected faces, in the scan's units squared
+    pub fn area(&self, welded: &WeldedMesh) -> f64 {
+        welded
+            .triangles
+            .iter()
+            .zip(self.faces(welded))
+            .filter(|(_, selected)| *selected)
+            .map(|(triangle, _)| {
+                let [a, b, c] = welded.corners(triangle);
+                let (a, b, c) = (a.map(f64::from), b.map(f64::from), c.map(f64::from));
+
+                (b - a).cross(&(c - a)).norm() / 2.0
+            })
+            .sum()
+    }
+}
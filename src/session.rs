@@ -0,0 +1,488 @@
+//! The labeling flow of a session: which scan is current, what has been
+//! recorded, and where urgent scans go, independent of windows, files, and
+//! meshes
+//!
+//! [`Session`] only decides what happens. Each step that records a scan
+//! returns a [`Record`] for the caller to write to its label files
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    clusters::{ClusterAssignment, Suggestion},
+    labels::{fnv1a, LabelRecord, SessionReport},
+};
+
+/// A scan committed to a label file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    /// Labeled with the category named `label`. `expedited` scans came from
+    /// the urgent queue
+    Labeled {
+        path: String,
+        label: String,
+        expedited: bool,
+    },
+    /// Could not be shown, so cannot be labeled
+    Invalid { path: String },
+    /// Passed over without a label, to be looked at again later
+    Skipped { path: String },
+}
+
+impl Record {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Labeled { path, .. } | Self::Invalid { path } | Self::Skipped { path } => path,
+        }
+    }
+}
+
+/// The queue of scans to label and the position in it
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    queue: Vec<String>,
+    /// Of the queue as the session started, before urgent scans were added
+    queue_hash: u64,
+    /// Scans committed in earlier sessions this one was resumed from
+    recorded_before: usize,
+    /// Index of the next scan to open. The current scan, if any, is the one
+    /// before it
+    cursor: usize,
+    /// Cleared until the first scan is opened
+    has_current: bool,
+    /// Set once the current scan is recorded as invalid, after which it is
+    /// not labeled again
+    recorded_invalid: bool,
+    /// Set once the current scan is labeled, recorded as invalid, or
+    /// skipped
+    committed: bool,
+    /// Where in the queue each scan committed this session is, and how it
+    /// was recorded, most recent last
+    commits: Vec<(usize, Record)>,
+    /// Urgent scans found, but not yet moved into the queue
+    waiting: Vec<String>,
+    /// Every urgent scan moved into the queue
+    expedited: HashSet<String>,
+    /// What happened this session, for bug reports
+    log: Vec<String>,
+    /// Labels given this session
+    report: SessionReport,
+    /// Set to label the queue a cluster at a time
+    clusters: Option<ClusterAssignment>,
+    /// Scans labeled in earlier sessions, whose clusters still suggest
+    /// their labels
+    recorded: Vec<LabelRecord>,
+}
+
+impl Session {
+    pub fn new(queue: Vec<String>) -> Self {
+        Self {
+            queue_hash: fnv1a(queue.join("\n").as_bytes()),
+            queue,
+            ..Self::default()
+        }
+    }
+
+    /// Label the queue a cluster at a time, in the order
+    /// [`ClusterAssignment::order`] puts it. Must be set before any scan is
+    /// opened or progress resumed
+    pub fn with_clusters(mut self, clusters: ClusterAssignment) -> Self {
+        self.queue = clusters.order(std::mem::take(&mut self.queue));
+        self.queue_hash = fnv1a(self.queue.join("\n").as_bytes());
+        self.clusters = Some(clusters);
+
+        self
+    }
+
+    /// Scans labeled in the sessions before this one, read back from the
+    /// label files, so a cluster whose exemplar was labeled before a
+    /// restart still suggests its label
+    pub fn with_recorded(mut self, recorded: Vec<LabelRecord>) -> Self {
+        self.recorded = recorded;
+
+        self
+    }
+
+    pub fn queue(&self) -> &[String] {
+        &self.queue
+    }
+
+    /// How many scans have been opened or passed over, which is also the
+    /// 1-based position of the current scan
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn current_path(&self) -> Option<&str> {
+        if !self.has_current {
+            return None;
+        }
+
+        self.queue
+            .get(self.cursor.saturating_sub(1))
+            .map(String::as_str)
+    }
+
+    pub fn next_path(&self) -> Option<&str> {
+        self.queue.get(self.cursor).map(String::as_str)
+    }
+
+    /// Whether the current scan was recorded as invalid, after which it
+    /// cannot be labeled
+    pub fn is_recorded_invalid(&self) -> bool {
+        self.recorded_invalid
+    }
+
+    /// Make [`Session::next_path`] the current scan, returning it, or `None`
+    /// at the end of the queue
+    pub fn advance(&mut self) -> Option<&str> {
+        self.open(|path| format!("opened {}", path))
+    }
+
+    /// Like [`Session::advance`], after the next scan failed to load. It is
+    /// still made current so it can be recorded as invalid
+    pub fn advance_failed(&mut self, error: &dyn fmt::Display) -> Option<&str> {
+        self.open(|path| format!("unable to open {}: {}", path, error))
+    }
+
+    fn open(&mut self, entry: impl FnOnce(&str) -> String) -> Option<&str> {
+        let path = self.queue.get(self.cursor)?;
+
+        self.log.push(entry(path));
+        self.cursor += 1;
+        self.has_current = true;
+        self.recorded_invalid = false;
+        self.committed = false;
+
+        self.current_path()
+    }
+
+    /// Move past [`Session::next_path`] without opening it, keeping the
+    /// current scan
+    pub fn skip_next(&mut self) {
+        if let Some(path) = self.queue.get(self.cursor) {
+            self.log
+                .push(format!("skipped {} while it was loading", path));
+            self.cursor += 1;
+        }
+    }
+
+    /// Set the session aside, e.g. to switch to another queue. A current
+    /// scan that was not committed becomes the next one again, so it is
+    /// opened first when the session is picked back up
+    pub fn shelve(&mut self) {
+        if let (Some(path), false) = (self.current_path(), self.committed) {
+            let entry = format!("shelved {}", path);
+            self.log.push(entry);
+            self.cursor -= 1;
+        }
+
+        self.has_current = false;
+    }
+
+    /// Pass over the current scan without a label. Returns `None` if there
+    /// is no current scan or it was already committed
+    pub fn skip(&mut self) -> Option<Record> {
+        let path = match self.current_path().map(str::to_owned) {
+            Some(path) if !self.committed => path,
+            _ => return None,
+        };
+
+        self.log.push(format!("skipped {}", path));
+        self.committed = true;
+
+        let record = Record::Skipped { path };
+        self.commits.push((self.cursor - 1, record.clone()));
+
+        Some(record)
+    }
+
+    /// Label the current scan with the category named `label`, then move
+    /// waiting urgent scans to the front of the queue. Returns `None` if
+    /// there is no current scan or it was already committed
+    pub fn label(&mut self, label: &str) -> Option<Record> {
+        let record = match self.current_path().map(str::to_owned) {
+            Some(path) if !self.committed => {
+                self.log.push(format!("labeled {} as {}", path, label));
+                self.report.push(label);
+                self.committed = true;
+
+                let record = Record::Labeled {
+                    expedited: self.expedited.contains(&path),
+                    path,
+                    label: label.to_owned(),
+                };
+                self.commits.push((self.cursor - 1, record.clone()));
+
+                Some(record)
+            }
+            _ => None,
+        };
+
+        self.promote_urgent();
+
+        record
+    }
+
+    /// Record the current scan as invalid, for scans that cannot be shown or
+    /// labeled, then move waiting urgent scans to the front of the queue.
+    /// Returns `None` if there is no current scan or it was already
+    /// committed
+    pub fn record_invalid(&mut self) -> Option<Record> {
+        let record = match self.current_path().map(str::to_owned) {
+            Some(path) if !self.committed => {
+                self.log.push(format!("recorded {} as invalid", path));
+                self.recorded_invalid = true;
+                self.committed = true;
+
+                let record = Record::Invalid { path };
+                self.commits.push((self.cursor - 1, record.clone()));
+
+                Some(record)
+            }
+            _ => None,
+        };
+
+        self.promote_urgent();
+
+        record
+    }
+
+    /// Take back the last scan committed, which becomes the next scan so it
+    /// can be labeled again. Scans opened after it are shown again after
+    /// it. Returns what was taken back, or `None` if nothing was committed
+    pub fn undo(&mut self) -> Option<Record> {
+        let (idx, record) = self.commits.pop()?;
+
+        self.log.push(format!("undid {}", record.path()));
+        self.cursor = idx;
+        self.has_current = false;
+        self.recorded_invalid = false;
+        self.committed = false;
+
+        if let Record::Labeled { label, .. } = &record {
+            self.report.remove(label);
+        }
+
+        Some(record)
+    }
+
+    /// Hold `paths` until the current scan is committed, then show them
+    /// before the rest of the queue
+    pub fn add_urgent(&mut self, paths: Vec<String>) {
+        for path in &paths {
+            self.log.push(format!("found urgent scan {}", path));
+        }
+
+        self.waiting.extend(paths);
+    }
+
+    /// Move every waiting urgent scan in front of the rest of the queue.
+    /// Called whenever the current scan is committed, so urgent scans never
+    /// interrupt one being inspected
+    pub fn promote_urgent(&mut self) {
+        if self.waiting.is_empty() {
+            return;
+        }
+
+        // Each urgent scan is shown once, and not at all if it already was
+        let visited: HashSet<&String> = self.queue[..self.cursor].iter().collect();
+        let mut waiting: Vec<String> = Vec::with_capacity(self.waiting.len());
+
+        for path in self.waiting.drain(..) {
+            if !visited.contains(&path) && !waiting.contains(&path) {
+                waiting.push(path);
+            }
+        }
+
+        let queue = &mut self.queue;
+
+        // Urgent scans already further down the queue are only labeled once
+        let mut idx = self.cursor;
+        while idx < queue.len() {
+            if waiting.contains(&queue[idx]) {
+                queue.remove(idx);
+            } else {
+                idx += 1;
+            }
+        }
+
+        for path in &waiting {
+            self.log.push(format!("expedited {}", path));
+        }
+
+        self.expedited.extend(waiting.iter().cloned());
+        queue.splice(self.cursor..self.cursor, waiting);
+    }
+
+    /// Whether the current scan came from the urgent queue
+    pub fn is_expedited(&self) -> bool {
+        self.current_path()
+            .map_or(false, |path| self.expedited.contains(path))
+    }
+
+    /// Urgent scans found that will be shown once the current one is
+    /// committed
+    pub fn urgent_waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+
+    /// Add an entry to the log for something that happened outside the
+    /// session, e.g. a retried load
+    pub fn note(&mut self, entry: String) {
+        self.log.push(entry);
+    }
+
+    pub fn report(&self) -> &SessionReport {
+        &self.report
+    }
+
+    /// Where the session is, to resume from after a restart. A current scan
+    /// that was not committed is not counted, so it is opened again
+    pub fn progress(&self) -> Progress {
+        let uncommitted = self.has_current && !self.committed;
+        let cursor = self.cursor - uncommitted as usize;
+
+        Progress {
+            queue_hash: self.queue_hash,
+            cursor: self.queue[..cursor]
+                .iter()
+                .filter(|path| !self.expedited.contains(*path))
+                .count(),
+            recorded: self.recorded_before + self.commits.len(),
+        }
+    }
+
+    /// Pick up where `progress` left off, if it was saved from a session
+    /// over the same queue and this one has not opened any scans yet.
+    /// Returns whether it was resumed
+    pub fn resume(&mut self, progress: &Progress) -> bool {
+        if self.cursor != 0
+            || self.has_current
+            || progress.queue_hash != self.queue_hash
+            || progress.cursor > self.queue.len()
+        {
+            return false;
+        }
+
+        self.log.push(format!(
+            "resumed at scan {} of {}",
+            progress.cursor,
+            self.queue.len()
+        ));
+        self.cursor = progress.cursor;
+        self.recorded_before = progress.recorded;
+
+        true
+    }
+
+    /// The label given to the first scan labeled in the current scan's
+    /// cluster, in an earlier session or this one, if any, to confirm or
+    /// override
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        let clusters = self.clusters.as_ref()?;
+        let path = self.current_path()?;
+        let cluster = clusters.cluster(path)?;
+
+        let earlier = self
+            .recorded
+            .iter()
+            .map(|record| (&record.path, &record.label));
+        let this_session = self.commits.iter().filter_map(|(_, record)| match record {
+            Record::Labeled { path, label, .. } => Some((path, label)),
+            _ => None,
+        });
+
+        earlier
+            .chain(this_session)
+            .find(|&(exemplar, _)| exemplar != path && clusters.cluster(exemplar) == Some(cluster))
+            .map(|(exemplar, label)| Suggestion {
+                cluster: cluster.to_owned(),
+                exemplar: exemplar.clone(),
+                label: label.clone(),
+            })
+    }
+
+    /// Where the session is, for bug reports
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            cursor: self.cursor,
+            current: self.current_path().map(str::to_owned),
+            queue_len: self.queue.len(),
+        }
+    }
+}
+
+/// A snapshot of a session's position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub cursor: usize,
+    pub current: Option<String>,
+    pub queue_len: usize,
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "cursor: {}", self.cursor)?;
+        writeln!(
+            f,
+            "current: {}",
+            self.current.as_deref().unwrap_or_default()
+        )?;
+        writeln!(f, "queue length: {}", self.queue_len)
+    }
+}
+
+/// How far through its queue a session got, saved after every scan
+/// committed so a crash does not mean starting over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Of the queue the session started with, so progress is only resumed
+    /// on the same queue
+    pub queue_hash: u64,
+    /// Scans of that queue opened or passed over, not counting urgent scans
+    pub cursor: usize,
+    /// Scans committed, including in the sessions resumed from
+    pub recorded: usize,
+}
+
+impl Progress {
+    /// Read progress as written by its [`fmt::Display`] impl, or `None` if
+    /// any field is missing or malformed
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut queue_hash = None;
+        let mut cursor = None;
+        let mut recorded = None;
+
+        for line in text.lines() {
+            let (key, value) = match line.find(':') {
+                Some(colon) => (line[..colon].trim(), line[colon + 1..].trim()),
+                None => continue,
+            };
+
+            match key {
+                "queue hash" => queue_hash = u64::from_str_radix(value, 16).ok(),
+                "cursor" => cursor = value.parse().ok(),
+                "recorded" => recorded = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            queue_hash: queue_hash?,
+            cursor: cursor?,
+            recorded: recorded?,
+        })
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "queue hash: {:016x}", self.queue_hash)?;
+        writeln!(f, "cursor: {}", self.cursor)?;
+        writeln!(f, "recorded: {}", self.recorded)
+    }
+}
@@ -59,11 +59,30 @@ pub enum Uniform<'a> {
     },
 }
 
+const BASIC_VERTEX_SHADER: &str = include_str!("shaders/basic-vs.shader");
+const BASIC_FRAGMENT_SHADER: &str = include_str!("shaders/basic-fs.shader");
+const INSTANCED_VERTEX_SHADER: &str = include_str!("shaders/instanced-vs.shader");
+const INSTANCED_FRAGMENT_SHADER: &str = include_str!("shaders/instanced-fs.shader");
+
 impl Shader {
     pub fn new(vertex: impl AsRef<Path>, fragment: impl AsRef<Path>) -> Self {
         let source = ShaderProgramSource::parse(vertex.as_ref(), fragment.as_ref());
 
-        let id = Self::create_shader(&source.vertex, &source.fragment);
+        Self::from_source(&source.vertex, &source.fragment)
+    }
+
+    pub fn from_source(vertex: &str, fragment: &str) -> Self {
+        Self::link(vertex, None, fragment)
+    }
+
+    /// Like [`Shader::from_source`], with a geometry stage between the
+    /// vertex and fragment shaders
+    pub fn with_geometry(vertex: &str, geometry: &str, fragment: &str) -> Self {
+        Self::link(vertex, Some(geometry), fragment)
+    }
+
+    fn link(vertex: &str, geometry: Option<&str>, fragment: &str) -> Self {
+        let id = Self::create_shader(vertex, geometry, fragment);
 
         check!(unsafe { gl::UseProgram(id) });
 
@@ -73,6 +92,20 @@ impl Shader {
         }
     }
 
+    /// The lit shader used to display scans, embedded in the binary so it
+    /// can be used regardless of the working directory
+    pub fn basic() -> Self {
+        Self::from_source(BASIC_VERTEX_SHADER, BASIC_FRAGMENT_SHADER)
+    }
+
+    /// Like [`Shader::basic`], but reads a model matrix and color per
+    /// instance, for use with [`Renderer::draw_instanced`]
+    ///
+    /// [`Renderer::draw_instanced`]: crate::Renderer::draw_instanced
+    pub fn instanced() -> Self {
+        Self::from_source(INSTANCED_VERTEX_SHADER, INSTANCED_FRAGMENT_SHADER)
+    }
+
     fn compile_shader(source: &str, kind: u32) -> u32 {
         let src = CString::new(source).unwrap();
 
@@ -104,13 +137,21 @@ impl Shader {
         }
     }
 
-    fn create_shader(vertex_shader: &str, fragment_shader: &str) -> u32 {
+    fn create_shader(
+        vertex_shader: &str,
+        geometry_shader: Option<&str>,
+        fragment_shader: &str,
+    ) -> u32 {
         let program = check!(unsafe { gl::CreateProgram() });
         let vs = Self::compile_shader(vertex_shader, gl::VERTEX_SHADER);
+        let gs = geometry_shader.map(|source| Self::compile_shader(source, gl::GEOMETRY_SHADER));
         let fs = Self::compile_shader(fragment_shader, gl::FRAGMENT_SHADER);
 
         unsafe {
             check!(gl::AttachShader(program, vs));
+            if let Some(gs) = gs {
+                check!(gl::AttachShader(program, gs));
+            }
             check!(gl::AttachShader(program, fs));
 
             check!(gl::LinkProgram(program));
@@ -123,6 +164,9 @@ impl Shader {
             assert_ne!(status, gl::FALSE as i32);
 
             check!(gl::DeleteShader(vs));
+            if let Some(gs) = gs {
+                check!(gl::DeleteShader(gs));
+            }
             check!(gl::DeleteShader(fs));
         }
 
@@ -172,6 +216,10 @@ impl Shader {
         }
     }
 
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     pub fn bind(&self) {
         unsafe { gl::UseProgram(self.id) }
     }
@@ -214,9 +262,21 @@ impl<'a> Material<'a> {
         Self { shader, uniforms }
     }
 
+    pub fn shader(&self) -> &Shader {
+        self.shader
+    }
+
+    pub fn uniforms(&self) -> &[Uniform<'a>] {
+        self.uniforms
+    }
+
     pub fn bind(&mut self) {
         self.shader.bind();
+        self.upload_uniforms();
+    }
 
+    /// Set the uniforms on the shader, which must already be bound
+    pub fn upload_uniforms(&mut self) {
         for uniform in self.uniforms {
             self.shader.set_uniform(uniform)
         }
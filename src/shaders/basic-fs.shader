@@ -1,10 +1,11 @@
-#version 410 core
+#version 330 core
 
 layout(location = 0) out vec4 color;
 
 uniform vec3 object_color;
 uniform vec3 light_color;
 uniform vec3 light_pos;
+uniform float alpha;
 
 in vec3 fs_normal;
 in vec3 frag_pos;
@@ -24,5 +25,5 @@ void main()
 
     vec3 result = (ambient + diffuse) * object_color;
 
-    color = vec4(result, 1.0);
+    color = vec4(result, alpha);
 }
@@ -0,0 +1,25 @@
+#version 330 core
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 vs_normal;
+// A mat4 attribute occupies four consecutive locations, 2 through 5
+layout(location = 2) in mat4 instance_model;
+layout(location = 6) in vec3 instance_color;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec3 fs_normal;
+out vec3 frag_pos;
+out vec3 fs_color;
+
+void main()
+{
+   mat4 world = model * instance_model;
+
+   gl_Position = projection * view * world * vec4(position, 1.0);
+   fs_normal = mat3(world) * vs_normal;
+   frag_pos = vec3(world * vec4(position, 1.0));
+   fs_color = instance_color;
+}
@@ -0,0 +1,54 @@
+#version 330 core
+
+// Core profile drivers may clamp glLineWidth to 1, so each line is expanded
+// into a screen-aligned quad instead
+
+layout(lines) in;
+layout(triangle_strip, max_vertices = 4) out;
+
+uniform vec2 viewport_size;
+/// In pixels
+uniform float line_width;
+
+in vec3 gs_color[];
+
+out vec3 fs_color;
+out vec2 fs_offset;
+
+void main()
+{
+    vec4 start = gl_in[0].gl_Position;
+    vec4 end = gl_in[1].gl_Position;
+
+    // Lines crossing the near plane are dropped rather than clipped
+    if (start.w <= 0.0 || end.w <= 0.0) {
+        return;
+    }
+
+    vec2 direction = end.xy / end.w * viewport_size - start.xy / start.w * viewport_size;
+
+    if (length(direction) < 1e-6) {
+        return;
+    }
+
+    direction = normalize(direction);
+
+    // Half the width, converted from pixels to normalized device coordinates
+    vec2 offset = vec2(-direction.y, direction.x) * line_width / viewport_size;
+
+    fs_offset = vec2(0.0);
+
+    fs_color = gs_color[0];
+    gl_Position = start + vec4(offset * start.w, 0.0, 0.0);
+    EmitVertex();
+    gl_Position = start - vec4(offset * start.w, 0.0, 0.0);
+    EmitVertex();
+
+    fs_color = gs_color[1];
+    gl_Position = end + vec4(offset * end.w, 0.0, 0.0);
+    EmitVertex();
+    gl_Position = end - vec4(offset * end.w, 0.0, 0.0);
+    EmitVertex();
+
+    EndPrimitive();
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+layout(location = 0) out vec4 color;
+
+in vec3 fs_color;
+/// Position within a point's quad, from -1 to 1. Always zero for lines
+in vec2 fs_offset;
+
+void main()
+{
+    if (dot(fs_offset, fs_offset) > 1.0) {
+        discard;
+    }
+
+    color = vec4(fs_color, 1.0);
+}
@@ -0,0 +1,36 @@
+#version 330 core
+
+layout(points) in;
+layout(triangle_strip, max_vertices = 4) out;
+
+uniform vec2 viewport_size;
+/// Diameter, in pixels
+uniform float point_size;
+
+in vec3 gs_color[];
+
+out vec3 fs_color;
+out vec2 fs_offset;
+
+void main()
+{
+    vec4 center = gl_in[0].gl_Position;
+
+    if (center.w <= 0.0) {
+        return;
+    }
+
+    vec2 radius = point_size / viewport_size;
+
+    fs_color = gs_color[0];
+
+    for (int i = 0; i < 4; i++) {
+        vec2 corner = vec2(i % 2 == 0 ? -1.0 : 1.0, i < 2 ? -1.0 : 1.0);
+
+        fs_offset = corner;
+        gl_Position = center + vec4(corner * radius * center.w, 0.0, 0.0);
+        EmitVertex();
+    }
+
+    EndPrimitive();
+}
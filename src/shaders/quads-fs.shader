@@ -0,0 +1,19 @@
+#version 330 core
+
+layout(location = 0) out vec4 color;
+
+in vec2 fs_uv;
+in vec4 fs_color;
+
+/// Straight alpha, multiplied by the vertex color
+uniform sampler2D image;
+/// Solid rectangles are drawn without sampling
+uniform bool textured;
+
+void main()
+{
+    vec4 straight = textured ? texture(image, fs_uv) * fs_color : fs_color;
+
+    // Premultiplied, so filtered edges of images do not fringe
+    color = vec4(straight.rgb * straight.a, straight.a);
+}
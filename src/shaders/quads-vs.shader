@@ -0,0 +1,19 @@
+#version 330 core
+
+/// In pixels from the top left of the viewport
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+/// Orthographic, from pixels to clip space
+uniform mat4 projection;
+
+out vec2 fs_uv;
+out vec4 fs_color;
+
+void main()
+{
+   gl_Position = projection * vec4(position, 0.0, 1.0);
+   fs_uv = uv;
+   fs_color = color;
+}
@@ -0,0 +1,41 @@
+// The scan pass of `WgpuBackend`, lit the same way as `basic-fs.shader`
+
+[[block]]
+struct Uniforms {
+    mvp: mat4x4<f32>;
+    model: mat4x4<f32>;
+    light_pos: vec4<f32>;
+    light_color: vec4<f32>;
+    object_color: vec4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] normal: vec3<f32>;
+    [[location(1)]] frag_pos: vec3<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec3<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = uniforms.mvp * vec4<f32>(position, 1.0);
+    out.normal = normal;
+    out.frag_pos = (uniforms.model * vec4<f32>(position, 1.0)).xyz;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let light_direction = normalize(uniforms.light_pos.xyz - in.frag_pos);
+    let ambient_strength = 0.2;
+    let diffuse = max(dot(normalize(in.normal), light_direction), 0.0);
+    let result = (ambient_strength + diffuse) * uniforms.light_color.rgb * uniforms.object_color.rgb;
+
+    return vec4<f32>(result, 1.0);
+}
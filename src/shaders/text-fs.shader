@@ -0,0 +1,35 @@
+#version 330 core
+
+layout(location = 0) out vec4 color;
+
+in vec2 fs_uv;
+in vec4 fs_color;
+
+/// Signed distance field of the glyphs, 0.5 on their outlines
+uniform sampler2D atlas;
+/// How far outside the outline the border reaches, in field units. No
+/// border is drawn if 0
+uniform float outline_width;
+uniform vec3 outline_color;
+
+void main()
+{
+    float distance = texture(atlas, fs_uv).r;
+    // About one pixel on screen, whatever the text's size
+    float smoothing = fwidth(distance) * 0.7;
+
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+
+    // Premultiplied, like everything in the 2D pass
+    if (outline_width <= 0.0) {
+        float alpha = fs_color.a * fill;
+        color = vec4(fs_color.rgb * alpha, alpha);
+        return;
+    }
+
+    float edge = 0.5 - outline_width;
+    float border = smoothstep(edge - smoothing, edge + smoothing, distance);
+
+    float alpha = fs_color.a * border;
+    color = vec4(mix(outline_color, fs_color.rgb, fill) * alpha, alpha);
+}
@@ -0,0 +1,18 @@
+#version 300 es
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 vs_normal;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec3 fs_normal;
+out vec3 frag_pos;
+
+void main()
+{
+   gl_Position = projection * view * model * vec4(position, 1.0);
+   fs_normal = vs_normal;
+   frag_pos = vec3(model * vec4(position, 1.0));
+}
@@ -0,0 +1,445 @@
+//! Printable contact sheets of labeled scans
+//!
+//! QA reviews each week's labels on paper. Thumbnails are tiled onto letter
+//! or A4 pages, each label starting a page of its own, with the scan's file
+//! name under each so a questioned scan can be found again
+
+use std::io::{self, Write};
+
+use nalgebra::Vector2;
+
+use crate::{font::FontAtlas, image::RgbaImage, stamp::draw_text};
+
+/// Page sizes sheets are laid out for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paper {
+    Letter,
+    A4,
+}
+
+impl Paper {
+    /// `letter` or `a4`, in any case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "letter" => Some(Self::Letter),
+            "a4" => Some(Self::A4),
+            _ => None,
+        }
+    }
+
+    /// Size in PostScript points, 72 to the inch
+    pub fn points(self) -> (f32, f32) {
+        match self {
+            Self::Letter => (612.0, 792.0),
+            Self::A4 => (595.28, 841.89),
+        }
+    }
+
+    /// Size in pixels printed at `dpi`
+    pub fn pixels(self, dpi: u32) -> (u32, u32) {
+        let (width, height) = self.points();
+        let scale = dpi as f32 / 72.0;
+
+        (
+            (width * scale).round() as u32,
+            (height * scale).round() as u32,
+        )
+    }
+}
+
+/// How thumbnails are arranged on a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetLayout {
+    pub paper: Paper,
+    pub dpi: u32,
+    /// Thumbnails across each page
+    pub columns: u32,
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        Self {
+            paper: Paper::Letter,
+            dpi: 150,
+            columns: 4,
+        }
+    }
+}
+
+impl SheetLayout {
+    /// Size of a page in pixels
+    pub fn page_size(&self) -> (u32, u32) {
+        self.paper.pixels(self.dpi)
+    }
+
+    /// Side of each square thumbnail in pixels
+    pub fn thumbnail_size(&self) -> u32 {
+        let columns = self.columns.max(1);
+        let content = self.page_size().0.saturating_sub(2 * self.margin());
+
+        (content.saturating_sub((columns - 1) * self.gap()) / columns).max(1)
+    }
+
+    /// Rows of thumbnails below the header of each page, at least one
+    pub fn rows(&self) -> u32 {
+        let content = self
+            .page_size()
+            .1
+            .saturating_sub(2 * self.margin() + self.header_height());
+
+        (content / self.row_height()).max(1)
+    }
+
+    pub fn per_page(&self) -> usize {
+        (self.columns.max(1) * self.rows()) as usize
+    }
+
+    /// Half an inch on every side, which any printer reaches
+    fn margin(&self) -> u32 {
+        self.dpi / 2
+    }
+
+    fn gap(&self) -> u32 {
+        self.dpi / 12
+    }
+
+    /// 14 point titles
+    fn title_size(&self) -> f32 {
+        self.dpi as f32 * 14.0 / 72.0
+    }
+
+    /// 7 point captions
+    fn caption_size(&self) -> f32 {
+        self.dpi as f32 * 7.0 / 72.0
+    }
+
+    fn header_height(&self) -> u32 {
+        (self.title_size() * 1.5).ceil() as u32
+    }
+
+    fn row_height(&self) -> u32 {
+        self.thumbnail_size() + (self.caption_size() * 1.5).ceil() as u32 + self.gap()
+    }
+}
+
+/// Lays out the thumbnails of one label, a page at a time
+pub struct ContactSheet<'a> {
+    layout: SheetLayout,
+    atlas: &'a FontAtlas,
+    title: String,
+    page: Option<RgbaImage>,
+    /// Thumbnails on the current page
+    count: usize,
+    /// Pages started so far
+    pages: usize,
+}
+
+impl<'a> ContactSheet<'a> {
+    /// A sheet headed with `title`, usually the label, on every page
+    pub fn new(layout: SheetLayout, atlas: &'a FontAtlas, title: impl Into<String>) -> Self {
+        Self {
+            layout,
+            atlas,
+            title: title.into(),
+            page: None,
+            count: 0,
+            pages: 0,
+        }
+    }
+
+    /// Add a thumbnail captioned with `name`, scaled to fit its cell if
+    /// needed. Returns the page it filled, if it was the last one to fit
+    pub fn push(&mut self, name: &str, thumbnail: &RgbaImage) -> Option<RgbaImage> {
+        let layout = self.layout;
+
+        if self.page.is_none() {
+            self.pages += 1;
+            self.page = Some(self.blank_page());
+        }
+
+        let page = self.page.as_mut().unwrap();
+
+        let (column, row) = (
+            self.count as u32 % layout.columns.max(1),
+            self.count as u32 / layout.columns.max(1),
+        );
+        let size = layout.thumbnail_size();
+        let left = layout.margin() + column * (size + layout.gap());
+        let top = layout.margin() + layout.header_height() + row * layout.row_height();
+
+        blit(page, thumbnail, left, top, size);
+
+        let caption = fit_caption(self.atlas, name, layout.caption_size(), size as f32);
+        let width = self.atlas.layout(&caption, layout.caption_size()).width;
+
+        draw_text(
+            page,
+            self.atlas,
+            &caption,
+            Vector2::new(
+                left as f32 + (size as f32 - width) / 2.0,
+                (top + size) as f32 + layout.caption_size() * 0.25,
+            ),
+            layout.caption_size(),
+            [0, 0, 0],
+        );
+
+        self.count += 1;
+
+        if self.count == layout.per_page() {
+            self.count = 0;
+            self.page.take()
+        } else {
+            None
+        }
+    }
+
+    /// The last page, if it was started but not filled
+    pub fn finish(mut self) -> Option<RgbaImage> {
+        self.page.take()
+    }
+
+    /// A white page with the title at the top left and its number at the
+    /// top right
+    fn blank_page(&self) -> RgbaImage {
+        let layout = self.layout;
+        let (width, height) = layout.page_size();
+
+        let mut page = RgbaImage::from_raw(width, height, vec![255; (width * height * 4) as usize]);
+
+        let margin = layout.margin() as f32;
+        let size = layout.title_size();
+
+        draw_text(
+            &mut page,
+            self.atlas,
+            &self.title,
+            Vector2::new(margin, margin),
+            size,
+            [0, 0, 0],
+        );
+
+        let number = self.pages.to_string();
+        let number_width = self.atlas.layout(&number, size).width;
+
+        draw_text(
+            &mut page,
+            self.atlas,
+            &number,
+            Vector2::new(width as f32 - margin - number_width, margin),
+            size,
+            [0, 0, 0],
+        );
+
+        page
+    }
+}
+
+/// Copy `source` into a `size` square of `page` at `left`, `top`, sampling
+/// the nearest pixel if their sizes differ
+fn blit(page: &mut RgbaImage, source: &RgbaImage, left: u32, top: u32, size: u32) {
+    if source.width() == 0 || source.height() == 0 {
+        return;
+    }
+
+    for y in 0..size.min(page.height().saturating_sub(top)) {
+        for x in 0..size.min(page.width().saturating_sub(left)) {
+            let pixel = source.get_pixel(
+                (x * source.width() / size).min(source.width() - 1),
+                (y * source.height() / size).min(source.height() - 1),
+            );
+
+            page.put_pixel(left + x, top + y, pixel);
+        }
+    }
+}
+
+/// `text`, shortened from the front with an ellipsis until it fits in
+/// `width`. File names differ most at the end
+fn fit_caption(atlas: &FontAtlas, text: &str, size: f32, width: f32) -> String {
+    if atlas.layout(text, size).width <= width {
+        return text.to_owned();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+
+    (1..chars.len())
+        .map(|start| format!("…{}", chars[start..].iter().collect::<String>()))
+        .find(|caption| atlas.layout(caption, size).width <= width)
+        .unwrap_or_default()
+}
+
+/// Writes pages into a PDF, one image per page, as they are laid out
+///
+/// Each page's pixels are compressed as a PNG, whose image data PDF can read
+/// as is: both use zlib streams of rows filtered by PNG's predictors
+pub struct PdfWriter<W: Write> {
+    writer: W,
+    /// Bytes written so far
+    position: usize,
+    /// Byte offset of each object, the first at index 0 being object 1
+    offsets: Vec<usize>,
+    /// Object number of each page
+    pages: Vec<usize>,
+}
+
+/// Object numbers of the catalog and the page tree, which are written last
+/// once every page is known
+const CATALOG: usize = 1;
+const PAGE_TREE: usize = 2;
+
+impl<W: Write> PdfWriter<W> {
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut pdf = Self {
+            writer,
+            position: 0,
+            offsets: vec![0; PAGE_TREE],
+            pages: Vec::new(),
+        };
+
+        // The comment's high bytes mark the file as binary to transfer tools
+        pdf.write(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")?;
+
+        Ok(pdf)
+    }
+
+    /// Add a page showing `image`, stretched to cover `paper`
+    pub fn add_page(&mut self, image: &RgbaImage, paper: Paper) -> io::Result<()> {
+        let (width, height) = paper.points();
+        let data = zlib_rgb(image)?;
+
+        let page = self.offsets.len() + 1;
+        let (contents, xobject) = (page + 1, page + 2);
+        self.pages.push(page);
+
+        self.object(
+            page,
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Page {} 0 R >> >> /Contents {} 0 R >>",
+                PAGE_TREE, width, height, xobject, contents
+            )
+            .as_bytes(),
+        )?;
+
+        let draw = format!("q {:.2} 0 0 {:.2} 0 0 cm /Page Do Q", width, height);
+        self.stream(contents, "", draw.as_bytes())?;
+
+        let dictionary = format!(
+            "/Type /XObject /Subtype /Image /Width {0} /Height {1} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /FlateDecode \
+             /DecodeParms << /Predictor 15 /Colors 3 /BitsPerComponent 8 /Columns {0} >> ",
+            image.width(),
+            image.height()
+        );
+        self.stream(xobject, &dictionary, &data)
+    }
+
+    /// Write the page tree and cross-reference table, returning the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let kids: Vec<String> = self
+            .pages
+            .iter()
+            .map(|page| format!("{} 0 R", page))
+            .collect();
+
+        self.object(
+            PAGE_TREE,
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .as_bytes(),
+        )?;
+        self.object(
+            CATALOG,
+            format!("<< /Type /Catalog /Pages {} 0 R >>", PAGE_TREE).as_bytes(),
+        )?;
+
+        let xref = self.position;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+
+        for offset in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            CATALOG,
+            xref
+        ));
+
+        self.write(table.as_bytes())?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn object(&mut self, number: usize, body: &[u8]) -> io::Result<()> {
+        if self.offsets.len() < number {
+            self.offsets.resize(number, 0);
+        }
+
+        self.offsets[number - 1] = self.position;
+
+        self.write(format!("{} 0 obj\n", number).as_bytes())?;
+        self.write(body)?;
+        self.write(b"\nendobj\n")
+    }
+
+    fn stream(&mut self, number: usize, dictionary: &str, data: &[u8]) -> io::Result<()> {
+        let mut body = format!("<< {}/Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+
+        self.object(number, &body)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.position += bytes.len();
+
+        Ok(())
+    }
+}
+
+/// `image` without alpha, as the zlib stream of an RGB PNG's `IDAT` chunks
+fn zlib_rgb(image: &RgbaImage) -> io::Result<Vec<u8>> {
+    let rgb: Vec<u8> = image
+        .pixels()
+        .chunks_exact(4)
+        .flat_map(|pixel| pixel[..3].iter().copied())
+        .collect();
+
+    let mut png = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&rgb))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    }
+
+    // The signature, then chunks of a length, a type, data, and a CRC
+    let mut data = Vec::new();
+    let mut chunks = &png[8..];
+
+    while chunks.len() >= 12 {
+        let length = u32::from_be_bytes([chunks[0], chunks[1], chunks[2], chunks[3]]) as usize;
+        let (kind, rest) = chunks[4..].split_at(4);
+
+        if kind == b"IDAT" {
+            data.extend_from_slice(&rest[..length]);
+        }
+
+        chunks = &rest[length + 4..];
+    }
+
+    Ok(data)
+}
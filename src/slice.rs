@@ -0,0 +1,151 @@
+//! Cross sections of a scan through a plane
+//!
+//! Seen in 3D, a collapsed region or a doubled wall hides behind the rest of
+//! the surface. Cut through, a healthy scan is one clean profile, and either
+//! defect shows up as a kink or as a second contour running alongside the
+//! first
+
+use std::collections::{HashMap, HashSet};
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::{analysis::WeldedMesh, fitting::Plane};
+
+/// Where a plane cuts a scan, in coordinates on the plane
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub plane: Plane,
+    /// Unit directions on the plane that `x` and `y` of the profile run
+    /// along, with `x × y` the plane's normal
+    pub axes: [Vector3<f32>; 2],
+    /// Connected runs of the cut, each closed if its first and last points
+    /// are equal
+    pub polylines: Vec<Vec<Vector2<f32>>>,
+}
+
+impl Section {
+    /// Cut `welded` with `plane`. Vertices on the plane count as in front of
+    /// it, so no triangle is cut through a corner
+    pub fn new(welded: &WeldedMesh, plane: Plane) -> Self {
+        let axes = plane_axes(plane.normal);
+        let distances: Vec<f32> = welded
+            .positions
+            .iter()
+            .map(|&position| plane.signed_distance(position))
+            .collect();
+
+        // Each cut triangle joins the crossings of two of its edges. Edges
+        // are shared, so keying crossings by edge links the pieces up
+        let mut links: HashMap<(u32, u32), Vec<(u32, u32)>> = HashMap::new();
+        let mut crossings = HashMap::new();
+
+        for &[a, b, c] in &welded.triangles {
+            let crossed: Vec<(u32, u32)> = [(a, b), (b, c), (c, a)]
+                .iter()
+                .filter(|&&(start, end)| {
+                    (distances[start as usize] >= 0.0) != (distances[end as usize] >= 0.0)
+                })
+                .map(|&(start, end)| (start.min(end), start.max(end)))
+                .collect();
+
+            if let [first, second] = crossed[..] {
+                for &edge in &[first, second] {
+                    crossings.entry(edge).or_insert_with(|| {
+                        let (start, end) = (edge.0 as usize, edge.1 as usize);
+                        let t = distances[start] / (distances[start] - distances[end]);
+                        let point = welded.positions[start].lerp(&welded.positions[end], t);
+                        let offset = point - plane.point;
+
+                        Vector2::new(offset.dot(&axes[0]), offset.dot(&axes[1]))
+                    });
+                }
+
+                links.entry(first).or_default().push(second);
+                links.entry(second).or_default().push(first);
+            }
+        }
+
+        // Open runs start from an end, so walk those before closed loops
+        let mut starts: Vec<(u32, u32)> = links.keys().copied().collect();
+        starts.sort_by_key(|edge| (links[edge].len() != 1, *edge));
+
+        let mut visited = HashSet::with_capacity(links.len());
+        let mut polylines = Vec::new();
+
+        for start in starts {
+            if visited.contains(&start) {
+                continue;
+            }
+
+            let mut polyline = vec![crossings[&start]];
+            let mut current = start;
+            visited.insert(current);
+
+            loop {
+                let next = links[&current]
+                    .iter()
+                    .copied()
+                    .find(|edge| !visited.contains(edge));
+
+                match next {
+                    Some(edge) => {
+                        polyline.push(crossings[&edge]);
+                        visited.insert(edge);
+                        current = edge;
+                    }
+                    None => {
+                        if polyline.len() > 2 && links[&current].contains(&start) {
+                            polyline.push(crossings[&start]);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            if polyline.len() > 1 {
+                polylines.push(polyline);
+            }
+        }
+
+        Self {
+            plane,
+            axes,
+            polylines,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.polylines.is_empty()
+    }
+
+    /// Total length of the cut
+    pub fn length(&self) -> f32 {
+        self.polylines
+            .iter()
+            .flat_map(|polyline| polyline.windows(2))
+            .map(|pair| (pair[1] - pair[0]).norm())
+            .sum()
+    }
+
+    /// Where `point` falls on the profile
+    pub fn project(&self, point: Vector3<f32>) -> Vector2<f32> {
+        let offset = point - self.plane.point;
+
+        Vector2::new(offset.dot(&self.axes[0]), offset.dot(&self.axes[1]))
+    }
+}
+
+/// Directions on a plane with `normal`, the second as close to up (z) as the
+/// plane allows so profiles are seen upright
+fn plane_axes(normal: Vector3<f32>) -> [Vector3<f32>; 2] {
+    let up = if normal.z.abs() < 0.9 {
+        Vector3::z()
+    } else {
+        Vector3::y()
+    };
+
+    let y = (up - normal * normal.dot(&up)).normalize();
+    let x = y.cross(&normal);
+
+    [x, y]
+}
@@ -0,0 +1,247 @@
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+use crate::{
+    backend::{PixelBackend, RenderBackend, ScanFrame},
+    color::Color,
+    image::RgbaImage,
+    mesh::Mesh,
+    renderer::{Light, RenderStats},
+};
+
+/// Same as the ambient term in `basic-fs.shader`
+const AMBIENT_STRENGTH: f32 = 0.2;
+
+/// A flat-shaded, depth-buffered CPU rasterizer for machines without a
+/// usable GL context
+///
+/// Pixels are stored as `0x00RRGGBB`, top row first
+pub struct SoftwareBackend {
+    width: u32,
+    height: u32,
+    pixels: Vec<u32>,
+    depth: Vec<f32>,
+    mesh: Option<Mesh>,
+    light: Light,
+    object_color: Color,
+    stats: RenderStats,
+}
+
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32, light: &Light, object_color: Color) -> Self {
+        let len = width as usize * height as usize;
+
+        Self {
+            width,
+            height,
+            pixels: vec![0; len],
+            depth: vec![f32::INFINITY; len],
+            mesh: None,
+            light: *light,
+            object_color,
+            stats: RenderStats::default(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+
+        for &pixel in &self.pixels {
+            bytes.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, 255]);
+        }
+
+        RgbaImage::from_raw(self.width, self.height, bytes)
+    }
+
+    fn shade(&self, normal: &Vector3<f32>, light_direction: &Vector3<f32>) -> u32 {
+        let diffuse = normal.dot(light_direction).max(0.0);
+
+        let channel = |light: f32, object: f32| {
+            (((AMBIENT_STRENGTH + diffuse) * light * object).min(1.0) * 255.0) as u32
+        };
+
+        let red = channel(self.light.color.red, self.object_color.red);
+        let green = channel(self.light.color.green, self.object_color.green);
+        let blue = channel(self.light.color.blue, self.object_color.blue);
+
+        (red << 16) | (green << 8) | blue
+    }
+
+    /// Project a model-space position to `(x, y, depth)` in pixels, or `None`
+    /// if it lies behind the camera
+    fn to_screen(&self, mvp: &Matrix4<f32>, pos: &Vector3<f32>) -> Option<Vector3<f32>> {
+        let clip = mvp * Vector4::new(pos.x, pos.y, pos.z, 1.0);
+
+        if clip.w <= f32::EPSILON {
+            return None;
+        }
+
+        let ndc = clip.xyz() / clip.w;
+
+        Some(Vector3::new(
+            (ndc.x + 1.0) / 2.0 * self.width as f32,
+            (1.0 - ndc.y) / 2.0 * self.height as f32,
+            ndc.z,
+        ))
+    }
+
+    fn fill_triangle(&mut self, corners: [Vector3<f32>; 3], color: u32) {
+        let [a, b, c] = corners;
+
+        let area = edge(&a, &b, &c);
+
+        if area.abs() <= f32::EPSILON {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+        let max_x = (a.x.max(b.x).max(c.x).ceil() as i64).min(self.width as i64 - 1);
+        let max_y = (a.y.max(b.y).max(c.y).ceil() as i64).min(self.height as i64 - 1);
+
+        if max_x < 0 || max_y < 0 {
+            return;
+        }
+
+        for y in min_y..=max_y as u32 {
+            for x in min_x..=max_x as u32 {
+                let p = Vector3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+
+                let w0 = edge(&b, &c, &p) / area;
+                let w1 = edge(&c, &a, &p) / area;
+                let w2 = edge(&a, &b, &p) / area;
+
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+
+                let depth = w0 * a.z + w1 * b.z + w2 * c.z;
+
+                if !(-1.0..=1.0).contains(&depth) {
+                    continue;
+                }
+
+                let idx = y as usize * self.width as usize + x as usize;
+
+                if depth < self.depth[idx] {
+                    self.depth[idx] = depth;
+                    self.pixels[idx] = color;
+                }
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `abp` in screen space
+fn edge(a: &Vector3<f32>, b: &Vector3<f32>, p: &Vector3<f32>) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn name(&self) -> &'static str {
+        "software"
+    }
+
+    fn load_scan(&mut self, mesh: &Mesh) {
+        self.mesh = Some(mesh.clone());
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        let len = width as usize * height as usize;
+
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; len];
+        self.depth = vec![f32::INFINITY; len];
+    }
+
+    fn set_object_color(&mut self, color: Color) {
+        self.object_color = color;
+    }
+
+    fn draw_scan(&mut self, frame: &ScanFrame) {
+        let background = ((frame.background.red * 255.0) as u32) << 16
+            | ((frame.background.green * 255.0) as u32) << 8
+            | (frame.background.blue * 255.0) as u32;
+
+        self.pixels.iter_mut().for_each(|pixel| *pixel = background);
+        self.depth
+            .iter_mut()
+            .for_each(|depth| *depth = f32::INFINITY);
+
+        let mesh = match self.mesh.take() {
+            Some(mesh) => mesh,
+            None => return,
+        };
+
+        let bbox = mesh.bounding_box();
+        let model = frame.model;
+        let mvp = frame.projection * frame.stationary.view(bbox) * model;
+        let light_pos = Vector3::from(frame.stationary.pos(bbox));
+
+        for [i0, i1, i2] in mesh.triangles() {
+            let local = [mesh.position(i0), mesh.position(i1), mesh.position(i2)];
+
+            let to_world = |p: &Vector3<f32>| (model * Vector4::new(p.x, p.y, p.z, 1.0)).xyz();
+            let world = [
+                to_world(&local[0]),
+                to_world(&local[1]),
+                to_world(&local[2]),
+            ];
+
+            let normal = (world[1] - world[0]).cross(&(world[2] - world[0]));
+
+            if normal.norm() <= f32::EPSILON {
+                continue;
+            }
+
+            let centroid = (world[0] + world[1] + world[2]) / 3.0;
+            let color = self.shade(&normal.normalize(), &(light_pos - centroid).normalize());
+
+            let screen = match (
+                self.to_screen(&mvp, &local[0]),
+                self.to_screen(&mvp, &local[1]),
+                self.to_screen(&mvp, &local[2]),
+            ) {
+                (Some(a), Some(b), Some(c)) => [a, b, c],
+                _ => continue,
+            };
+
+            self.fill_triangle(screen, color);
+        }
+
+        self.stats.draw_calls += 1;
+        self.stats.triangles += mesh.triangle_count() as u64;
+
+        self.mesh = Some(mesh);
+    }
+
+    fn take_stats(&mut self) -> RenderStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+impl PixelBackend for SoftwareBackend {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Cursor, Read},
+    path::Path,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use zip::ZipArchive;
+
+use crate::queue::{build_queue, is_scan_path};
+
+/// Separates a `zip://` archive path from the entry inside it
+const ARCHIVE_SEPARATOR: char = '!';
+
+/// What a source knows about a scan without reading it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanMetadata {
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+/// Somewhere scans can be listed and read from, e.g. a directory or an
+/// archive
+///
+/// Sources are registered in a [`SourceRegistry`] under a URI scheme, and
+/// are only given the part of a URI after `scheme://`
+pub trait ScanSource: Send + Sync {
+    /// Every scan at or below `location`, in a stable order
+    fn list(&self, location: &str) -> io::Result<Vec<String>>;
+
+    /// The contents of the scan at `location`
+    fn fetch(&self, location: &str) -> io::Result<Box<dyn Read + Send>>;
+
+    fn metadata(&self, location: &str) -> io::Result<ScanMetadata>;
+}
+
+/// Scans on the local filesystem, or a mounted network share
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSource;
+
+impl ScanSource for FileSource {
+    fn list(&self, location: &str) -> io::Result<Vec<String>> {
+        build_queue(location)
+    }
+
+    fn fetch(&self, location: &str) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(fs::File::open(location)?))
+    }
+
+    fn metadata(&self, location: &str) -> io::Result<ScanMetadata> {
+        let metadata = fs::metadata(location)?;
+
+        Ok(ScanMetadata {
+            size: Some(metadata.len()),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Scans inside a zip archive, addressed as `archive.zip!path/in/archive.stl`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZipSource;
+
+impl ZipSource {
+    fn open(archive: &str) -> io::Result<ZipArchive<fs::File>> {
+        ZipArchive::new(fs::File::open(archive)?).map_err(zip_error)
+    }
+
+    fn split(location: &str) -> io::Result<(&str, &str)> {
+        let mut parts = location.splitn(2, ARCHIVE_SEPARATOR);
+
+        match (parts.next(), parts.next()) {
+            (Some(archive), Some(entry)) => Ok((archive, entry)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected `archive.zip{}entry.stl`, got {}",
+                    ARCHIVE_SEPARATOR, location
+                ),
+            )),
+        }
+    }
+}
+
+impl ScanSource for ZipSource {
+    fn list(&self, location: &str) -> io::Result<Vec<String>> {
+        if location.contains(ARCHIVE_SEPARATOR) {
+            return Ok(vec![location.to_owned()]);
+        }
+
+        let archive = Self::open(location)?;
+
+        let mut entries: Vec<String> = archive
+            .file_names()
+            .filter(|name| is_scan_path(Path::new(name)))
+            .map(|name| format!("{}{}{}", location, ARCHIVE_SEPARATOR, name))
+            .collect();
+
+        entries.sort();
+
+        Ok(entries)
+    }
+
+    fn fetch(&self, location: &str) -> io::Result<Box<dyn Read + Send>> {
+        let (archive, entry) = Self::split(location)?;
+
+        let mut archive = Self::open(archive)?;
+        let mut file = archive.by_name(entry).map_err(zip_error)?;
+
+        // Entries borrow the archive, so they are decompressed up front
+        let mut contents = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut contents)?;
+
+        Ok(Box::new(Cursor::new(contents)))
+    }
+
+    fn metadata(&self, location: &str) -> io::Result<ScanMetadata> {
+        let (archive, entry) = Self::split(location)?;
+
+        let mut archive = Self::open(archive)?;
+        let file = archive.by_name(entry).map_err(zip_error)?;
+
+        Ok(ScanMetadata {
+            size: Some(file.size()),
+            modified: None,
+        })
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    match e {
+        zip::result::ZipError::Io(e) => e,
+        zip::result::ZipError::FileNotFound => io::Error::new(io::ErrorKind::NotFound, e),
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+/// Dispatches queue entries to a [`ScanSource`] by URI scheme
+///
+/// Entries without a scheme, and `file://` URIs, are read from the
+/// filesystem. `zip://` URIs are read from archives. Other schemes, such as
+/// an HTTP or PACS source, can be added with [`SourceRegistry::register`]
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: HashMap<String, Arc<dyn ScanSource>>,
+}
+
+impl fmt::Debug for SourceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.sources.keys()).finish()
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register("file", FileSource);
+        registry.register("zip", ZipSource);
+
+        registry
+    }
+}
+
+impl SourceRegistry {
+    /// A registry with no sources, not even the filesystem
+    pub fn empty() -> Self {
+        Self {
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Replaces any source already registered for `scheme`
+    pub fn register(&mut self, scheme: &str, source: impl ScanSource + 'static) {
+        self.sources
+            .insert(scheme.to_ascii_lowercase(), Arc::new(source));
+    }
+
+    /// Every scan at or below `uri`, as URIs that can be passed to
+    /// [`SourceRegistry::fetch`]
+    pub fn list(&self, uri: &str) -> io::Result<Vec<String>> {
+        let (scheme, source, location) = self.resolve(uri)?;
+
+        let entries = source.list(location)?;
+
+        Ok(match scheme {
+            Some(scheme) => entries
+                .into_iter()
+                .map(|entry| format!("{}://{}", scheme, entry))
+                .collect(),
+            None => entries,
+        })
+    }
+
+    pub fn fetch(&self, uri: &str) -> io::Result<Box<dyn Read + Send>> {
+        let (_, source, location) = self.resolve(uri)?;
+
+        source.fetch(location)
+    }
+
+    pub fn metadata(&self, uri: &str) -> io::Result<ScanMetadata> {
+        let (_, source, location) = self.resolve(uri)?;
+
+        source.metadata(location)
+    }
+
+    /// The scheme as written in `uri`, if any, the source registered for
+    /// it, and the rest of the URI
+    fn resolve<'a>(&self, uri: &'a str) -> io::Result<(Option<&'a str>, &dyn ScanSource, &'a str)> {
+        let (scheme, location) = match split_scheme(uri) {
+            Some((scheme, location)) => (Some(scheme), location),
+            None => (None, uri),
+        };
+
+        let key = scheme.unwrap_or("file").to_ascii_lowercase();
+
+        match self.sources.get(&key) {
+            Some(source) => Ok((scheme, source.as_ref(), location)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no scan source registered for {}://", key),
+            )),
+        }
+    }
+}
+
+/// `Some(("zip", "a.zip!b.stl"))` for `"zip://a.zip!b.stl"`
+///
+/// Schemes must be at least two characters, so Windows drive letters are
+/// not mistaken for one
+fn split_scheme(uri: &str) -> Option<(&str, &str)> {
+    let end = uri.find("://")?;
+    let scheme = &uri[..end];
+
+    let is_scheme = scheme.len() >= 2
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+
+    if is_scheme {
+        Some((scheme, &uri[end + 3..]))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,212 @@
+//! Exported images that describe themselves
+//!
+//! An image pasted into a report or ticket loses its file name quickly. The
+//! scan, label, annotator, and time are drawn into a band along the bottom
+//! of the image and also stored as PNG text chunks, so either a reader or a
+//! script can tell where an image came from
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nalgebra::Vector2;
+
+use crate::{
+    font::{FontAtlas, BASE_SIZE, SPREAD},
+    image::RgbaImage,
+};
+
+/// Height of stamped text in pixels
+const TEXT_SIZE: f32 = 14.0;
+/// Between the text and the edges of its band, in pixels
+const PADDING: f32 = 6.0;
+/// Opacity of the band behind the text
+const BAND_ALPHA: f32 = 0.6;
+
+/// What an exported image shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageStamp {
+    /// Path of the scan in the queue
+    pub scan: String,
+    /// Category name, if one was given or is pending
+    pub label: Option<String>,
+    pub annotator: Option<String>,
+    pub time: SystemTime,
+}
+
+impl ImageStamp {
+    /// A stamp for `scan` taken now
+    pub fn new(scan: impl Into<String>) -> Self {
+        Self {
+            scan: scan.into(),
+            label: None,
+            annotator: None,
+            time: SystemTime::now(),
+        }
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn unix_seconds(&self) -> u64 {
+        self.time
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default()
+    }
+
+    /// The PNG text chunks to store, by their keyword. `Creation Time` and
+    /// `Author` are keywords the PNG specification defines; the others are
+    /// this tool's
+    pub fn text_chunks(&self) -> Vec<(&'static str, String)> {
+        let mut chunks = vec![("Scan", self.scan.clone())];
+
+        if let Some(label) = &self.label {
+            chunks.push(("Label", label.clone()));
+        }
+
+        if let Some(annotator) = &self.annotator {
+            chunks.push(("Author", annotator.clone()));
+        }
+
+        chunks.push(("Creation Time", format_utc(self.unix_seconds())));
+        chunks.push((
+            "Software",
+            concat!("scan-classification ", env!("CARGO_PKG_VERSION")).to_owned(),
+        ));
+
+        chunks
+    }
+
+    /// The line drawn into the image
+    pub fn caption(&self) -> String {
+        let mut parts = vec![self.scan.clone()];
+        parts.extend(self.label.iter().cloned());
+        parts.extend(self.annotator.iter().cloned());
+        parts.push(format_utc(self.unix_seconds()));
+
+        parts.join("  •  ")
+    }
+
+    /// Draw [`ImageStamp::caption`] in white on a dark band along the
+    /// bottom of `image`, laid out with `atlas`
+    pub fn draw(&self, image: &mut RgbaImage, atlas: &FontAtlas) {
+        let caption = self.caption();
+        let band_height = (atlas.layout(&caption, TEXT_SIZE).height + 2.0 * PADDING).ceil() as u32;
+        let top = image.height().saturating_sub(band_height);
+
+        for y in top..image.height() {
+            for x in 0..image.width() {
+                blend(image, x, y, [0, 0, 0], BAND_ALPHA);
+            }
+        }
+
+        let origin = Vector2::new(PADDING, top as f32 + PADDING);
+        draw_text(image, atlas, &caption, origin, TEXT_SIZE, [255, 255, 255]);
+    }
+}
+
+/// Rasterize `text`, `size` pixels tall, into `image` with its top left at
+/// `origin`. Returns the size of the text
+pub(crate) fn draw_text(
+    image: &mut RgbaImage,
+    atlas: &FontAtlas,
+    text: &str,
+    origin: Vector2<f32>,
+    size: f32,
+    color: [u8; 3],
+) -> Vector2<f32> {
+    let layout = atlas.layout(text, size);
+    let atlas_size = Vector2::new(atlas.width() as f32, atlas.height() as f32);
+
+    // The field changes by this much per pixel of text, so edges are
+    // antialiased over about one pixel
+    let smoothing = BASE_SIZE / size / (2.0 * SPREAD);
+
+    for quad in &layout.quads {
+        let (min, max) = (origin + quad.min, origin + quad.max);
+        let extent = max - min;
+
+        if extent.x <= 0.0 || extent.y <= 0.0 {
+            continue;
+        }
+
+        let x_range = min.x.floor().max(0.0) as u32..(max.x.ceil() as u32).min(image.width());
+        let y_range = min.y.floor().max(0.0) as u32..(max.y.ceil() as u32).min(image.height());
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let t = (Vector2::new(x as f32 + 0.5, y as f32 + 0.5) - min).component_div(&extent);
+                let uv = quad.uv_min + (quad.uv_max - quad.uv_min).component_mul(&t);
+                let distance = sample(atlas, uv.component_mul(&atlas_size));
+                let coverage = ((distance - 0.5) / smoothing + 0.5).clamp(0.0, 1.0);
+
+                if coverage > 0.0 {
+                    blend(image, x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    Vector2::new(layout.width, layout.height)
+}
+
+/// The distance field at `position`, in atlas pixels, filtered bilinearly
+fn sample(atlas: &FontAtlas, position: Vector2<f32>) -> f32 {
+    let (width, height) = (atlas.width(), atlas.height());
+    let value = |x: usize, y: usize| {
+        atlas.pixels()[y.min(height - 1) * width + x.min(width - 1)] as f32 / 255.0
+    };
+
+    let position = position - Vector2::repeat(0.5);
+    let (x, y) = (position.x.max(0.0), position.y.max(0.0));
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (fx, fy) = (x.fract(), y.fract());
+
+    let top = value(x0, y0) * (1.0 - fx) + value(x0 + 1, y0) * fx;
+    let bottom = value(x0, y0 + 1) * (1.0 - fx) + value(x0 + 1, y0 + 1) * fx;
+
+    top * (1.0 - fy) + bottom * fy
+}
+
+/// Composite `color` over a pixel at `alpha`. Images are sRGB, and blending
+/// text in it directly matches how the HUD draws
+fn blend(image: &mut RgbaImage, x: u32, y: u32, color: [u8; 3], alpha: f32) {
+    let mut pixel = image.get_pixel(x, y);
+
+    for (channel, &value) in pixel.iter_mut().zip(&color) {
+        *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+    }
+
+    image.put_pixel(x, y, pixel);
+}
+
+/// `seconds` since the Unix epoch as an ISO 8601 UTC time, e.g.
+/// `2021-03-01T12:00:00Z`
+pub fn format_utc(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // From days since the epoch to a civil date, see Howard Hinnant,
+    // "chrono-Compatible Low-Level Date Algorithms"
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
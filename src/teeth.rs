@@ -0,0 +1,194 @@
+//! Estimating how many teeth an arch scan shows
+//!
+//! Partial arches, with fewer than about ten teeth, are labeled apart from
+//! full ones, and annotators otherwise count by eye. Seen from the biting
+//! side, an arch is a ring of crowns with dips between neighbouring teeth, so
+//! following the top of the crowns around the arch and counting its
+//! prominent peaks gives a count that is usually within a tooth or two.
+//! Molars with deep grooves can count twice, and worn or crowded teeth once
+//! for two, so the count is a hint rather than a measurement
+
+use nalgebra::Vector3;
+
+use crate::{analysis::WeldedMesh, arch::ArchEstimate};
+
+/// Directions around the arch the crown profile is sampled in
+const BINS: usize = 180;
+
+/// Peaks closer than this many bins on either side of a higher one are part
+/// of the same crown
+const PEAK_WINDOW: usize = 2;
+
+/// Directions the lowest stretch of the profile, where it is cut open, is
+/// looked for over
+const OPENING_WINDOW: usize = BINS / 12;
+
+/// Two peaks count as separate teeth if the profile between them dips below
+/// both by this fraction of the arch's mean radius, about 1.5 mm on a
+/// typical adult arch
+const PROMINENCE: f32 = 0.06;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToothCount {
+    /// The tip of each tooth found, in order around the arch
+    pub teeth: Vec<Vector3<f32>>,
+}
+
+impl ToothCount {
+    /// `None` if too little of the arch is covered to follow it
+    pub fn new(welded: &WeldedMesh, arch: &ArchEstimate) -> Option<Self> {
+        let occlusal = arch.occlusal;
+        let across = if occlusal.x.abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        let u = (across - occlusal * occlusal.dot(&across)).normalize();
+        let v = occlusal.cross(&u);
+
+        // The highest vertex in each direction from the center, and how far
+        // out vertices lie on average
+        let mut profile: Vec<Option<(f32, u32)>> = vec![None; BINS];
+        let mut radius = 0.0;
+
+        for (idx, &position) in welded.positions.iter().enumerate() {
+            let offset = position - arch.center;
+            let height = offset.dot(&occlusal);
+            let (x, y) = (offset.dot(&u), offset.dot(&v));
+            radius += x.hypot(y);
+
+            let turn = (y.atan2(x) / std::f32::consts::TAU).rem_euclid(1.0);
+            let bin = ((turn * BINS as f32) as usize).min(BINS - 1);
+
+            match profile[bin] {
+                Some((highest, _)) if highest >= height => {}
+                _ => profile[bin] = Some((height, idx as u32)),
+            }
+        }
+
+        radius /= welded.positions.len().max(1) as f32;
+
+        // Start at the back of the arch, so the profile runs from one end to
+        // the other
+        let start = opening(&profile)?;
+        let profile: Vec<Option<(f32, u32)>> = (0..BINS)
+            .map(|offset| profile[(start + offset) % BINS])
+            .collect();
+
+        let (first, heights) = fill_gaps(&profile)?;
+        let smoothed: Vec<f32> = (0..heights.len())
+            .map(|idx| {
+                let window = &heights[idx.saturating_sub(1)..(idx + 2).min(heights.len())];
+                window.iter().sum::<f32>() / window.len() as f32
+            })
+            .collect();
+
+        let prominence = PROMINENCE * radius;
+        let mut peaks: Vec<usize> = Vec::new();
+
+        for idx in local_maxima(&smoothed) {
+            let last = match peaks.last() {
+                Some(&last) => last,
+                None => {
+                    peaks.push(idx);
+                    continue;
+                }
+            };
+
+            let dip = smoothed[last..=idx]
+                .iter()
+                .copied()
+                .fold(f32::MAX, f32::min);
+
+            if smoothed[last] - dip >= prominence && smoothed[idx] - dip >= prominence {
+                peaks.push(idx);
+            } else if smoothed[idx] > smoothed[last] {
+                *peaks.last_mut().unwrap() = idx;
+            }
+        }
+
+        let teeth = peaks
+            .into_iter()
+            .filter_map(|idx| profile[first + idx])
+            .map(|(_, vertex)| welded.positions[vertex as usize])
+            .collect();
+
+        Some(Self { teeth })
+    }
+
+    pub fn count(&self) -> usize {
+        self.teeth.len()
+    }
+}
+
+/// The middle of the lowest stretch of the profile, where there are no
+/// crowns: the opening at the back of the arch, or the palate in front of
+/// it. Directions no vertex fell in count as lower than any that one did.
+/// `None` if none did
+fn opening(profile: &[Option<(f32, u32)>]) -> Option<usize> {
+    let heights = profile
+        .iter()
+        .filter_map(|bin| bin.map(|(height, _)| height));
+    let (lowest, highest) = heights.fold((f32::MAX, f32::MIN), |(lo, hi), height| {
+        (lo.min(height), hi.max(height))
+    });
+
+    if lowest > highest {
+        return None;
+    }
+
+    let empty = lowest - (highest - lowest).max(f32::EPSILON);
+    let height = |idx: usize| profile[idx % profile.len()].map_or(empty, |(height, _)| height);
+
+    (0..profile.len())
+        .map(|start| {
+            let window: f32 = (start..start + OPENING_WINDOW).map(height).sum();
+            (start + OPENING_WINDOW / 2, window)
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(middle, _)| middle % profile.len())
+}
+
+/// Where the profile's sampled directions start, and the heights from there
+/// to the last, with directions no vertex fell in interpolated from their
+/// neighbours. `None` if fewer than a quarter of the directions were
+/// sampled
+fn fill_gaps(profile: &[Option<(f32, u32)>]) -> Option<(usize, Vec<f32>)> {
+    let sampled: Vec<(usize, f32)> = profile
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, bin)| bin.map(|(height, _)| (idx, height)))
+        .collect();
+
+    if sampled.len() < profile.len() / 4 {
+        return None;
+    }
+
+    let (first, last) = (sampled[0].0, sampled[sampled.len() - 1].0);
+    let mut heights = vec![sampled[0].1; last - first + 1];
+
+    for pair in sampled.windows(2) {
+        let ((a, low), (b, high)) = (pair[0], pair[1]);
+
+        for idx in a..=b {
+            let t = (idx - a) as f32 / (b - a) as f32;
+            heights[idx - first] = low + (high - low) * t;
+        }
+    }
+
+    Some((first, heights))
+}
+
+/// Directions no lower than any within [`PEAK_WINDOW`] of them, and higher
+/// than the one before
+fn local_maxima(heights: &[f32]) -> Vec<usize> {
+    (0..heights.len())
+        .filter(|&idx| {
+            let window = &heights
+                [idx.saturating_sub(PEAK_WINDOW)..(idx + PEAK_WINDOW + 1).min(heights.len())];
+            let highest = window.iter().copied().fold(f32::MIN, f32::max);
+
+            heights[idx] >= highest && (idx == 0 || heights[idx] > heights[idx - 1])
+        })
+        .collect()
+}
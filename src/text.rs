@@ -0,0 +1,192 @@
+use nalgebra::{Matrix4, Vector2};
+
+use crate::{
+    buffer::{BufferElementType, VertexBuffer, VertexBufferLayout},
+    check,
+    color::Color,
+    font::{FontAtlas, TextLayout, SPREAD},
+    shader::{Shader, Uniform},
+    texture::Texture,
+    vertex_array::VertexArray,
+};
+
+const TEXT_VERTEX_SHADER: &str = include_str!("shaders/text-vs.shader");
+const TEXT_FRAGMENT_SHADER: &str = include_str!("shaders/text-fs.shader");
+
+/// Number of `f32`s per vertex: a position, atlas coordinates, and a color
+/// with alpha
+const STRIDE: usize = 8;
+
+/// Text to draw in the 2D pass, in pixels from the top left of the viewport
+#[derive(Debug, Clone, Default)]
+pub struct TextBatch {
+    vertices: Vec<f32>,
+}
+
+impl TextBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Lay out `text`, `size` pixels tall, with its top left at `position`.
+    /// Returns the size of the text
+    pub fn text(
+        &mut self,
+        atlas: &FontAtlas,
+        text: &str,
+        position: Vector2<f32>,
+        size: f32,
+        color: Color,
+        alpha: f32,
+    ) -> Vector2<f32> {
+        let layout = atlas.layout(text, size);
+        self.layout(&layout, position, color, alpha);
+
+        Vector2::new(layout.width, layout.height)
+    }
+
+    /// Add text laid out ahead of time, e.g. to center it after measuring
+    pub fn layout(
+        &mut self,
+        layout: &TextLayout,
+        position: Vector2<f32>,
+        color: Color,
+        alpha: f32,
+    ) {
+        for quad in &layout.quads {
+            let (min, max) = (position + quad.min, position + quad.max);
+            let (uv_min, uv_max) = (quad.uv_min, quad.uv_max);
+
+            let corners = [
+                (min.x, min.y, uv_min.x, uv_min.y),
+                (max.x, min.y, uv_max.x, uv_min.y),
+                (max.x, max.y, uv_max.x, uv_max.y),
+                (min.x, max.y, uv_min.x, uv_max.y),
+            ];
+
+            for &idx in &[0, 1, 2, 0, 2, 3] {
+                let (x, y, u, v) = corners[idx];
+
+                self.vertices.extend_from_slice(&[
+                    x,
+                    y,
+                    u,
+                    v,
+                    color.red,
+                    color.green,
+                    color.blue,
+                    alpha,
+                ]);
+            }
+        }
+    }
+}
+
+/// The border drawn around text so it reads over any background
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub outline_color: Color,
+    /// In pixels at [`crate::font::BASE_SIZE`], so the border scales with
+    /// the text. At most [`crate::font::SPREAD`]; 0 for none
+    pub outline_width: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            outline_color: Color::BLACK,
+            outline_width: 3.0,
+        }
+    }
+}
+
+/// Draws a [`TextBatch`] with a signed distance field font, so glyphs stay
+/// sharp at any size and HUD scale. Usually driven by
+/// [`crate::OverlayPass`], which sets up the blending and depth state it
+/// expects
+pub struct TextRenderer {
+    shader: Shader,
+    va: VertexArray,
+    vb: VertexBuffer,
+    texture: Texture,
+    atlas: FontAtlas,
+}
+
+impl TextRenderer {
+    /// Requires a current GL context with loaded function pointers
+    pub fn new(atlas: FontAtlas) -> Self {
+        let mut va = VertexArray::new();
+        let vb = VertexBuffer::dynamic(&[]);
+        let mut layout = VertexBufferLayout::new();
+
+        layout.push(BufferElementType::Float, 2, false);
+        layout.push(BufferElementType::Float, 2, false);
+        layout.push(BufferElementType::Float, 4, false);
+        va.add_buffer(&vb, &layout);
+
+        va.unbind();
+        vb.unbind();
+
+        let texture = Texture::single_channel(atlas.width(), atlas.height(), atlas.pixels());
+
+        Self {
+            shader: Shader::from_source(TEXT_VERTEX_SHADER, TEXT_FRAGMENT_SHADER),
+            va,
+            vb,
+            texture,
+            atlas,
+        }
+    }
+
+    /// For laying out text to draw with this renderer
+    pub fn atlas(&self) -> &FontAtlas {
+        &self.atlas
+    }
+
+    /// Draw with premultiplied alpha blending, `projection` taking pixels to
+    /// clip space
+    pub fn draw(&mut self, batch: &TextBatch, projection: &Matrix4<f32>, style: &TextStyle) {
+        if batch.is_empty() {
+            return;
+        }
+
+        self.vb.update(&batch.vertices);
+        self.va.bind();
+        self.texture.bind(0);
+
+        self.shader.bind();
+        self.shader.set_uniform(&Uniform::OneInteger {
+            name: "atlas",
+            v0: 0,
+        });
+        self.shader.set_uniform(&Uniform::MatrixFourFv {
+            name: "projection",
+            matrix: projection,
+        });
+        self.shader.set_uniform(&Uniform::OneFloat {
+            name: "outline_width",
+            v0: style.outline_width.max(0.0).min(SPREAD) / (2.0 * SPREAD),
+        });
+        self.shader.set_uniform(&Uniform::ThreeFloat {
+            name: "outline_color",
+            v0: style.outline_color.red,
+            v1: style.outline_color.green,
+            v2: style.outline_color.blue,
+        });
+
+        let vertices = (batch.vertices.len() / STRIDE) as i32;
+        check!(unsafe { gl::DrawArrays(gl::TRIANGLES, 0, vertices) });
+
+        self.texture.unbind();
+        self.va.unbind();
+        self.vb.unbind();
+    }
+}
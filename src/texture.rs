@@ -1,14 +1,15 @@
 use rpng::Png;
 use std::path::Path;
 
-use crate::check;
+use crate::{check, image::RgbaImage};
 
 pub struct Texture {
     id: u32,
 }
 
 impl Texture {
-    pub fn new(p: impl AsRef<Path>) -> Self {
+    /// A linearly filtered texture, left bound
+    fn create() -> u32 {
         let mut id = 0;
         check!(unsafe { gl::GenTextures(1, &mut id) });
 
@@ -27,6 +28,12 @@ impl Texture {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32)
         });
 
+        id
+    }
+
+    pub fn new(p: impl AsRef<Path>) -> Self {
+        let id = Self::create();
+
         let png = Png::open(p).unwrap();
         let mut pixels = png.pixels().unwrap();
 
@@ -50,6 +57,57 @@ impl Texture {
         Self { id }
     }
 
+    /// A single-channel texture from row-major `pixels`, e.g. a font atlas.
+    /// Sampled values are in the red channel
+    pub fn single_channel(width: usize, height: usize, pixels: &[u8]) -> Self {
+        assert_eq!(pixels.len(), width * height);
+
+        let id = Self::create();
+
+        unsafe {
+            // Rows of single bytes are not padded to 4
+            check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1));
+            check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr() as *const _,
+            ));
+            check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4));
+            check!(gl::BindTexture(gl::TEXTURE_2D, 0));
+        }
+
+        Self { id }
+    }
+
+    /// A texture of `image`, e.g. a thumbnail for the 2D pass. Its first row
+    /// is at texture coordinate 0, unlike [`Texture::new`]
+    pub fn from_image(image: &RgbaImage) -> Self {
+        let id = Self::create();
+
+        check!(unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                image.width() as i32,
+                image.height() as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                image.pixels().as_ptr() as *const _,
+            )
+        });
+        check!(unsafe { gl::BindTexture(gl::TEXTURE_2D, 0) });
+
+        Self { id }
+    }
+
     pub fn bind(&self, slot: u32) {
         check!(unsafe { gl::ActiveTexture(gl::TEXTURE0 + slot) });
         check!(unsafe { gl::BindTexture(gl::TEXTURE_2D, self.id) });
@@ -0,0 +1,180 @@
+//! The colors used to identify categories and encode values, kept in one
+//! place so chips, flashes, and overlays always agree
+
+use serde::Deserialize;
+
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Tableau 10
+    Default,
+    /// The Okabe-Ito palette, distinguishable with all common forms of
+    /// color vision deficiency
+    OkabeIto,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+const TABLEAU_10: [u32; 10] = [
+    0x4E79A7, 0xF28E2B, 0xE15759, 0x76B7B2, 0x59A14F, 0xEDC948, 0xB07AA1, 0xFF9DA7, 0x9C755F,
+    0xBAB0AC,
+];
+
+/// Black is left out, since it is indistinguishable from the background
+const OKABE_ITO: [u32; 7] = [
+    0xE69F00, 0x56B4E9, 0x009E73, 0xF0E442, 0x0072B2, 0xD55E00, 0xCC79A7,
+];
+
+/// Blue to red, through white
+const DIVERGING_HEATMAP: [u32; 5] = [0x3B4CC0, 0x8DB0FE, 0xDDDDDD, 0xF49A7B, 0xB40426];
+
+/// Cividis, which stays monotonic in lightness for color-blind viewers
+const CIVIDIS_HEATMAP: [u32; 5] = [0x00224E, 0x575C6D, 0x8E8A78, 0xC9B86E, 0xFEE838];
+
+/// Base HUD sizes in logical pixels, before [`Theme::hud_scale`]
+const TEXT_SIZE: f32 = 14.0;
+const CHIP_SIZE: f32 = 12.0;
+const OUTLINE_WIDTH: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    /// Pure white scans, thicker outlines, and larger category chips
+    pub high_contrast: bool,
+    /// Multiplier for HUD text and chips, e.g. `1.5` for 150%
+    pub hud_scale: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            high_contrast: false,
+            hud_scale: 1.0,
+        }
+    }
+}
+
+impl Theme {
+    /// [`Theme::hud_scale`], limited to a range that keeps the HUD on screen
+    pub fn hud_scale(&self) -> f32 {
+        if self.hud_scale.is_finite() {
+            self.hud_scale.clamp(0.5, 4.0)
+        } else {
+            1.0
+        }
+    }
+
+    pub fn text_size(&self) -> f32 {
+        TEXT_SIZE * self.hud_scale()
+    }
+
+    /// Side length of the square identifying a category in the HUD
+    pub fn chip_size(&self) -> f32 {
+        let size = CHIP_SIZE * self.hud_scale();
+
+        if self.high_contrast {
+            size * 1.5
+        } else {
+            size
+        }
+    }
+
+    pub fn outline_width(&self) -> f32 {
+        let width = OUTLINE_WIDTH * self.hud_scale();
+
+        if self.high_contrast {
+            width * 3.0
+        } else {
+            width
+        }
+    }
+
+    pub fn object_color(&self) -> Color {
+        if self.high_contrast {
+            Color::WHITE
+        } else {
+            Color::new(0.8, 0.8, 0.8)
+        }
+    }
+
+    pub fn background(&self) -> Color {
+        Color::BLACK
+    }
+
+    /// Geometry flagged by analysis, such as floating debris
+    pub fn warning_color(&self) -> Color {
+        match self.palette {
+            Palette::Default => Color::from_hex(0xE15759),
+            Palette::OkabeIto => Color::from_hex(0xD55E00),
+        }
+    }
+
+    /// Geometry marked for removal
+    pub fn removal_color(&self) -> Color {
+        Color::from_hex(0x505050)
+    }
+
+    /// Surface regions painted with the brush
+    pub fn selection_color(&self) -> Color {
+        match self.palette {
+            Palette::Default => Color::from_hex(0xB07AA1),
+            Palette::OkabeIto => Color::from_hex(0xCC79A7),
+        }
+    }
+
+    /// Wireframes and guides drawn over the scan
+    pub fn overlay_color(&self) -> Color {
+        if self.high_contrast {
+            Color::from_hex(0xFFFF00)
+        } else {
+            Color::from_hex(0x56B4E9)
+        }
+    }
+
+    /// The x, y, or z axis in the orientation gadget
+    pub fn axis_color(&self, axis: usize) -> Color {
+        let colors = match self.palette {
+            Palette::Default => [0xE15759, 0x59A14F, 0x4E79A7],
+            Palette::OkabeIto => [0xD55E00, 0x009E73, 0x0072B2],
+        };
+
+        Color::from_hex(colors[axis % 3])
+    }
+
+    fn category_colors(&self) -> &'static [u32] {
+        match self.palette {
+            Palette::Default => &TABLEAU_10,
+            Palette::OkabeIto => &OKABE_ITO,
+        }
+    }
+
+    /// The sRGB color identifying the category at `index`, wrapping around
+    /// once the palette is exhausted
+    pub fn category_color(&self, index: usize) -> Color {
+        let colors = self.category_colors();
+
+        Color::from_hex(colors[index % colors.len()])
+    }
+
+    /// Map `t` in `[0, 1]` to an sRGB color, interpolating between stops in
+    /// linear space
+    pub fn heatmap(&self, t: f32) -> Color {
+        let stops = match self.palette {
+            Palette::Default => &DIVERGING_HEATMAP,
+            Palette::OkabeIto => &CIVIDIS_HEATMAP,
+        };
+
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let idx = (position.floor() as usize).min(stops.len() - 2);
+
+        Color::from_hex(stops[idx]).mix(Color::from_hex(stops[idx + 1]), position - idx as f32)
+    }
+}
@@ -0,0 +1,213 @@
+//! Reading 3MF scans: a zip archive holding an XML model
+//!
+//! The model part is found through the package relationships, falling back
+//! to the usual `3D/3dmodel.model`. Every object's mesh is read, scaled from
+//! the model's unit to millimeters, and shaded flat like an STL. Components,
+//! build transforms, materials, and extensions are ignored; scans exported
+//! as 3MF are a single mesh placed where it was scanned
+
+use std::io::{Cursor, Read};
+
+use nalgebra::Vector3;
+use zip::ZipArchive;
+
+use crate::{
+    loader::FacetBuilder,
+    mesh::{Mesh, MeshError},
+    scratch::ScanScratch,
+};
+
+const RELATIONSHIPS_PATH: &str = "_rels/.rels";
+const DEFAULT_MODEL_PATH: &str = "3D/3dmodel.model";
+/// Ends the `Type` of the relationship pointing at the model part
+const MODEL_RELATIONSHIP: &str = "/3dmodel";
+
+pub fn parse(buffer: &[u8], scratch: &mut ScanScratch) -> Result<Mesh, MeshError> {
+    let mut archive = ZipArchive::new(Cursor::new(buffer)).map_err(zip_error)?;
+
+    let model_path = read_entry(&mut archive, RELATIONSHIPS_PATH)
+        .ok()
+        .and_then(|rels| model_target(&rels))
+        .unwrap_or_else(|| DEFAULT_MODEL_PATH.to_owned());
+    let model = read_entry(&mut archive, &model_path)?;
+
+    let mut mesh = FacetBuilder::new(scratch);
+    let mut scale = 1.0;
+    // Of the object being read. Triangles index into their own object's
+    // vertices
+    let mut vertices: Vec<Vector3<f32>> = Vec::new();
+    let mut objects = 0;
+
+    for tag in Tags::new(&model) {
+        match tag.name {
+            "model" => {
+                scale = tag
+                    .attribute("unit")
+                    .map_or(Some(1.0), unit_scale)
+                    .ok_or_else(|| error("unknown unit"))?;
+            }
+            "object" => {
+                vertices.clear();
+                objects += 1;
+            }
+            "vertex" => {
+                let coordinate = |name: &str| -> Result<f32, MeshError> {
+                    tag.attribute(name)
+                        .and_then(|value| value.parse::<f32>().ok())
+                        .ok_or_else(|| error("vertex without x, y, and z"))
+                };
+
+                vertices.push(
+                    Vector3::new(coordinate("x")?, coordinate("y")?, coordinate("z")?) * scale,
+                );
+            }
+            "triangle" => {
+                let corner = |name: &str| -> Result<Vector3<f32>, MeshError> {
+                    tag.attribute(name)
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .and_then(|index| vertices.get(index).copied())
+                        .ok_or_else(|| error("triangle refers to a missing vertex"))
+                };
+
+                mesh.push(
+                    [corner("v1")?, corner("v2")?, corner("v3")?],
+                    Vector3::zeros(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    if objects == 0 {
+        return Err(error("no objects in the model"));
+    }
+
+    Ok(mesh.finish())
+}
+
+/// The model part named in the package relationships, without its leading
+/// `/`
+fn model_target(relationships: &str) -> Option<String> {
+    Tags::new(relationships)
+        .filter(|tag| tag.name == "Relationship")
+        .find(|tag| {
+            tag.attribute("Type")
+                .map_or(false, |kind| kind.ends_with(MODEL_RELATIONSHIP))
+        })
+        .and_then(|tag| tag.attribute("Target"))
+        .map(|target| target.trim_start_matches('/').to_owned())
+}
+
+/// Millimeters per model unit
+fn unit_scale(unit: &str) -> Option<f32> {
+    Some(match unit {
+        "micron" => 0.001,
+        "millimeter" => 1.0,
+        "centimeter" => 10.0,
+        "inch" => 25.4,
+        "foot" => 304.8,
+        "meter" => 1000.0,
+        _ => return None,
+    })
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String, MeshError> {
+    let mut entry = archive.by_name(name).map_err(zip_error)?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+
+    Ok(contents)
+}
+
+/// An opening or self-closing XML tag, with its namespace prefix removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tag<'a> {
+    name: &'a str,
+    /// Everything between the name and the closing `>` or `/>`
+    attributes: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    /// The value of the attribute `name`, as written. Entities are left
+    /// escaped, since numbers and part names do not use them
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        let mut rest = self.attributes;
+
+        loop {
+            let eq = rest.find('=')?;
+            let key = rest[..eq].trim();
+            let after = rest[eq + 1..].trim_start();
+            let quote = after.chars().next()?;
+
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+
+            let end = after[1..].find(quote)?;
+            let value = &after[1..end + 1];
+
+            if key.rsplit(':').next() == Some(name) {
+                return Some(value);
+            }
+
+            rest = &after[end + 2..];
+        }
+    }
+}
+
+/// The opening and self-closing tags of an XML document, in order.
+/// Closing tags, comments, declarations, and text are skipped; nothing in a
+/// 3MF model needs them
+struct Tags<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tags<'a> {
+    fn new(xml: &'a str) -> Self {
+        Self { rest: xml }
+    }
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Tag<'a>> {
+        loop {
+            let start = self.rest.find('<')?;
+            self.rest = &self.rest[start + 1..];
+
+            if self.rest.starts_with("!--") {
+                let end = self.rest.find("-->")?;
+                self.rest = &self.rest[end + 3..];
+                continue;
+            }
+
+            let end = self.rest.find('>')?;
+            let inner = &self.rest[..end];
+            self.rest = &self.rest[end + 1..];
+
+            if inner.starts_with('/') || inner.starts_with('?') || inner.starts_with('!') {
+                continue;
+            }
+
+            let inner = inner.trim_end_matches('/');
+            let name_end = inner
+                .find(|ch: char| ch.is_ascii_whitespace())
+                .unwrap_or(inner.len());
+            let name = &inner[..name_end];
+
+            return Some(Tag {
+                name: name.rsplit(':').next().unwrap_or(name),
+                attributes: &inner[name_end..],
+            });
+        }
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> MeshError {
+    MeshError::Parse(format!("3MF: {}", e))
+}
+
+fn error(message: &str) -> MeshError {
+    MeshError::Parse(format!("3MF: {}", message))
+}
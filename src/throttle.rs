@@ -0,0 +1,51 @@
+use std::{
+    io::{self, Read},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Reads are split into chunks of at most this size, so each pause is short
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Caps the average rate at which `inner` is read by sleeping between reads,
+/// so background loads leave bandwidth for everything else on the link
+#[derive(Debug)]
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_sec: u64,
+    started: Instant,
+    bytes_read: u64,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    pub fn new(inner: R, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: Instant::now(),
+            bytes_read: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(CHUNK_SIZE);
+        let n = self.inner.read(&mut buf[..len])?;
+
+        self.bytes_read += n as u64;
+
+        let expected = Duration::from_secs_f64(self.bytes_read as f64 / self.bytes_per_sec as f64);
+        let elapsed = self.started.elapsed();
+
+        if expected > elapsed {
+            thread::sleep(expected - elapsed);
+        }
+
+        Ok(n)
+    }
+}
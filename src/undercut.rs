@@ -0,0 +1,101 @@
+//! Which parts of a die scan are hidden when looking down its insertion axis
+//!
+//! A crown slides onto its prep along one direction, so any surface that
+//! cannot be seen from that direction is an undercut: the crown cannot reach
+//! it, and a margin running through it was either not prepped or not
+//! captured. Hidden surface is found by casting a ray from each triangle
+//! toward the viewer and checking whether the scan is in the way
+
+use nalgebra::Vector3;
+
+use crate::{
+    analysis::WeldedMesh,
+    picking::{Ray, TriangleBvh},
+};
+
+/// Rays start this fraction of the scan's size off their triangle, so they
+/// do not hit it or its neighbours
+const RAY_OFFSET: f32 = 1e-4;
+
+/// Scans whose surface, summed with its orientation, is below this fraction
+/// of its area are taken as closed, with no side they open toward
+const CLOSED_FRACTION: f32 = 0.05;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Undercuts {
+    /// Unit direction the restoration is seated along, pointing from the
+    /// prep toward the viewer
+    pub axis: Vector3<f32>,
+    /// Per triangle of the scan, whether it is hidden along `axis`
+    pub faces: Vec<bool>,
+    /// Total area of hidden triangles, in squared scan units
+    pub area: f64,
+    /// Hidden area as a fraction of the scan's surface
+    pub fraction: f64,
+}
+
+impl Undercuts {
+    /// Find the triangles of `welded` hidden along `axis`. `bvh` is built
+    /// from the scan as loaded, so its triangles are indexed like `welded`'s
+    pub fn new(welded: &WeldedMesh, bvh: &TriangleBvh, axis: Vector3<f32>) -> Self {
+        let axis = axis.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::z);
+
+        let (min, max) = welded.positions.iter().fold(
+            (Vector3::repeat(f32::MAX), Vector3::repeat(f32::MIN)),
+            |(min, max), position| (min.inf(position), max.sup(position)),
+        );
+        let offset = RAY_OFFSET * (max - min).norm();
+
+        let (mut area, mut total) = (0.0, 0.0);
+
+        let faces = welded
+            .triangles
+            .iter()
+            .enumerate()
+            .map(|(idx, tri)| {
+                let [a, b, c] = welded.corners(tri);
+                let triangle_area = (b - a).cross(&(c - a)).norm() as f64 / 2.0;
+                total += triangle_area;
+
+                let ray = Ray {
+                    origin: (a + b + c) / 3.0 + axis * offset,
+                    direction: axis,
+                };
+                let hidden = bvh.pick(&ray).map_or(false, |hit| hit.triangle != idx);
+
+                if hidden {
+                    area += triangle_area;
+                }
+
+                hidden
+            })
+            .collect();
+
+        Self {
+            axis,
+            faces,
+            area,
+            fraction: if total > 0.0 { area / total } else { 0.0 },
+        }
+    }
+
+    /// The direction a die scan opens toward, which is its likely insertion
+    /// axis: scans are open at the base of the die, so their surface faces
+    /// the occlusal side on the whole. +z if the scan is closed, or nearly
+    pub fn default_axis(welded: &WeldedMesh) -> Vector3<f32> {
+        let (sum, total) = welded.triangles.iter().fold(
+            (Vector3::zeros(), 0.0),
+            |(sum, total): (Vector3<f32>, f32), triangle| {
+                let [a, b, c] = welded.corners(triangle);
+                let normal = (b - a).cross(&(c - a));
+                (sum + normal, total + normal.norm())
+            },
+        );
+
+        if sum.norm() > CLOSED_FRACTION * total {
+            sum.normalize()
+        } else {
+            Vector3::z()
+        }
+    }
+}
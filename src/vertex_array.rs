@@ -23,6 +23,10 @@ impl VertexArray {
         Self { id }
     }
 
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindVertexArray(self.id);
@@ -36,6 +40,27 @@ impl VertexArray {
     }
 
     pub fn add_buffer(&mut self, vb: &VertexBuffer, layout: &VertexBufferLayout) {
+        self.add_attributes(vb, layout, 0, 0);
+    }
+
+    /// Add attributes that advance once per instance rather than once per
+    /// vertex, starting at location `first_location`
+    pub fn add_instance_buffer(
+        &mut self,
+        vb: &VertexBuffer,
+        layout: &VertexBufferLayout,
+        first_location: u32,
+    ) {
+        self.add_attributes(vb, layout, first_location, 1);
+    }
+
+    fn add_attributes(
+        &mut self,
+        vb: &VertexBuffer,
+        layout: &VertexBufferLayout,
+        first_location: u32,
+        divisor: u32,
+    ) {
         vb.bind();
         self.bind();
 
@@ -43,19 +68,22 @@ impl VertexArray {
 
         let mut offset = 0;
 
-        for (idx, element) in elements.into_iter().enumerate() {
+        for (idx, element) in elements.iter().enumerate() {
+            let location = first_location + idx as u32;
+
             unsafe {
-                gl::EnableVertexAttribArray(idx as u32);
+                gl::EnableVertexAttribArray(location);
                 gl::VertexAttribPointer(
-                    idx as u32,
+                    location,
                     element.count as i32,
                     element.ty as u32,
                     element.normalized as u8,
                     layout.stride as i32,
                     offset as *const _,
                 );
+                gl::VertexAttribDivisor(location, divisor);
             }
-            offset += element.count * element.ty.size_of() as u32;
+            offset += element.ty.attribute_size(element.count);
         }
     }
 }
@@ -0,0 +1,139 @@
+//! How a scan's vertices are stored on the GPU
+//!
+//! Full precision takes 24 bytes per vertex. Half-float normals bring that
+//! to 20 and packed normals to 16, and half-float positions save another 4,
+//! which lets the biggest scans fit in the memory of integrated GPUs without
+//! visible loss
+
+use nalgebra::{Matrix4, Vector3};
+use serde::Deserialize;
+
+use crate::mesh::{Mesh, VERTEX_STRIDE};
+
+/// Encoding of the position attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PositionFormat {
+    /// Three `f32`s, exactly as parsed
+    Float,
+    /// Four `f16`s, the last unused, quantized relative to the bounding box.
+    /// Precision is about 1/2000th of the scan's extent along each axis
+    Half,
+}
+
+impl Default for PositionFormat {
+    fn default() -> Self {
+        Self::Float
+    }
+}
+
+/// Encoding of the normal attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalFormat {
+    /// Three `f32`s
+    Float,
+    /// Four `f16`s, the last unused so attributes stay 4-byte aligned
+    Half,
+    /// Three signed 10-bit components in one `INT_2_10_10_10_REV` word,
+    /// accurate to about a tenth of a degree
+    Packed,
+}
+
+impl Default for NormalFormat {
+    fn default() -> Self {
+        Self::Float
+    }
+}
+
+/// The encoding of each vertex attribute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VertexFormat {
+    pub positions: PositionFormat,
+    pub normals: NormalFormat,
+}
+
+impl VertexFormat {
+    /// Bytes per vertex
+    pub fn stride(self) -> usize {
+        let positions = match self.positions {
+            PositionFormat::Float => 12,
+            PositionFormat::Half => 8,
+        };
+
+        let normals = match self.normals {
+            NormalFormat::Float => 12,
+            NormalFormat::Half => 8,
+            NormalFormat::Packed => 4,
+        };
+
+        positions + normals
+    }
+
+    /// The vertices of `mesh`, interleaved in this format
+    pub fn encode(self, mesh: &Mesh) -> EncodedVertices {
+        let bbox = mesh.bounding_box();
+        let extent = (bbox.max - bbox.min).map(|e| if e > 0.0 { e } else { 1.0 });
+
+        let mut data = Vec::with_capacity(mesh.vertex_count() * self.stride());
+
+        for vertex in mesh.vertices().chunks_exact(VERTEX_STRIDE) {
+            let position = Vector3::new(vertex[0], vertex[1], vertex[2]);
+
+            match self.positions {
+                PositionFormat::Float => push_floats(&mut data, &vertex[..3]),
+                PositionFormat::Half => {
+                    let quantized = (position - bbox.min).component_div(&extent);
+                    push_halves(&mut data, quantized.as_slice());
+                }
+            }
+
+            match self.normals {
+                NormalFormat::Float => push_floats(&mut data, &vertex[3..]),
+                NormalFormat::Half => push_halves(&mut data, &vertex[3..]),
+                NormalFormat::Packed => {
+                    data.extend_from_slice(&pack_normal(&vertex[3..]).to_ne_bytes())
+                }
+            }
+        }
+
+        let dequantize = match self.positions {
+            PositionFormat::Float => Matrix4::identity(),
+            PositionFormat::Half => {
+                Matrix4::new_translation(&bbox.min) * Matrix4::new_nonuniform_scaling(&extent)
+            }
+        };
+
+        EncodedVertices { data, dequantize }
+    }
+}
+
+/// Vertex data ready to upload, see [`VertexFormat::encode`]
+#[derive(Debug, Clone)]
+pub struct EncodedVertices {
+    pub data: Vec<u8>,
+    /// Takes encoded positions back to model space, applied before the
+    /// scan's model matrix
+    pub dequantize: Matrix4<f32>,
+}
+
+fn push_floats(data: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        data.extend_from_slice(&value.to_ne_bytes());
+    }
+}
+
+/// Three values and a zero, padding the attribute to 8 bytes
+fn push_halves(data: &mut Vec<u8>, values: &[f32]) {
+    for &value in values.iter().chain(&[0.0]) {
+        data.extend_from_slice(&half::f16::from_f32(value).to_bits().to_ne_bytes());
+    }
+}
+
+/// A unit vector as signed normalized 10-bit components, with the 2-bit
+/// component left zero
+fn pack_normal(normal: &[f32]) -> u32 {
+    let component = |value: f32| (value.max(-1.0).min(1.0) * 511.0).round() as i32 as u32 & 0x3FF;
+
+    component(normal[0]) | (component(normal[1]) << 10) | (component(normal[2]) << 20)
+}
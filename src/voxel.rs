@@ -0,0 +1,210 @@
+//! Scans as blocks on a regular grid
+//!
+//! Every voxel the surface passes through is filled, so a thin wall shows
+//! up as a single layer of blocks and a doubled surface as two layers with a
+//! gap, both easy to miss on the smooth-shaded scan. The same occupancy grid
+//! can be saved as a NumPy array for training models on
+
+use std::io::{self, Write};
+
+use nalgebra::Vector3;
+
+use crate::mesh::{Mesh, VERTEX_STRIDE};
+
+/// Points are sampled on each triangle at most this far apart, relative to
+/// the voxel size, so no voxel a triangle crosses is missed
+const SAMPLE_SPACING: f32 = 0.5;
+
+/// Which voxels of a grid over a scan's bounding box its surface passes
+/// through
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+    /// The corner of voxel `[0, 0, 0]` with the smallest coordinates
+    pub origin: Vector3<f32>,
+    /// Length of each voxel's sides
+    pub voxel_size: f32,
+    /// Voxels along x, y, and z
+    pub dims: [usize; 3],
+    /// Indexed by x, then y, then z, as [`VoxelGrid::index`] gives
+    occupied: Vec<bool>,
+}
+
+impl VoxelGrid {
+    /// Voxelize the surface of `mesh` with `resolution` voxels along the
+    /// longest side of its bounding box
+    pub fn new(mesh: &Mesh, resolution: usize) -> Self {
+        let bbox = mesh.bounding_box();
+        let delta = bbox.delta();
+        let longest = delta.x.max(delta.y).max(delta.z);
+        let voxel_size = if longest > 0.0 {
+            longest / resolution.max(1) as f32
+        } else {
+            1.0
+        };
+
+        let dims = [
+            ((delta.x / voxel_size).ceil() as usize).max(1),
+            ((delta.y / voxel_size).ceil() as usize).max(1),
+            ((delta.z / voxel_size).ceil() as usize).max(1),
+        ];
+
+        let mut grid = Self {
+            origin: bbox.min,
+            voxel_size,
+            dims,
+            occupied: vec![false; dims[0] * dims[1] * dims[2]],
+        };
+
+        for triangle in mesh.triangles() {
+            let [a, b, c] = [
+                mesh.position(triangle[0]),
+                mesh.position(triangle[1]),
+                mesh.position(triangle[2]),
+            ];
+            let longest_edge = (b - a).norm().max((c - a).norm()).max((c - b).norm());
+            let steps = ((longest_edge / (voxel_size * SAMPLE_SPACING)).ceil() as usize).max(1);
+
+            for i in 0..=steps {
+                for j in 0..=steps - i {
+                    let point = a
+                        + (b - a) * (i as f32 / steps as f32)
+                        + (c - a) * (j as f32 / steps as f32);
+
+                    let voxel = grid.voxel_at(point);
+                    let idx = grid.index(voxel);
+                    grid.occupied[idx] = true;
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// The voxel containing `point`, clamped to the grid
+    fn voxel_at(&self, point: Vector3<f32>) -> [usize; 3] {
+        let offset = (point - self.origin) / self.voxel_size;
+        let clamp = |value: f32, dim: usize| (value.max(0.0) as usize).min(dim - 1);
+
+        [
+            clamp(offset.x, self.dims[0]),
+            clamp(offset.y, self.dims[1]),
+            clamp(offset.z, self.dims[2]),
+        ]
+    }
+
+    fn index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (x * self.dims[1] + y) * self.dims[2] + z
+    }
+
+    pub fn is_occupied(&self, voxel: [usize; 3]) -> bool {
+        voxel.iter().zip(&self.dims).all(|(&at, &dim)| at < dim) && self.occupied[self.index(voxel)]
+    }
+
+    pub fn occupied_count(&self) -> usize {
+        self.occupied.iter().filter(|&&occupied| occupied).count()
+    }
+
+    /// A cube for each occupied voxel, with only the faces not against
+    /// another occupied voxel, shaded flat
+    pub fn to_mesh(&self) -> Mesh {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for x in 0..self.dims[0] {
+            for y in 0..self.dims[1] {
+                for z in 0..self.dims[2] {
+                    let voxel = [x, y, z];
+
+                    if !self.occupied[self.index(voxel)] {
+                        continue;
+                    }
+
+                    for axis in 0..3 {
+                        for &positive in &[false, true] {
+                            let mut neighbor = voxel;
+                            let exposed = if positive {
+                                neighbor[axis] += 1;
+                                !self.is_occupied(neighbor)
+                            } else {
+                                voxel[axis].checked_sub(1).map_or(true, |at| {
+                                    neighbor[axis] = at;
+                                    !self.is_occupied(neighbor)
+                                })
+                            };
+
+                            if exposed {
+                                self.push_face(voxel, axis, positive, &mut vertices, &mut indices);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Mesh::new(vertices, indices)
+    }
+
+    /// The side of `voxel` facing along `axis`, wound counterclockwise seen
+    /// from outside
+    fn push_face(
+        &self,
+        voxel: [usize; 3],
+        axis: usize,
+        positive: bool,
+        vertices: &mut Vec<f32>,
+        indices: &mut Vec<u32>,
+    ) {
+        // Across `u` then `v` turns counterclockwise about +axis
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        let mut normal = Vector3::zeros();
+        normal[axis] = if positive { 1.0 } else { -1.0 };
+
+        let mut corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        if !positive {
+            corners.reverse();
+        }
+
+        let first = (vertices.len() / VERTEX_STRIDE) as u32;
+
+        for &(du, dv) in &corners {
+            let mut corner = Vector3::new(voxel[0] as f32, voxel[1] as f32, voxel[2] as f32);
+            corner[axis] += if positive { 1.0 } else { 0.0 };
+            corner[u] += du;
+            corner[v] += dv;
+
+            let position = self.origin + corner * self.voxel_size;
+            vertices.extend(position.iter().chain(normal.iter()));
+        }
+
+        indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    /// Write the grid as a NumPy `.npy` array of `uint8`, shaped x by y by
+    /// z, 1 where occupied
+    pub fn write_npy(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}, {}), }}",
+            self.dims[0], self.dims[1], self.dims[2]
+        );
+
+        // The magic, version, and header length take 10 bytes, and data
+        // must start on a multiple of 64
+        let padded = (10 + header.len() + 1 + 63) / 64 * 64;
+        while 10 + header.len() + 1 < padded {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        writer.write_all(b"\x93NUMPY\x01\x00")?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+
+        let data: Vec<u8> = self
+            .occupied
+            .iter()
+            .map(|&occupied| occupied as u8)
+            .collect();
+        writer.write_all(&data)
+    }
+}
@@ -0,0 +1,466 @@
+//! Browser bindings, built with `wasm-pack build --target web`
+//!
+//! [`Viewer`] draws scans into a canvas through WebGL2, and [`WebSession`]
+//! runs the labeling flow. Writing labels down is left to the page
+
+use glow::HasContext;
+use nalgebra::Matrix4;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
+
+use crate::{
+    analysis::MeshMetrics,
+    camera::StationaryCamera,
+    mesh::{BoundingBox, Mesh, VERTEX_STRIDE},
+    scene::{NodeId, Scene},
+    session::{Progress, Session},
+    theme::Theme,
+};
+
+const VERTEX_SHADER: &str = include_str!("shaders/web-vs.shader");
+const FRAGMENT_SHADER: &str = include_str!("shaders/web-fs.shader");
+
+type Program = <glow::Context as HasContext>::Program;
+type Buffer = <glow::Context as HasContext>::Buffer;
+type Shader = <glow::Context as HasContext>::Shader;
+type VertexArray = <glow::Context as HasContext>::VertexArray;
+
+#[wasm_bindgen]
+pub struct ScanAnalysis {
+    metrics: MeshMetrics,
+}
+
+#[wasm_bindgen]
+impl ScanAnalysis {
+    #[wasm_bindgen(getter)]
+    pub fn volume(&self) -> f64 {
+        self.metrics.volume
+    }
+
+    #[wasm_bindgen(getter = surfaceArea)]
+    pub fn surface_area(&self) -> f64 {
+        self.metrics.surface_area
+    }
+
+    #[wasm_bindgen(getter = holeCount)]
+    pub fn hole_count(&self) -> u32 {
+        self.metrics.hole_count as u32
+    }
+
+    #[wasm_bindgen(getter = shellCount)]
+    pub fn shell_count(&self) -> u32 {
+        self.metrics.shell_count as u32
+    }
+
+    /// Triangles wound against their neighbors
+    #[wasm_bindgen(getter = inconsistentTriangleCount)]
+    pub fn inconsistent_triangle_count(&self) -> u32 {
+        self.metrics.inconsistent_triangle_count as u32
+    }
+
+    #[wasm_bindgen(getter = degenerateTriangleCount)]
+    pub fn degenerate_triangle_count(&self) -> u32 {
+        self.metrics.degenerate_triangle_count as u32
+    }
+
+    #[wasm_bindgen(getter = duplicateTriangleCount)]
+    pub fn duplicate_triangle_count(&self) -> u32 {
+        self.metrics.duplicate_triangle_count as u32
+    }
+
+    #[wasm_bindgen(getter = triangleCount)]
+    pub fn triangle_count(&self) -> u32 {
+        self.metrics.triangle_count as u32
+    }
+
+    /// Surface roughness in degrees
+    #[wasm_bindgen(getter)]
+    pub fn noise(&self) -> f64 {
+        self.metrics.noise
+    }
+
+    /// `[min_x, min_y, min_z, max_x, max_y, max_z]`
+    #[wasm_bindgen(getter = boundingBox)]
+    pub fn bounding_box(&self) -> Vec<f32> {
+        let bbox = self.metrics.bounding_box;
+
+        vec![
+            bbox.min.x, bbox.min.y, bbox.min.z, bbox.max.x, bbox.max.y, bbox.max.z,
+        ]
+    }
+}
+
+/// Analyze the bytes of a scan in any supported format, e.g. from a `fetch`
+/// response. `name`'s extension is only consulted if the contents are not
+/// recognized
+#[wasm_bindgen(js_name = analyzeScan)]
+pub fn analyze_scan(bytes: &[u8], name: Option<String>) -> Result<ScanAnalysis, JsValue> {
+    let mesh = parse_scan(bytes, name)?;
+
+    Ok(ScanAnalysis {
+        metrics: MeshMetrics::new(&mesh),
+    })
+}
+
+fn parse_scan(bytes: &[u8], name: Option<String>) -> Result<Mesh, JsValue> {
+    Mesh::from_bytes(bytes, name.as_deref()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Vertex and index data of a parsed scan, ready to upload with
+/// `gl.bufferData`
+#[wasm_bindgen]
+pub struct ScanBuffers {
+    mesh: Mesh,
+}
+
+#[wasm_bindgen]
+impl ScanBuffers {
+    /// Parse a scan as [`analyze_scan`] does
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8], name: Option<String>) -> Result<ScanBuffers, JsValue> {
+        Ok(Self {
+            mesh: parse_scan(bytes, name)?,
+        })
+    }
+
+    /// Interleaved `[x, y, z, nx, ny, nz]` vertex data
+    pub fn vertices(&self) -> Vec<f32> {
+        self.mesh.vertices().to_vec()
+    }
+
+    pub fn indices(&self) -> Vec<u32> {
+        self.mesh.indices().to_vec()
+    }
+}
+
+/// A scan drawn into a canvas through WebGL2, rotated by dragging
+#[wasm_bindgen]
+pub struct Viewer {
+    canvas: HtmlCanvasElement,
+    gl: glow::Context,
+    program: Program,
+    scan: Option<UploadedScan>,
+    stationary: StationaryCamera,
+    scene: Scene,
+    scan_node: NodeId,
+    theme: Theme,
+}
+
+struct UploadedScan {
+    vertex_array: VertexArray,
+    vertices: Buffer,
+    indices: Buffer,
+    index_count: i32,
+    bbox: BoundingBox,
+}
+
+#[wasm_bindgen]
+impl Viewer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement) -> Result<Viewer, JsValue> {
+        let context = canvas
+            .get_context("webgl2")?
+            .ok_or_else(|| JsValue::from_str("WebGL2 is not supported"))?
+            .dyn_into::<WebGl2RenderingContext>()?;
+        let gl = glow::Context::from_webgl2_context(context);
+
+        let program = unsafe { link_program(&gl) }.map_err(|e| JsValue::from_str(&e))?;
+
+        let mut scene = Scene::new();
+        let scan_node = scene.add("scan", StationaryCamera::default_model(), None);
+
+        Ok(Self {
+            canvas,
+            gl,
+            program,
+            scan: None,
+            stationary: StationaryCamera::new(),
+            scene,
+            scan_node,
+            theme: Theme::default(),
+        })
+    }
+
+    /// Replace the displayed scan, shown in the default orientation. If it
+    /// cannot be uploaded, nothing is shown
+    pub fn load(&mut self, scan: &ScanBuffers) -> Result<(), JsValue> {
+        self.unload();
+        self.scene.node_mut(self.scan_node).local = StationaryCamera::default_model();
+
+        if scan.mesh.is_empty() {
+            return Ok(());
+        }
+
+        let uploaded = unsafe { UploadedScan::new(&self.gl, &scan.mesh) };
+        self.scan = Some(uploaded.map_err(|e| JsValue::from_str(&e))?);
+
+        Ok(())
+    }
+
+    /// Turn the scan as a mouse drag of `dx` by `dy` pixels would in the
+    /// desktop viewer
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        self.stationary
+            .move_mouse(&mut self.scene, self.scan_node, dx, dy);
+    }
+
+    /// Draw a frame at the canvas's current size
+    pub fn draw(&mut self) {
+        let (width, height) = (self.canvas.width() as i32, self.canvas.height() as i32);
+        let background = self.theme.background();
+        let gl = &self.gl;
+
+        unsafe {
+            gl.viewport(0, 0, width, height);
+            gl.enable(glow::DEPTH_TEST);
+            gl.clear_color(background.red, background.green, background.blue, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+
+        let scan = match &self.scan {
+            Some(scan) if width > 0 && height > 0 => scan,
+            _ => return,
+        };
+
+        let projection: Matrix4<f32> = nalgebra_glm::perspective(
+            width as f32 / height as f32,
+            45.0_f32.to_radians(),
+            1.0,
+            1000.0,
+        );
+        let model = self.scene.world(self.scan_node);
+        let view = self.stationary.view(scan.bbox);
+        let light_pos = self.stationary.pos(scan.bbox);
+        let object_color = self.theme.object_color();
+        let program = self.program;
+
+        unsafe {
+            gl.use_program(Some(program));
+
+            let matrix = |name: &str, matrix: &Matrix4<f32>| {
+                gl.uniform_matrix_4_f32_slice(
+                    gl.get_uniform_location(program, name).as_ref(),
+                    false,
+                    matrix.as_slice(),
+                );
+            };
+            matrix("model", &model);
+            matrix("view", &view);
+            matrix("projection", &projection);
+
+            let vector = |name: &str, [x, y, z]: [f32; 3]| {
+                gl.uniform_3_f32(gl.get_uniform_location(program, name).as_ref(), x, y, z);
+            };
+            vector("light_pos", light_pos);
+            vector("light_color", [1.0, 1.0, 1.0]);
+            vector(
+                "object_color",
+                [object_color.red, object_color.green, object_color.blue],
+            );
+
+            gl.bind_vertex_array(Some(scan.vertex_array));
+            gl.draw_elements(glow::TRIANGLES, scan.index_count, glow::UNSIGNED_INT, 0);
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    fn unload(&mut self) {
+        if let Some(scan) = self.scan.take() {
+            unsafe { scan.delete(&self.gl) };
+        }
+    }
+}
+
+impl UploadedScan {
+    /// Objects created before a failure are deleted again
+    unsafe fn new(gl: &glow::Context, mesh: &Mesh) -> Result<Self, String> {
+        let vertex_array = gl.create_vertex_array()?;
+
+        let (vertices, indices) = match (gl.create_buffer(), gl.create_buffer()) {
+            (Ok(vertices), Ok(indices)) => (vertices, indices),
+            (vertices, indices) => {
+                gl.delete_vertex_array(vertex_array);
+                vertices
+                    .iter()
+                    .chain(&indices)
+                    .for_each(|&buffer| gl.delete_buffer(buffer));
+
+                return Err(vertices.and(indices).err().unwrap_or_default());
+            }
+        };
+
+        let scan = Self {
+            vertex_array,
+            vertices,
+            indices,
+            index_count: mesh.indices().len() as i32,
+            bbox: mesh.bounding_box(),
+        };
+
+        gl.bind_vertex_array(Some(vertex_array));
+
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertices));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            bytemuck::cast_slice(mesh.vertices()),
+            glow::STATIC_DRAW,
+        );
+
+        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(indices));
+        gl.buffer_data_u8_slice(
+            glow::ELEMENT_ARRAY_BUFFER,
+            bytemuck::cast_slice(mesh.indices()),
+            glow::STATIC_DRAW,
+        );
+
+        // Positions, then normals, as in `web-vs.shader`
+        let stride = (VERTEX_STRIDE * std::mem::size_of::<f32>()) as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, glow::FLOAT, false, stride, 12);
+        gl.enable_vertex_attrib_array(1);
+
+        gl.bind_vertex_array(None);
+
+        // Most likely out of memory for a large scan
+        match gl.get_error() {
+            glow::NO_ERROR => Ok(scan),
+            error => {
+                scan.delete(gl);
+
+                Err(format!("unable to upload the scan (GL error {:#x})", error))
+            }
+        }
+    }
+
+    unsafe fn delete(self, gl: &glow::Context) {
+        gl.delete_vertex_array(self.vertex_array);
+        gl.delete_buffer(self.vertices);
+        gl.delete_buffer(self.indices);
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        self.unload();
+
+        unsafe { self.gl.delete_program(self.program) };
+    }
+}
+
+/// The program and its shaders are deleted again if any fails to build
+unsafe fn link_program(gl: &glow::Context) -> Result<Program, String> {
+    let program = gl.create_program()?;
+    let mut shaders = Vec::new();
+    let mut result = Ok(());
+
+    for &(kind, source) in &[
+        (glow::VERTEX_SHADER, VERTEX_SHADER),
+        (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+    ] {
+        match compile_shader(gl, kind, source) {
+            Ok(shader) => {
+                gl.attach_shader(program, shader);
+                shaders.push(shader);
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    if result.is_ok() {
+        gl.link_program(program);
+
+        if !gl.get_program_link_status(program) {
+            result = Err(gl.get_program_info_log(program));
+        }
+    }
+
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+
+    if result.is_err() {
+        gl.delete_program(program);
+    }
+
+    result.map(|()| program)
+}
+
+unsafe fn compile_shader(gl: &glow::Context, kind: u32, source: &str) -> Result<Shader, String> {
+    let shader = gl.create_shader(kind)?;
+    gl.shader_source(shader, source);
+    gl.compile_shader(shader);
+
+    if gl.get_shader_compile_status(shader) {
+        Ok(shader)
+    } else {
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+
+        Err(log)
+    }
+}
+
+/// The labeling flow of [`Session`], for a page that shows each scan with
+/// [`Viewer`] and writes down the labels itself
+#[wasm_bindgen(js_name = Session)]
+pub struct WebSession {
+    session: Session,
+}
+
+#[wasm_bindgen(js_class = Session)]
+impl WebSession {
+    /// `queue` is an array of scan paths or URLs, in the order to label them
+    #[wasm_bindgen(constructor)]
+    pub fn new(queue: Box<[JsValue]>) -> WebSession {
+        let queue = queue.iter().filter_map(JsValue::as_string).collect();
+
+        Self {
+            session: Session::new(queue),
+        }
+    }
+
+    #[wasm_bindgen(getter = currentPath)]
+    pub fn current_path(&self) -> Option<String> {
+        self.session.current_path().map(str::to_owned)
+    }
+
+    /// Open the next scan, returning its path, or `undefined` at the end of
+    /// the queue
+    pub fn advance(&mut self) -> Option<String> {
+        self.session.advance().map(str::to_owned)
+    }
+
+    /// Label the current scan, returning its path, or `undefined` if there
+    /// is no current scan
+    pub fn label(&mut self, label: &str) -> Option<String> {
+        self.session
+            .label(label)
+            .map(|record| record.path().to_owned())
+    }
+
+    /// Take back the last scan committed, which is opened again by the next
+    /// call to `advance`. Returns its path, or `undefined` if nothing was
+    /// committed
+    pub fn undo(&mut self) -> Option<String> {
+        self.session.undo().map(|record| record.path().to_owned())
+    }
+
+    /// Where the session is, in the format of the desktop tool's progress
+    /// file, to store and pass to `resume` after a reload
+    pub fn progress(&self) -> String {
+        self.session.progress().to_string()
+    }
+
+    /// Pick up where `progress` left off, returning whether it was resumed
+    pub fn resume(&mut self, progress: &str) -> bool {
+        match Progress::parse(progress) {
+            Some(progress) => self.session.resume(&progress),
+            None => false,
+        }
+    }
+}
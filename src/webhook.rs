@@ -0,0 +1,255 @@
+//! Notifications posted to a webhook, such as a Slack incoming webhook, so
+//! leads hear when a session finishes or runs into trouble without asking
+//!
+//! A message is posted when the queue is exhausted, when too many of the
+//! scans opened recently failed to load, when the tool panics, and when it
+//! starts again after a session that ended without shutting down cleanly.
+//! The last is noticed from a marker file left in the working directory
+//! while a session runs
+
+use std::{
+    collections::VecDeque,
+    fmt, fs, io, panic,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::{config::NotificationConfig, i18n::Catalog};
+
+/// Exists while a session runs, so one that crashed or was killed is
+/// noticed when the tool starts again
+pub const RUNNING_MARKER_PATH: &str = "./.scan-classification-running";
+
+/// What a notification is about, available to payload templates as
+/// `{event}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    Finished,
+    ErrorSpike,
+    Crashed,
+    Restarted,
+}
+
+impl WebhookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Finished => "finished",
+            Self::ErrorSpike => "error_spike",
+            Self::Crashed => "crashed",
+            Self::Restarted => "restarted",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+    Http(Box<ureq::Error>),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Http(e) => write!(f, "unable to post notification: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WebhookError {}
+
+/// Where notifications are posted and what they look like, cheap to clone
+/// into the panic hook and sending threads
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    url: String,
+    template: String,
+    annotator: Option<String>,
+}
+
+impl Target {
+    /// `template` with `{event}`, `{message}`, and `{annotator}` replaced,
+    /// each escaped to sit inside a JSON string
+    fn payload(&self, event: WebhookEvent, message: &str) -> String {
+        self.template
+            .replace("{event}", &json_escape(event.name()))
+            .replace("{message}", &json_escape(message))
+            .replace(
+                "{annotator}",
+                &json_escape(self.annotator.as_deref().unwrap_or_default()),
+            )
+    }
+
+    fn post(&self, event: WebhookEvent, message: &str) -> Result<(), WebhookError> {
+        ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&self.payload(event, message))
+            .map(drop)
+            .map_err(|e| WebhookError::Http(Box::new(e)))
+    }
+}
+
+#[derive(Debug)]
+pub struct Webhook {
+    target: Target,
+    catalog: Catalog,
+    marker: PathBuf,
+    error_rate: f64,
+    error_window: usize,
+    /// Whether each of the last `error_window` scans opened failed to load
+    recent: VecDeque<bool>,
+    /// Set once a spike is reported, and cleared when the rate falls back
+    /// under `error_rate`, so one spike is only reported once
+    spiking: bool,
+    finished: bool,
+}
+
+impl Webhook {
+    /// `None` unless `notifications.webhook_url` is set. Reports a previous
+    /// session that did not shut down cleanly, then marks this one as
+    /// running
+    pub fn new(
+        config: &NotificationConfig,
+        annotator: Option<String>,
+        catalog: Catalog,
+    ) -> Option<Self> {
+        let url = config.webhook_url.clone().filter(|url| !url.is_empty())?;
+
+        let webhook = Self {
+            target: Target {
+                url,
+                template: config.payload_template.clone(),
+                annotator,
+            },
+            catalog,
+            marker: PathBuf::from(RUNNING_MARKER_PATH),
+            error_rate: config.error_rate,
+            error_window: config.error_window.max(1),
+            recent: VecDeque::new(),
+            spiking: false,
+            finished: false,
+        };
+
+        if webhook.marker.exists() {
+            webhook.send(
+                WebhookEvent::Restarted,
+                &webhook.catalog.get("webhook-restarted"),
+            );
+        }
+
+        if let Err(e) = mark_running(&webhook.marker) {
+            eprintln!(
+                "{}",
+                webhook.catalog.format("webhook-failed", &[("error", &e)])
+            );
+        }
+
+        Some(webhook)
+    }
+
+    /// Post a notification when the tool panics, after the default hook
+    /// prints the panic. The post blocks, since the process is going down
+    pub fn install_panic_hook(&self) {
+        let target = self.target.clone();
+        let catalog = self.catalog.clone();
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+
+            let message = catalog.format("webhook-crashed", &[("error", info)]);
+
+            if let Err(e) = target.post(WebhookEvent::Crashed, &message) {
+                eprintln!("{}", catalog.format("webhook-failed", &[("error", &e)]));
+            }
+        }));
+    }
+
+    /// Note that a scan was opened, or failed to, reporting a spike once
+    /// the share of failures among the last scans reaches the threshold
+    pub fn scan_opened(&mut self, failed: bool) {
+        if self.recent.len() == self.error_window {
+            self.recent.pop_front();
+        }
+
+        self.recent.push_back(failed);
+
+        if self.recent.len() < self.error_window {
+            return;
+        }
+
+        let failures = self.recent.iter().filter(|&&failed| failed).count();
+        let rate = failures as f64 / self.error_window as f64;
+
+        if rate < self.error_rate {
+            self.spiking = false;
+        } else if !self.spiking {
+            self.spiking = true;
+
+            let message = self.catalog.format(
+                "webhook-error-spike",
+                &[("failed", &failures), ("window", &self.error_window)],
+            );
+            self.send(WebhookEvent::ErrorSpike, &message);
+        }
+    }
+
+    /// Report that every scan in the queue was opened, once. The post
+    /// blocks, since the tool exits next
+    pub fn queue_finished(&mut self, labeled: usize, scans: usize) {
+        if self.finished {
+            return;
+        }
+
+        self.finished = true;
+
+        let message = self.catalog.format(
+            "webhook-finished",
+            &[("labeled", &labeled), ("scans", &scans)],
+        );
+
+        if let Err(e) = self.target.post(WebhookEvent::Finished, &message) {
+            eprintln!(
+                "{}",
+                self.catalog.format("webhook-failed", &[("error", &e)])
+            );
+        }
+    }
+
+    /// Post on another thread, so a slow endpoint does not hold up labeling
+    fn send(&self, event: WebhookEvent, message: &str) {
+        let target = self.target.clone();
+        let catalog = self.catalog.clone();
+        let message = message.to_owned();
+
+        thread::spawn(move || {
+            if let Err(e) = target.post(event, &message) {
+                eprintln!("{}", catalog.format("webhook-failed", &[("error", &e)]));
+            }
+        });
+    }
+}
+
+impl Drop for Webhook {
+    /// Left in place when unwinding from a panic, so the next start reports
+    /// the crash
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            let _ = fs::remove_file(&self.marker);
+        }
+    }
+}
+
+/// Record the process ID, for anyone wondering which session left a marker
+/// behind
+fn mark_running(marker: &Path) -> io::Result<()> {
+    fs::write(marker, format!("{}\n", std::process::id()))
+}
+
+/// `text` as it appears between the quotes of a JSON string
+fn json_escape(text: &str) -> String {
+    let quoted = serde_json::to_string(text).unwrap_or_default();
+
+    quoted
+        .get(1..quoted.len().saturating_sub(1))
+        .unwrap_or_default()
+        .to_owned()
+}
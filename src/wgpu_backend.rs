@@ -0,0 +1,452 @@
+use std::{fmt, mem, num::NonZeroU32};
+
+use nalgebra::{Matrix4, Vector3};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    backend::{PixelBackend, RenderBackend, ScanFrame},
+    color::Color,
+    mesh::{BoundingBox, Mesh, VERTEX_STRIDE},
+    renderer::{Light, RenderStats},
+};
+
+const SHADER: &str = include_str!("shaders/scan.wgsl");
+
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Two matrices and three vectors, see `scan.wgsl`
+const UNIFORM_FLOATS: usize = 16 * 2 + 4 * 3;
+
+#[derive(Debug)]
+pub enum WgpuError {
+    /// No Direct3D, Vulkan, or Metal driver could be found
+    NoAdapter,
+    Device(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for WgpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoAdapter => write!(f, "no Direct3D, Vulkan, or Metal adapter"),
+            Self::Device(e) => write!(f, "unable to open the GPU: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WgpuError {}
+
+/// A smooth-shaded backend for machines whose OpenGL driver is broken,
+/// drawing through Direct3D, Vulkan, or Metal instead
+///
+/// Frames are drawn offscreen and read back, so they can be shown in the
+/// same plain window as [`SoftwareBackend`](crate::SoftwareBackend)'s
+pub struct WgpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    api: wgpu::Backend,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    target: Target,
+    scan: Option<UploadedScan>,
+    light: Light,
+    object_color: Color,
+    /// `0x00RRGGBB`, top row first
+    pixels: Vec<u32>,
+    stats: RenderStats,
+}
+
+/// Textures drawn into, and the buffer they are read back through
+struct Target {
+    width: u32,
+    height: u32,
+    color: wgpu::Texture,
+    color_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+    readback: wgpu::Buffer,
+    /// Rows of the readback buffer are padded to wgpu's copy alignment
+    padded_row: u32,
+}
+
+struct UploadedScan {
+    vertices: wgpu::Buffer,
+    indices: wgpu::Buffer,
+    index_count: u32,
+    bbox: BoundingBox,
+}
+
+impl WgpuBackend {
+    pub fn new(
+        width: u32,
+        height: u32,
+        light: &Light,
+        object_color: Color,
+    ) -> Result<Self, WgpuError> {
+        // Every API but OpenGL, which is what this backend stands in for
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY | wgpu::BackendBit::DX11);
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+        }))
+        .ok_or(WgpuError::NoAdapter)?;
+        let api = adapter.get_info().backend;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("scan"),
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+            },
+            None,
+        ))
+        .map_err(WgpuError::Device)?;
+
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("scan"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            flags: wgpu::ShaderFlags::all(),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("scan uniforms"),
+            size: (UNIFORM_FLOATS * mem::size_of::<f32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("scan uniforms"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("scan uniforms"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("scan"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Positions, then normals, as in `basic-vs.shader`
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("scan"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: (VERTEX_STRIDE * mem::size_of::<f32>()) as wgpu::BufferAddress,
+                    step_mode: wgpu::InputStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float3, 1 => Float3],
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[COLOR_FORMAT.into()],
+            }),
+        });
+
+        let target = Target::new(&device, width, height);
+
+        Ok(Self {
+            device,
+            queue,
+            api,
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            target,
+            scan: None,
+            light: *light,
+            object_color,
+            pixels: vec![0; width as usize * height as usize],
+            stats: RenderStats::default(),
+        })
+    }
+
+    /// The API drawn through, for telling the user
+    pub fn api(&self) -> &'static str {
+        match self.api {
+            wgpu::Backend::Dx12 => "Direct3D 12",
+            wgpu::Backend::Dx11 => "Direct3D 11",
+            wgpu::Backend::Vulkan => "Vulkan",
+            wgpu::Backend::Metal => "Metal",
+            _ => "wgpu",
+        }
+    }
+
+    fn write_uniforms(&self, frame: &ScanFrame, bbox: BoundingBox) {
+        let mvp = depth_correction() * frame.projection * frame.stationary.view(bbox) * frame.model;
+        let light_pos = Vector3::from(frame.stationary.pos(bbox));
+        let light = self.light.color;
+        let object = self.object_color;
+
+        let mut uniforms = Vec::with_capacity(UNIFORM_FLOATS);
+        uniforms.extend_from_slice(mvp.as_slice());
+        uniforms.extend_from_slice(frame.model.as_slice());
+        uniforms.extend_from_slice(&[light_pos.x, light_pos.y, light_pos.z, 1.0]);
+        uniforms.extend_from_slice(&[light.red, light.green, light.blue, 1.0]);
+        uniforms.extend_from_slice(&[object.red, object.green, object.blue, 1.0]);
+
+        self.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&uniforms));
+    }
+
+    /// Copy the color target into `pixels`
+    fn read_back(&mut self) {
+        let slice = self.target.readback.slice(..);
+        let mapped = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+
+        if pollster::block_on(mapped).is_err() {
+            return;
+        }
+
+        {
+            let data = slice.get_mapped_range();
+            let rows = data.chunks(self.target.padded_row as usize);
+
+            for (row, pixels) in rows.zip(self.pixels.chunks_mut(self.target.width as usize)) {
+                for (pixel, rgba) in pixels.iter_mut().zip(row.chunks_exact(4)) {
+                    *pixel =
+                        u32::from(rgba[0]) << 16 | u32::from(rgba[1]) << 8 | u32::from(rgba[2]);
+                }
+            }
+        }
+
+        self.target.readback.unmap();
+    }
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = |label, format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+            })
+        };
+
+        let color = texture(
+            "scan color",
+            COLOR_FORMAT,
+            wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        );
+        let depth = texture(
+            "scan depth",
+            DEPTH_FORMAT,
+            wgpu::TextureUsage::RENDER_ATTACHMENT,
+        );
+
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = (width * 4 + alignment - 1) / alignment * alignment;
+
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("scan readback"),
+            size: u64::from(padded_row) * u64::from(height),
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            width,
+            height,
+            color_view: color.create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_view: depth.create_view(&wgpu::TextureViewDescriptor::default()),
+            color,
+            readback,
+            padded_row,
+        }
+    }
+}
+
+impl RenderBackend for WgpuBackend {
+    fn name(&self) -> &'static str {
+        "wgpu"
+    }
+
+    fn load_scan(&mut self, mesh: &Mesh) {
+        // wgpu does not allow empty buffers
+        if mesh.is_empty() {
+            self.scan = None;
+            return;
+        }
+
+        let buffer = |label, contents: &[u8], usage| {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents,
+                    usage,
+                })
+        };
+
+        self.scan = Some(UploadedScan {
+            vertices: buffer(
+                "scan vertices",
+                bytemuck::cast_slice(mesh.vertices()),
+                wgpu::BufferUsage::VERTEX,
+            ),
+            indices: buffer(
+                "scan indices",
+                bytemuck::cast_slice(mesh.indices()),
+                wgpu::BufferUsage::INDEX,
+            ),
+            index_count: mesh.indices().len() as u32,
+            bbox: mesh.bounding_box(),
+        });
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.target = Target::new(&self.device, width, height);
+        self.pixels = vec![0; width as usize * height as usize];
+    }
+
+    fn set_object_color(&mut self, color: Color) {
+        self.object_color = color;
+    }
+
+    fn draw_scan(&mut self, frame: &ScanFrame) {
+        if let Some(scan) = &self.scan {
+            self.write_uniforms(frame, scan.bbox);
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("scan"),
+            });
+
+        {
+            let background = frame.background;
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("scan"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &self.target.color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: f64::from(background.red),
+                            g: f64::from(background.green),
+                            b: f64::from(background.blue),
+                            a: 1.0,
+                        }),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.target.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            if let Some(scan) = &self.scan {
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &self.bind_group, &[]);
+                pass.set_vertex_buffer(0, scan.vertices.slice(..));
+                pass.set_index_buffer(scan.indices.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..scan.index_count, 0, 0..1);
+
+                self.stats.draw_calls += 1;
+                self.stats.triangles += u64::from(scan.index_count / 3);
+            }
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.target.color,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.target.readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(self.target.padded_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.target.width,
+                height: self.target.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.queue.submit(Some(encoder.finish()));
+        self.read_back();
+    }
+
+    fn take_stats(&mut self) -> RenderStats {
+        mem::take(&mut self.stats)
+    }
+}
+
+impl PixelBackend for WgpuBackend {
+    fn width(&self) -> u32 {
+        self.target.width
+    }
+
+    fn height(&self) -> u32 {
+        self.target.height
+    }
+
+    fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+}
+
+/// Projections are built for OpenGL, whose depth runs from -1 to 1, where
+/// wgpu's runs from 0 to 1
+#[rustfmt::skip]
+fn depth_correction() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.5,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
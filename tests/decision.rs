@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use scan_classification::{
+    format_utc, parse_decisions, Decision, LabelRecord, Mesh, MeshMetrics, Record,
+};
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+#[test]
+fn decisions_round_trip_through_json_lines() {
+    let triangle = Mesh::new(
+        vec![
+            0.0, 0.0, 0.0, 0.0, 0.0, 1.0, //
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, //
+            0.0, 1.0, 0.0, 0.0, 0.0, 1.0,
+        ],
+        vec![0, 1, 2],
+    );
+    let metrics = MeshMetrics::new(&triangle);
+    let decisions = [
+        (
+            Record::Labeled {
+                path: "a.stl".to_owned(),
+                label: "w".to_owned(),
+                expedited: false,
+            },
+            Some(&metrics),
+        ),
+        (
+            Record::Invalid {
+                path: "b.stl".to_owned(),
+            },
+            None,
+        ),
+        (
+            Record::Skipped {
+                path: "c.stl".to_owned(),
+            },
+            Some(&metrics),
+        ),
+    ];
+
+    let mut written = Vec::new();
+
+    for (record, metrics) in &decisions {
+        Decision::new(record, "3f9a61c0b2d4e871", 1_614_600_000, *metrics)
+            .write_line(&mut written)
+            .unwrap();
+    }
+
+    let written = String::from_utf8(written).unwrap();
+    let lines: Vec<Value> = written
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let expected = [
+        ("a.stl", "labeled", Some("w")),
+        ("b.stl", "invalid", None),
+        ("c.stl", "skipped", None),
+    ];
+
+    assert_eq!(lines.len(), expected.len());
+
+    for ((line, (path, decision, label)), (_, metrics)) in
+        lines.iter().zip(&expected).zip(&decisions)
+    {
+        let mut keys: Vec<&str> = line
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+
+        assert_eq!(
+            keys,
+            [
+                "decision",
+                "label",
+                "metrics",
+                "path",
+                "session_id",
+                "timestamp"
+            ]
+        );
+        assert_eq!(line["path"], *path);
+        assert_eq!(line["decision"], *decision);
+        assert_eq!(line["label"].as_str(), *label);
+        assert_eq!(line["timestamp"], format_utc(1_614_600_000));
+        assert_eq!(line["session_id"], "3f9a61c0b2d4e871");
+
+        match metrics {
+            Some(metrics) => assert_eq!(line["metrics"]["triangle_count"], metrics.triangle_count),
+            None => assert!(line["metrics"].is_null()),
+        }
+    }
+
+    // Only the scan labeled is read back as a label
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(written.as_bytes()).unwrap();
+    let recorded = parse_decisions(file.path()).unwrap();
+
+    assert_eq!(
+        recorded,
+        [LabelRecord {
+            path: "a.stl".to_owned(),
+            label: "w".to_owned(),
+        }]
+    );
+}
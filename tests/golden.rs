@@ -0,0 +1,174 @@
+//! Renders the sample scans and compares them to reference images in
+//! `tests/golden`
+//!
+//! A missing reference fails the test, and `UPDATE_GOLDEN=1 cargo test
+//! --test golden` writes or rewrites all of them after an intentional change
+//! to the renderer. On a mismatch, the rendering and a diff are saved to
+//! `target/golden` for inspection
+
+use std::{
+    env,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use scan_classification::{
+    render_software, Color, Mesh, OffscreenRenderer, RenderError, RenderOptions, RgbaImage,
+    StationaryCamera,
+};
+
+/// Largest difference in any channel for two pixels to count as equal
+const CHANNEL_TOLERANCE: u8 = 8;
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn options() -> RenderOptions {
+    RenderOptions {
+        width: 128,
+        height: 128,
+        ..RenderOptions::default()
+    }
+}
+
+fn write(image: &RgbaImage, path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image
+        .write_png(BufWriter::new(File::create(path).unwrap()))
+        .unwrap();
+}
+
+/// Differing pixels are white in the returned image
+fn diff(expected: &RgbaImage, actual: &RgbaImage) -> (usize, RgbaImage) {
+    let mut image = RgbaImage::new(expected.width(), expected.height());
+    let mut differing = 0;
+
+    for y in 0..expected.height() {
+        for x in 0..expected.width() {
+            let a = expected.get_pixel(x, y);
+            let b = actual.get_pixel(x, y);
+
+            let equal = a
+                .iter()
+                .zip(&b)
+                .all(|(&a, &b)| (a as i16 - b as i16).abs() <= CHANNEL_TOLERANCE as i16);
+
+            if equal {
+                image.put_pixel(x, y, [0, 0, 0, 255]);
+            } else {
+                image.put_pixel(x, y, [255, 255, 255, 255]);
+                differing += 1;
+            }
+        }
+    }
+
+    (differing, image)
+}
+
+/// Compare `actual` to the reference image `name`, allowing up to
+/// `max_differing` of its pixels to differ
+fn assert_golden(name: &str, actual: &RgbaImage, max_differing: f64) {
+    let golden = root().join("tests/golden").join(format!("{}.png", name));
+    let failures: PathBuf = root().join("target/golden");
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        write(actual, &golden);
+        eprintln!("wrote {}", golden.display());
+        return;
+    }
+
+    if !golden.exists() {
+        write(actual, &failures.join(format!("{}.actual.png", name)));
+
+        panic!(
+            "{}: no reference at {}, see {} and run with UPDATE_GOLDEN=1 to write it",
+            name,
+            golden.display(),
+            failures.display()
+        );
+    }
+
+    let expected = RgbaImage::read_png(File::open(&golden).unwrap()).unwrap();
+
+    assert_eq!(
+        (expected.width(), expected.height()),
+        (actual.width(), actual.height()),
+        "{} changed size",
+        name
+    );
+
+    let (differing, diff_image) = diff(&expected, actual);
+    let fraction = differing as f64 / (expected.width() * expected.height()) as f64;
+
+    if fraction > max_differing {
+        write(actual, &failures.join(format!("{}.actual.png", name)));
+        write(&diff_image, &failures.join(format!("{}.diff.png", name)));
+
+        panic!(
+            "{}: {:.2}% of pixels differ from {}, see {}",
+            name,
+            fraction * 100.0,
+            golden.display(),
+            failures.display()
+        );
+    }
+}
+
+fn sample(name: &str) -> Mesh {
+    Mesh::open(root().join(name)).unwrap()
+}
+
+#[test]
+fn software_teapot() {
+    let image = render_software(&sample("Utah_teapot_(solid).stl"), &options());
+
+    assert_golden("software-teapot", &image, 0.001);
+}
+
+#[test]
+fn software_eiffel_tower() {
+    let image = render_software(&sample("Eiffel_tower_sample.stl"), &options());
+
+    assert_golden("software-eiffel-tower", &image, 0.001);
+}
+
+#[test]
+fn software_teapot_rotated() {
+    let options = RenderOptions {
+        model: nalgebra_glm::rotate(
+            &StationaryCamera::default_model(),
+            90.0_f32.to_radians(),
+            &nalgebra::Vector3::y_axis(),
+        ),
+        object_color: Color::from_hex(0x4E79A7),
+        background: Color::WHITE,
+        ..options()
+    };
+
+    let image = render_software(&sample("Utah_teapot_(solid).stl"), &options);
+
+    assert_golden("software-teapot-rotated", &image, 0.001);
+}
+
+/// Drivers rasterize and multisample slightly differently, so the GL
+/// renderings get more leeway than the software ones. Skipped on machines
+/// without a GL context
+#[test]
+fn gl_teapot() {
+    let mut renderer = match OffscreenRenderer::new() {
+        Ok(renderer) => renderer,
+        Err(RenderError::Context(e)) => {
+            eprintln!("skipping, no GL context: {}", e);
+            return;
+        }
+        Err(e) => panic!("{}", e),
+    };
+
+    let image = renderer
+        .render_path(root().join("Utah_teapot_(solid).stl"), &options())
+        .unwrap();
+
+    assert_golden("gl-teapot", &image, 0.02);
+}
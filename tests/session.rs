@@ -0,0 +1,631 @@
+use std::collections::{HashMap, HashSet};
+
+use proptest::prelude::*;
+use scan_classification::{ClusterAssignment, LabelRecord, Progress, Record, Session, Table};
+
+fn session(paths: &[&str]) -> Session {
+    Session::new(paths.iter().map(|&path| path.to_owned()).collect())
+}
+
+fn labeled(path: &str, label: &str, expedited: bool) -> Option<Record> {
+    Some(Record::Labeled {
+        path: path.to_owned(),
+        label: label.to_owned(),
+        expedited,
+    })
+}
+
+#[test]
+fn starts_before_the_first_scan() {
+    let session = session(&["a.stl", "b.stl"]);
+
+    assert_eq!(session.cursor(), 0);
+    assert_eq!(session.current_path(), None);
+    assert_eq!(session.next_path(), Some("a.stl"));
+}
+
+#[test]
+fn advance_opens_scans_in_order() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    assert_eq!(session.advance(), Some("a.stl"));
+    assert_eq!(session.current_path(), Some("a.stl"));
+    assert_eq!(session.next_path(), Some("b.stl"));
+
+    assert_eq!(session.advance(), Some("b.stl"));
+    assert_eq!(session.cursor(), 2);
+    assert_eq!(session.next_path(), None);
+}
+
+#[test]
+fn advance_past_the_end_keeps_the_last_scan() {
+    let mut session = session(&["a.stl"]);
+
+    session.advance();
+
+    assert_eq!(session.advance(), None);
+    assert_eq!(session.cursor(), 1);
+    assert_eq!(session.current_path(), Some("a.stl"));
+}
+
+#[test]
+fn empty_queue_has_nothing_to_open() {
+    let mut session = Session::new(Vec::new());
+
+    assert_eq!(session.advance(), None);
+    assert_eq!(session.label("w"), None);
+    assert_eq!(session.record_invalid(), None);
+}
+
+#[test]
+fn label_requires_a_current_scan() {
+    let mut session = session(&["a.stl"]);
+
+    assert_eq!(session.label("w"), None);
+    assert_eq!(session.report().total, 0);
+}
+
+#[test]
+fn label_records_the_current_scan() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+
+    assert_eq!(session.label("w"), labeled("a.stl", "w", false));
+    assert_eq!(session.report().counts.get("w"), Some(&1));
+    assert_eq!(session.report().total, 1);
+}
+
+#[test]
+fn invalid_scans_are_not_labeled() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+
+    assert_eq!(
+        session.record_invalid(),
+        Some(Record::Invalid {
+            path: "a.stl".to_owned()
+        })
+    );
+    assert!(session.is_recorded_invalid());
+    assert_eq!(session.label("w"), None);
+    assert_eq!(session.report().total, 0);
+}
+
+#[test]
+fn advancing_clears_invalid() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+    session.record_invalid();
+    session.advance();
+
+    assert!(!session.is_recorded_invalid());
+    assert_eq!(session.label("a"), labeled("b.stl", "a", false));
+}
+
+#[test]
+fn failed_scans_can_be_recorded_as_invalid() {
+    let mut session = session(&["a.stl"]);
+
+    assert_eq!(session.advance_failed(&"truncated file"), Some("a.stl"));
+    assert_eq!(
+        session.record_invalid().as_ref().map(Record::path),
+        Some("a.stl")
+    );
+    assert!(session
+        .log()
+        .iter()
+        .any(|entry| entry.contains("truncated file")));
+}
+
+#[test]
+fn skip_next_passes_over_a_scan() {
+    let mut session = session(&["a.stl", "b.stl", "c.stl"]);
+
+    session.advance();
+    session.skip_next();
+
+    assert_eq!(session.next_path(), Some("c.stl"));
+    assert_eq!(session.advance(), Some("c.stl"));
+}
+
+#[test]
+fn shelving_reopens_an_uncommitted_scan() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+    session.shelve();
+
+    assert_eq!(session.current_path(), None);
+    assert_eq!(session.advance(), Some("a.stl"));
+
+    session.label("w");
+    session.shelve();
+
+    assert_eq!(session.next_path(), Some("b.stl"));
+}
+
+#[test]
+fn undo_reopens_the_last_labeled_scan() {
+    let mut session = session(&["a.stl", "b.stl", "c.stl"]);
+
+    session.advance();
+    session.label("w");
+    session.advance();
+
+    assert_eq!(session.undo(), labeled("a.stl", "w", false));
+    assert_eq!(session.current_path(), None);
+    assert_eq!(session.report().total, 0);
+    assert_eq!(session.advance(), Some("a.stl"));
+
+    session.label("s");
+
+    assert_eq!(session.advance(), Some("b.stl"));
+    assert_eq!(session.report().counts.get("s"), Some(&1));
+    assert_eq!(session.report().counts.get("w"), None);
+}
+
+#[test]
+fn undo_with_nothing_committed_does_nothing() {
+    let mut session = session(&["a.stl"]);
+
+    session.advance();
+
+    assert_eq!(session.undo(), None);
+    assert_eq!(session.current_path(), Some("a.stl"));
+}
+
+#[test]
+fn skip_is_logged_without_a_label() {
+    let mut session = session(&["a.stl"]);
+
+    session.advance();
+    session.skip();
+
+    assert_eq!(session.report().total, 0);
+    assert_eq!(
+        session.log().last().map(String::as_str),
+        Some("skipped a.stl")
+    );
+}
+
+#[test]
+fn skip_records_the_scan_once() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+
+    assert_eq!(
+        session.skip(),
+        Some(Record::Skipped {
+            path: "a.stl".to_owned()
+        })
+    );
+    assert_eq!(session.skip(), None);
+
+    session.shelve();
+
+    assert_eq!(session.next_path(), Some("b.stl"));
+}
+
+#[test]
+fn committed_scans_are_not_recorded_again() {
+    let mut session = session(&["a.stl", "b.stl", "c.stl"]);
+
+    session.advance();
+    assert!(session.label("w").is_some());
+    assert_eq!(session.label("a"), None);
+    assert_eq!(session.record_invalid(), None);
+    assert_eq!(session.skip(), None);
+
+    session.advance();
+    assert!(session.skip().is_some());
+    assert_eq!(session.label("w"), None);
+    assert_eq!(session.record_invalid(), None);
+
+    session.advance();
+    assert!(session.record_invalid().is_some());
+    assert_eq!(session.record_invalid(), None);
+    assert_eq!(session.label("w"), None);
+
+    assert_eq!(session.report().counts.get("w"), Some(&1));
+    assert_eq!(session.report().total, 1);
+
+    assert_eq!(session.undo().as_ref().map(Record::path), Some("c.stl"));
+    assert_eq!(session.undo().as_ref().map(Record::path), Some("b.stl"));
+    assert_eq!(session.undo(), labeled("a.stl", "w", false));
+    assert_eq!(session.undo(), None);
+}
+
+#[test]
+fn resume_continues_after_the_last_committed_scan() {
+    let mut first = session(&["a.stl", "b.stl", "c.stl"]);
+
+    first.advance();
+    first.label("w");
+    first.advance();
+
+    let progress = Progress::parse(&first.progress().to_string()).unwrap();
+    assert_eq!(progress, first.progress());
+    assert_eq!(progress.recorded, 1);
+
+    let mut second = session(&["a.stl", "b.stl", "c.stl"]);
+
+    assert!(second.resume(&progress));
+    assert_eq!(second.advance(), Some("b.stl"));
+    assert_eq!(second.progress().recorded, 1);
+}
+
+#[test]
+fn resume_ignores_progress_from_another_queue() {
+    let mut first = session(&["a.stl", "b.stl"]);
+
+    first.advance();
+    first.label("w");
+
+    let mut second = session(&["b.stl", "c.stl"]);
+
+    assert!(!second.resume(&first.progress()));
+    assert_eq!(second.advance(), Some("b.stl"));
+}
+
+#[test]
+fn urgent_scans_wait_for_a_commit() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+    session.add_urgent(vec!["u.stl".to_owned()]);
+
+    assert_eq!(session.urgent_waiting(), 1);
+    assert_eq!(session.next_path(), Some("b.stl"));
+
+    session.label("w");
+
+    assert_eq!(session.urgent_waiting(), 0);
+    assert_eq!(session.next_path(), Some("u.stl"));
+
+    session.advance();
+
+    assert!(session.is_expedited());
+    assert_eq!(session.label("d"), labeled("u.stl", "d", true));
+    assert_eq!(session.advance(), Some("b.stl"));
+    assert!(!session.is_expedited());
+}
+
+#[test]
+fn invalid_scans_release_urgent_scans() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+    session.add_urgent(vec!["u.stl".to_owned()]);
+    session.record_invalid();
+
+    assert_eq!(session.next_path(), Some("u.stl"));
+}
+
+#[test]
+fn urgent_scans_later_in_the_queue_are_moved_up() {
+    let mut session = session(&["a.stl", "b.stl", "c.stl"]);
+
+    session.advance();
+    session.add_urgent(vec!["c.stl".to_owned()]);
+    session.label("w");
+
+    assert_eq!(session.queue(), &["a.stl", "c.stl", "b.stl"]);
+}
+
+#[test]
+fn urgent_scans_keep_their_order() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+    session.add_urgent(vec!["u1.stl".to_owned(), "u2.stl".to_owned()]);
+    session.add_urgent(vec!["u3.stl".to_owned()]);
+    session.label("w");
+
+    assert_eq!(
+        session.queue(),
+        &["a.stl", "u1.stl", "u2.stl", "u3.stl", "b.stl"]
+    );
+}
+
+#[test]
+fn checkpoint_describes_the_position() {
+    let mut session = session(&["a.stl", "b.stl"]);
+
+    session.advance();
+
+    let checkpoint = session.checkpoint();
+
+    assert_eq!(checkpoint.cursor, 1);
+    assert_eq!(checkpoint.current.as_deref(), Some("a.stl"));
+    assert_eq!(checkpoint.queue_len, 2);
+    assert_eq!(
+        checkpoint.to_string(),
+        "cursor: 1\ncurrent: a.stl\nqueue length: 2\n"
+    );
+}
+
+fn clustered(paths: &[&str], table: &str) -> Session {
+    let clusters = ClusterAssignment::from_table(&Table::parse(table)).unwrap();
+
+    session(paths).with_clusters(clusters)
+}
+
+#[test]
+fn clusters_are_labeled_together_exemplar_first() {
+    let session = clustered(
+        &["a.stl", "b.stl", "c.stl", "d.stl", "e.stl"],
+        "path,cluster\nd.stl,1\nb.stl,0\na.stl,1\nc.stl,0\n",
+    );
+
+    assert_eq!(
+        session.queue(),
+        &["d.stl", "a.stl", "b.stl", "c.stl", "e.stl"]
+    );
+}
+
+#[test]
+fn cluster_members_suggest_the_exemplars_label() {
+    let mut session = clustered(
+        &["a.stl", "b.stl", "c.stl", "d.stl"],
+        "path,cluster\na.stl,0\nb.stl,0\nc.stl,1\n",
+    );
+
+    session.advance();
+    assert_eq!(session.suggestion(), None);
+    session.label("d");
+
+    session.advance();
+    let suggestion = session.suggestion().unwrap();
+    assert_eq!(suggestion.cluster, "0");
+    assert_eq!(suggestion.exemplar, "a.stl");
+    assert_eq!(suggestion.label, "d");
+
+    // Overriding the suggestion does not change the exemplar
+    session.label("w");
+    session.advance();
+    assert_eq!(session.suggestion(), None);
+    session.label("s");
+
+    session.advance();
+    assert_eq!(session.current_path(), Some("d.stl"));
+    assert_eq!(session.suggestion(), None);
+}
+
+#[test]
+fn resuming_partway_through_a_cluster_keeps_its_suggestion() {
+    let paths = ["a.stl", "b.stl", "c.stl"];
+    let table = "path,cluster\na.stl,0\nb.stl,0\nc.stl,0\n";
+    let recorded = || {
+        vec![LabelRecord {
+            path: "a.stl".to_owned(),
+            label: "d".to_owned(),
+        }]
+    };
+
+    let mut first = clustered(&paths, table);
+    first.advance();
+    first.label("d");
+    let progress = first.progress();
+
+    // Restarted, with the exemplar's label read back from its file
+    let mut resumed = clustered(&paths, table).with_recorded(recorded());
+
+    assert!(resumed.resume(&progress));
+    assert_eq!(resumed.advance(), Some("b.stl"));
+
+    let suggestion = resumed.suggestion().unwrap();
+    assert_eq!(suggestion.exemplar, "a.stl");
+    assert_eq!(suggestion.label, "d");
+
+    // A kiosk leaves the labeled scan out of the queue instead
+    let mut kiosk = clustered(&paths[1..], table).with_recorded(recorded());
+
+    assert_eq!(kiosk.advance(), Some("b.stl"));
+    assert_eq!(kiosk.suggestion(), Some(suggestion));
+}
+
+#[test]
+fn undoing_the_exemplar_takes_back_its_suggestion() {
+    let mut session = clustered(&["a.stl", "b.stl"], "path,cluster\na.stl,0\nb.stl,0\n");
+
+    session.advance();
+    session.label("a");
+    session.undo();
+    session.advance();
+
+    assert_eq!(session.current_path(), Some("a.stl"));
+    assert_eq!(session.suggestion(), None);
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Advance,
+    AdvanceFailed,
+    SkipNext,
+    Skip,
+    Label(usize),
+    RecordInvalid,
+    AddUrgent(Vec<usize>),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => Just(Op::Advance),
+        Just(Op::AdvanceFailed),
+        Just(Op::SkipNext),
+        Just(Op::Skip),
+        3 => (0..4_usize).prop_map(Op::Label),
+        Just(Op::RecordInvalid),
+        prop::collection::vec(0..20_usize, 0..3).prop_map(Op::AddUrgent),
+    ]
+}
+
+const LABELS: [&str; 4] = ["w", "a", "s", "d"];
+
+proptest! {
+    #[test]
+    fn flow_stays_consistent(
+        queue_len in 0..12_usize,
+        ops in prop::collection::vec(op(), 0..60),
+    ) {
+        let queue: Vec<String> = (0..queue_len).map(|idx| format!("scan-{}.stl", idx)).collect();
+        let mut session = Session::new(queue.clone());
+
+        let mut labeled = 0;
+        let mut invalid = false;
+        let mut records = Vec::new();
+
+        for op in ops {
+            let cursor = session.cursor();
+            let current = session.current_path().map(str::to_owned);
+
+            match op {
+                Op::Advance | Op::AdvanceFailed => {
+                    let next = session.next_path().map(str::to_owned);
+                    let opened = match op {
+                        Op::Advance => session.advance().map(str::to_owned),
+                        _ => session.advance_failed(&"error").map(str::to_owned),
+                    };
+
+                    prop_assert_eq!(&opened, &next);
+
+                    if opened.is_some() {
+                        prop_assert_eq!(session.cursor(), cursor + 1);
+                        prop_assert_eq!(session.current_path().map(str::to_owned), opened);
+                        invalid = false;
+                    } else {
+                        prop_assert_eq!(session.cursor(), cursor);
+                    }
+                }
+                Op::SkipNext => {
+                    let had_next = session.next_path().is_some();
+                    session.skip_next();
+
+                    prop_assert_eq!(session.cursor(), cursor + had_next as usize);
+                }
+                Op::Skip => {
+                    session.skip();
+
+                    prop_assert_eq!(session.cursor(), cursor);
+                }
+                Op::Label(idx) => {
+                    let record = session.label(LABELS[idx]);
+
+                    match (&current, invalid) {
+                        (Some(path), false) => {
+                            prop_assert_eq!(record.as_ref().map(Record::path), Some(path.as_str()));
+                            labeled += 1;
+                        }
+                        _ => prop_assert_eq!(&record, &None),
+                    }
+
+                    records.extend(record);
+                    prop_assert_eq!(session.urgent_waiting(), 0);
+                }
+                Op::RecordInvalid => {
+                    let record = session.record_invalid();
+
+                    prop_assert_eq!(record.is_some(), current.is_some());
+
+                    if record.is_some() {
+                        invalid = true;
+                    }
+
+                    prop_assert_eq!(session.urgent_waiting(), 0);
+                }
+                Op::AddUrgent(paths) => {
+                    let waiting = session.urgent_waiting();
+                    let paths: Vec<String> =
+                        paths.iter().map(|idx| format!("scan-{}.stl", idx)).collect();
+                    let added = paths.len();
+
+                    session.add_urgent(paths);
+
+                    prop_assert_eq!(session.urgent_waiting(), waiting + added);
+                    prop_assert_eq!(session.cursor(), cursor);
+                }
+            }
+
+            // The scans already opened or passed over never change
+            prop_assert!(session.cursor() <= session.queue().len());
+            prop_assert_eq!(session.report().total, labeled);
+        }
+
+        // Every scan of the original queue is still somewhere in it
+        for path in &queue {
+            prop_assert!(session.queue().contains(path));
+        }
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        for record in &records {
+            if let Record::Labeled { label, .. } = record {
+                *counts.entry(label.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        for (label, count) in counts {
+            prop_assert_eq!(session.report().counts.get(label), Some(&count));
+        }
+    }
+
+    #[test]
+    fn promotion_never_duplicates_scans(
+        queue_len in 1..12_usize,
+        opened in 0..12_usize,
+        urgent in prop::collection::vec(0..24_usize, 0..8),
+        repeated in prop::collection::vec(any::<prop::sample::Index>(), 0..4),
+    ) {
+        let queue: Vec<String> = (0..queue_len).map(|idx| format!("scan-{}.stl", idx)).collect();
+        let mut session = Session::new(queue);
+
+        for _ in 0..opened.min(queue_len) {
+            session.advance();
+        }
+
+        let mut urgent: Vec<String> =
+            urgent.iter().map(|idx| format!("scan-{}.stl", idx)).collect();
+
+        // The urgent file can list a scan more than once
+        if !urgent.is_empty() {
+            for index in &repeated {
+                urgent.push(index.get(&urgent).clone());
+            }
+        }
+
+        let cursor = session.cursor();
+        let before: Vec<String> = session.queue()[..cursor].to_vec();
+
+        // Each urgent scan not seen yet, where it is first listed
+        let mut promoted: Vec<String> = Vec::new();
+
+        for path in &urgent {
+            if !before.contains(path) && !promoted.contains(path) {
+                promoted.push(path.clone());
+            }
+        }
+
+        session.add_urgent(urgent);
+        session.promote_urgent();
+
+        // Scans already seen are left alone, and urgent ones come next
+        prop_assert_eq!(&session.queue()[..cursor], before.as_slice());
+        prop_assert_eq!(
+            &session.queue()[cursor..cursor + promoted.len()],
+            promoted.as_slice()
+        );
+
+        let unvisited = &session.queue()[cursor..];
+        let distinct: HashSet<&String> = unvisited.iter().collect();
+
+        prop_assert_eq!(distinct.len(), unvisited.len());
+
+        for path in unvisited {
+            prop_assert!(!before.contains(path));
+        }
+    }
+}